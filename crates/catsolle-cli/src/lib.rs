@@ -1,4 +1,6 @@
 use clap::{Parser, Subcommand};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "catsolle", version, about = "catsolle TUI SSH client")]
@@ -19,6 +21,44 @@ pub enum Command {
         target: String,
         #[arg(long)]
         quick: bool,
+        /// Local SOCKS5 proxy over the session, as `[bind_address:]port` (like `ssh -D`).
+        #[arg(long, short = 'D', value_name = "[BIND:]PORT", value_parser = parse_bind_addr)]
+        dynamic_forward: Option<SocketAddr>,
+        /// Local port tunnelled to a host reachable from the server, as
+        /// `[bind_address:]port:host:hostport` (like `ssh -L`). Repeatable.
+        #[arg(long, short = 'L', value_name = "[BIND:]PORT:HOST:HOSTPORT", value_parser = parse_local_forward)]
+        local_forward: Vec<LocalForwardSpec>,
+        /// Server port tunnelled back to a host reachable from here, as
+        /// `port:host:hostport` (like `ssh -R`). Repeatable.
+        #[arg(long, short = 'R', value_name = "PORT:HOST:HOSTPORT", value_parser = parse_remote_forward)]
+        remote_forward: Vec<RemoteForwardSpec>,
+    },
+    /// Runs one command on a saved connection or `user@host[:port]` and exits with its status
+    /// (255 when it was killed by a signal or reported none).
+    Exec {
+        target: String,
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+        /// Gives up on connecting and running the command after this long.
+        #[arg(long, value_name = "MS")]
+        timeout_ms: Option<u64>,
+    },
+    /// Copies files to or from a server, scp style: remote sides are written `target:path`,
+    /// where the target is a saved connection or `user@host`.
+    Cp {
+        #[arg(value_parser = parse_copy_location)]
+        source: CopyLocation,
+        #[arg(value_parser = parse_copy_location)]
+        dest: CopyLocation,
+        /// Copies directories recursively.
+        #[arg(short = 'r', long)]
+        recursive: bool,
+        /// Checks checksums after copying; defaults to `transfer.verify_checksum`.
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+        verify: Option<bool>,
+        /// Continues partial files; defaults to `transfer.resume`.
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+        resume: Option<bool>,
     },
     Keys {
         #[command(subcommand)]
@@ -27,6 +67,26 @@ pub enum Command {
     Config {
         #[arg(long)]
         init: bool,
+        /// Writes all connections and groups to a JSON file.
+        #[arg(long, value_name = "FILE", conflicts_with = "import")]
+        export: Option<PathBuf>,
+        /// Adds connections and groups from a JSON export, skipping ones already present.
+        #[arg(long, value_name = "FILE")]
+        import: Option<PathBuf>,
+        /// Sets a value in the user config, as `section.key=value` (e.g. `ai.model=gpt-4o`).
+        /// Repeatable.
+        #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_setting)]
+        set: Vec<(String, String)>,
+    },
+    /// Replays an asciicast v2 recording in the terminal.
+    Play {
+        file: PathBuf,
+        /// Playback speed multiplier.
+        #[arg(long, default_value_t = 1.0, value_parser = parse_positive)]
+        speed: f64,
+        /// Longest pause between events, in seconds.
+        #[arg(long, value_name = "SECONDS", value_parser = parse_positive)]
+        max_idle: Option<f64>,
     },
 }
 
@@ -43,8 +103,229 @@ pub enum KeyCommand {
         curve: Option<String>,
     },
     List,
+    /// Prints the SHA256 fingerprint and type of a public or private key file.
+    Fingerprint {
+        #[arg(long)]
+        path: String,
+    },
+    /// Deletes a key pair from the ssh directory.
+    Remove {
+        #[arg(long)]
+        name: String,
+        /// Skips the confirmation prompt.
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
     AddAgent {
         #[arg(long)]
         path: String,
     },
+    /// Lists the identities loaded in the ssh agent.
+    AgentList,
+    /// Removes one identity from the ssh agent.
+    AgentRemove {
+        /// SHA256 fingerprint as printed by `agent-list`.
+        #[arg(long)]
+        fingerprint: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocalForwardSpec {
+    pub bind: SocketAddr,
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CopyLocation {
+    Local(PathBuf),
+    Remote { target: String, path: String },
+}
+
+/// Like scp, a colon before any slash makes the part in front of it a remote target.
+/// An empty remote path means the login directory.
+fn parse_copy_location(value: &str) -> Result<CopyLocation, String> {
+    let remote = value
+        .split_once(':')
+        .filter(|(target, _)| !target.is_empty() && !target.contains('/'));
+    match remote {
+        Some((target, path)) => Ok(CopyLocation::Remote {
+            target: target.to_string(),
+            path: if path.is_empty() { "." } else { path }.to_string(),
+        }),
+        None if value.is_empty() => Err("expected a path or TARGET:PATH".to_string()),
+        None => Ok(CopyLocation::Local(PathBuf::from(value))),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteForwardSpec {
+    pub remote_port: u16,
+    pub host: String,
+    pub port: u16,
+}
+
+/// A bare port binds to loopback only, matching `ssh -D`.
+fn parse_bind_addr(value: &str) -> Result<SocketAddr, String> {
+    if let Ok(port) = value.parse::<u16>() {
+        return Ok(SocketAddr::from((Ipv4Addr::LOCALHOST, port)));
+    }
+    value
+        .parse::<SocketAddr>()
+        .map_err(|_| format!("expected PORT or ADDRESS:PORT, got {value}"))
+}
+
+fn parse_setting(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, val)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), val.trim().to_string()))
+        }
+        _ => Err(format!("expected KEY=VALUE, got {value}")),
+    }
+}
+
+fn parse_positive(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(v) if v.is_finite() && v > 0.0 => Ok(v),
+        _ => Err(format!("expected a positive number, got {value}")),
+    }
+}
+
+/// Parses `[bind_address:]port:host:hostport`; IPv6 addresses go in brackets.
+fn parse_local_forward(value: &str) -> Result<LocalForwardSpec, String> {
+    let invalid = || format!("expected [BIND:]PORT:HOST:HOSTPORT, got {value}");
+    let (rest, port) = value.rsplit_once(':').ok_or_else(invalid)?;
+    let port = port.parse::<u16>().map_err(|_| invalid())?;
+    let (bind, host) = match rest.strip_suffix(']') {
+        Some(rest) => {
+            let (bind, host) = rest.rsplit_once('[').ok_or_else(invalid)?;
+            (bind.strip_suffix(':').ok_or_else(invalid)?, host)
+        }
+        None => rest.rsplit_once(':').ok_or_else(invalid)?,
+    };
+    if host.is_empty() {
+        return Err(invalid());
+    }
+    Ok(LocalForwardSpec {
+        bind: parse_bind_addr(bind)?,
+        host: host.to_string(),
+        port,
+    })
+}
+
+/// Parses `port:host:hostport`; an IPv6 host goes in brackets.
+fn parse_remote_forward(value: &str) -> Result<RemoteForwardSpec, String> {
+    let invalid = || format!("expected PORT:HOST:HOSTPORT, got {value}");
+    let (rest, port) = value.rsplit_once(':').ok_or_else(invalid)?;
+    let (remote_port, host) = rest.split_once(':').ok_or_else(invalid)?;
+    let host = host
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host);
+    if host.is_empty() {
+        return Err(invalid());
+    }
+    Ok(RemoteForwardSpec {
+        remote_port: remote_port.parse().map_err(|_| invalid())?,
+        host: host.to_string(),
+        port: port.parse().map_err(|_| invalid())?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_local_forwards() {
+        assert_eq!(
+            parse_local_forward("8080:db:5432"),
+            Ok(LocalForwardSpec {
+                bind: SocketAddr::from((Ipv4Addr::LOCALHOST, 8080)),
+                host: "db".to_string(),
+                port: 5432,
+            })
+        );
+        assert_eq!(
+            parse_local_forward("0.0.0.0:8080:db:5432").unwrap().bind,
+            "0.0.0.0:8080".parse().unwrap()
+        );
+        assert_eq!(
+            parse_local_forward("[::1]:8080:db:5432").unwrap().bind,
+            "[::1]:8080".parse().unwrap()
+        );
+        let spec = parse_local_forward("[::1]:8080:[fe80::1]:80").unwrap();
+        assert_eq!(spec.bind, "[::1]:8080".parse().unwrap());
+        assert_eq!(spec.host, "fe80::1");
+        assert_eq!(spec.port, 80);
+
+        for value in [
+            "",
+            "8080",
+            "8080:db",
+            "8080:db:",
+            "8080::5432",
+            ":db:5432",
+            "8080:db:65536",
+            "70000:db:5432",
+            "8080:db:http",
+            "8080[::1]:80",
+        ] {
+            assert!(parse_local_forward(value).is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn parses_remote_forwards() {
+        assert_eq!(
+            parse_remote_forward("9000:localhost:3000"),
+            Ok(RemoteForwardSpec {
+                remote_port: 9000,
+                host: "localhost".to_string(),
+                port: 3000,
+            })
+        );
+        assert_eq!(parse_remote_forward("9000:[::1]:3000").unwrap().host, "::1");
+
+        for value in [
+            "",
+            "9000",
+            "9000:localhost",
+            "9000::3000",
+            "9000:[]:3000",
+            "x:localhost:3000",
+            "9000:localhost:65536",
+            "-1:localhost:3000",
+        ] {
+            assert!(parse_remote_forward(value).is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn parses_copy_locations() {
+        assert_eq!(
+            parse_copy_location("web:/var/log/app.log"),
+            Ok(CopyLocation::Remote {
+                target: "web".to_string(),
+                path: "/var/log/app.log".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_copy_location("root@web:"),
+            Ok(CopyLocation::Remote {
+                target: "root@web".to_string(),
+                path: ".".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_copy_location("./odd:name"),
+            Ok(CopyLocation::Local(PathBuf::from("./odd:name")))
+        );
+        assert_eq!(
+            parse_copy_location("notes.txt"),
+            Ok(CopyLocation::Local(PathBuf::from("notes.txt")))
+        );
+        assert!(parse_copy_location("").is_err());
+    }
 }
@@ -2,6 +2,7 @@ app-name = catsolle
 welcome = Welcome back, nya~
 connect = Connect
 connections = Connections
+connections-search = search: { $query }
 status-connected = Connected
 status-disconnected = Disconnected
 menu-new = New
@@ -15,6 +16,7 @@ value-off = off
 error-connection-failed = Connection failed
 prompt = (=^･ω･^=)>
 session = Session
+session-reconnecting = reconnecting { $attempt }/{ $max }…
 empty-connections-title = No connections yet
 empty-connections-body = Import from SSH config or create a new connection.
 empty-connections-actions = Press I to import, N to add, R to reload, ? for help.
@@ -25,36 +27,115 @@ label-user = User
 label-auth = Auth
 label-last = Last connected
 label-tags = Tags
+label-encoding = Encoding
+label-backend = Transfers
+label-locale = Locale
+label-transfer-dirs = Transfer dirs
+label-key-type = Key type
+label-fingerprint = Fingerprint
+label-favorite = favorite
+label-locale-default = { $locale } (default)
 label-none = none
 label-never = never
+time-just-now = just now
+time-minutes-ago = { $count }m ago
+time-hours-ago = { $count }h ago
+time-yesterday = yesterday
+time-days-ago = { $count } days ago
+time-weeks-ago = { $count ->
+    [one] { $count } week ago
+   *[other] { $count } weeks ago
+}
 empty-details = Select a connection to see details.
 auth-agent = SSH agent
 auth-password = Password
 auth-key = Private key
 auth-keyboard = Keyboard interactive
 auth-certificate = Certificate
-footer-connections = [Enter] Connect  [I] Import  [N] New  [E] Edit  [P] Password  [R] Reload  [F9] AI  [?] Help  [Q] Quit
-footer-session = [F10] Helper  [F12] Files  [F9] AI  [Ctrl+T] Focus  [Tab/Left/Right] Panel  [Up/Down] Move  [Enter] Open  [Backspace] Up  [F5] Copy  [Esc] Back  [Ctrl+Q] Quit
-footer-assistant = [Enter] Send  [Ctrl+Y] Run  [Ctrl+N] Skip  [PgUp/PgDn] Scroll  [Esc] Back  [Ctrl+Q] Quit
+footer-connections = [Enter] Connect  [I] Import  [N] New  [E] Edit  [G] Group  [F] Favorite  [C] Encoding  [L] Locale  [D] Dirs  [S] View  [H] Theme  [K] Known hosts  [J] Jump hosts  [P] Password  [R] Reload  [/] Search  [F9] AI  [?] Help  [Q] Quit
+footer-connection-search = [Enter] Connect  [Up/Down] Move  [Esc] Clear
+footer-session = [F10] Helper  [F12] Files  [F9] AI  [F6/F7/F8] Tools/Agent/Auto  [Ctrl+T] Focus  [Tab/Left/Right] Panel  [Up/Down] Move  [Enter] Open  [Backspace] Up  [F5] Copy  [F6/F7] Pause/Resume (files)  [Esc] Back  [Ctrl+Q] Quit
+footer-assistant = [Enter] Send  [Ctrl+Y] Run  [Ctrl+A] Run all  [Ctrl+N] Skip  [Alt+Up/Down] History  [PgUp/PgDn] Scroll  [Esc] Back  [Ctrl+Q] Quit
 footer-quick-add = [Enter] Save  [Esc] Cancel
-footer-edit = [Enter] Save  [Esc] Cancel
+footer-group = [Enter] Move  [Esc] Cancel
+footer-locale = [Enter] Save  [Esc] Cancel
+footer-delete = [Enter/Y] Delete  [Esc/N] Cancel
+footer-tool-approval = [Enter/Y] Approve  [A] Approve all  [N] Deny  [D] Deny all  [Esc] Decide later
+footer-rename = [Enter] Rename  [Esc] Cancel
+footer-mkdir = [Enter] Create  [Esc] Cancel
+footer-bookmarks = [Enter] Go  [A] Add current  [D] Delete  [Up/Down] Move  [Esc] Close
+footer-known-hosts = [D/Del] Remove  [Up/Down] Move  [Esc] Close
+footer-jump-hosts = [A] Add  [D/Del] Remove  [Shift+Up/Down] Reorder  [Esc] Close
+footer-jump-hosts-input = [Enter] Add  [Esc] Cancel
+footer-host-key-changed = [Shift+R] Replace saved key and connect  [Esc/N] Abort
+footer-unknown-host-key = [Enter/Y] Trust and save  [Esc/N] Reject
+footer-edit = [Enter] Edit  [←/→] Auth  [Ctrl+S] Save  [Esc] Cancel
 footer-password = [Enter] Confirm  [Tab] Switch  [F2] Save  [Esc] Cancel
 footer-help = [Esc] Close
 footer-ai-settings = [Enter] Edit  [Space] Toggle  [Ctrl+S] Save  [Esc] Close
 help-title = Help
-help-connections = Connections: Enter connect, I import, N new, E edit, P password, R reload, F9 AI, ? help, Q quit
-help-session = Session: F10 helper, F12 files, F9 AI, Ctrl+T focus, Tab/Left/Right panel, Up/Down move, Enter open, Backspace up, F5 copy, Esc back (files), Ctrl+Q quit
-help-assistant = Assistant: type question, Enter send, Ctrl+Y run tool, Ctrl+N skip tool, PgUp/PgDn scroll, Esc back
+help-connections = Connections: Enter connect, I import, N new, E edit, G group, C cycle terminal encoding, T cycle transfer backend, F toggle favorite, L remote locale, D default transfer dirs, S list by group or auth method, H cycle theme, K known hosts, J jump hosts, Enter on a header collapses it, P password, R reload, / search, F9 AI, ? help, Q quit
+help-session = Session: F10 helper, F12 files, F9 AI, F6 tools, F7 agent, F8 auto, Shift+PgUp/PgDn terminal scrollback, Ctrl+T focus, Tab/Left/Right panel, Ctrl+U swap panels, = sync navigation, c compare panels, Up/Down move, Enter open, Backspace up, F5 copy, u send to default dir, Shift+S sync directory, Delete remove, r rename, m mkdir, b bookmarks, s sort, / filter, . hidden, F6 pause and F7 resume transfer (in the file panels), Esc back (files), Ctrl+Q quit
+help-assistant = Assistant: type question, Enter send, Ctrl+Y run tool, Ctrl+A run all pending tools, Ctrl+N skip tool, Alt+Up/Down previous prompts (plain Up/Down once the input has text), PgUp/PgDn scroll, Esc back
 help-ai-settings = AI settings: Up/Down select, Enter edit, Space toggle, Ctrl+S save, Esc close
 help-quick-add = New connection: type name|user@host:port, Enter save, Esc cancel
-help-edit = Edit connection: update name or host, Enter save, Esc cancel
+help-edit = Edit connection: name, target, auth method and key paths, Ctrl+S save, Esc cancel
 help-password = Password: enter password, Tab switch, F2 save toggle, Enter confirm, Esc cancel
 prompt-new-connection = New connection
 prompt-new-connection-hint = Format: name|user@host:port (name optional)
 prompt-new-connection-error = Invalid input
 prompt-edit-connection = Edit connection
-prompt-edit-connection-hint = Format: name|user@host:port
+edit-name = Name
+edit-target = Target
+edit-key-path = Private key
+edit-cert-path = Certificate
+prompt-group = Move to group
+prompt-group-hint = Group name (created if missing); leave empty to ungroup
+prompt-locale = Remote locale
+prompt-locale-hint = Sent as LANG and LC_ALL, e.g. en_US.UTF-8; leave empty to use the default
+prompt-locale-error = Locale may only contain letters, digits and _ . @ -
+prompt-transfer-dirs = Default transfer directories
+prompt-transfer-dirs-hint = local|remote, e.g. ~/Downloads|/srv/uploads; leave a side empty to clear it
+prompt-transfer-dirs-error = Use local|remote with absolute paths (~ is allowed on the local side)
+prompt-delete = Delete
+prompt-delete-file = Delete { $name }?
+prompt-delete-dir = Delete directory { $name } and everything inside it (recursive)?
+prompt-rename = Rename
+prompt-rename-hint = New name for { $name }
+prompt-rename-error = Name must not be empty or contain path separators
+prompt-rename-failed = Rename failed: { $error }
+prompt-rename-exists = { $name } already exists
+prompt-mkdir = New directory
+prompt-mkdir-hint = Create in { $path }
+prompt-mkdir-error = Name must not be empty or contain path separators
+prompt-mkdir-exists = { $name } already exists
+prompt-mkdir-failed = Could not create { $name }: { $error }
+prompt-bookmarks = Bookmarks
+prompt-bookmarks-empty = No bookmarks yet. Press A to bookmark the current directory.
+prompt-known-hosts = Known hosts
+prompt-known-hosts-empty = known_hosts has no keys yet.
+prompt-jump-hosts = Jump hosts
+prompt-jump-hosts-empty = No jump hosts; the connection goes directly to the host.
+prompt-jump-hosts-hint = Hop: user@host:port, optionally followed by a private key path (agent otherwise)
+prompt-jump-hosts-error = Invalid hop, expected user@host:port
+prompt-jump-hosts-same = A jump host cannot be the connection's own host
+prompt-bookmark-failed = Could not open { $path }: { $error }
+prompt-host-key-changed = Host key changed
+prompt-host-key-changed-warning = The host key for { $host } is not the one saved in known_hosts. Someone may be intercepting the connection, or the server was reinstalled.
+prompt-host-key-changed-hint = Only replace the saved key if you know why it changed.
+prompt-unknown-host-key = Unknown host key
+prompt-unknown-host-key-text = { $host } is not in known_hosts yet. Compare the fingerprint with the one the server administrator gave you before trusting it.
+group-ungrouped = Ungrouped
+list-by-group = by group
+list-by-auth = by auth method
+status-list-mode = Connection list: { $mode }
+status-theme = Theme: { $name }
+status-theme-unknown = Unknown theme { $name }, using the kawaii palette
+status-keymap-error = Keybindings not loaded: { $error }
+status-password-auth-warning = { $count } connections still use password auth; consider keys (S lists them by auth method)
+prompt-edit-connection-hint = Target format: user@host:port. Key paths may start with ~/
 prompt-edit-connection-error = Invalid input
+prompt-edit-key-missing = Key file not found: { $path }
 prompt-password-title = Password
 prompt-password-connect = Enter password to connect
 prompt-password-save = Set password for connection
@@ -73,11 +154,75 @@ status-connection-added = Connection added: { $name }
 status-connection-create-error = Failed to add connection
 status-connection-error = Connection operation failed
 status-connection-updated = Connection updated: { $name }
+status-group-assigned = { $name } moved to { $group }
+status-encoding-set = Terminal encoding for { $name }: { $encoding }
+status-favorite-added = { $name } added to favorites
+status-favorite-removed = { $name } removed from favorites
+status-backend-set = File transfers for { $name }: { $backend }
+status-scrollback = Scrollback -{ $lines }
+status-recording = ● REC
+status-recording-failed = Recording not started: { $error }
+status-locale-set = Locale for { $name }: { $locale }
+status-transfer-dirs-set = Transfer dirs for { $name }: { $dirs }
+status-transfer-dir-missing = No default transfer directory for this side; set one with D on the connection screen
+status-quick-send = Queued copy to { $path }
+status-sync-dir-queued = Syncing { $count } entries (newer files on the other side are kept)
+status-sync-dir-nothing = Nothing to sync, the other panel is up to date
+status-deleted = Deleted { $name }
+status-delete-error = Could not delete { $name }: { $error }
+status-renamed = Renamed { $from } to { $to }
+status-mkdir = Created directory { $name }
+status-bookmark-added = Bookmarked { $name }
+status-bookmark-error = Could not load bookmarks: { $error }
+status-known-host-removed = Removed the saved host key for { $host }
+status-known-hosts-error = Could not read known_hosts: { $error }
+status-jump-hosts-saved = { $name }: { $count ->
+    [0] no jump hosts
+    [one] 1 jump host
+   *[other] { $count } jump hosts
+}
+sort-dirs-first = dirs first
+sort-name-asc = name ↑
+sort-name-desc = name ↓
+sort-size-desc = size ↓
+panel-filter = filter: { $filter }
+panel-sync = sync
+panel-compare = compare
+panel-sftp-unavailable = SFTP is not available on this server, so remote files cannot be listed. Transfers fall back to SCP when the connection's backend is auto or scp (T on the connection screen). Error: { $error }
+status-hidden-shown = Showing hidden files
+status-panels-swapped = Panels swapped
+status-sync-on = Panel sync on: navigation is mirrored in the other panel
+status-sync-off = Panel sync off
+status-compare-on = Compare: { $local } only local, { $remote } only remote, { $differ } differ (yellow: one side only, red: differs)
+status-compare-off = Compare off
+status-sync-missing = Not mirrored, { $path } does not exist in the other panel
+status-hidden-hidden = Hiding hidden files
+status-group-cleared = { $name } removed from its group
 status-connection-failed = Connection failed: { $error }
+status-host-key-rejected = Host key for { $host } was rejected; check known_hosts
+status-host-key-aborted = Connection to { $host } aborted; the saved host key was kept
+status-host-key-replaced = Replaced the saved host key for { $host }
+status-host-key-trusted = Trusted the host key for { $host }
+status-host-key-replace-failed = Could not replace the host key for { $host }: { $error }
+status-connection-timeout = Connection timed out
+status-connection-network = Network error: { $error }
+status-sftp-unavailable = Remote browsing unavailable: the server refused SFTP ({ $error })
+status-shell-unavailable = No shell on this server, file access only: { $error }
+status-env-sent = Environment sent with SetEnv: { $names }
+status-env-exported = The server refused SetEnv; exported in the shell instead: { $names }
+status-env-refused = The server refused SetEnv for { $names }
+status-shell-closed = The shell has exited; files are still available
+status-session-lost-error = Connection lost: { $error }
+status-session-timeout = The server stopped responding to keepalives
+status-reconnected = Reconnected
+status-reconnect-failed = Could not reconnect: { $error }
 status-connection-connecting = Connecting to { $target }
 status-password-saved = Password saved
 status-agent-missing = SSH agent not available. Enter password.
 status-transfer = Copy
+status-transfer-paused = Copy paused
+status-transfer-cancelled = Copy cancelled
+transfer-none-active = No active transfer
 status-sftp-on = SFTP on
 status-sftp-off = SFTP off
 status-ai-on = AI on
@@ -88,8 +233,21 @@ status-ai-auto = Auto
 status-ai-agent = Agent
 status-ai-tools = Tools
 status-ai-stream = Stream
+status-ai-stream-fallback = No stream
+status-ai-pending = pending: { $count }
+status-ai-steps = steps: { $remaining }/{ $max }
+notify-bell-title = Terminal bell
+notify-bell-body = { $target } rang the bell
+notify-transfer-done-title = Transfer complete
+notify-transfer-done-body = { $files } files, { $bytes }
+notify-transfer-failed-title = Transfer failed
+notify-transfer-failed-body = Transfer failed: { $error }
+notify-transfer-summary = { $copied } copied, { $skipped } skipped
+notify-transfer-summary-failed = { $copied } copied, { $skipped } skipped, { $failed } failed ({ $path })
 ai-title = Helper
 ai-input-title = Ask
+ai-input-answer = Answer
+ai-question-pending = AI is waiting for your answer
 ai-placeholder = Type a question about commands...
 ai-disabled = AI helper disabled in config.
 ai-hint = Ask me how to run a command, fix an error, or plan steps. Chat commands: /help.
@@ -100,17 +258,27 @@ ai-role-system = System
 ai-role-error = Error
 ai-role-tool = Tool
 ai-error = AI error: { $error }
+ai-stream-fallback = Streaming failed, retrying without it: { $error }
 ai-config-provider = Unknown AI provider
 ai-config-endpoint = AI endpoint is empty
 ai-config-model = AI model is empty
 ai-config-token = AI token is missing
 ai-tool-pending = Pending tool: { $name }
+ai-tool-batch = Pending tools ({ $count }), Ctrl+A to run all:
+ai-tool-batch-approved = Running { $count } tools as a batch; a failure stops the rest
+ai-tool-batch-halted = Batch stopped after a failure, { $count } tools cancelled
 ai-tool-running = Tool running
 ai-tool-skipped = Tool skipped
+ai-tool-blocked = Tool { $name } is not in the allowed tools list
+ai-tool-denied-all = Denied { $count } pending tools
+tool-approval-title = Run tool?
+tool-approval-args = Arguments:
+tool-approval-destructive = This tool can delete or overwrite data or run commands
+tool-approval-queued = { $count } more waiting
 ai-tool-approved = Tool approved
 ai-tool-error = Tool error: { $error }
 ai-settings-title = AI settings
-ai-settings-hint = Up/Down select, Enter edit, Space toggle, Ctrl+S save, Esc close
+ai-settings-hint = Up/Down select, Enter edit, Space toggle, Left/Right pick a tool in Allowed tools, Ctrl+S save, Esc close
 ai-settings-enabled = Enabled
 ai-settings-provider = Provider
 ai-settings-endpoint = Endpoint
@@ -122,17 +290,23 @@ ai-settings-history = History
 ai-settings-timeout = Timeout ms
 ai-settings-streaming = Streaming
 ai-settings-agent = Agent mode
+ai-settings-continue = Continue after tool
 ai-settings-auto = Auto approve
 ai-settings-max-steps = Max steps
 ai-settings-tools = Tools
+ai-settings-native-tools = Native tool calls
+ai-settings-allowed-tools = Allowed tools
+ai-settings-allowed-tools-value = ‹ { $tool }: { $state } › ({ $allowed }/{ $total } allowed)
 ai-settings-system = System prompt
 ai-settings-saved = AI settings saved
 ai-settings-error-number = Invalid number
+ai-settings-error-no-tools = At least one tool must stay allowed; turn Tools off instead
 ai-settings-provider-ollama = Ollama
 ai-settings-provider-openai = OpenAI
 ai-settings-provider-openrouter = OpenRouter
 ai-settings-provider-anthropic = Anthropic
-ai-command-help = Commands: /ai on|off, /agent on|off, /tools on|off, /auto on|off, /steps N (0=unlimited), /run, /skip, /clear, /status, /help
+ai-settings-provider-gemini = Gemini
+ai-command-help = Commands: /ai on|off, /agent on|off, /tools on|off, /auto on|off, /steps N (0=unlimited), /run, /run all, /skip, /clear, /status, /help
 ai-command-unknown = Unknown command. /help
 ai-command-missing = Missing parameter.
 ai-command-invalid = Invalid parameter.
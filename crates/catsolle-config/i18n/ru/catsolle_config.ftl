@@ -2,6 +2,7 @@ app-name = catsolle
 welcome = С возвращением, ня~
 connect = Подключиться
 connections = Соединения
+connections-search = поиск: { $query }
 status-connected = Подключено
 status-disconnected = Отключено
 menu-new = Новое
@@ -15,6 +16,7 @@ value-off = выкл
 error-connection-failed = Ошибка подключения
 prompt = (=^･ω･^=)>
 session = Сессия
+session-reconnecting = переподключение { $attempt }/{ $max }…
 empty-connections-title = Подключений пока нет
 empty-connections-body = Импортируйте из SSH config или создайте новое подключение.
 empty-connections-actions = Нажмите I для импорта, N для создания, R для обновления, ? для помощи.
@@ -25,36 +27,112 @@ label-user = Пользователь
 label-auth = Аутентификация
 label-last = Последнее подключение
 label-tags = Теги
+label-encoding = Кодировка
+label-backend = Передача файлов
+label-locale = Локаль
+label-transfer-dirs = Каталоги передачи
+label-key-type = Тип ключа
+label-fingerprint = Отпечаток
+label-favorite = избранное
+label-locale-default = { $locale } (по умолчанию)
 label-none = нет
 label-never = никогда
+time-just-now = только что
+time-minutes-ago = { $count } мин назад
+time-hours-ago = { $count } ч назад
+time-yesterday = вчера
+time-days-ago = { $count } дн назад
+time-weeks-ago = { $count } нед назад
 empty-details = Выберите подключение, чтобы увидеть детали.
 auth-agent = SSH агент
 auth-password = Пароль
 auth-key = Приватный ключ
 auth-keyboard = Интерактивная клавиатура
 auth-certificate = Сертификат
-footer-connections = [Enter] Подключиться  [I] Импорт  [N] Новое  [E] Редактировать  [P] Пароль  [R] Обновить  [F9] ИИ  [?] Помощь  [Q] Выход
-footer-session = [F10] Хелпер  [F12] Файлы  [F9] ИИ  [Ctrl+T] Фокус  [Tab/Left/Right] Панель  [Up/Down] Перемещение  [Enter] Открыть  [Backspace] Вверх  [F5] Копировать  [Esc] Назад  [Ctrl+Q] Выход
-footer-assistant = [Enter] Отправить  [Ctrl+Y] Запуск  [Ctrl+N] Пропуск  [PgUp/PgDn] Скролл  [Esc] Назад  [Ctrl+Q] Выход
+footer-connections = [Enter] Подключиться  [I] Импорт  [N] Новое  [E] Редактировать  [G] Группа  [F] Избранное  [C] Кодировка  [L] Локаль  [D] Каталоги  [S] Вид  [H] Тема  [K] Хосты  [J] Бастионы  [P] Пароль  [R] Обновить  [/] Поиск  [F9] ИИ  [?] Помощь  [Q] Выход
+footer-connection-search = [Enter] Подключиться  [Up/Down] Выбор  [Esc] Сбросить
+footer-session = [F10] Хелпер  [F12] Файлы  [F9] ИИ  [F6/F7/F8] Инструменты/Агент/Авто  [Ctrl+T] Фокус  [Tab/Left/Right] Панель  [Up/Down] Перемещение  [Enter] Открыть  [Backspace] Вверх  [F5] Копировать  [F6/F7] Пауза/Продолжить (файлы)  [Esc] Назад  [Ctrl+Q] Выход
+footer-assistant = [Enter] Отправить  [Ctrl+Y] Запуск  [Ctrl+A] Запустить все  [Ctrl+N] Пропуск  [Alt+Up/Down] История  [PgUp/PgDn] Скролл  [Esc] Назад  [Ctrl+Q] Выход
 footer-quick-add = [Enter] Сохранить  [Esc] Отмена
-footer-edit = [Enter] Сохранить  [Esc] Отмена
+footer-edit = [Enter] Редактировать  [←/→] Аутентификация  [Ctrl+S] Сохранить  [Esc] Отмена
+footer-group = [Enter] Переместить  [Esc] Отмена
+footer-locale = [Enter] Сохранить  [Esc] Отмена
+footer-delete = [Enter/Y] Удалить  [Esc/N] Отмена
+footer-tool-approval = [Enter/Y] Разрешить  [A] Разрешить все  [N] Отклонить  [D] Отклонить все  [Esc] Решить позже
+footer-rename = [Enter] Переименовать  [Esc] Отмена
+footer-mkdir = [Enter] Создать  [Esc] Отмена
+footer-bookmarks = [Enter] Перейти  [A] Добавить текущий  [D] Удалить  [Up/Down] Выбор  [Esc] Закрыть
+footer-known-hosts = [D/Del] Удалить  [Up/Down] Выбор  [Esc] Закрыть
+footer-jump-hosts = [A] Добавить  [D/Del] Удалить  [Shift+Up/Down] Порядок  [Esc] Закрыть
+footer-jump-hosts-input = [Enter] Добавить  [Esc] Отмена
+footer-host-key-changed = [Shift+R] Заменить ключ и подключиться  [Esc/N] Отменить
+footer-unknown-host-key = [Enter/Y] Доверять и сохранить  [Esc/N] Отклонить
 footer-password = [Enter] Подтвердить  [Tab] Переключить  [F2] Сохранение  [Esc] Отмена
 footer-help = [Esc] Закрыть
 footer-ai-settings = [Enter] Редактировать  [Space] Переключить  [Ctrl+S] Сохранить  [Esc] Закрыть
 help-title = Помощь
-help-connections = Соединения: Enter подключить, I импорт, N новое, E редактировать, P пароль, R обновить, F9 ИИ, ? помощь, Q выход
-help-session = Сессия: F10 хелпер, F12 файлы, F9 ИИ, Ctrl+T фокус, Tab/Left/Right панель, Up/Down перемещение, Enter открыть, Backspace вверх, F5 копировать, Esc назад (файлы), Ctrl+Q выход
-help-assistant = Хелпер: введите вопрос, Enter отправить, Ctrl+Y запуск, Ctrl+N пропуск, PgUp/PgDn скролл, Esc назад
+help-connections = Соединения: Enter подключить, I импорт, N новое, E редактировать, G группа, C сменить кодировку терминала, T сменить протокол передачи, F избранное, L локаль на сервере, D каталоги передачи, S список по группам или способу входа, H сменить тему, K известные хосты, J промежуточные хосты, Enter на заголовке сворачивает его, P пароль, R обновить, / поиск, F9 ИИ, ? помощь, Q выход
+help-session = Сессия: F10 хелпер, F12 файлы, F9 ИИ, F6 инструменты, F7 агент, F8 авто, Shift+PgUp/PgDn прокрутка терминала, Ctrl+T фокус, Tab/Left/Right панель, Ctrl+U поменять панели, = синхронная навигация, c сравнить панели, Up/Down перемещение, Enter открыть, Backspace вверх, F5 копировать, u отправить в каталог по умолчанию, Shift+S синхронизировать каталог, Delete удалить, r переименовать, m создать каталог, b закладки, s сортировка, / фильтр, . скрытые, F6 пауза и F7 продолжение копирования (в файловых панелях), Esc назад (файлы), Ctrl+Q выход
+help-assistant = Хелпер: введите вопрос, Enter отправить, Ctrl+Y запуск, Ctrl+A запустить все, Ctrl+N пропуск, Alt+Up/Down предыдущие запросы (просто Up/Down, если в поле есть текст), PgUp/PgDn скролл, Esc назад
 help-ai-settings = Настройки ИИ: Up/Down выбор, Enter редактировать, Space переключить, Ctrl+S сохранить, Esc закрыть
 help-quick-add = Новое подключение: введите name|user@host:port, Enter сохранить, Esc отмена
-help-edit = Редактирование: измените имя или хост, Enter сохранить, Esc отмена
+help-edit = Редактирование: имя, адрес, способ аутентификации и пути к ключам, Ctrl+S сохранить, Esc отмена
 help-password = Пароль: введите пароль, Tab поле, F2 сохранить, Enter подтвердить, Esc отмена
 prompt-new-connection = Новое подключение
 prompt-new-connection-hint = Формат: name|user@host:port (имя опционально)
 prompt-new-connection-error = Некорректный ввод
+prompt-group = Переместить в группу
+prompt-group-hint = Имя группы (создаётся при необходимости); пусто — убрать из группы
+prompt-locale = Локаль на сервере
+prompt-locale-hint = Передаётся как LANG и LC_ALL, например ru_RU.UTF-8; оставьте пустым для значения по умолчанию
+prompt-locale-error = Локаль может содержать только буквы, цифры и _ . @ -
+prompt-transfer-dirs = Каталоги передачи по умолчанию
+prompt-transfer-dirs-hint = локальный|удалённый, например ~/Downloads|/srv/uploads; пустая сторона сбрасывается
+prompt-transfer-dirs-error = Формат локальный|удалённый, пути должны быть абсолютными (слева можно ~)
+prompt-delete = Удаление
+prompt-delete-file = Удалить { $name }?
+prompt-delete-dir = Удалить каталог { $name } со всем содержимым (рекурсивно)?
+prompt-rename = Переименование
+prompt-rename-hint = Новое имя для { $name }
+prompt-rename-error = Имя не должно быть пустым или содержать разделители пути
+prompt-rename-failed = Не удалось переименовать: { $error }
+prompt-rename-exists = { $name } уже существует
+prompt-mkdir = Новый каталог
+prompt-mkdir-hint = Создать в { $path }
+prompt-mkdir-error = Имя не должно быть пустым или содержать разделители пути
+prompt-mkdir-exists = { $name } уже существует
+prompt-mkdir-failed = Не удалось создать { $name }: { $error }
+prompt-bookmarks = Закладки
+prompt-bookmarks-empty = Закладок пока нет. Нажмите A, чтобы добавить текущий каталог.
+prompt-known-hosts = Известные хосты
+prompt-known-hosts-empty = В known_hosts пока нет ключей.
+prompt-jump-hosts = Промежуточные хосты
+prompt-jump-hosts-empty = Промежуточных хостов нет, подключение идёт напрямую.
+prompt-jump-hosts-hint = Хоп: user@host:port, можно добавить путь к приватному ключу (иначе агент)
+prompt-jump-hosts-error = Некорректный хоп, ожидается user@host:port
+prompt-jump-hosts-same = Промежуточный хост не может совпадать с хостом подключения
+prompt-bookmark-failed = Не удалось открыть { $path }: { $error }
+prompt-host-key-changed = Ключ хоста изменился
+prompt-host-key-changed-warning = Ключ хоста { $host } не совпадает с сохранённым в known_hosts. Возможно, соединение перехватывают, или сервер переустановили.
+prompt-host-key-changed-hint = Заменяйте сохранённый ключ, только если знаете, почему он изменился.
+prompt-unknown-host-key = Незнакомый ключ хоста
+prompt-unknown-host-key-text = Хоста { $host } ещё нет в known_hosts. Сверьте отпечаток с тем, что дал администратор сервера, прежде чем доверять ему.
+group-ungrouped = Без группы
+list-by-group = по группам
+list-by-auth = по способу входа
+status-list-mode = Список соединений: { $mode }
+status-theme = Тема: { $name }
+status-theme-unknown = Неизвестная тема { $name }, используется палитра kawaii
+status-keymap-error = Привязки клавиш не загружены: { $error }
+status-password-auth-warning = Соединений с входом по паролю: { $count }; лучше перейти на ключи (S покажет их по способу входа)
 prompt-edit-connection = Редактировать подключение
-prompt-edit-connection-hint = Формат: name|user@host:port
+edit-name = Имя
+edit-target = Адрес
+edit-key-path = Приватный ключ
+edit-cert-path = Сертификат
+prompt-edit-connection-hint = Формат адреса: user@host:port. Пути к ключам могут начинаться с ~/
 prompt-edit-connection-error = Некорректный ввод
+prompt-edit-key-missing = Файл ключа не найден: { $path }
 prompt-password-title = Пароль
 prompt-password-connect = Введите пароль для подключения
 prompt-password-save = Установить пароль для подключения
@@ -73,11 +151,71 @@ status-connection-added = Добавлено подключение: { $name }
 status-connection-create-error = Не удалось добавить подключение
 status-connection-error = Ошибка операции с подключением
 status-connection-updated = Подключение обновлено: { $name }
+status-group-assigned = { $name } перемещено в { $group }
+status-encoding-set = Кодировка терминала для { $name }: { $encoding }
+status-favorite-added = { $name } добавлено в избранное
+status-favorite-removed = { $name } убрано из избранного
+status-backend-set = Передача файлов для { $name }: { $backend }
+status-scrollback = Прокрутка -{ $lines }
+status-recording = ● ЗАПИСЬ
+status-recording-failed = Запись не начата: { $error }
+status-locale-set = Локаль для { $name }: { $locale }
+status-transfer-dirs-set = Каталоги передачи для { $name }: { $dirs }
+status-transfer-dir-missing = Для этой стороны нет каталога по умолчанию; задайте его клавишей D на экране соединений
+status-quick-send = Копирование в { $path } поставлено в очередь
+status-sync-dir-queued = Синхронизация записей: { $count } (более новые файлы на другой стороне сохраняются)
+status-sync-dir-nothing = Синхронизировать нечего, другая панель актуальна
+status-deleted = Удалено: { $name }
+status-delete-error = Не удалось удалить { $name }: { $error }
+status-renamed = { $from } переименован в { $to }
+status-mkdir = Создан каталог { $name }
+status-bookmark-added = Закладка { $name } добавлена
+status-bookmark-error = Не удалось загрузить закладки: { $error }
+status-known-host-removed = Сохранённый ключ хоста { $host } удалён
+status-known-hosts-error = Не удалось прочитать known_hosts: { $error }
+status-jump-hosts-saved = { $name }: промежуточных хостов: { $count }
+sort-dirs-first = сначала каталоги
+sort-name-asc = имя ↑
+sort-name-desc = имя ↓
+sort-size-desc = размер ↓
+panel-filter = фильтр: { $filter }
+panel-sync = синхр.
+panel-compare = сравнение
+panel-sftp-unavailable = SFTP на этом сервере недоступен, поэтому список удалённых файлов не показать. Передача файлов переходит на SCP, если протокол соединения auto или scp (T на экране соединений). Ошибка: { $error }
+status-hidden-shown = Скрытые файлы показаны
+status-panels-swapped = Панели поменялись местами
+status-sync-on = Синхронизация панелей включена: переходы повторяются в другой панели
+status-sync-off = Синхронизация панелей выключена
+status-compare-on = Сравнение: только локально { $local }, только на сервере { $remote }, различаются { $differ } (жёлтый: только с одной стороны, красный: различаются)
+status-compare-off = Сравнение выключено
+status-sync-missing = Не повторено, { $path } нет в другой панели
+status-hidden-hidden = Скрытые файлы скрыты
+status-group-cleared = { $name } убрано из группы
 status-connection-failed = Ошибка подключения: { $error }
+status-host-key-rejected = Ключ хоста { $host } отклонён; проверьте known_hosts
+status-host-key-aborted = Подключение к { $host } отменено; сохранённый ключ хоста оставлен
+status-host-key-replaced = Сохранённый ключ хоста { $host } заменён
+status-host-key-trusted = Ключ хоста { $host } сохранён как доверенный
+status-host-key-replace-failed = Не удалось заменить ключ хоста { $host }: { $error }
+status-connection-timeout = Превышено время ожидания подключения
+status-connection-network = Сетевая ошибка: { $error }
+status-sftp-unavailable = Просмотр файлов на сервере недоступен: сервер отклонил SFTP ({ $error })
+status-shell-unavailable = Оболочка на сервере недоступна, доступны только файлы: { $error }
+status-env-sent = Переменные окружения переданы через SetEnv: { $names }
+status-env-exported = Сервер отклонил SetEnv, переменные заданы через export в оболочке: { $names }
+status-env-refused = Сервер отклонил SetEnv для { $names }
+status-shell-closed = Оболочка завершилась; файлы по-прежнему доступны
+status-session-lost-error = Соединение потеряно: { $error }
+status-session-timeout = Сервер перестал отвечать на keepalive
+status-reconnected = Соединение восстановлено
+status-reconnect-failed = Не удалось переподключиться: { $error }
 status-connection-connecting = Подключение к { $target }
 status-password-saved = Пароль сохранен
 status-agent-missing = SSH агент недоступен. Введите пароль.
 status-transfer = Копирование
+status-transfer-paused = Копирование на паузе
+status-transfer-cancelled = Копирование отменено
+transfer-none-active = Нет активного копирования
 status-sftp-on = SFTP включен
 status-sftp-off = SFTP скрыт
 status-ai-on = ИИ включен
@@ -87,8 +225,21 @@ status-ai-disabled = ИИ отключен
 status-ai-auto = Авто
 status-ai-agent = Агент
 status-ai-tools = Инструменты
+status-ai-pending = в очереди: { $count }
+status-ai-steps = шаги: { $remaining }/{ $max }
+status-ai-stream-fallback = Без стрима
 status-ai-stream = Стрим
+notify-bell-title = Сигнал терминала
+notify-bell-body = { $target }: сигнал в терминале
+notify-transfer-done-title = Передача завершена
+notify-transfer-done-body = Файлов: { $files }, { $bytes }
+notify-transfer-failed-title = Ошибка передачи
+notify-transfer-failed-body = Ошибка передачи: { $error }
+notify-transfer-summary = Скопировано: { $copied }, пропущено: { $skipped }
+notify-transfer-summary-failed = Скопировано: { $copied }, пропущено: { $skipped }, с ошибкой: { $failed } ({ $path })
 ai-title = Хелпер
+ai-input-answer = Ответ
+ai-question-pending = ИИ ждет вашего ответа
 ai-input-title = Вопрос
 ai-placeholder = Спросите про команды и действия...
 ai-disabled = ИИ отключен в конфиге.
@@ -99,18 +250,28 @@ ai-role-assistant = ИИ
 ai-role-system = Система
 ai-role-error = Ошибка
 ai-role-tool = Инструмент
+ai-stream-fallback = Стриминг не работает, повтор без него: { $error }
 ai-error = Ошибка ИИ: { $error }
 ai-config-provider = Неизвестный провайдер ИИ
 ai-config-endpoint = Не задан endpoint ИИ
 ai-config-model = Не задана модель ИИ
 ai-config-token = Не задан токен ИИ
 ai-tool-pending = Ожидается инструмент: { $name }
+ai-tool-batch = Ожидают инструменты ({ $count }), Ctrl+A запустить все:
+ai-tool-batch-approved = Запуск { $count } инструментов пакетом; ошибка остановит остальные
+ai-tool-batch-halted = Пакет остановлен после ошибки, отменено инструментов: { $count }
 ai-tool-running = Инструмент выполняется
 ai-tool-skipped = Инструмент пропущен
+ai-tool-blocked = Инструмент { $name } не входит в список разрешённых
+ai-tool-denied-all = Отклонено ожидающих инструментов: { $count }
+tool-approval-title = Запустить инструмент?
+tool-approval-args = Аргументы:
+tool-approval-destructive = Этот инструмент может удалить или перезаписать данные либо выполнить команды
+tool-approval-queued = Ещё в очереди: { $count }
 ai-tool-approved = Инструмент подтвержден
 ai-tool-error = Ошибка инструмента: { $error }
 ai-settings-title = Настройки ИИ
-ai-settings-hint = Up/Down выбор, Enter редактировать, Space переключить, Ctrl+S сохранить, Esc закрыть
+ai-settings-hint = Up/Down выбор, Enter редактировать, Space переключить, Left/Right выбор инструмента в разрешённых, Ctrl+S сохранить, Esc закрыть
 ai-settings-enabled = Включить
 ai-settings-provider = Провайдер
 ai-settings-endpoint = Endpoint
@@ -122,17 +283,23 @@ ai-settings-history = История
 ai-settings-timeout = Таймаут мс
 ai-settings-streaming = Стриминг
 ai-settings-agent = Агент
+ai-settings-continue = Продолжать после инструмента
 ai-settings-auto = Авторежим
 ai-settings-max-steps = Макс шагов
 ai-settings-tools = Инструменты
+ai-settings-native-tools = Нативный вызов инструментов
+ai-settings-allowed-tools = Разрешённые инструменты
+ai-settings-allowed-tools-value = ‹ { $tool }: { $state } › (разрешено { $allowed } из { $total })
 ai-settings-system = Системный промпт
 ai-settings-saved = Настройки ИИ сохранены
 ai-settings-error-number = Некорректное число
+ai-settings-error-no-tools = Хотя бы один инструмент должен остаться разрешённым; иначе выключите инструменты
 ai-settings-provider-ollama = Ollama
 ai-settings-provider-openai = OpenAI
 ai-settings-provider-openrouter = OpenRouter
 ai-settings-provider-anthropic = Anthropic
-ai-command-help = Команды: /ai on|off, /agent on|off, /tools on|off, /auto on|off, /steps N (0=безлимит), /run, /skip, /clear, /status, /help
+ai-settings-provider-gemini = Gemini
+ai-command-help = Команды: /ai on|off, /agent on|off, /tools on|off, /auto on|off, /steps N (0=безлимит), /run, /run all, /skip, /clear, /status, /help
 ai-command-unknown = Неизвестная команда. /help
 ai-command-missing = Не задан параметр.
 ai-command-invalid = Некорректный параметр.
@@ -11,6 +11,7 @@ pub struct AppPaths {
     pub config_file: PathBuf,
     pub db_file: PathBuf,
     pub recordings_dir: PathBuf,
+    pub conversations_file: PathBuf,
 }
 
 impl AppPaths {
@@ -24,6 +25,7 @@ impl AppPaths {
         let config_file = config_dir.join("config.toml");
         let db_file = data_dir.join("catsolle.db");
         let recordings_dir = data_dir.join("recordings");
+        let conversations_file = data_dir.join("conversations.json");
         Ok(Self {
             config_dir,
             data_dir,
@@ -32,6 +34,7 @@ impl AppPaths {
             config_file,
             db_file,
             recordings_dir,
+            conversations_file,
         })
     }
 
@@ -1,6 +1,7 @@
 use crate::paths::AppPaths;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -10,6 +11,10 @@ pub enum ConfigError {
     Io(#[from] std::io::Error),
     #[error("toml error: {0}")]
     Toml(#[from] toml::de::Error),
+    #[error("unknown setting: {0}")]
+    UnknownSetting(String),
+    #[error("invalid value for {key}: {message}")]
+    InvalidValue { key: String, message: String },
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
@@ -63,6 +68,47 @@ impl AppConfigLayer {
             cfg.recording.apply(layer);
         }
     }
+
+    /// Builds a layer holding a single dotted-key setting such as `ai.model`. The value is
+    /// read as a TOML literal when it is one of the right type, and as a plain string
+    /// otherwise, so `ai.max_tokens=1024` and `ai.model=gpt-4o` both work unquoted.
+    pub fn from_setting(key: &str, value: &str) -> Result<Self, ConfigError> {
+        let unknown = || ConfigError::UnknownSetting(key.to_string());
+        let (section, field) = key.split_once('.').ok_or_else(unknown)?;
+        if section.is_empty() || field.is_empty() || field.contains('.') {
+            return Err(unknown());
+        }
+        let layer_with = |value: toml::Value| {
+            let mut fields = toml::Table::new();
+            fields.insert(field.to_string(), value);
+            let mut root = toml::Table::new();
+            root.insert(section.to_string(), toml::Value::Table(fields));
+            toml::Value::Table(root).try_into::<AppConfigLayer>()
+        };
+        let literal = toml::from_str::<toml::Table>(&format!("v = {value}"))
+            .ok()
+            .and_then(|mut table| table.remove("v"));
+        let invalid = |err: toml::de::Error| ConfigError::InvalidValue {
+            key: key.to_string(),
+            message: err.message().to_string(),
+        };
+        let as_string = || layer_with(toml::Value::String(value.to_string()));
+        let layer = match literal.map(layer_with) {
+            Some(Ok(layer)) => layer,
+            Some(Err(err)) => as_string().map_err(|_| invalid(err))?,
+            None => as_string().map_err(invalid)?,
+        };
+        // Layers ignore keys they do not know, so check the field actually landed.
+        let known = toml::Value::try_from(&layer)
+            .ok()
+            .and_then(|root| root.get(section)?.get(field).cloned())
+            .is_some();
+        if known {
+            Ok(layer)
+        } else {
+            Err(unknown())
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -109,7 +155,12 @@ pub struct UiConfig {
     pub layout: String,
     pub scrollback_lines: usize,
     pub show_hidden_files: bool,
+    /// Shows the remote panel on the left and the local one on the right.
+    pub swap_panels: bool,
     pub keybindings: Option<PathBuf>,
+    pub bell_notifications: bool,
+    pub datetime_format: String,
+    pub timezone: String,
 }
 
 impl Default for UiConfig {
@@ -119,7 +170,11 @@ impl Default for UiConfig {
             layout: "split-horizontal".to_string(),
             scrollback_lines: 20000,
             show_hidden_files: false,
+            swap_panels: false,
             keybindings: None,
+            bell_notifications: true,
+            datetime_format: "%Y-%m-%d %H:%M".to_string(),
+            timezone: "local".to_string(),
         }
     }
 }
@@ -130,7 +185,11 @@ pub struct UiConfigLayer {
     pub layout: Option<String>,
     pub scrollback_lines: Option<usize>,
     pub show_hidden_files: Option<bool>,
+    pub swap_panels: Option<bool>,
     pub keybindings: Option<PathBuf>,
+    pub bell_notifications: Option<bool>,
+    pub datetime_format: Option<String>,
+    pub timezone: Option<String>,
 }
 
 impl UiConfig {
@@ -147,9 +206,21 @@ impl UiConfig {
         if let Some(v) = layer.show_hidden_files {
             self.show_hidden_files = v;
         }
+        if let Some(v) = layer.swap_panels {
+            self.swap_panels = v;
+        }
         if layer.keybindings.is_some() {
             self.keybindings = layer.keybindings;
         }
+        if let Some(v) = layer.bell_notifications {
+            self.bell_notifications = v;
+        }
+        if let Some(v) = layer.datetime_format {
+            self.datetime_format = v;
+        }
+        if let Some(v) = layer.timezone {
+            self.timezone = v;
+        }
     }
 }
 
@@ -164,12 +235,27 @@ pub struct AiConfig {
     pub max_tokens: u32,
     pub history_max: usize,
     pub timeout_ms: u64,
+    /// Extra attempts after a rate limit, server error, timeout or dropped connection.
+    pub max_retries: u32,
     pub system_prompt: String,
     pub streaming: bool,
     pub agent_enabled: bool,
     pub auto_mode: bool,
+    /// Sends tool output back to the model after a successful call even outside agent
+    /// mode, within the `max_steps` budget.
+    pub continue_after_tool: bool,
     pub max_steps: u32,
     pub tools_enabled: bool,
+    /// Sends tools as a function-calling `tools` array instead of describing the `@tool`
+    /// convention in the prompt. Only the openai and openai-compatible providers support it.
+    pub native_tools: bool,
+    /// Tool names the model may call; empty allows every tool.
+    pub allowed_tools: Vec<String>,
+    pub streaming_overrides: BTreeMap<String, bool>,
+    pub context_target: bool,
+    pub context_local_path: bool,
+    pub context_remote_path: bool,
+    pub context_tools: bool,
 }
 
 impl Default for AiConfig {
@@ -184,12 +270,21 @@ impl Default for AiConfig {
             max_tokens: 512,
             history_max: 40,
             timeout_ms: 20000,
+            max_retries: 2,
             system_prompt: "Answer in Russian. Be concise.".to_string(),
             streaming: true,
             agent_enabled: true,
             auto_mode: false,
+            continue_after_tool: false,
             max_steps: 6,
             tools_enabled: true,
+            native_tools: false,
+            allowed_tools: Vec::new(),
+            streaming_overrides: BTreeMap::new(),
+            context_target: true,
+            context_local_path: true,
+            context_remote_path: true,
+            context_tools: true,
         }
     }
 }
@@ -205,12 +300,21 @@ pub struct AiConfigLayer {
     pub max_tokens: Option<u32>,
     pub history_max: Option<usize>,
     pub timeout_ms: Option<u64>,
+    pub max_retries: Option<u32>,
     pub system_prompt: Option<String>,
     pub streaming: Option<bool>,
     pub agent_enabled: Option<bool>,
     pub auto_mode: Option<bool>,
+    pub continue_after_tool: Option<bool>,
     pub max_steps: Option<u32>,
     pub tools_enabled: Option<bool>,
+    pub native_tools: Option<bool>,
+    pub allowed_tools: Option<Vec<String>>,
+    pub streaming_overrides: Option<BTreeMap<String, bool>>,
+    pub context_target: Option<bool>,
+    pub context_local_path: Option<bool>,
+    pub context_remote_path: Option<bool>,
+    pub context_tools: Option<bool>,
 }
 
 impl AiConfig {
@@ -242,6 +346,9 @@ impl AiConfig {
         if let Some(v) = layer.timeout_ms {
             self.timeout_ms = v;
         }
+        if let Some(v) = layer.max_retries {
+            self.max_retries = v;
+        }
         if let Some(v) = layer.system_prompt {
             self.system_prompt = v;
         }
@@ -254,12 +361,57 @@ impl AiConfig {
         if let Some(v) = layer.auto_mode {
             self.auto_mode = v;
         }
+        if let Some(v) = layer.continue_after_tool {
+            self.continue_after_tool = v;
+        }
         if let Some(v) = layer.max_steps {
             self.max_steps = v;
         }
         if let Some(v) = layer.tools_enabled {
             self.tools_enabled = v;
         }
+        if let Some(v) = layer.native_tools {
+            self.native_tools = v;
+        }
+        if let Some(v) = layer.allowed_tools {
+            self.allowed_tools = v;
+        }
+        if let Some(v) = layer.streaming_overrides {
+            self.streaming_overrides.extend(v);
+        }
+        if let Some(v) = layer.context_target {
+            self.context_target = v;
+        }
+        if let Some(v) = layer.context_local_path {
+            self.context_local_path = v;
+        }
+        if let Some(v) = layer.context_remote_path {
+            self.context_remote_path = v;
+        }
+        if let Some(v) = layer.context_tools {
+            self.context_tools = v;
+        }
+    }
+
+    /// Streaming mode for the active provider: a `streaming_overrides` entry wins over `streaming`.
+    pub fn streaming_for_provider(&self) -> bool {
+        let provider = self.provider.trim().to_lowercase();
+        self.streaming_overrides
+            .get(&provider)
+            .copied()
+            .unwrap_or(self.streaming)
+    }
+
+    pub fn tool_allowed(&self, name: &str) -> bool {
+        self.allowed_tools.is_empty() || self.allowed_tools.iter().any(|tool| tool == name)
+    }
+
+    /// Whether tool calls go through native function calling for the active provider.
+    pub fn native_tools_active(&self) -> bool {
+        let provider = self.provider.trim().to_lowercase();
+        self.tools_enabled
+            && self.native_tools
+            && matches!(provider.as_str(), "openai" | "openai-compatible")
     }
 }
 
@@ -272,9 +424,21 @@ pub struct SshDefaults {
     pub reconnect_backoff_ms: u64,
     pub agent_forwarding: bool,
     pub x11_forwarding: bool,
+    /// Sent as `LANG`/`LC_ALL` unless a connection sets its own; empty disables it.
+    pub locale: String,
     pub preferred_kex: Vec<String>,
     pub preferred_ciphers: Vec<String>,
     pub preferred_macs: Vec<String>,
+    /// Warns at startup about connections that still authenticate with a password.
+    pub warn_password_auth: bool,
+    /// Asks in the TUI before trusting a host key seen for the first time.
+    pub confirm_new_host_keys: bool,
+    /// `shell` types a connection's startup commands into the interactive shell; `exec`
+    /// runs each one on its own channel right after connecting.
+    pub startup_commands_mode: String,
+    /// Exports the variables a server refuses through SetEnv in the shell instead. Off by
+    /// default: the `export` line is typed into the shell for everyone to see.
+    pub env_export_fallback: bool,
 }
 
 impl Default for SshDefaults {
@@ -287,9 +451,14 @@ impl Default for SshDefaults {
             reconnect_backoff_ms: 1000,
             agent_forwarding: false,
             x11_forwarding: false,
+            locale: "en_US.UTF-8".to_string(),
             preferred_kex: Vec::new(),
             preferred_ciphers: Vec::new(),
             preferred_macs: Vec::new(),
+            warn_password_auth: false,
+            confirm_new_host_keys: false,
+            startup_commands_mode: "shell".to_string(),
+            env_export_fallback: false,
         }
     }
 }
@@ -303,9 +472,14 @@ pub struct SshDefaultsLayer {
     pub reconnect_backoff_ms: Option<u64>,
     pub agent_forwarding: Option<bool>,
     pub x11_forwarding: Option<bool>,
+    pub locale: Option<String>,
     pub preferred_kex: Option<Vec<String>>,
     pub preferred_ciphers: Option<Vec<String>>,
     pub preferred_macs: Option<Vec<String>>,
+    pub warn_password_auth: Option<bool>,
+    pub confirm_new_host_keys: Option<bool>,
+    pub startup_commands_mode: Option<String>,
+    pub env_export_fallback: Option<bool>,
 }
 
 impl SshDefaults {
@@ -331,6 +505,9 @@ impl SshDefaults {
         if let Some(v) = layer.x11_forwarding {
             self.x11_forwarding = v;
         }
+        if let Some(v) = layer.locale {
+            self.locale = v;
+        }
         if let Some(v) = layer.preferred_kex {
             self.preferred_kex = v;
         }
@@ -340,6 +517,26 @@ impl SshDefaults {
         if let Some(v) = layer.preferred_macs {
             self.preferred_macs = v;
         }
+        if let Some(v) = layer.warn_password_auth {
+            self.warn_password_auth = v;
+        }
+        if let Some(v) = layer.confirm_new_host_keys {
+            self.confirm_new_host_keys = v;
+        }
+        if let Some(v) = layer.startup_commands_mode {
+            self.startup_commands_mode = v;
+        }
+        if let Some(v) = layer.env_export_fallback {
+            self.env_export_fallback = v;
+        }
+    }
+
+    /// Whether startup commands go to the interactive shell rather than over exec.
+    pub fn startup_commands_in_shell(&self) -> bool {
+        !self
+            .startup_commands_mode
+            .trim()
+            .eq_ignore_ascii_case("exec")
     }
 }
 
@@ -351,6 +548,9 @@ pub struct TransferConfig {
     pub preserve_times: bool,
     pub verify_checksum: bool,
     pub resume: bool,
+    pub notify_on_finish: bool,
+    pub max_bytes_per_sec: u64,
+    pub continue_on_error: bool,
 }
 
 impl Default for TransferConfig {
@@ -362,6 +562,9 @@ impl Default for TransferConfig {
             preserve_times: true,
             verify_checksum: true,
             resume: true,
+            notify_on_finish: true,
+            max_bytes_per_sec: 0,
+            continue_on_error: false,
         }
     }
 }
@@ -374,6 +577,9 @@ pub struct TransferConfigLayer {
     pub preserve_times: Option<bool>,
     pub verify_checksum: Option<bool>,
     pub resume: Option<bool>,
+    pub notify_on_finish: Option<bool>,
+    pub max_bytes_per_sec: Option<u64>,
+    pub continue_on_error: Option<bool>,
 }
 
 impl TransferConfig {
@@ -396,6 +602,15 @@ impl TransferConfig {
         if let Some(v) = layer.resume {
             self.resume = v;
         }
+        if let Some(v) = layer.notify_on_finish {
+            self.notify_on_finish = v;
+        }
+        if let Some(v) = layer.max_bytes_per_sec {
+            self.max_bytes_per_sec = v;
+        }
+        if let Some(v) = layer.continue_on_error {
+            self.continue_on_error = v;
+        }
     }
 }
 
@@ -574,6 +789,17 @@ impl ConfigManager {
         Ok(())
     }
 
+    /// Applies dotted-key settings on top of the user config file and saves it. Project
+    /// configs are left out so their overrides are not copied into the user file.
+    pub fn set_values(&self, settings: &[(String, String)]) -> Result<AppConfig> {
+        let mut cfg = self.load(None, None)?;
+        for (key, value) in settings {
+            AppConfigLayer::from_setting(key, value)?.apply_to(&mut cfg);
+        }
+        self.save_config(&cfg)?;
+        Ok(cfg)
+    }
+
     pub fn save_config(&self, cfg: &AppConfig) -> Result<()> {
         if let Some(parent) = self.paths.config_file.parent() {
             fs::create_dir_all(parent)?;
@@ -607,9 +833,18 @@ mod tests {
                 reconnect_backoff_ms: Some(10),
                 agent_forwarding: Some(true),
                 x11_forwarding: Some(true),
+                locale: Some("C.UTF-8".to_string()),
                 preferred_kex: Some(vec!["kex".to_string()]),
                 preferred_ciphers: Some(vec!["cipher".to_string()]),
                 preferred_macs: Some(vec!["mac".to_string()]),
+                warn_password_auth: Some(true),
+                confirm_new_host_keys: Some(true),
+                startup_commands_mode: Some("exec".to_string()),
+                env_export_fallback: Some(true),
+            }),
+            ui: Some(UiConfigLayer {
+                swap_panels: Some(true),
+                ..Default::default()
             }),
             ..Default::default()
         };
@@ -620,6 +855,91 @@ mod tests {
         assert_eq!(cfg.ssh.port, 2222);
         assert_eq!(cfg.ssh.connect_timeout_ms, 1234);
         assert_eq!(cfg.ssh.keepalive_interval_secs, 7);
+        assert_eq!(cfg.ssh.locale, "C.UTF-8");
+        assert!(!cfg.ssh.reconnect);
+        assert!(cfg.ssh.warn_password_auth);
+        assert!(cfg.ssh.confirm_new_host_keys);
+        assert!(!cfg.ssh.startup_commands_in_shell());
+        assert!(cfg.ssh.env_export_fallback);
+        assert!(cfg.ui.swap_panels);
+    }
+
+    #[test]
+    fn dotted_settings_build_layers() {
+        let mut cfg = AppConfig::default();
+        for (key, value) in [
+            ("ai.provider", "openai"),
+            ("ai.model", "gpt-4o"),
+            ("ai.max_tokens", "1024"),
+            ("ai.temperature", "1"),
+            ("ssh.locale", "123"),
+            ("ssh.reconnect", "false"),
+            ("transfer.overwrite_mode", "\"skip\""),
+        ] {
+            AppConfigLayer::from_setting(key, value)
+                .unwrap()
+                .apply_to(&mut cfg);
+        }
+        assert_eq!(cfg.ai.provider, "openai");
+        assert_eq!(cfg.ai.model, "gpt-4o");
+        assert_eq!(cfg.ai.max_tokens, 1024);
+        assert_eq!(cfg.ai.temperature, 1.0);
+        assert_eq!(cfg.ssh.locale, "123");
         assert!(!cfg.ssh.reconnect);
+        assert_eq!(cfg.transfer.overwrite_mode, "skip");
+
+        for key in ["ai.nope", "nope.model", "ai", "ai.model.extra"] {
+            assert!(matches!(
+                AppConfigLayer::from_setting(key, "x"),
+                Err(ConfigError::UnknownSetting(_))
+            ));
+        }
+        assert!(matches!(
+            AppConfigLayer::from_setting("ssh.port", "70000"),
+            Err(ConfigError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            AppConfigLayer::from_setting("transfer.resume", "maybe"),
+            Err(ConfigError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn streaming_override_per_provider() {
+        let mut cfg = AiConfig::default();
+        assert!(cfg.streaming_for_provider());
+        cfg.provider = "OpenRouter".to_string();
+        cfg.streaming_overrides
+            .insert("openrouter".to_string(), false);
+        assert!(!cfg.streaming_for_provider());
+        cfg.provider = "ollama".to_string();
+        assert!(cfg.streaming_for_provider());
+    }
+
+    #[test]
+    fn allowlist_limits_tools() {
+        let mut cfg = AiConfig::default();
+        assert!(cfg.tool_allowed("local.exec"));
+        cfg.apply(AiConfigLayer {
+            allowed_tools: Some(vec!["local.list".to_string(), "remote.read".to_string()]),
+            max_retries: Some(0),
+            ..Default::default()
+        });
+        assert_eq!(cfg.max_retries, 0);
+        assert!(cfg.tool_allowed("remote.read"));
+        assert!(!cfg.tool_allowed("local.exec"));
+    }
+
+    #[test]
+    fn native_tools_only_for_openai_providers() {
+        let mut cfg = AiConfig {
+            native_tools: true,
+            ..AiConfig::default()
+        };
+        assert!(!cfg.native_tools_active());
+        cfg.provider = "OpenAI-Compatible".to_string();
+        assert!(cfg.native_tools_active());
+        cfg.tools_enabled = false;
+        assert!(!cfg.native_tools_active());
     }
 }
@@ -0,0 +1,129 @@
+use crate::error::CoreError;
+use crate::remote_fs::RemoteFs;
+use sha2::Digest;
+use std::path::Path;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+const HASH_BUFFER_BYTES: usize = 128 * 1024;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Sha1,
+    Md5,
+}
+
+impl HashAlgorithm {
+    pub fn as_key(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Md5 => "md5",
+        }
+    }
+
+    pub fn from_key(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().replace('-', "").as_str() {
+            "sha256" => Some(HashAlgorithm::Sha256),
+            "sha1" => Some(HashAlgorithm::Sha1),
+            "md5" => Some(HashAlgorithm::Md5),
+            _ => None,
+        }
+    }
+}
+
+enum Hasher {
+    Sha256(sha2::Sha256),
+    Sha1(sha1::Sha1),
+    Md5(md5::Context),
+}
+
+impl Hasher {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+            HashAlgorithm::Sha1 => Hasher::Sha1(sha1::Sha1::new()),
+            HashAlgorithm::Md5 => Hasher::Md5(md5::Context::new()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sha256(h) => h.update(data),
+            Hasher::Sha1(h) => h.update(data),
+            Hasher::Md5(h) => h.consume(data),
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        match self {
+            Hasher::Sha256(h) => h.finalize().to_vec(),
+            Hasher::Sha1(h) => h.finalize().to_vec(),
+            Hasher::Md5(h) => h.compute().to_vec(),
+        }
+    }
+}
+
+/// Hashes a reader chunk by chunk so large files never sit in memory whole.
+pub async fn hash_reader<R>(mut reader: R, algorithm: HashAlgorithm) -> std::io::Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut hasher = Hasher::new(algorithm);
+    let mut buf = vec![0u8; HASH_BUFFER_BYTES];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finish())
+}
+
+pub async fn hash_local_file(path: &Path, algorithm: HashAlgorithm) -> Result<Vec<u8>, CoreError> {
+    let file = tokio::fs::File::open(path).await?;
+    Ok(hash_reader(file, algorithm).await?)
+}
+
+pub async fn hash_remote_file(
+    remote: &dyn RemoteFs,
+    path: &str,
+    algorithm: HashAlgorithm,
+) -> Result<Vec<u8>, CoreError> {
+    let remote = remote.open_read_at(path, 0).await?;
+    hash_reader(remote, algorithm)
+        .await
+        .map_err(|e| CoreError::Ssh(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn hashes_with_each_algorithm() {
+        let cases = [
+            (
+                HashAlgorithm::Sha256,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                HashAlgorithm::Sha1,
+                "a9993e364706816aba3e25717850c26c9cd0d89d",
+            ),
+            (HashAlgorithm::Md5, "900150983cd24fb0d6963f7d28e17f72"),
+        ];
+        for (algorithm, expected) in cases {
+            let digest = hash_reader(&b"abc"[..], algorithm).await.unwrap();
+            assert_eq!(hex::encode(digest), expected);
+            assert_eq!(HashAlgorithm::from_key(algorithm.as_key()), Some(algorithm));
+        }
+        assert_eq!(
+            HashAlgorithm::from_key("SHA-256"),
+            Some(HashAlgorithm::Sha256)
+        );
+        assert_eq!(HashAlgorithm::from_key("crc32"), None);
+    }
+}
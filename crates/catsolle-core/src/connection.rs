@@ -1,5 +1,8 @@
+use crate::encoding::TerminalEncoding;
 use crate::error::CoreError;
+use crate::transfer::TransferBackend;
 use chrono::{DateTime, Utc};
+use directories::UserDirs;
 use rusqlite::{params, Connection as SqlConnection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
@@ -21,6 +24,16 @@ pub struct Connection {
     pub proxy: Option<ProxyConfig>,
     pub startup_commands: Vec<String>,
     pub env_vars: Vec<EnvVar>,
+    #[serde(default)]
+    pub terminal_encoding: TerminalEncoding,
+    #[serde(default)]
+    pub transfer_backend: TransferBackend,
+    /// Local directory quick transfers from the server land in.
+    #[serde(default)]
+    pub transfer_local_dir: Option<String>,
+    /// Remote directory quick transfers to the server land in.
+    #[serde(default)]
+    pub transfer_remote_dir: Option<String>,
     pub group_id: Option<Uuid>,
     pub tags: Vec<ConnectionTag>,
     pub color: Option<String>,
@@ -32,6 +45,98 @@ pub struct Connection {
     pub is_favorite: bool,
 }
 
+/// Variables that carry the remote locale; a connection overriding either one opts out of
+/// the configured default.
+pub const LOCALE_ENV_KEYS: [&str; 2] = ["LANG", "LC_ALL"];
+
+impl Connection {
+    /// A connection named after `host` that logs in through the agent, with every other
+    /// setting at its default.
+    pub fn new(host: String, port: u16, username: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            name: host.clone(),
+            host,
+            port,
+            username,
+            auth_method: AuthMethod::Agent,
+            jump_hosts: Vec::new(),
+            proxy: None,
+            startup_commands: Vec::new(),
+            env_vars: Vec::new(),
+            terminal_encoding: TerminalEncoding::default(),
+            transfer_backend: TransferBackend::default(),
+            transfer_local_dir: None,
+            transfer_remote_dir: None,
+            group_id: None,
+            tags: Vec::new(),
+            color: None,
+            icon: None,
+            notes: None,
+            created_at: now,
+            updated_at: now,
+            last_connected_at: None,
+            is_favorite: false,
+        }
+    }
+
+    /// Locale this connection overrides via its environment, if any.
+    pub fn locale(&self) -> Option<&str> {
+        LOCALE_ENV_KEYS.iter().rev().find_map(|key| {
+            self.env_vars
+                .iter()
+                .find(|v| v.key == *key)
+                .map(|v| v.value.as_str())
+        })
+    }
+
+    /// Replaces the locale override; `None` falls back to the configured default.
+    pub fn set_locale(&mut self, locale: Option<&str>) {
+        self.env_vars
+            .retain(|v| !LOCALE_ENV_KEYS.contains(&v.key.as_str()));
+        if let Some(locale) = locale {
+            self.env_vars
+                .extend(LOCALE_ENV_KEYS.iter().map(|key| EnvVar {
+                    key: key.to_string(),
+                    value: locale.to_string(),
+                }));
+        }
+    }
+
+    /// Environment sent with the shell request: the connection's own variables, plus
+    /// `default_locale` when the connection has no override and the default is not empty.
+    pub fn session_env(&self, default_locale: &str) -> Vec<(String, String)> {
+        let mut env: Vec<(String, String)> = self
+            .env_vars
+            .iter()
+            .map(|v| (v.key.clone(), v.value.clone()))
+            .collect();
+        if self.locale().is_none() && !default_locale.is_empty() {
+            env.extend(
+                LOCALE_ENV_KEYS
+                    .iter()
+                    .map(|key| (key.to_string(), default_locale.to_string())),
+            );
+        }
+        env
+    }
+
+    /// Whether any hop or the proxy authenticates with a keychain secret, which does not
+    /// travel with an export.
+    pub fn uses_stored_secrets(&self) -> bool {
+        self.auth_method.secret_ref().is_some()
+            || self
+                .jump_hosts
+                .iter()
+                .any(|hop| hop.auth_method.secret_ref().is_some())
+            || self
+                .proxy
+                .as_ref()
+                .is_some_and(|proxy| proxy.password_ref.is_some())
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EnvVar {
     pub key: String,
@@ -51,6 +156,18 @@ pub struct ConnectionGroup {
     pub sort_order: i64,
 }
 
+/// A saved directory. Local bookmarks are shared by all connections; remote ones belong to
+/// `connection_id`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub id: Uuid,
+    pub connection_id: Option<ConnectionId>,
+    pub path: String,
+    pub name: String,
+    pub is_local: bool,
+    pub created_at: DateTime<Utc>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum AuthMethod {
     Password {
@@ -92,6 +209,21 @@ pub enum ProxyType {
     HttpConnect,
 }
 
+const EXPORT_VERSION: u32 = 1;
+const EXPORT_NOTE: &str = "Passwords and passphrases are exported as keychain references \
+only; re-enter them after importing on another machine.";
+
+/// Layout of `ConnectionStore::export_json`.
+#[derive(Serialize, Deserialize)]
+struct ConnectionExport {
+    version: u32,
+    #[serde(default)]
+    note: String,
+    #[serde(default)]
+    groups: Vec<ConnectionGroup>,
+    connections: Vec<Connection>,
+}
+
 #[derive(Clone, Debug)]
 pub struct ConnectionStore {
     db_path: PathBuf,
@@ -159,6 +291,33 @@ impl ConnectionStore {
             "#,
         )
         .map_err(|e| CoreError::Database(e.to_string()))?;
+        Self::ensure_column(&conn, "connections", "terminal_encoding", "TEXT")?;
+        Self::ensure_column(&conn, "connections", "transfer_backend", "TEXT")?;
+        Self::ensure_column(&conn, "connections", "transfer_local_dir", "TEXT")?;
+        Self::ensure_column(&conn, "connections", "transfer_remote_dir", "TEXT")?;
+        Ok(())
+    }
+
+    /// Adds a column missing from databases created by older versions.
+    fn ensure_column(
+        conn: &SqlConnection,
+        table: &str,
+        column: &str,
+        decl: &str,
+    ) -> Result<(), CoreError> {
+        let exists = conn
+            .prepare(&format!("PRAGMA table_info({table})"))
+            .and_then(|mut stmt| {
+                let names = stmt.query_map([], |row| row.get::<_, String>("name"))?;
+                names.collect::<Result<Vec<_>, _>>()
+            })
+            .map_err(|e| CoreError::Database(e.to_string()))?
+            .iter()
+            .any(|name| name == column);
+        if !exists {
+            conn.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl}"))
+                .map_err(|e| CoreError::Database(e.to_string()))?;
+        }
         Ok(())
     }
 
@@ -169,8 +328,9 @@ impl ConnectionStore {
             INSERT INTO connections (
                 id, name, host, port, username, auth_method, auth_data, jump_hosts, proxy,
                 startup_commands, env_vars, group_id, tags, color, icon, notes,
-                created_at, updated_at, last_connected_at, is_favorite
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)
+                created_at, updated_at, last_connected_at, is_favorite, terminal_encoding,
+                transfer_backend, transfer_local_dir, transfer_remote_dir
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24)
             "#,
             params![
                 conn.id.to_string(),
@@ -198,6 +358,10 @@ impl ConnectionStore {
                 conn.updated_at.to_rfc3339(),
                 conn.last_connected_at.map(|v| v.to_rfc3339()),
                 if conn.is_favorite { 1 } else { 0 },
+                conn.terminal_encoding.as_key(),
+                conn.transfer_backend.as_key(),
+                conn.transfer_local_dir,
+                conn.transfer_remote_dir,
             ],
         )
         .map_err(|e| CoreError::Database(e.to_string()))?;
@@ -226,7 +390,11 @@ impl ConnectionStore {
                 notes = ?16,
                 updated_at = ?17,
                 last_connected_at = ?18,
-                is_favorite = ?19
+                is_favorite = ?19,
+                terminal_encoding = ?20,
+                transfer_backend = ?21,
+                transfer_local_dir = ?22,
+                transfer_remote_dir = ?23
             WHERE id = ?1
             "#,
             params![
@@ -257,6 +425,10 @@ impl ConnectionStore {
                 conn.updated_at.to_rfc3339(),
                 conn.last_connected_at.map(|v| v.to_rfc3339()),
                 if conn.is_favorite { 1 } else { 0 },
+                conn.terminal_encoding.as_key(),
+                conn.transfer_backend.as_key(),
+                conn.transfer_local_dir,
+                conn.transfer_remote_dir,
             ],
         )
         .map_err(|e| CoreError::Database(e.to_string()))?;
@@ -270,6 +442,11 @@ impl ConnectionStore {
             params![id.to_string()],
         )
         .map_err(|e| CoreError::Database(e.to_string()))?;
+        db.execute(
+            "DELETE FROM bookmarks WHERE connection_id = ?1",
+            params![id.to_string()],
+        )
+        .map_err(|e| CoreError::Database(e.to_string()))?;
         Ok(())
     }
 
@@ -285,10 +462,29 @@ impl ConnectionStore {
         row.ok_or(CoreError::NotFound)
     }
 
+    pub fn set_favorite(&self, id: ConnectionId, favorite: bool) -> Result<(), CoreError> {
+        let db = self.open()?;
+        let updated = db
+            .execute(
+                "UPDATE connections SET is_favorite = ?2, updated_at = ?3 WHERE id = ?1",
+                params![
+                    id.to_string(),
+                    if favorite { 1 } else { 0 },
+                    Utc::now().to_rfc3339()
+                ],
+            )
+            .map_err(|e| CoreError::Database(e.to_string()))?;
+        if updated == 0 {
+            return Err(CoreError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Favorites first, then by name.
     pub fn list_connections(&self) -> Result<Vec<Connection>, CoreError> {
         let db = self.open()?;
         let mut stmt = db
-            .prepare("SELECT * FROM connections ORDER BY name ASC")
+            .prepare("SELECT * FROM connections ORDER BY is_favorite DESC, name ASC")
             .map_err(|e| CoreError::Database(e.to_string()))?;
         let rows = stmt
             .query_map([], Self::row_to_connection)
@@ -315,20 +511,232 @@ impl ConnectionStore {
         Ok(out)
     }
 
+    pub fn list_groups(&self) -> Result<Vec<ConnectionGroup>, CoreError> {
+        let db = self.open()?;
+        let mut stmt = db
+            .prepare("SELECT * FROM connection_groups ORDER BY sort_order ASC, name ASC")
+            .map_err(|e| CoreError::Database(e.to_string()))?;
+        let rows = stmt
+            .query_map([], Self::row_to_group)
+            .map_err(|e| CoreError::Database(e.to_string()))?;
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row.map_err(|e| CoreError::Database(e.to_string()))?);
+        }
+        Ok(out)
+    }
+
+    pub fn create_group(&self, name: &str) -> Result<ConnectionGroup, CoreError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(CoreError::Invalid("group name is empty".to_string()));
+        }
+        let db = self.open()?;
+        let sort_order: i64 = db
+            .query_row(
+                "SELECT COALESCE(MAX(sort_order), -1) + 1 FROM connection_groups",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| CoreError::Database(e.to_string()))?;
+        let group = ConnectionGroup {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            parent_id: None,
+            sort_order,
+        };
+        db.execute(
+            "INSERT INTO connection_groups (id, name, parent_id, sort_order) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                group.id.to_string(),
+                group.name,
+                group.parent_id.map(|id| id.to_string()),
+                group.sort_order
+            ],
+        )
+        .map_err(|e| CoreError::Database(e.to_string()))?;
+        Ok(group)
+    }
+
+    /// Moves a connection into `group_id`, or out of any group when it is `None`.
+    pub fn assign_group(
+        &self,
+        conn_id: ConnectionId,
+        group_id: Option<Uuid>,
+    ) -> Result<(), CoreError> {
+        let db = self.open()?;
+        let updated = db
+            .execute(
+                "UPDATE connections SET group_id = ?2, updated_at = ?3 WHERE id = ?1",
+                params![
+                    conn_id.to_string(),
+                    group_id.map(|id| id.to_string()),
+                    Utc::now().to_rfc3339()
+                ],
+            )
+            .map_err(|e| CoreError::Database(e.to_string()))?;
+        if updated == 0 {
+            return Err(CoreError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Saves `path` as a bookmark, or returns the one already saved for it. Local bookmarks
+    /// ignore `conn_id`; remote ones need it.
+    pub fn add_bookmark(
+        &self,
+        conn_id: Option<ConnectionId>,
+        path: &str,
+        name: &str,
+        is_local: bool,
+    ) -> Result<Bookmark, CoreError> {
+        let conn_id = if is_local { None } else { conn_id };
+        if !is_local && conn_id.is_none() {
+            return Err(CoreError::Invalid(
+                "remote bookmarks need a connection".to_string(),
+            ));
+        }
+        if let Some(existing) = self
+            .list_bookmarks(conn_id)?
+            .into_iter()
+            .find(|b| b.is_local == is_local && b.path == path)
+        {
+            return Ok(existing);
+        }
+        let bookmark = Bookmark {
+            id: Uuid::new_v4(),
+            connection_id: conn_id,
+            path: path.to_string(),
+            name: name.to_string(),
+            is_local,
+            created_at: Utc::now(),
+        };
+        let db = self.open()?;
+        db.execute(
+            "INSERT INTO bookmarks (id, connection_id, path, name, is_local, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                bookmark.id.to_string(),
+                bookmark.connection_id.map(|id| id.to_string()),
+                bookmark.path,
+                bookmark.name,
+                if bookmark.is_local { 1 } else { 0 },
+                bookmark.created_at.to_rfc3339()
+            ],
+        )
+        .map_err(|e| CoreError::Database(e.to_string()))?;
+        Ok(bookmark)
+    }
+
+    /// Local bookmarks plus the remote ones of `conn_id`, local first and by name.
+    pub fn list_bookmarks(
+        &self,
+        conn_id: Option<ConnectionId>,
+    ) -> Result<Vec<Bookmark>, CoreError> {
+        let db = self.open()?;
+        let mut stmt = db
+            .prepare(
+                "SELECT * FROM bookmarks WHERE is_local = 1 OR connection_id = ?1 \
+                 ORDER BY is_local DESC, name ASC",
+            )
+            .map_err(|e| CoreError::Database(e.to_string()))?;
+        let rows = stmt
+            .query_map(
+                params![conn_id.map(|id| id.to_string())],
+                Self::row_to_bookmark,
+            )
+            .map_err(|e| CoreError::Database(e.to_string()))?;
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row.map_err(|e| CoreError::Database(e.to_string()))?);
+        }
+        Ok(out)
+    }
+
+    pub fn delete_bookmark(&self, id: Uuid) -> Result<(), CoreError> {
+        let db = self.open()?;
+        let deleted = db
+            .execute(
+                "DELETE FROM bookmarks WHERE id = ?1",
+                params![id.to_string()],
+            )
+            .map_err(|e| CoreError::Database(e.to_string()))?;
+        if deleted == 0 {
+            return Err(CoreError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Serializes every connection and group. Auth secrets stay in the keychain; only their
+    /// references are written.
+    pub fn export_json(&self) -> Result<String, CoreError> {
+        let export = ConnectionExport {
+            version: EXPORT_VERSION,
+            note: EXPORT_NOTE.to_string(),
+            groups: self.list_groups()?,
+            connections: self.list_connections()?,
+        };
+        serde_json::to_string_pretty(&export).map_err(|e| CoreError::Invalid(e.to_string()))
+    }
+
+    /// Inserts groups and connections from `export_json` output, skipping IDs that already
+    /// exist. Returns the connections that were added.
+    pub fn import_json(&self, json: &str) -> Result<Vec<Connection>, CoreError> {
+        let export: ConnectionExport =
+            serde_json::from_str(json).map_err(|e| CoreError::Invalid(e.to_string()))?;
+        if export.version != EXPORT_VERSION {
+            return Err(CoreError::Invalid(format!(
+                "unsupported export version {}",
+                export.version
+            )));
+        }
+        let known_groups: HashSet<Uuid> = self.list_groups()?.iter().map(|g| g.id).collect();
+        let db = self.open()?;
+        for group in export
+            .groups
+            .iter()
+            .filter(|g| !known_groups.contains(&g.id))
+        {
+            db.execute(
+                "INSERT INTO connection_groups (id, name, parent_id, sort_order) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    group.id.to_string(),
+                    group.name,
+                    group.parent_id.map(|id| id.to_string()),
+                    group.sort_order
+                ],
+            )
+            .map_err(|e| CoreError::Database(e.to_string()))?;
+        }
+        let known: HashSet<ConnectionId> = self.list_connections()?.iter().map(|c| c.id).collect();
+        let mut imported = Vec::new();
+        for conn in export.connections {
+            if known.contains(&conn.id) {
+                continue;
+            }
+            self.create_connection(&conn)?;
+            imported.push(conn);
+        }
+        Ok(imported)
+    }
+
+    /// Imports the `Host` blocks of an OpenSSH client config. `ProxyJump` hops and simple
+    /// `ssh -W`/`nc` proxy commands become jump hosts; other proxy commands are left in the
+    /// connection notes to be set up by hand. Of several `IdentityFile` lines the first key
+    /// found on disk is used.
     pub fn import_from_ssh_config(&self, path: &Path) -> Result<Vec<Connection>, CoreError> {
         let content = fs::read_to_string(path)?;
         let mut entries = Vec::new();
-        let mut current_hosts: Vec<String> = Vec::new();
-        let mut current: HashMap<String, String> = HashMap::new();
+        let blocks = parse_ssh_config_blocks(&content);
         let existing = self.list_connections().unwrap_or_default();
         let mut known = HashSet::new();
         for conn in existing {
             known.insert((conn.host, conn.port, conn.username));
         }
 
-        let mut flush = |hosts: &mut Vec<String>, map: &mut HashMap<String, String>| {
+        for block in &blocks {
+            let (hosts, map) = (&block.hosts, &block.settings);
             if hosts.is_empty() {
-                return;
+                continue;
             }
             let host_name = hosts
                 .iter()
@@ -344,18 +752,30 @@ impl ConnectionStore {
                 .get("port")
                 .and_then(|v| v.parse::<u16>().ok())
                 .unwrap_or(22);
-            let identity = map.get("identityfile").map(PathBuf::from);
-            let auth_method = if let Some(identity) = identity {
-                AuthMethod::Key {
-                    private_key_path: identity,
-                    passphrase_ref: None,
-                }
-            } else {
-                AuthMethod::Agent
+            let auth_method = block.auth_method();
+            let mut notes = None;
+            let jump_hosts = match (map.get("proxyjump"), map.get("proxycommand")) {
+                (Some(jumps), _) if jumps.eq_ignore_ascii_case("none") => Vec::new(),
+                (Some(jumps), _) => jumps
+                    .split(',')
+                    .filter_map(|hop| JumpSpec::parse(hop.trim()))
+                    .map(|spec| spec.resolve(&blocks))
+                    .collect(),
+                (None, Some(command)) if command.eq_ignore_ascii_case("none") => Vec::new(),
+                (None, Some(command)) => match JumpSpec::from_proxy_command(command) {
+                    Some(spec) => vec![spec.resolve(&blocks)],
+                    None => {
+                        notes = Some(format!(
+                            "ProxyCommand was not imported, set up a jump host or proxy by hand: {command}"
+                        ));
+                        Vec::new()
+                    }
+                },
+                (None, None) => Vec::new(),
             };
             let key = (host.clone(), port, username.clone());
             if known.contains(&key) {
-                return;
+                continue;
             }
             known.insert(key);
             let now = Utc::now();
@@ -366,39 +786,25 @@ impl ConnectionStore {
                 port,
                 username,
                 auth_method,
-                jump_hosts: Vec::new(),
+                jump_hosts,
                 proxy: None,
                 startup_commands: Vec::new(),
                 env_vars: Vec::new(),
+                terminal_encoding: TerminalEncoding::default(),
+                transfer_backend: TransferBackend::default(),
+                transfer_local_dir: None,
+                transfer_remote_dir: None,
                 group_id: None,
                 tags: Vec::new(),
                 color: None,
                 icon: None,
-                notes: None,
+                notes,
                 created_at: now,
                 updated_at: now,
                 last_connected_at: None,
                 is_favorite: false,
             });
-        };
-
-        for raw in content.lines() {
-            let line = raw.trim();
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
-            let mut parts = line.split_whitespace();
-            let key = parts.next().unwrap_or("").to_lowercase();
-            let value = parts.collect::<Vec<&str>>().join(" ");
-            if key == "host" {
-                flush(&mut current_hosts, &mut current);
-                current_hosts = value.split_whitespace().map(|v| v.to_string()).collect();
-                current = HashMap::new();
-            } else if !key.is_empty() {
-                current.insert(key, value);
-            }
         }
-        flush(&mut current_hosts, &mut current);
 
         for entry in &entries {
             let _ = self.create_connection(entry);
@@ -468,6 +874,16 @@ impl ConnectionStore {
             proxy,
             startup_commands,
             env_vars,
+            terminal_encoding: row
+                .get::<_, Option<String>>("terminal_encoding")?
+                .and_then(|v| TerminalEncoding::from_key(&v))
+                .unwrap_or_default(),
+            transfer_backend: row
+                .get::<_, Option<String>>("transfer_backend")?
+                .and_then(|v| TransferBackend::from_key(&v))
+                .unwrap_or_default(),
+            transfer_local_dir: row.get("transfer_local_dir")?,
+            transfer_remote_dir: row.get("transfer_remote_dir")?,
             group_id: row
                 .get::<_, Option<String>>("group_id")?
                 .and_then(|v| Uuid::parse_str(&v).ok()),
@@ -500,13 +916,54 @@ impl ConnectionStore {
         })
     }
 
+    fn row_to_group(row: &rusqlite::Row<'_>) -> Result<ConnectionGroup, rusqlite::Error> {
+        let id: String = row.get("id")?;
+        Ok(ConnectionGroup {
+            id: Uuid::parse_str(&id).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    0,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })?,
+            name: row.get("name")?,
+            parent_id: row
+                .get::<_, Option<String>>("parent_id")?
+                .and_then(|v| Uuid::parse_str(&v).ok()),
+            sort_order: row.get("sort_order")?,
+        })
+    }
+
+    fn row_to_bookmark(row: &rusqlite::Row<'_>) -> Result<Bookmark, rusqlite::Error> {
+        let id: String = row.get("id")?;
+        let created_at: String = row.get("created_at")?;
+        Ok(Bookmark {
+            id: Uuid::parse_str(&id).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    0,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })?,
+            connection_id: row
+                .get::<_, Option<String>>("connection_id")?
+                .and_then(|v| Uuid::parse_str(&v).ok()),
+            path: row.get("path")?,
+            name: row.get("name")?,
+            is_local: row.get::<_, i64>("is_local")? == 1,
+            created_at: DateTime::parse_from_rfc3339(&created_at)
+                .map(|v| v.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+        })
+    }
+
     fn open(&self) -> Result<SqlConnection, CoreError> {
         SqlConnection::open(&self.db_path).map_err(|e| CoreError::Database(e.to_string()))
     }
 }
 
 impl AuthMethod {
-    pub fn as_key(&self) -> &str {
+    pub fn as_key(&self) -> &'static str {
         match self {
             AuthMethod::Password { .. } => "password",
             AuthMethod::Key { .. } => "key",
@@ -515,6 +972,216 @@ impl AuthMethod {
             AuthMethod::Certificate { .. } => "certificate",
         }
     }
+
+    /// Keychain reference of the password or key passphrase, if one is stored.
+    pub fn secret_ref(&self) -> Option<&str> {
+        match self {
+            AuthMethod::Password { secret_ref } => Some(secret_ref),
+            AuthMethod::Key { passphrase_ref, .. }
+            | AuthMethod::Certificate { passphrase_ref, .. } => passphrase_ref.as_deref(),
+            AuthMethod::Agent | AuthMethod::KeyboardInteractive => None,
+        }
+    }
+}
+
+/// One `Host` block of an ssh client config.
+#[derive(Debug, Default)]
+struct SshConfigBlock {
+    hosts: Vec<String>,
+    /// Lowercased keywords; like ssh, the first value of a keyword wins.
+    settings: HashMap<String, String>,
+    /// Every `IdentityFile`, in config order.
+    identities: Vec<String>,
+}
+
+impl SshConfigBlock {
+    fn setting(&self, key: &str) -> Option<&String> {
+        self.settings.get(key)
+    }
+
+    /// The first identity that exists on disk. Without one the agent is used, unless
+    /// `IdentitiesOnly` rules it out and the first listed key is kept instead.
+    fn auth_method(&self) -> AuthMethod {
+        let paths: Vec<PathBuf> = self
+            .identities
+            .iter()
+            .map(|path| expand_home(path))
+            .collect();
+        let identities_only = self
+            .setting("identitiesonly")
+            .is_some_and(|v| v.eq_ignore_ascii_case("yes"));
+        let key = match paths.iter().find(|path| path.is_file()) {
+            Some(path) => Some(path.clone()),
+            None if identities_only => paths.first().cloned(),
+            None => None,
+        };
+        match key {
+            Some(private_key_path) => AuthMethod::Key {
+                private_key_path,
+                passphrase_ref: None,
+            },
+            None => AuthMethod::Agent,
+        }
+    }
+}
+
+/// Splits an ssh client config into `Host` blocks.
+fn parse_ssh_config_blocks(content: &str) -> Vec<SshConfigBlock> {
+    let mut blocks = Vec::new();
+    let mut current = SshConfigBlock::default();
+    for raw in content.lines() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let key = parts.next().unwrap_or("").to_lowercase();
+        let value = parts.collect::<Vec<&str>>().join(" ");
+        if key == "host" {
+            blocks.push(std::mem::take(&mut current));
+            current.hosts = value.split_whitespace().map(|v| v.to_string()).collect();
+        } else if value.is_empty() {
+            continue;
+        } else if key == "identityfile" {
+            current.identities.push(value);
+        } else if !key.is_empty() {
+            current.settings.entry(key).or_insert(value);
+        }
+    }
+    blocks.push(current);
+    blocks
+}
+
+/// Resolves a leading `~` or `~/` to the home directory, the way ssh does for identity
+/// files. `~user` and paths without a home directory are returned unchanged.
+pub fn expand_home(path: &str) -> PathBuf {
+    let rest = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => rest.trim_start_matches('/'),
+        _ => return PathBuf::from(path),
+    };
+    match UserDirs::new() {
+        Some(dirs) => dirs.home_dir().join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// A hop as written in `ProxyJump` or a proxy command, before `Host` aliases are resolved.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct JumpSpec {
+    user: Option<String>,
+    host: String,
+    port: Option<u16>,
+    identity: Option<String>,
+}
+
+impl JumpSpec {
+    /// Parses `[ssh://][user@]host[:port]`.
+    fn parse(spec: &str) -> Option<Self> {
+        let spec = spec.strip_prefix("ssh://").unwrap_or(spec);
+        let (user, rest) = match spec.rsplit_once('@') {
+            Some((user, rest)) => (Some(user.to_string()), rest),
+            None => (None, spec),
+        };
+        let (host, port) = if let Some(rest) = rest.strip_prefix('[') {
+            let (host, after) = rest.split_once(']')?;
+            (host, after.strip_prefix(':'))
+        } else {
+            match rest.split_once(':') {
+                Some((host, port)) if !port.contains(':') => (host, Some(port)),
+                _ => (rest, None),
+            }
+        };
+        let port = match port {
+            Some(port) => Some(port.parse().ok()?),
+            None => None,
+        };
+        if host.is_empty() || user.as_deref() == Some("") {
+            return None;
+        }
+        Some(Self {
+            user,
+            host: host.to_string(),
+            port,
+            identity: None,
+        })
+    }
+
+    /// Recognizes `ssh [options] jump -W %h:%p` and `ssh [options] jump nc %h %p`.
+    fn from_proxy_command(command: &str) -> Option<Self> {
+        let mut words = command.split_whitespace();
+        let program = words.next()?;
+        if program != "ssh" && !program.ends_with("/ssh") {
+            return None;
+        }
+        let mut options = Self::default();
+        let mut forwards = false;
+        let mut destination = None;
+        let mut remote_command = Vec::new();
+        while let Some(word) = words.next() {
+            if destination.is_some() {
+                remote_command.push(word);
+                continue;
+            }
+            match word {
+                "-W" => forwards = matches!(words.next()?, "%h:%p" | "[%h]:%p"),
+                "-p" => options.port = Some(words.next()?.parse().ok()?),
+                "-l" => options.user = Some(words.next()?.to_string()),
+                "-i" => options.identity = Some(words.next()?.to_string()),
+                // Flags without an argument that do not change where the hop goes.
+                flag if flag.starts_with('-') => {
+                    if !flag[1..].chars().all(|c| "46AaCqTxN".contains(c)) {
+                        return None;
+                    }
+                }
+                host => destination = Some(host),
+            }
+        }
+        let netcat = matches!(remote_command.as_slice(), ["nc" | "netcat", "%h", "%p"]);
+        let supported = if forwards {
+            remote_command.is_empty()
+        } else {
+            netcat
+        };
+        if !supported {
+            return None;
+        }
+        let parsed = Self::parse(destination?)?;
+        Some(Self {
+            user: options.user.or(parsed.user),
+            host: parsed.host,
+            port: options.port.or(parsed.port),
+            identity: options.identity,
+        })
+    }
+
+    /// Fills in what the config says about `host` when it is one of its aliases.
+    fn resolve(self, blocks: &[SshConfigBlock]) -> JumpHost {
+        let alias = blocks.iter().find(|block| block.hosts.contains(&self.host));
+        let setting = |key: &str| alias.and_then(|block| block.setting(key)).cloned();
+        let host = setting("hostname").unwrap_or_else(|| self.host.clone());
+        let username = self
+            .user
+            .or_else(|| setting("user"))
+            .unwrap_or_else(whoami::username);
+        let port = self
+            .port
+            .or_else(|| setting("port").and_then(|v| v.parse().ok()))
+            .unwrap_or(22);
+        let auth_method = match (self.identity, alias) {
+            (Some(identity), _) => AuthMethod::Key {
+                private_key_path: expand_home(&identity),
+                passphrase_ref: None,
+            },
+            (None, Some(block)) => block.auth_method(),
+            (None, None) => AuthMethod::Agent,
+        };
+        JumpHost {
+            host,
+            port,
+            username,
+            auth_method,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -534,6 +1201,10 @@ mod tests {
             proxy: None,
             startup_commands: Vec::new(),
             env_vars: Vec::new(),
+            terminal_encoding: TerminalEncoding::default(),
+            transfer_backend: TransferBackend::default(),
+            transfer_local_dir: None,
+            transfer_remote_dir: None,
             group_id: None,
             tags: Vec::new(),
             color: None,
@@ -553,12 +1224,349 @@ mod tests {
         let store = ConnectionStore::new(db);
         store.init().unwrap();
 
-        let conn = sample_connection();
+        let mut conn = sample_connection();
+        conn.terminal_encoding = TerminalEncoding::Koi8R;
+        conn.transfer_backend = TransferBackend::Scp;
+        conn.transfer_remote_dir = Some("/srv/uploads".to_string());
         store.create_connection(&conn).unwrap();
         let loaded = store.get_connection(conn.id).unwrap();
 
         assert_eq!(loaded.name, conn.name);
         assert_eq!(loaded.host, conn.host);
         assert_eq!(loaded.username, conn.username);
+        assert_eq!(loaded.terminal_encoding, TerminalEncoding::Koi8R);
+        assert_eq!(loaded.transfer_backend, TransferBackend::Scp);
+        assert_eq!(loaded.transfer_local_dir, None);
+        assert_eq!(loaded.transfer_remote_dir.as_deref(), Some("/srv/uploads"));
+    }
+
+    #[test]
+    fn older_exports_default_to_utf8() {
+        let mut json = serde_json::to_value(sample_connection()).unwrap();
+        json.as_object_mut().unwrap().remove("terminal_encoding");
+        let conn: Connection = serde_json::from_value(json).unwrap();
+        assert_eq!(conn.terminal_encoding, TerminalEncoding::Utf8);
+    }
+
+    #[test]
+    fn locale_override_replaces_default_env() {
+        let mut conn = sample_connection();
+        conn.env_vars.push(EnvVar {
+            key: "EDITOR".to_string(),
+            value: "vim".to_string(),
+        });
+        let env = conn.session_env("en_US.UTF-8");
+        assert!(env.contains(&("LC_ALL".to_string(), "en_US.UTF-8".to_string())));
+        assert!(env.contains(&("EDITOR".to_string(), "vim".to_string())));
+        assert!(conn.session_env("").iter().all(|(k, _)| k != "LANG"));
+
+        conn.set_locale(Some("ru_RU.KOI8-R"));
+        conn.set_locale(Some("de_DE.UTF-8"));
+        assert_eq!(conn.locale(), Some("de_DE.UTF-8"));
+        let env = conn.session_env("en_US.UTF-8");
+        assert_eq!(env.len(), 3);
+        assert!(env.contains(&("LANG".to_string(), "de_DE.UTF-8".to_string())));
+
+        conn.set_locale(None);
+        assert_eq!(conn.locale(), None);
+        assert_eq!(conn.env_vars.len(), 1);
+    }
+
+    #[test]
+    fn init_adds_columns_to_older_databases() {
+        let dir = TempDir::new().unwrap();
+        let db = dir.path().join("old.db");
+        SqlConnection::open(&db)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE connections (id TEXT PRIMARY KEY, group_id TEXT, is_favorite INTEGER)",
+            )
+            .unwrap();
+        let store = ConnectionStore::new(db.clone());
+        store.init().unwrap();
+        store.init().unwrap();
+
+        let db = SqlConnection::open(&db).unwrap();
+        let mut stmt = db.prepare("PRAGMA table_info(connections)").unwrap();
+        let columns: Vec<String> = stmt
+            .query_map([], |row| row.get("name"))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(columns.iter().any(|c| c == "terminal_encoding"));
+        assert!(columns.iter().any(|c| c == "transfer_backend"));
+        assert!(columns.iter().any(|c| c == "transfer_remote_dir"));
+    }
+
+    #[test]
+    fn create_group_and_assign_connection() {
+        let dir = TempDir::new().unwrap();
+        let store = ConnectionStore::new(dir.path().join("test.db"));
+        store.init().unwrap();
+
+        let conn = sample_connection();
+        store.create_connection(&conn).unwrap();
+        let prod = store.create_group("prod").unwrap();
+        let dev = store.create_group("dev").unwrap();
+        assert!(store.create_group("  ").is_err());
+
+        let groups = store.list_groups().unwrap();
+        let names: Vec<_> = groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["prod", "dev"]);
+        assert!(prod.sort_order < dev.sort_order);
+
+        store.assign_group(conn.id, Some(dev.id)).unwrap();
+        assert_eq!(
+            store.get_connection(conn.id).unwrap().group_id,
+            Some(dev.id)
+        );
+        store.assign_group(conn.id, None).unwrap();
+        assert_eq!(store.get_connection(conn.id).unwrap().group_id, None);
+        assert!(matches!(
+            store.assign_group(Uuid::new_v4(), Some(dev.id)),
+            Err(CoreError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn favorites_are_listed_first() {
+        let dir = TempDir::new().unwrap();
+        let store = ConnectionStore::new(dir.path().join("test.db"));
+        store.init().unwrap();
+        for name in ["alpha", "beta", "gamma"] {
+            let mut conn = sample_connection();
+            conn.name = name.to_string();
+            store.create_connection(&conn).unwrap();
+        }
+        let names = |store: &ConnectionStore| {
+            store
+                .list_connections()
+                .unwrap()
+                .into_iter()
+                .map(|c| c.name)
+                .collect::<Vec<_>>()
+        };
+        let gamma = store.list_connections().unwrap()[2].id;
+        store.set_favorite(gamma, true).unwrap();
+        assert!(store.get_connection(gamma).unwrap().is_favorite);
+        assert_eq!(names(&store), ["gamma", "alpha", "beta"]);
+        store.set_favorite(gamma, false).unwrap();
+        assert_eq!(names(&store), ["alpha", "beta", "gamma"]);
+        assert!(matches!(
+            store.set_favorite(Uuid::new_v4(), true),
+            Err(CoreError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn bookmarks_are_scoped_to_connections() {
+        let dir = TempDir::new().unwrap();
+        let store = ConnectionStore::new(dir.path().join("test.db"));
+        store.init().unwrap();
+        let web = sample_connection();
+        let db = sample_connection();
+        store.create_connection(&web).unwrap();
+        store.create_connection(&db).unwrap();
+
+        let logs = store
+            .add_bookmark(Some(web.id), "/var/log", "log", false)
+            .unwrap();
+        let again = store
+            .add_bookmark(Some(web.id), "/var/log", "logs", false)
+            .unwrap();
+        assert_eq!(again.id, logs.id);
+        let home = store
+            .add_bookmark(Some(db.id), "/home/me", "home", true)
+            .unwrap();
+        assert_eq!(home.connection_id, None);
+        assert!(store.add_bookmark(None, "/srv", "srv", false).is_err());
+
+        let names = |conn_id| {
+            store
+                .list_bookmarks(conn_id)
+                .unwrap()
+                .into_iter()
+                .map(|b| b.name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(Some(web.id)), ["home", "log"]);
+        assert_eq!(names(Some(db.id)), ["home"]);
+        assert_eq!(names(None), ["home"]);
+
+        store.delete_bookmark(home.id).unwrap();
+        assert!(matches!(
+            store.delete_bookmark(home.id),
+            Err(CoreError::NotFound)
+        ));
+        store.delete_connection(web.id).unwrap();
+        assert!(store.list_bookmarks(Some(web.id)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn export_and_import_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let source = ConnectionStore::new(dir.path().join("source.db"));
+        source.init().unwrap();
+        let group = source.create_group("prod").unwrap();
+        let mut web = sample_connection();
+        web.name = "web".to_string();
+        web.group_id = Some(group.id);
+        web.auth_method = AuthMethod::Password {
+            secret_ref: "conn:web".to_string(),
+        };
+        web.tags.push(ConnectionTag {
+            name: "nginx".to_string(),
+        });
+        source.create_connection(&web).unwrap();
+        let db = sample_connection();
+        source.create_connection(&db).unwrap();
+
+        let json = source.export_json().unwrap();
+        assert!(json.contains("conn:web"));
+        assert!(json.contains("re-enter"));
+
+        let target = ConnectionStore::new(dir.path().join("target.db"));
+        target.init().unwrap();
+        target.create_connection(&db).unwrap();
+        let imported = target.import_json(&json).unwrap();
+        assert_eq!(imported.len(), 1);
+        assert!(imported[0].uses_stored_secrets());
+        assert!(!db.uses_stored_secrets());
+
+        let loaded = target.get_connection(web.id).unwrap();
+        assert_eq!(loaded.group_id, Some(group.id));
+        assert_eq!(loaded.tags[0].name, "nginx");
+        assert_eq!(target.list_groups().unwrap()[0].name, "prod");
+        assert_eq!(target.list_connections().unwrap().len(), 2);
+
+        assert!(target.import_json(&json).unwrap().is_empty());
+        assert_eq!(target.list_groups().unwrap().len(), 1);
+        assert!(target
+            .import_json("{\"version\": 9, \"connections\": []}")
+            .is_err());
+    }
+
+    #[test]
+    fn imports_jump_hosts_from_ssh_config() {
+        let dir = TempDir::new().unwrap();
+        let store = ConnectionStore::new(dir.path().join("test.db"));
+        store.init().unwrap();
+        let key = dir.path().join("bastion");
+        fs::write(&key, "key").unwrap();
+        let config = dir.path().join("config");
+        fs::write(
+            &config,
+            format!(
+                "Host bastion\n\
+             \x20 HostName bastion.example.com\n\
+             \x20 User jump\n\
+             \x20 IdentityFile {}\n\
+             Host db\n\
+             \x20 HostName 10.0.0.5\n\
+             \x20 ProxyJump bastion,ops@edge:2222\n\
+             Host cache\n\
+             \x20 HostName 10.0.0.6\n\
+             \x20 ProxyCommand ssh -q -W %h:%p -p 2200 admin@gw\n\
+             Host legacy\n\
+             \x20 HostName 10.0.0.7\n\
+             \x20 ProxyCommand corkscrew proxy 8080 %h %p\n",
+                key.display()
+            ),
+        )
+        .unwrap();
+
+        let imported = store.import_from_ssh_config(&config).unwrap();
+        let find = |name: &str| imported.iter().find(|c| c.name == name).unwrap();
+        let db = find("db");
+        assert_eq!(db.jump_hosts.len(), 2);
+        assert_eq!(db.jump_hosts[0].host, "bastion.example.com");
+        assert_eq!(db.jump_hosts[0].username, "jump");
+        assert!(matches!(
+            db.jump_hosts[0].auth_method,
+            AuthMethod::Key { ref private_key_path, .. }
+                if *private_key_path == key
+        ));
+        assert_eq!(
+            (
+                db.jump_hosts[1].username.as_str(),
+                db.jump_hosts[1].host.as_str()
+            ),
+            ("ops", "edge")
+        );
+        assert_eq!(db.jump_hosts[1].port, 2222);
+        assert!(db.notes.is_none());
+
+        let cache = find("cache");
+        assert_eq!(cache.jump_hosts.len(), 1);
+        assert_eq!(
+            (
+                cache.jump_hosts[0].username.as_str(),
+                cache.jump_hosts[0].port
+            ),
+            ("admin", 2200)
+        );
+
+        let legacy = find("legacy");
+        assert!(legacy.jump_hosts.is_empty());
+        assert!(legacy.notes.as_deref().unwrap().contains("corkscrew"));
+        let stored = store.get_connection(db.id).unwrap();
+        assert_eq!(stored.jump_hosts.len(), 2);
+    }
+
+    #[test]
+    fn parses_proxy_commands() {
+        let spec = JumpSpec::from_proxy_command("ssh bastion nc %h %p").unwrap();
+        assert_eq!(spec.host, "bastion");
+        assert!(JumpSpec::from_proxy_command("ssh -W %h:%p -o Foo=bar bastion").is_none());
+        assert!(JumpSpec::from_proxy_command("ssh bastion sh -c 'nc %h %p'").is_none());
+        assert!(JumpSpec::from_proxy_command("nc -X 5 -x proxy:1080 %h %p").is_none());
+        assert_eq!(
+            JumpSpec::parse("[::1]:2022").map(|s| (s.host, s.port)),
+            Some(("::1".to_string(), Some(2022)))
+        );
+    }
+
+    #[test]
+    fn picks_the_first_existing_identity() {
+        let dir = TempDir::new().unwrap();
+        let store = ConnectionStore::new(dir.path().join("test.db"));
+        store.init().unwrap();
+        let second = dir.path().join("id_second");
+        fs::write(&second, "key").unwrap();
+        let missing = dir.path().join("id_missing");
+        let config = dir.path().join("config");
+        fs::write(
+            &config,
+            format!(
+                "Host web\n\
+                 \x20 Port 2200\n\
+                 \x20 Port 2300\n\
+                 \x20 IdentityFile {missing}\n\
+                 \x20 IdentityFile\n\
+                 \x20 IdentityFile {second}\n\
+                 Host strict\n\
+                 \x20 IdentitiesOnly yes\n\
+                 \x20 IdentityFile {missing}\n\
+                 Host loose\n\
+                 \x20 IdentityFile {missing}\n",
+                missing = missing.display(),
+                second = second.display(),
+            ),
+        )
+        .unwrap();
+
+        let imported = store.import_from_ssh_config(&config).unwrap();
+        let find = |name: &str| imported.iter().find(|c| c.name == name).unwrap();
+        let web = find("web");
+        assert_eq!(web.port, 2200);
+        assert!(matches!(
+            web.auth_method,
+            AuthMethod::Key { ref private_key_path, .. } if *private_key_path == second
+        ));
+        assert!(matches!(
+            find("strict").auth_method,
+            AuthMethod::Key { ref private_key_path, .. } if *private_key_path == missing
+        ));
+        assert!(matches!(find("loose").auth_method, AuthMethod::Agent));
     }
 }
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Upper halves (0x80..=0xFF) of the supported single-byte code pages.
+const WINDOWS_1251_HIGH: &str = "ЂЃ‚ѓ„…†‡€‰Љ‹ЊЌЋЏђ‘’“”•–—\u{FFFD}™љ›њќћџ\u{A0}ЎўЈ¤Ґ¦§Ё©Є«¬\u{AD}®Ї°±Ііґµ¶·ё№є»јЅѕїАБВГДЕЖЗИЙКЛМНОПРСТУФХЦЧШЩЪЫЬЭЮЯабвгдежзийклмнопрстуфхцчшщъыьэюя";
+const KOI8_R_HIGH: &str = "─│┌┐└┘├┤┬┴┼▀▄█▌▐░▒▓⌠■∙√≈≤≥\u{A0}⌡°²·÷═║╒ё╓╔╕╖╗╘╙╚╛╜╝╞╟╠╡Ё╢╣╤╥╦╧╨╩╪╫╬©юабцдефгхийклмнопярстужвьызшэщчъЮАБЦДЕФГХИЙКЛМНОПЯРСТУЖВЬЫЗШЭЩЧЪ";
+
+/// Character set the remote side uses for terminal I/O. Everything except UTF-8 is a
+/// single-byte code page, so chunks can be transcoded without carrying state between reads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TerminalEncoding {
+    #[default]
+    Utf8,
+    Latin1,
+    Windows1251,
+    Koi8R,
+}
+
+impl TerminalEncoding {
+    pub const ALL: [TerminalEncoding; 4] = [
+        TerminalEncoding::Utf8,
+        TerminalEncoding::Latin1,
+        TerminalEncoding::Windows1251,
+        TerminalEncoding::Koi8R,
+    ];
+
+    pub fn as_key(&self) -> &'static str {
+        match self {
+            TerminalEncoding::Utf8 => "utf-8",
+            TerminalEncoding::Latin1 => "iso-8859-1",
+            TerminalEncoding::Windows1251 => "windows-1251",
+            TerminalEncoding::Koi8R => "koi8-r",
+        }
+    }
+
+    pub fn from_key(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Some(TerminalEncoding::Utf8),
+            "iso-8859-1" | "latin1" | "latin-1" => Some(TerminalEncoding::Latin1),
+            "windows-1251" | "cp1251" => Some(TerminalEncoding::Windows1251),
+            "koi8-r" | "koi8r" => Some(TerminalEncoding::Koi8R),
+            _ => None,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|e| *e == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    fn high_char(self, byte: u8) -> char {
+        match self {
+            TerminalEncoding::Utf8 | TerminalEncoding::Latin1 => char::from(byte),
+            TerminalEncoding::Windows1251 => high_table(WINDOWS_1251_HIGH, byte),
+            TerminalEncoding::Koi8R => high_table(KOI8_R_HIGH, byte),
+        }
+    }
+
+    /// Converts remote output to UTF-8.
+    pub fn decode<'a>(&self, data: &'a [u8]) -> Cow<'a, [u8]> {
+        if *self == TerminalEncoding::Utf8 || data.is_ascii() {
+            return Cow::Borrowed(data);
+        }
+        let mut out = Vec::with_capacity(data.len() * 2);
+        let mut buf = [0u8; 4];
+        for &byte in data {
+            if byte.is_ascii() {
+                out.push(byte);
+            } else {
+                out.extend_from_slice(self.high_char(byte).encode_utf8(&mut buf).as_bytes());
+            }
+        }
+        Cow::Owned(out)
+    }
+
+    /// Converts UTF-8 input to the remote encoding; unmappable characters become `?`.
+    pub fn encode<'a>(&self, data: &'a [u8]) -> Cow<'a, [u8]> {
+        if *self == TerminalEncoding::Utf8 || data.is_ascii() {
+            return Cow::Borrowed(data);
+        }
+        let text = String::from_utf8_lossy(data);
+        let out = text
+            .chars()
+            .map(|c| {
+                if c.is_ascii() {
+                    return c as u8;
+                }
+                (0x80..=0xFF)
+                    .find(|byte| self.high_char(*byte) == c)
+                    .unwrap_or(b'?')
+            })
+            .collect();
+        Cow::Owned(out)
+    }
+}
+
+fn high_table(table: &str, byte: u8) -> char {
+    table
+        .chars()
+        .nth(usize::from(byte - 0x80))
+        .unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables_cover_the_upper_half() {
+        assert_eq!(WINDOWS_1251_HIGH.chars().count(), 128);
+        assert_eq!(KOI8_R_HIGH.chars().count(), 128);
+    }
+
+    #[test]
+    fn round_trips_single_byte_encodings() {
+        let cases = [
+            (TerminalEncoding::Latin1, &b"caf\xe9"[..], "café"),
+            (
+                TerminalEncoding::Windows1251,
+                &b"\xcf\xf0\xe8\xe2\xe5\xf2"[..],
+                "Привет",
+            ),
+            (
+                TerminalEncoding::Koi8R,
+                &b"\xf0\xd2\xc9\xd7\xc5\xd4"[..],
+                "Привет",
+            ),
+        ];
+        for (encoding, raw, text) in cases {
+            assert_eq!(encoding.decode(raw).as_ref(), text.as_bytes());
+            assert_eq!(encoding.encode(text.as_bytes()).as_ref(), raw);
+        }
+        assert_eq!(
+            TerminalEncoding::Latin1.encode("ж".as_bytes()).as_ref(),
+            b"?"
+        );
+        assert_eq!(
+            TerminalEncoding::Utf8.decode(b"\xff").as_ref(),
+            &b"\xff"[..]
+        );
+    }
+
+    #[test]
+    fn parses_keys_and_cycles() {
+        for encoding in TerminalEncoding::ALL {
+            assert_eq!(
+                TerminalEncoding::from_key(encoding.as_key()),
+                Some(encoding)
+            );
+        }
+        assert_eq!(
+            TerminalEncoding::from_key("CP1251"),
+            Some(TerminalEncoding::Windows1251)
+        );
+        assert_eq!(TerminalEncoding::Koi8R.next(), TerminalEncoding::Utf8);
+    }
+}
@@ -1,3 +1,5 @@
+use catsolle_keychain::SecretError;
+use catsolle_ssh::SshError;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -8,8 +10,23 @@ pub enum CoreError {
     Io(#[from] std::io::Error),
     #[error("ssh error: {0}")]
     Ssh(String),
+    #[error("connection failed: {0}")]
+    Connect(#[from] SshError),
+    #[error("credentials required: {0}")]
+    CredentialsRequired(String),
     #[error("invalid data: {0}")]
     Invalid(String),
     #[error("not found")]
     NotFound,
+    #[error("cancelled")]
+    Cancelled,
+}
+
+impl From<SecretError> for CoreError {
+    fn from(err: SecretError) -> Self {
+        match err {
+            SecretError::MasterPasswordRequired => CoreError::CredentialsRequired(err.to_string()),
+            other => CoreError::Invalid(other.to_string()),
+        }
+    }
 }
@@ -1,5 +1,9 @@
 use crate::session::SessionState;
-use crate::transfer::TransferProgress;
+use crate::transfer::{FileResult, TransferProgress, TransferState};
+use catsolle_ssh::CloseReason;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
 use uuid::Uuid;
 
 #[derive(Clone, Debug)]
@@ -8,9 +12,21 @@ pub enum Event {
         session_id: Uuid,
         state: SessionState,
     },
+    /// The shell of a session ended; any reason but `Eof` means the connection is gone.
+    SessionClosed {
+        session_id: Uuid,
+        reason: CloseReason,
+    },
     TransferProgress {
         job_id: Uuid,
+        state: TransferState,
+        progress: TransferProgress,
+    },
+    TransferFinished {
+        job_id: Uuid,
+        state: TransferState,
         progress: TransferProgress,
+        results: Vec<FileResult>,
     },
     Notification {
         level: String,
@@ -18,22 +34,196 @@ pub enum Event {
     },
 }
 
+/// Latest state of live sessions and transfers, kept so late subscribers can catch up.
+/// Entries are dropped once a session disconnects or fails and once a transfer finishes.
+#[derive(Debug, Default)]
+struct RetainedState {
+    sessions: HashMap<Uuid, SessionState>,
+    transfers: HashMap<Uuid, (TransferState, TransferProgress)>,
+}
+
+impl RetainedState {
+    fn apply(&mut self, event: &Event) {
+        match event {
+            Event::SessionStateChanged { session_id, state } => match state {
+                SessionState::Connecting | SessionState::Connected => {
+                    self.sessions.insert(*session_id, state.clone());
+                }
+                SessionState::Disconnected | SessionState::Failed(_) => {
+                    self.sessions.remove(session_id);
+                }
+            },
+            Event::SessionClosed { session_id, reason } => {
+                if *reason != CloseReason::Eof {
+                    self.sessions.remove(session_id);
+                }
+            }
+            Event::TransferProgress {
+                job_id,
+                state,
+                progress,
+            } => {
+                self.transfers
+                    .insert(*job_id, (state.clone(), progress.clone()));
+            }
+            Event::TransferFinished { job_id, .. } => {
+                self.transfers.remove(job_id);
+            }
+            Event::Notification { .. } => {}
+        }
+    }
+
+    fn snapshot(&self) -> Vec<Event> {
+        let sessions = self
+            .sessions
+            .iter()
+            .map(|(session_id, state)| Event::SessionStateChanged {
+                session_id: *session_id,
+                state: state.clone(),
+            });
+        let transfers =
+            self.transfers
+                .iter()
+                .map(|(job_id, (state, progress))| Event::TransferProgress {
+                    job_id: *job_id,
+                    state: state.clone(),
+                    progress: progress.clone(),
+                });
+        sessions.chain(transfers).collect()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct EventBus {
     sender: tokio::sync::broadcast::Sender<Event>,
+    retained: Arc<Mutex<RetainedState>>,
 }
 
 impl EventBus {
     pub fn new(capacity: usize) -> Self {
         let (sender, _) = tokio::sync::broadcast::channel(capacity);
-        Self { sender }
+        Self {
+            sender,
+            retained: Arc::new(Mutex::new(RetainedState::default())),
+        }
     }
 
     pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<Event> {
         self.sender.subscribe()
     }
 
+    /// Subscribes and returns events describing the current state, to be handled before
+    /// anything from the receiver. Nothing is lost or duplicated between the two.
+    pub fn subscribe_with_snapshot(&self) -> (Vec<Event>, tokio::sync::broadcast::Receiver<Event>) {
+        let retained = self.retained.lock();
+        (retained.snapshot(), self.sender.subscribe())
+    }
+
+    /// Current state of live sessions and transfers as replayable events.
+    pub fn snapshot(&self) -> Vec<Event> {
+        self.retained.lock().snapshot()
+    }
+
+    pub fn session_state(&self, session_id: Uuid) -> Option<SessionState> {
+        self.retained.lock().sessions.get(&session_id).cloned()
+    }
+
+    pub fn latest_transfer_progress(
+        &self,
+        job_id: Uuid,
+    ) -> Option<(TransferState, TransferProgress)> {
+        self.retained.lock().transfers.get(&job_id).cloned()
+    }
+
     pub fn send(&self, event: Event) {
+        let mut retained = self.retained.lock();
+        retained.apply(&event);
         let _ = self.sender.send(event);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(bytes: u64) -> TransferProgress {
+        TransferProgress {
+            bytes_transferred: bytes,
+            bytes_total: 100,
+            ..TransferProgress::default()
+        }
+    }
+
+    #[test]
+    fn retains_live_state_for_late_subscribers() {
+        let bus = EventBus::new(4);
+        let session_id = Uuid::new_v4();
+        let job_id = Uuid::new_v4();
+        bus.send(Event::SessionStateChanged {
+            session_id,
+            state: SessionState::Connected,
+        });
+        for bytes in [10, 40] {
+            bus.send(Event::TransferProgress {
+                job_id,
+                state: TransferState::InProgress,
+                progress: progress(bytes),
+            });
+        }
+
+        assert!(matches!(
+            bus.session_state(session_id),
+            Some(SessionState::Connected)
+        ));
+        let (state, latest) = bus.latest_transfer_progress(job_id).unwrap();
+        assert!(matches!(state, TransferState::InProgress));
+        assert_eq!(latest.bytes_transferred, 40);
+
+        let (snapshot, mut rx) = bus.subscribe_with_snapshot();
+        assert_eq!(snapshot.len(), 2);
+        bus.send(Event::TransferFinished {
+            job_id,
+            state: TransferState::Completed,
+            progress: progress(100),
+            results: Vec::new(),
+        });
+        assert!(matches!(rx.try_recv(), Ok(Event::TransferFinished { .. })));
+        assert!(bus.latest_transfer_progress(job_id).is_none());
+    }
+
+    #[test]
+    fn forgets_closed_sessions() {
+        let bus = EventBus::new(4);
+        let session_id = Uuid::new_v4();
+        bus.send(Event::SessionStateChanged {
+            session_id,
+            state: SessionState::Connecting,
+        });
+        bus.send(Event::SessionStateChanged {
+            session_id,
+            state: SessionState::Disconnected,
+        });
+        assert!(bus.session_state(session_id).is_none());
+        assert!(bus.snapshot().is_empty());
+    }
+
+    #[test]
+    fn dropped_connections_end_the_session() {
+        let bus = EventBus::new(4);
+        let session_id = Uuid::new_v4();
+        bus.send(Event::SessionStateChanged {
+            session_id,
+            state: SessionState::Connected,
+        });
+        bus.send(Event::SessionClosed {
+            session_id,
+            reason: CloseReason::Eof,
+        });
+        assert!(bus.session_state(session_id).is_some());
+        bus.send(Event::SessionClosed {
+            session_id,
+            reason: CloseReason::KeepaliveTimeout,
+        });
+        assert!(bus.session_state(session_id).is_none());
+    }
+}
@@ -1,19 +1,28 @@
+pub mod checksum;
 pub mod connection;
+pub mod encoding;
 pub mod error;
 pub mod events;
+#[cfg(any(test, feature = "test-support"))]
+pub mod memory_fs;
 pub mod recording;
+pub mod remote_fs;
 pub mod session;
 pub mod transfer;
 
+pub use checksum::HashAlgorithm;
 pub use connection::{
-    AuthMethod, Connection, ConnectionGroup, ConnectionId, ConnectionStore, ConnectionTag,
-    JumpHost, ProxyConfig, ProxyType,
+    expand_home, AuthMethod, Bookmark, Connection, ConnectionGroup, ConnectionId, ConnectionStore,
+    ConnectionTag, JumpHost, ProxyConfig, ProxyType,
 };
+pub use encoding::TerminalEncoding;
 pub use error::CoreError;
 pub use events::{Event, EventBus};
 pub use recording::{AsciinemaRecorder, RecordingEvent};
+pub use remote_fs::{RemoteFs, RemoteMetadata};
 pub use session::{SessionHandle, SessionManager, SessionState};
 pub use transfer::{
-    TransferEndpoint, TransferFile, TransferJob, TransferOptions, TransferProgress, TransferQueue,
-    TransferState,
+    expand_transfer_files, format_bytes, format_duration, ControlSignal, FileOutcome, FileResult,
+    TransferBackend, TransferEndpoint, TransferFile, TransferJob, TransferOptions,
+    TransferProgress, TransferQueue, TransferState,
 };
@@ -0,0 +1,219 @@
+use crate::error::CoreError;
+use crate::remote_fs::{RemoteFs, RemoteMetadata, RemoteReader, RemoteWriter};
+use async_trait::async_trait;
+use catsolle_ssh::SftpEntry;
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+#[derive(Clone, Debug)]
+enum MemoryNode {
+    Dir,
+    File { data: Vec<u8>, mtime: Option<u64> },
+}
+
+type MemoryTree = Arc<Mutex<BTreeMap<String, MemoryNode>>>;
+
+/// In-memory remote side for tests, kept as a map of absolute paths. Clones share the same
+/// tree, so a test can hand one out and inspect the result through another. Other crates
+/// get it through the `test-support` feature.
+#[derive(Clone, Default)]
+pub struct MemoryFs {
+    tree: MemoryTree,
+    corrupt_writes: bool,
+}
+
+impl MemoryFs {
+    pub fn with_file(self, path: &str, data: &[u8], mtime: Option<u64>) -> Self {
+        self.tree.lock().insert(
+            path.to_string(),
+            MemoryNode::File {
+                data: data.to_vec(),
+                mtime,
+            },
+        );
+        self
+    }
+
+    pub fn with_dir(self, path: &str) -> Self {
+        self.tree.lock().insert(path.to_string(), MemoryNode::Dir);
+        self
+    }
+
+    /// Flips the first byte of every write so checksum verification has something to catch.
+    pub fn with_corrupt_writes(mut self) -> Self {
+        self.corrupt_writes = true;
+        self
+    }
+
+    pub fn file(&self, path: &str) -> Option<Vec<u8>> {
+        match self.tree.lock().get(path) {
+            Some(MemoryNode::File { data, .. }) => Some(data.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn is_dir(&self, path: &str) -> bool {
+        matches!(self.tree.lock().get(path), Some(MemoryNode::Dir))
+    }
+
+    pub fn remove_file(&self, path: &str) -> Result<(), CoreError> {
+        let mut tree = self.tree.lock();
+        match tree.get(path) {
+            Some(MemoryNode::File { .. }) => {
+                tree.remove(path);
+                Ok(())
+            }
+            _ => Err(not_found(path)),
+        }
+    }
+
+    /// Removes an empty directory.
+    pub fn remove_dir(&self, path: &str) -> Result<(), CoreError> {
+        let mut tree = self.tree.lock();
+        let prefix = format!("{path}/");
+        if tree.keys().any(|entry| entry.starts_with(&prefix)) {
+            return Err(CoreError::Ssh(format!("directory not empty: {path}")));
+        }
+        match tree.get(path) {
+            Some(MemoryNode::Dir) => {
+                tree.remove(path);
+                Ok(())
+            }
+            _ => Err(not_found(path)),
+        }
+    }
+
+    pub fn rename(&self, from: &str, to: &str) -> Result<(), CoreError> {
+        let mut tree = self.tree.lock();
+        let node = tree.remove(from).ok_or_else(|| not_found(from))?;
+        tree.insert(to.to_string(), node);
+        Ok(())
+    }
+}
+
+fn not_found(path: &str) -> CoreError {
+    CoreError::Ssh(format!("no such file: {path}"))
+}
+
+#[async_trait]
+impl RemoteFs for MemoryFs {
+    async fn metadata(&self, path: &str) -> Result<RemoteMetadata, CoreError> {
+        match self.tree.lock().get(path) {
+            Some(MemoryNode::Dir) => Ok(RemoteMetadata {
+                is_dir: true,
+                ..Default::default()
+            }),
+            Some(MemoryNode::File { data, mtime }) => Ok(RemoteMetadata {
+                size: Some(data.len() as u64),
+                mtime: *mtime,
+                ..Default::default()
+            }),
+            None => Err(not_found(path)),
+        }
+    }
+
+    async fn read_dir(&self, path: &str) -> Result<Vec<SftpEntry>, CoreError> {
+        let tree = self.tree.lock();
+        match tree.get(path) {
+            Some(MemoryNode::Dir) => {}
+            Some(MemoryNode::File { .. }) => {
+                return Err(CoreError::Ssh(format!("not a directory: {path}")))
+            }
+            None => return Err(not_found(path)),
+        }
+        let prefix = format!("{}/", path.trim_end_matches('/'));
+        Ok(tree
+            .iter()
+            .filter_map(|(entry, node)| {
+                let name = entry.strip_prefix(&prefix)?;
+                (!name.contains('/')).then(|| SftpEntry {
+                    name: name.to_string(),
+                    path: entry.clone(),
+                    size: match node {
+                        MemoryNode::File { data, .. } => data.len() as u64,
+                        MemoryNode::Dir => 0,
+                    },
+                    is_dir: matches!(node, MemoryNode::Dir),
+                    is_symlink: false,
+                    modified: None,
+                    permissions: None,
+                })
+            })
+            .collect())
+    }
+
+    async fn check_writable(&self, _dir: &str, _bytes: u64) -> Result<(), CoreError> {
+        Ok(())
+    }
+
+    async fn open_read_at(&self, path: &str, offset: u64) -> Result<RemoteReader, CoreError> {
+        let data = self.file(path).ok_or_else(|| not_found(path))?;
+        let mut cursor = std::io::Cursor::new(data);
+        cursor.set_position(offset);
+        Ok(Box::new(cursor))
+    }
+
+    async fn open_write(&self, path: &str, truncate: bool) -> Result<RemoteWriter, CoreError> {
+        let mut tree = self.tree.lock();
+        let node = tree.entry(path.to_string()).or_insert(MemoryNode::File {
+            data: Vec::new(),
+            mtime: None,
+        });
+        match node {
+            MemoryNode::File { data, .. } if truncate => data.clear(),
+            MemoryNode::File { .. } => {}
+            MemoryNode::Dir => return Err(CoreError::Ssh(format!("is a directory: {path}"))),
+        }
+        Ok(Box::new(MemoryWriter {
+            fs: self.clone(),
+            path: path.to_string(),
+        }))
+    }
+
+    async fn create_dir_all(&self, path: &str) -> Result<(), CoreError> {
+        let mut current = String::new();
+        for part in path.split('/').filter(|part| !part.is_empty()) {
+            current.push('/');
+            current.push_str(part);
+            self.tree
+                .lock()
+                .entry(current.clone())
+                .or_insert(MemoryNode::Dir);
+        }
+        Ok(())
+    }
+}
+
+struct MemoryWriter {
+    fs: MemoryFs,
+    path: String,
+}
+
+impl tokio::io::AsyncWrite for MemoryWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let mut tree = self.fs.tree.lock();
+        if let Some(MemoryNode::File { data, .. }) = tree.get_mut(&self.path) {
+            let start = data.len();
+            data.extend_from_slice(buf);
+            if self.fs.corrupt_writes && !buf.is_empty() {
+                data[start] ^= 0xff;
+            }
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
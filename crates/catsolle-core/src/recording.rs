@@ -1,10 +1,10 @@
 use chrono::Utc;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufWriter, Write};
 use std::path::Path;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 #[derive(Clone, Debug, Serialize)]
 pub struct RecordingEvent {
@@ -13,6 +13,16 @@ pub struct RecordingEvent {
     pub data: String,
 }
 
+/// Header line of an asciicast v2 file; fields playback does not need are ignored.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct CastHeader {
+    pub version: u32,
+    pub width: u16,
+    pub height: u16,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
 pub struct AsciinemaRecorder {
     writer: BufWriter<File>,
     start: Instant,
@@ -24,6 +34,7 @@ impl AsciinemaRecorder {
         path: &Path,
         width: u16,
         height: u16,
+        title: Option<&str>,
         env: HashMap<String, String>,
     ) -> anyhow::Result<Self> {
         if let Some(parent) = path.parent() {
@@ -31,13 +42,16 @@ impl AsciinemaRecorder {
         }
         let file = File::create(path)?;
         let mut writer = BufWriter::new(file);
-        let header = serde_json::json!({
+        let mut header = serde_json::json!({
             "version": 2,
             "width": width,
             "height": height,
             "timestamp": Utc::now().timestamp(),
             "env": env,
         });
+        if let Some(title) = title {
+            header["title"] = title.into();
+        }
         writeln!(writer, "{}", header)?;
         writer.flush()?;
         Ok(Self {
@@ -75,3 +89,110 @@ impl AsciinemaRecorder {
         Ok(())
     }
 }
+
+pub fn read_cast(path: &Path) -> anyhow::Result<(CastHeader, Vec<RecordingEvent>)> {
+    let content = std::fs::read_to_string(path)?;
+    parse_cast(&content)
+}
+
+/// Parses an asciicast v2 file: a JSON header line, then one `[time, kind, data]` array per
+/// line.
+pub fn parse_cast(content: &str) -> anyhow::Result<(CastHeader, Vec<RecordingEvent>)> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let (_, first) = lines
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("empty recording"))?;
+    let header: CastHeader = serde_json::from_str(first)
+        .map_err(|err| anyhow::anyhow!("invalid asciicast header: {err}"))?;
+    if header.version != 2 {
+        anyhow::bail!("unsupported asciicast version {}", header.version);
+    }
+    let mut events = Vec::new();
+    for (index, line) in lines {
+        let (time, kind, data): (f64, String, String) = serde_json::from_str(line)
+            .map_err(|err| anyhow::anyhow!("invalid event on line {}: {err}", index + 1))?;
+        events.push(RecordingEvent { time, kind, data });
+    }
+    Ok((header, events))
+}
+
+/// How long to wait before each event when replaying at `speed`. Gaps longer than
+/// `max_idle` seconds (after scaling) are cut down to it.
+pub fn playback_delays(
+    events: &[RecordingEvent],
+    speed: f64,
+    max_idle: Option<f64>,
+) -> Vec<Duration> {
+    let mut prev = 0.0;
+    events
+        .iter()
+        .map(|event| {
+            let mut gap = (event.time - prev).max(0.0) / speed;
+            prev = event.time.max(prev);
+            if let Some(max_idle) = max_idle {
+                gap = gap.min(max_idle);
+            }
+            Duration::from_secs_f64(gap)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_header_and_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/session.cast");
+        let env = HashMap::from([("TERM".to_string(), "xterm-256color".to_string())]);
+        let mut recorder = AsciinemaRecorder::start(&path, 80, 24, Some("root@host"), env).unwrap();
+        recorder.record_output(b"hello\r\n").unwrap();
+        recorder.close().unwrap();
+        recorder.record_output(b"ignored").unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[0]["width"], 80);
+        assert_eq!(lines[0]["title"], "root@host");
+        assert_eq!(lines[0]["env"]["TERM"], "xterm-256color");
+        assert_eq!(lines[1][1], "o");
+        assert_eq!(lines[1][2], "hello\r\n");
+    }
+
+    #[test]
+    fn parses_casts_and_scales_delays() {
+        let content = concat!(
+            "{\"version\": 2, \"width\": 80, \"height\": 24, \"title\": \"demo\"}\n",
+            "[0.5, \"o\", \"$ \"]\n",
+            "[1.5, \"i\", \"ls\\r\"]\n",
+            "\n",
+            "[31.5, \"o\", \"file\\r\\n\"]\n",
+        );
+        let (header, events) = parse_cast(content).unwrap();
+        assert_eq!(header.width, 80);
+        assert_eq!(header.title.as_deref(), Some("demo"));
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[2].kind, "o");
+        assert_eq!(events[2].data, "file\r\n");
+
+        let secs =
+            |delays: Vec<Duration>| delays.iter().map(Duration::as_secs_f64).collect::<Vec<_>>();
+        assert_eq!(secs(playback_delays(&events, 1.0, None)), [0.5, 1.0, 30.0]);
+        assert_eq!(
+            secs(playback_delays(&events, 2.0, Some(2.0))),
+            [0.25, 0.5, 2.0]
+        );
+
+        assert!(parse_cast("{\"version\": 1, \"width\": 80, \"height\": 24}").is_err());
+        assert!(parse_cast("{\"version\": 2, \"width\": 80, \"height\": 24}\n[1.0]").is_err());
+    }
+}
@@ -0,0 +1,84 @@
+use crate::error::CoreError;
+use async_trait::async_trait;
+use catsolle_ssh::{SftpClient, SftpEntry};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+pub type RemoteReader = Box<dyn AsyncRead + Send + Unpin>;
+pub type RemoteWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// Attributes of a remote path; whatever the server does not report stays `None`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RemoteMetadata {
+    pub size: Option<u64>,
+    /// Seconds since the epoch.
+    pub mtime: Option<u64>,
+    pub atime: Option<u64>,
+    pub permissions: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub is_dir: bool,
+}
+
+/// File operations the transfer engine runs on the remote side. `SftpClient` is the real
+/// implementation; tests drive jobs through an in-memory one.
+#[async_trait]
+pub trait RemoteFs: Send + Sync {
+    /// Follows symlinks.
+    async fn metadata(&self, path: &str) -> Result<RemoteMetadata, CoreError>;
+    async fn read_dir(&self, path: &str) -> Result<Vec<SftpEntry>, CoreError>;
+    /// Fails when `dir` obviously cannot take `bytes` of new data.
+    async fn check_writable(&self, dir: &str, bytes: u64) -> Result<(), CoreError>;
+    async fn open_read_at(&self, path: &str, offset: u64) -> Result<RemoteReader, CoreError>;
+    async fn open_write(&self, path: &str, truncate: bool) -> Result<RemoteWriter, CoreError>;
+    async fn create_dir_all(&self, path: &str) -> Result<(), CoreError>;
+}
+
+fn ssh_error(err: catsolle_ssh::SshError) -> CoreError {
+    CoreError::Ssh(err.to_string())
+}
+
+#[async_trait]
+impl RemoteFs for SftpClient {
+    async fn metadata(&self, path: &str) -> Result<RemoteMetadata, CoreError> {
+        let meta = SftpClient::metadata(self, path).await.map_err(ssh_error)?;
+        Ok(RemoteMetadata {
+            size: meta.size,
+            mtime: meta.mtime.map(u64::from),
+            atime: meta.atime.map(u64::from),
+            permissions: meta.permissions,
+            uid: meta.uid,
+            gid: meta.gid,
+            is_dir: meta.file_type().is_dir(),
+        })
+    }
+
+    async fn read_dir(&self, path: &str) -> Result<Vec<SftpEntry>, CoreError> {
+        SftpClient::read_dir(self, path).await.map_err(ssh_error)
+    }
+
+    async fn check_writable(&self, dir: &str, bytes: u64) -> Result<(), CoreError> {
+        SftpClient::check_writable(self, dir, bytes)
+            .await
+            .map_err(ssh_error)
+    }
+
+    async fn open_read_at(&self, path: &str, offset: u64) -> Result<RemoteReader, CoreError> {
+        let file = SftpClient::open_read_at(self, path, offset)
+            .await
+            .map_err(ssh_error)?;
+        Ok(Box::new(file))
+    }
+
+    async fn open_write(&self, path: &str, truncate: bool) -> Result<RemoteWriter, CoreError> {
+        let file = SftpClient::open_write(self, path, truncate)
+            .await
+            .map_err(ssh_error)?;
+        Ok(Box::new(file))
+    }
+
+    async fn create_dir_all(&self, path: &str) -> Result<(), CoreError> {
+        SftpClient::create_dir_all(self, path)
+            .await
+            .map_err(ssh_error)
+    }
+}
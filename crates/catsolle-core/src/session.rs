@@ -3,20 +3,24 @@ use crate::connection::{
 };
 use crate::error::CoreError;
 use crate::events::{Event, EventBus};
+use crate::transfer::TransferBackend;
 use catsolle_config::AppConfig;
 use catsolle_keychain::KeychainManager;
 use catsolle_ssh::config::{HostKeyPolicy, KeyboardInteractiveHandler};
 use catsolle_ssh::{
-    AuthMethod as SshAuthMethod, JumpHost as SshJumpHost, ProxyConfig as SshProxyConfig,
-    ProxyType as SshProxyType, SshClient, SshConnectConfig, SshSession,
+    AuthMethod as SshAuthMethod, CloseReason, DynamicForward, JumpHost as SshJumpHost,
+    KnownHostEntry, KnownHosts, LocalForward, NewHostKey, ProxyConfig as SshProxyConfig,
+    ProxyType as SshProxyType, PublicKey, SshClient, SshConnectConfig, SshSession,
 };
 use chrono::Utc;
 use parking_lot::Mutex;
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
+use tokio::sync::mpsc;
 use tokio::task;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 use zeroize::Zeroizing;
 #[derive(Clone)]
@@ -42,6 +46,7 @@ pub struct SessionManager {
     config: AppConfig,
     bus: EventBus,
     sessions: Arc<Mutex<HashMap<Uuid, SessionHandle>>>,
+    host_key_prompts: Arc<Mutex<Option<mpsc::Sender<NewHostKey>>>>,
 }
 
 impl SessionManager {
@@ -57,9 +62,16 @@ impl SessionManager {
             config,
             bus,
             sessions: Arc::new(Mutex::new(HashMap::new())),
+            host_key_prompts: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// Sends host keys seen for the first time to `prompts` and waits for the user instead
+    /// of trusting them right away; `None` goes back to trusting them silently.
+    pub fn set_host_key_prompts(&self, prompts: Option<mpsc::Sender<NewHostKey>>) {
+        *self.host_key_prompts.lock() = prompts;
+    }
+
     pub async fn connect_by_id(
         &self,
         id: ConnectionId,
@@ -107,7 +119,7 @@ impl SessionManager {
         };
         self.keychain
             .store_secret(&secret_ref, password, master_password)
-            .map_err(|e| CoreError::Invalid(e.to_string()))?;
+            .map_err(CoreError::from)?;
         conn.auth_method = AuthMethod::Password { secret_ref };
         conn.updated_at = Utc::now();
         self.store
@@ -116,6 +128,39 @@ impl SessionManager {
         Ok(())
     }
 
+    /// Trusts `key` for `host:port` in place of the key known_hosts has for it. Only call
+    /// this after the user confirmed the change.
+    pub fn replace_host_key(
+        &self,
+        host: &str,
+        port: u16,
+        key: &PublicKey,
+    ) -> Result<(), CoreError> {
+        let mut known_hosts = KnownHosts::load(default_known_hosts_path())
+            .map_err(|e| CoreError::Invalid(e.to_string()))?;
+        known_hosts
+            .replace(host, port, key, "catsolle")
+            .map_err(|e| CoreError::Invalid(e.to_string()))
+    }
+
+    /// Host keys trusted in known_hosts.
+    pub fn known_hosts(&self) -> Result<Vec<KnownHostEntry>, CoreError> {
+        let known_hosts = KnownHosts::load(default_known_hosts_path())
+            .map_err(|e| CoreError::Invalid(e.to_string()))?;
+        Ok(known_hosts.entries())
+    }
+
+    /// Drops the keys known_hosts has for `host:port`, so the next connection treats the host as new.
+    pub fn forget_host_key(&self, host: &str, port: u16) -> Result<(), CoreError> {
+        let mut known_hosts = KnownHosts::load(default_known_hosts_path())
+            .map_err(|e| CoreError::Invalid(e.to_string()))?;
+        match known_hosts.remove(host, port) {
+            Ok(0) => Err(CoreError::NotFound),
+            Ok(_) => Ok(()),
+            Err(e) => Err(CoreError::Invalid(e.to_string())),
+        }
+    }
+
     async fn connect_with_config(
         &self,
         conn: Connection,
@@ -133,10 +178,14 @@ impl SessionManager {
             Ok(session) => session,
             Err(err) => {
                 error!(connection_id = %conn.id, error = %err, "session connect failed");
-                return Err(CoreError::Ssh(err.to_string()));
+                return Err(CoreError::Connect(err));
             }
         };
-        let _ = session.send_startup_commands().await;
+        if !self.config.ssh.startup_commands_in_shell() {
+            if let Err(err) = session.send_startup_commands().await {
+                warn!(connection_id = %conn.id, error = %err, "startup commands failed");
+            }
+        }
 
         let handle = SessionHandle {
             id: session_id,
@@ -173,10 +222,74 @@ impl SessionManager {
         self.sessions.lock().get(&id).cloned()
     }
 
+    /// Backend configured on the session's connection; `Auto` when it can't be looked up.
+    pub async fn transfer_backend(&self, session_id: Uuid) -> TransferBackend {
+        let Some(handle) = self.get_session(session_id) else {
+            return TransferBackend::default();
+        };
+        self.get_connection(handle.connection_id)
+            .await
+            .map(|conn| conn.transfer_backend)
+            .unwrap_or_default()
+    }
+
     pub fn list_sessions(&self) -> Vec<SessionHandle> {
         self.sessions.lock().values().cloned().collect()
     }
 
+    pub async fn open_dynamic_forward(
+        &self,
+        id: Uuid,
+        bind_addr: SocketAddr,
+    ) -> Result<DynamicForward, CoreError> {
+        let handle = self.get_session(id).ok_or(CoreError::NotFound)?;
+        let forward = handle
+            .session
+            .open_dynamic_forward(bind_addr)
+            .await
+            .map_err(|e| CoreError::Ssh(e.to_string()))?;
+        info!(session_id = %id, addr = %forward.local_addr(), "dynamic forward started");
+        Ok(forward)
+    }
+
+    pub async fn open_local_forward(
+        &self,
+        id: Uuid,
+        local: SocketAddr,
+        remote_host: String,
+        remote_port: u16,
+    ) -> Result<LocalForward, CoreError> {
+        let handle = self.get_session(id).ok_or(CoreError::NotFound)?;
+        let forward = handle
+            .session
+            .open_local_forward(local, remote_host, remote_port)
+            .await
+            .map_err(|e| CoreError::Ssh(e.to_string()))?;
+        info!(session_id = %id, addr = %forward.local_addr(), "local forward started");
+        Ok(forward)
+    }
+
+    /// Works out why the shell of session `id` ended and publishes `Event::SessionClosed`.
+    pub async fn report_shell_closed(&self, id: Uuid) -> CloseReason {
+        let reason = match self.get_session(id) {
+            Some(handle) => handle
+                .session
+                .close_reason()
+                .await
+                .unwrap_or_else(|| CloseReason::Transport("shell output ended".to_string())),
+            None => CloseReason::Transport("session not found".to_string()),
+        };
+        match &reason {
+            CloseReason::Eof => info!(session_id = %id, "shell closed"),
+            other => warn!(session_id = %id, reason = ?other, "connection lost"),
+        }
+        self.bus.send(Event::SessionClosed {
+            session_id: id,
+            reason: reason.clone(),
+        });
+        reason
+    }
+
     pub async fn disconnect(&self, id: Uuid) {
         let mut map = self.sessions.lock();
         if let Some(mut handle) = map.remove(&id) {
@@ -220,7 +333,7 @@ impl SessionManager {
                 let password = if let Some(ref id) = proxy.password_ref {
                     self.keychain
                         .get_secret(id, master)
-                        .map_err(|e| CoreError::Invalid(e.to_string()))?
+                        .map_err(CoreError::from)?
                 } else {
                     None
                 };
@@ -239,6 +352,10 @@ impl SessionManager {
         };
 
         let known_hosts = default_known_hosts_path();
+        let host_key_policy = match self.host_key_prompts.lock().clone() {
+            Some(prompts) => HostKeyPolicy::AcceptNewInteractive(prompts),
+            None => HostKeyPolicy::AcceptNew,
+        };
 
         Ok(SshConnectConfig {
             host: conn.host.clone(),
@@ -247,7 +364,7 @@ impl SessionManager {
             auth_method: auth,
             jump_hosts,
             proxy,
-            host_key_policy: HostKeyPolicy::AcceptNew,
+            host_key_policy,
             known_hosts_path: Some(known_hosts),
             keepalive_interval_secs: self.config.ssh.keepalive_interval_secs,
             connect_timeout_ms: self.config.ssh.connect_timeout_ms,
@@ -255,11 +372,8 @@ impl SessionManager {
             term: "xterm-256color".to_string(),
             term_width: 120,
             term_height: 40,
-            env: conn
-                .env_vars
-                .iter()
-                .map(|v| (v.key.clone(), v.value.clone()))
-                .collect(),
+            env: conn.session_env(&self.config.ssh.locale),
+            env_export_fallback: self.config.ssh.env_export_fallback,
             startup_commands: conn.startup_commands.clone(),
             agent_forwarding: self.config.ssh.agent_forwarding,
             x11_forwarding: self.config.ssh.x11_forwarding,
@@ -276,8 +390,10 @@ impl SessionManager {
                 let secret = self
                     .keychain
                     .get_secret(secret_ref, master)
-                    .map_err(|e| CoreError::Invalid(e.to_string()))?
-                    .ok_or_else(|| CoreError::Invalid("missing password".to_string()))?;
+                    .map_err(CoreError::from)?
+                    .ok_or_else(|| {
+                        CoreError::CredentialsRequired("missing password".to_string())
+                    })?;
                 Ok(SshAuthMethod::Password { password: secret })
             }
             AuthMethod::Key {
@@ -287,7 +403,7 @@ impl SessionManager {
                 let passphrase = if let Some(ref id) = passphrase_ref {
                     self.keychain
                         .get_secret(id, master)
-                        .map_err(|e| CoreError::Invalid(e.to_string()))?
+                        .map_err(CoreError::from)?
                 } else {
                     None
                 };
@@ -306,7 +422,7 @@ impl SessionManager {
                 let passphrase = if let Some(ref id) = passphrase_ref {
                     self.keychain
                         .get_secret(id, master)
-                        .map_err(|e| CoreError::Invalid(e.to_string()))?
+                        .map_err(CoreError::from)?
                 } else {
                     None
                 };
@@ -1,17 +1,24 @@
+use crate::checksum::{hash_local_file, hash_remote_file, HashAlgorithm};
 use crate::error::CoreError;
 use crate::events::{Event, EventBus};
+use crate::remote_fs::RemoteFs;
 use crate::session::SessionManager;
 use catsolle_config::TransferConfig;
-use catsolle_ssh::SftpClient;
+use catsolle_ssh::shell;
+use catsolle_ssh::{ScpChannel, ScpDownload, SshSession};
 use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::sync::mpsc;
-use tracing::{error, info};
+use tokio::sync::{mpsc, watch};
+use tracing::{error, info, warn};
 use uuid::Uuid;
+use walkdir::WalkDir;
 
 #[derive(Clone, Debug)]
 pub enum TransferEndpoint {
@@ -35,6 +42,49 @@ pub struct TransferOptions {
     pub verify_checksum: bool,
     pub resume: bool,
     pub buffer_size: usize,
+    pub max_bytes_per_sec: Option<u64>,
+    /// Record a failed file and move on to the next one instead of failing the job.
+    pub continue_on_error: bool,
+}
+
+/// Protocol used for remote files. `Auto` prefers SFTP and falls back to SCP over `exec`
+/// when the server refuses the SFTP subsystem.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransferBackend {
+    #[default]
+    Auto,
+    Sftp,
+    Scp,
+}
+
+impl TransferBackend {
+    pub const ALL: [TransferBackend; 3] = [
+        TransferBackend::Auto,
+        TransferBackend::Sftp,
+        TransferBackend::Scp,
+    ];
+
+    pub fn as_key(&self) -> &'static str {
+        match self {
+            TransferBackend::Auto => "auto",
+            TransferBackend::Sftp => "sftp",
+            TransferBackend::Scp => "scp",
+        }
+    }
+
+    pub fn from_key(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "auto" => Some(TransferBackend::Auto),
+            "sftp" => Some(TransferBackend::Sftp),
+            "scp" => Some(TransferBackend::Scp),
+            _ => None,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|b| *b == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -45,7 +95,7 @@ pub enum OverwriteMode {
     IfNewer,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum TransferState {
     Queued,
     InProgress,
@@ -55,6 +105,13 @@ pub enum TransferState {
     Cancelled,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControlSignal {
+    Run,
+    Pause,
+    Cancel,
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct TransferProgress {
     pub bytes_transferred: u64,
@@ -66,6 +123,21 @@ pub struct TransferProgress {
     pub eta_seconds: Option<u64>,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FileOutcome {
+    Copied,
+    Skipped,
+    Failed { error: String },
+}
+
+/// What happened to one `TransferFile`; a job records one per file it got to.
+#[derive(Clone, Debug)]
+pub struct FileResult {
+    pub source_path: String,
+    pub dest_path: String,
+    pub outcome: FileOutcome,
+}
+
 #[derive(Clone, Debug)]
 pub struct TransferJob {
     pub id: Uuid,
@@ -75,6 +147,7 @@ pub struct TransferJob {
     pub options: TransferOptions,
     pub state: TransferState,
     pub progress: TransferProgress,
+    pub results: Vec<FileResult>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -84,24 +157,75 @@ struct CopyContext<'a> {
     started_at: Instant,
     last_emit: &'a mut Instant,
     last_bytes: &'a mut u64,
+    control: &'a mut watch::Receiver<ControlSignal>,
+    throttle: &'a mut Option<Throttle>,
+}
+
+/// Token bucket that lets a burst of up to `capacity` bytes through and then holds
+/// the average rate at `rate` bytes per second.
+struct Throttle {
+    rate: u64,
+    capacity: u64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Throttle {
+    fn new(rate: u64, capacity: u64, now: Instant) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            rate: rate.max(1),
+            capacity,
+            tokens: capacity as f64,
+            updated: now,
+        }
+    }
+
+    fn consume(&mut self, bytes: u64, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.updated = now;
+        self.tokens = (self.tokens + elapsed * self.rate as f64).min(self.capacity as f64);
+        self.tokens -= bytes as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate as f64)
+        }
+    }
 }
 
+type ControlMap = Arc<Mutex<HashMap<Uuid, watch::Sender<ControlSignal>>>>;
+
 #[derive(Clone)]
 pub struct TransferQueue {
-    sender: mpsc::Sender<TransferJob>,
+    sender: mpsc::Sender<(TransferJob, watch::Receiver<ControlSignal>)>,
+    controls: ControlMap,
 }
 
 impl TransferQueue {
     pub fn new(session_manager: Arc<SessionManager>, bus: EventBus, cfg: TransferConfig) -> Self {
-        let (tx, mut rx) = mpsc::channel::<TransferJob>(32);
+        let (tx, mut rx) = mpsc::channel::<(TransferJob, watch::Receiver<ControlSignal>)>(32);
+        let controls: ControlMap = Arc::new(Mutex::new(HashMap::new()));
+        let worker_controls = controls.clone();
         let options = cfg;
         tokio::spawn(async move {
-            while let Some(mut job) = rx.recv().await {
+            while let Some((mut job, mut control)) = rx.recv().await {
                 job.state = TransferState::InProgress;
                 info!(job_id = %job.id, "transfer start");
-                let result = process_job(&mut job, &session_manager, &bus, &options).await;
+                let result =
+                    process_job(&mut job, &session_manager, &bus, &options, &mut control).await;
+                worker_controls.lock().remove(&job.id);
+                let failed = job
+                    .results
+                    .iter()
+                    .filter(|r| matches!(r.outcome, FileOutcome::Failed { .. }))
+                    .count();
                 job.state = match result {
+                    Ok(_) if failed > 0 => TransferState::Failed {
+                        error: format!("{failed} of {} files failed", job.results.len()),
+                    },
                     Ok(_) => TransferState::Completed,
+                    Err(CoreError::Cancelled) => TransferState::Cancelled,
                     Err(err) => TransferState::Failed {
                         error: err.to_string(),
                     },
@@ -111,18 +235,221 @@ impl TransferQueue {
                 }
                 bus.send(Event::TransferProgress {
                     job_id: job.id,
+                    state: job.state.clone(),
                     progress: job.progress.clone(),
                 });
+                bus.send(Event::TransferFinished {
+                    job_id: job.id,
+                    state: job.state.clone(),
+                    progress: job.progress.clone(),
+                    results: job.results.clone(),
+                });
             }
         });
-        Self { sender: tx }
+        Self {
+            sender: tx,
+            controls,
+        }
     }
 
     pub async fn enqueue(&self, job: TransferJob) -> Result<(), CoreError> {
-        self.sender
-            .send(job)
+        let (control_tx, control_rx) = watch::channel(ControlSignal::Run);
+        let job_id = job.id;
+        self.controls.lock().insert(job_id, control_tx);
+        self.sender.send((job, control_rx)).await.map_err(|e| {
+            self.controls.lock().remove(&job_id);
+            CoreError::Invalid(e.to_string())
+        })
+    }
+
+    /// Stops a queued or running job between buffer writes until `resume` or `cancel`.
+    pub fn pause(&self, job_id: Uuid) -> Result<(), CoreError> {
+        self.signal(job_id, ControlSignal::Pause)
+    }
+
+    pub fn resume(&self, job_id: Uuid) -> Result<(), CoreError> {
+        self.signal(job_id, ControlSignal::Run)
+    }
+
+    pub fn cancel(&self, job_id: Uuid) -> Result<(), CoreError> {
+        self.signal(job_id, ControlSignal::Cancel)
+    }
+
+    fn signal(&self, job_id: Uuid, signal: ControlSignal) -> Result<(), CoreError> {
+        let controls = self.controls.lock();
+        let sender = controls.get(&job_id).ok_or(CoreError::NotFound)?;
+        sender.send_replace(signal);
+        Ok(())
+    }
+}
+
+/// Builds the file list for copying `source_path` to `dest_path`. Directories are
+/// walked recursively: every nested directory (including empty ones) precedes its
+/// contents, and symlinks to directories are skipped rather than followed.
+pub async fn expand_transfer_files(
+    session_manager: &SessionManager,
+    source: &TransferEndpoint,
+    dest: &TransferEndpoint,
+    source_path: &str,
+    dest_path: &str,
+) -> Result<Vec<TransferFile>, CoreError> {
+    let remote_dest = matches!(dest, TransferEndpoint::Remote { .. });
+    match source {
+        TransferEndpoint::Local { .. } => {
+            let source_path = PathBuf::from(source_path);
+            let dest_path = dest_path.to_string();
+            tokio::task::spawn_blocking(move || {
+                expand_local_tree(&source_path, &dest_path, remote_dest)
+            })
             .await
-            .map_err(|e| CoreError::Invalid(e.to_string()))
+            .map_err(|e| CoreError::Invalid(e.to_string()))?
+        }
+        TransferEndpoint::Remote { session_id, .. } => {
+            match open_remote_io(session_manager, *session_id).await? {
+                RemoteIo::Sftp(sftp) => {
+                    expand_remote_tree(sftp.as_ref(), source_path, dest_path, remote_dest).await
+                }
+                // SCP cannot list directories; the size arrives with the file header.
+                RemoteIo::Scp(_) => Ok(vec![TransferFile {
+                    source_path: source_path.to_string(),
+                    dest_path: dest_path.to_string(),
+                    size: 0,
+                    is_dir: false,
+                }]),
+            }
+        }
+    }
+}
+
+pub fn expand_local_tree(
+    source: &Path,
+    dest: &str,
+    remote_dest: bool,
+) -> Result<Vec<TransferFile>, CoreError> {
+    let meta = std::fs::metadata(source)?;
+    if !meta.is_dir() {
+        return Ok(vec![TransferFile {
+            source_path: source.to_string_lossy().to_string(),
+            dest_path: dest.to_string(),
+            size: meta.len(),
+            is_dir: false,
+        }]);
+    }
+    let mut files = Vec::new();
+    for entry in WalkDir::new(source).follow_links(false) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                warn!(error = %err, "skipping unreadable entry");
+                continue;
+            }
+        };
+        let rel = entry
+            .path()
+            .strip_prefix(source)
+            .map_err(|e| CoreError::Invalid(e.to_string()))?;
+        let rel_parts: Vec<String> = rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        let dest_path = join_relative(dest, &rel_parts, remote_dest);
+        let file_type = entry.file_type();
+        let (is_dir, size) = if file_type.is_symlink() {
+            match std::fs::metadata(entry.path()) {
+                Ok(target) if target.is_file() => (false, target.len()),
+                _ => continue,
+            }
+        } else if file_type.is_dir() {
+            (true, 0)
+        } else {
+            (false, entry.metadata().map(|m| m.len()).unwrap_or(0))
+        };
+        files.push(TransferFile {
+            source_path: entry.path().to_string_lossy().to_string(),
+            dest_path,
+            size,
+            is_dir,
+        });
+    }
+    Ok(files)
+}
+
+pub async fn expand_remote_tree(
+    sftp: &dyn RemoteFs,
+    source: &str,
+    dest: &str,
+    remote_dest: bool,
+) -> Result<Vec<TransferFile>, CoreError> {
+    let meta = sftp.metadata(source).await?;
+    if !meta.is_dir {
+        return Ok(vec![TransferFile {
+            source_path: source.to_string(),
+            dest_path: dest.to_string(),
+            size: meta.size.unwrap_or(0),
+            is_dir: false,
+        }]);
+    }
+    let mut files = vec![TransferFile {
+        source_path: source.to_string(),
+        dest_path: dest.to_string(),
+        size: 0,
+        is_dir: true,
+    }];
+    let mut stack = vec![(source.to_string(), Vec::<String>::new())];
+    while let Some((dir, rel)) = stack.pop() {
+        let mut entries = sftp.read_dir(&dir).await?;
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        for entry in entries {
+            let mut rel_parts = rel.clone();
+            rel_parts.push(entry.name.clone());
+            let dest_path = join_relative(dest, &rel_parts, remote_dest);
+            if entry.is_symlink {
+                match sftp.metadata(&entry.path).await {
+                    Ok(target) if !target.is_dir => {
+                        files.push(TransferFile {
+                            source_path: entry.path,
+                            dest_path,
+                            size: target.size.unwrap_or(0),
+                            is_dir: false,
+                        });
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            files.push(TransferFile {
+                source_path: entry.path.clone(),
+                dest_path,
+                size: if entry.is_dir { 0 } else { entry.size },
+                is_dir: entry.is_dir,
+            });
+            if entry.is_dir {
+                stack.push((entry.path, rel_parts));
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn join_relative(base: &str, parts: &[String], remote: bool) -> String {
+    if remote {
+        let mut out = base.trim_end_matches('/').to_string();
+        if out.is_empty() && base.starts_with('/') {
+            out.push('/');
+        }
+        for part in parts {
+            if !out.ends_with('/') {
+                out.push('/');
+            }
+            out.push_str(part);
+        }
+        out
+    } else {
+        let mut out = PathBuf::from(base);
+        for part in parts {
+            out.push(part);
+        }
+        out.to_string_lossy().to_string()
     }
 }
 
@@ -131,108 +458,282 @@ async fn process_job(
     session_manager: &SessionManager,
     bus: &EventBus,
     cfg: &TransferConfig,
+    control: &mut watch::Receiver<ControlSignal>,
+) -> Result<(), CoreError> {
+    let remote = match (&job.source, &job.dest) {
+        (_, TransferEndpoint::Remote { session_id, .. })
+        | (TransferEndpoint::Remote { session_id, .. }, _) => {
+            Some(open_remote_io(session_manager, *session_id).await?)
+        }
+        _ => None,
+    };
+    run_job(job, remote.as_ref(), bus, cfg, control).await
+}
+
+/// Copies every file of `job` over an already opened remote side.
+async fn run_job(
+    job: &mut TransferJob,
+    remote: Option<&RemoteIo>,
+    bus: &EventBus,
+    cfg: &TransferConfig,
+    control: &mut watch::Receiver<ControlSignal>,
 ) -> Result<(), CoreError> {
     let started_at = Instant::now();
     let mut last_emit = Instant::now();
     let mut last_bytes = 0u64;
+    let mut throttle = job
+        .options
+        .max_bytes_per_sec
+        .filter(|rate| *rate > 0)
+        .map(|rate| Throttle::new(rate, cfg.buffer_size as u64, started_at));
 
     let total_bytes: u64 = job.files.iter().map(|f| f.size).sum();
     job.progress.bytes_total = total_bytes;
     job.progress.files_total = job.files.len();
     update_progress(job, bus, started_at, &mut last_emit, &mut last_bytes, true);
 
+    if let (TransferEndpoint::Local { .. }, Some(RemoteIo::Sftp(sftp))) = (&job.source, remote) {
+        check_remote_dest(job, sftp.as_ref()).await?;
+    }
+
     let files = job.files.clone();
     for file in files {
         job.progress.current_file = Some(file.source_path.clone());
         update_progress(job, bus, started_at, &mut last_emit, &mut last_bytes, true);
-        match (&job.source, &job.dest) {
-            (TransferEndpoint::Local { .. }, TransferEndpoint::Remote { session_id, .. }) => {
-                let session = session_manager
-                    .get_session(*session_id)
-                    .ok_or_else(|| CoreError::Invalid("session not found".to_string()))?;
-                let sftp = session
-                    .session
-                    .open_sftp()
-                    .await
-                    .map_err(|e| CoreError::Ssh(e.to_string()))?;
-                {
-                    let mut ctx = CopyContext {
-                        cfg,
-                        bus,
-                        started_at,
-                        last_emit: &mut last_emit,
-                        last_bytes: &mut last_bytes,
-                    };
-                    copy_local_to_remote(job, &file, &sftp, &mut ctx).await?;
+        let outcome = {
+            let mut ctx = CopyContext {
+                cfg,
+                bus,
+                started_at,
+                last_emit: &mut last_emit,
+                last_bytes: &mut last_bytes,
+                control,
+                throttle: &mut throttle,
+            };
+            wait_for_control(job, &mut ctx).await?;
+            transfer_file(job, &file, remote, &mut ctx).await
+        };
+        let outcome = match outcome {
+            Ok(outcome) => outcome,
+            Err(CoreError::Cancelled) => return Err(CoreError::Cancelled),
+            Err(err) => {
+                warn!(job_id = %job.id, path = %file.source_path, error = %err, "file transfer failed");
+                let outcome = FileOutcome::Failed {
+                    error: err.to_string(),
+                };
+                if !job.options.continue_on_error {
+                    job.results.push(file_result(&file, outcome));
+                    return Err(err);
                 }
+                outcome
             }
-            (TransferEndpoint::Remote { session_id, .. }, TransferEndpoint::Local { .. }) => {
-                let session = session_manager
-                    .get_session(*session_id)
-                    .ok_or_else(|| CoreError::Invalid("session not found".to_string()))?;
-                let sftp = session
-                    .session
-                    .open_sftp()
-                    .await
-                    .map_err(|e| CoreError::Ssh(e.to_string()))?;
-                {
-                    let mut ctx = CopyContext {
-                        cfg,
-                        bus,
-                        started_at,
-                        last_emit: &mut last_emit,
-                        last_bytes: &mut last_bytes,
-                    };
-                    copy_remote_to_local(job, &file, &sftp, &mut ctx).await?;
-                }
+        };
+        job.results.push(file_result(&file, outcome));
+        job.progress.files_completed += 1;
+        update_progress(job, bus, started_at, &mut last_emit, &mut last_bytes, true);
+    }
+
+    Ok(())
+}
+
+fn file_result(file: &TransferFile, outcome: FileOutcome) -> FileResult {
+    FileResult {
+        source_path: file.source_path.clone(),
+        dest_path: file.dest_path.clone(),
+        outcome,
+    }
+}
+
+/// Copies one file or creates one directory. `CoreError::Cancelled` always stops the
+/// whole job; other errors only do unless `continue_on_error` is set.
+async fn transfer_file(
+    job: &mut TransferJob,
+    file: &TransferFile,
+    remote: Option<&RemoteIo>,
+    ctx: &mut CopyContext<'_>,
+) -> Result<FileOutcome, CoreError> {
+    let skip_existing = job.options.overwrite == OverwriteMode::Skip && !file.is_dir;
+    let if_newer = job.options.overwrite == OverwriteMode::IfNewer && !file.is_dir;
+    match (&job.source, &job.dest, remote) {
+        (TransferEndpoint::Local { .. }, TransferEndpoint::Remote { .. }, Some(remote)) => {
+            if skip_existing && remote.exists(&file.dest_path).await {
+                return Ok(FileOutcome::Skipped);
             }
-            (TransferEndpoint::Local { .. }, TransferEndpoint::Local { .. }) => {
-                let mut ctx = CopyContext {
-                    cfg,
-                    bus,
-                    started_at,
-                    last_emit: &mut last_emit,
-                    last_bytes: &mut last_bytes,
-                };
-                copy_local_to_local(job, &file, &mut ctx).await?;
+            if if_newer
+                && !source_is_newer(
+                    local_mtime(&file.source_path).await,
+                    remote.mtime(&file.dest_path).await,
+                )
+            {
+                return Ok(FileOutcome::Skipped);
             }
-            _ => {
-                return Err(CoreError::Invalid(
-                    "remote to remote copy not supported".to_string(),
-                ));
+            match remote {
+                RemoteIo::Sftp(sftp) => copy_local_to_remote(job, file, sftp.as_ref(), ctx).await?,
+                RemoteIo::Scp(session) => scp_upload(job, file, session, ctx).await?,
             }
         }
-        job.progress.files_completed += 1;
-        update_progress(job, bus, started_at, &mut last_emit, &mut last_bytes, true);
+        (TransferEndpoint::Remote { .. }, TransferEndpoint::Local { .. }, Some(remote)) => {
+            if skip_existing && tokio::fs::try_exists(&file.dest_path).await? {
+                return Ok(FileOutcome::Skipped);
+            }
+            if if_newer
+                && !source_is_newer(
+                    remote.mtime(&file.source_path).await,
+                    local_mtime(&file.dest_path).await,
+                )
+            {
+                return Ok(FileOutcome::Skipped);
+            }
+            match remote {
+                RemoteIo::Sftp(sftp) => copy_remote_to_local(job, file, sftp.as_ref(), ctx).await?,
+                RemoteIo::Scp(session) => scp_download(job, file, session, ctx).await?,
+            }
+        }
+        (TransferEndpoint::Local { .. }, TransferEndpoint::Local { .. }, _) => {
+            if skip_existing && tokio::fs::try_exists(&file.dest_path).await? {
+                return Ok(FileOutcome::Skipped);
+            }
+            if if_newer
+                && !source_is_newer(
+                    local_mtime(&file.source_path).await,
+                    local_mtime(&file.dest_path).await,
+                )
+            {
+                return Ok(FileOutcome::Skipped);
+            }
+            copy_local_to_local(job, file, ctx).await?;
+        }
+        _ => {
+            return Err(CoreError::Invalid(
+                "remote to remote copy not supported".to_string(),
+            ));
+        }
     }
+    Ok(FileOutcome::Copied)
+}
 
-    Ok(())
+/// `IfNewer` copies unless the destination exists with an mtime at least as recent as the
+/// source's. A missing destination or an unknown time on either side means copy.
+fn source_is_newer(source: Option<u64>, dest: Option<u64>) -> bool {
+    match (source, dest) {
+        (Some(source), Some(dest)) => source > dest,
+        _ => true,
+    }
+}
+
+async fn local_mtime(path: &str) -> Option<u64> {
+    let modified = tokio::fs::metadata(path).await.ok()?.modified().ok()?;
+    Some(
+        modified
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_secs(),
+    )
+}
+
+/// Refuses an upload up front when the destination cannot take it, rather than failing
+/// halfway and leaving partial files behind.
+async fn check_remote_dest(job: &TransferJob, sftp: &dyn RemoteFs) -> Result<(), CoreError> {
+    let Some(root) = job
+        .files
+        .iter()
+        .map(|f| f.dest_path.as_str())
+        .min_by_key(|p| p.len())
+    else {
+        return Ok(());
+    };
+    let dir = match root.trim_end_matches('/').rsplit_once('/') {
+        Some(("", _)) | None => "/",
+        Some((parent, _)) => parent,
+    };
+    let bytes = job.files.iter().map(|f| f.size).sum();
+    sftp.check_writable(dir, bytes).await
+}
+
+/// Remote side of a job, opened once per job with the connection's `TransferBackend`.
+enum RemoteIo {
+    Sftp(Box<dyn RemoteFs>),
+    Scp(SshSession),
+}
+
+impl RemoteIo {
+    async fn exists(&self, path: &str) -> bool {
+        match self {
+            RemoteIo::Sftp(sftp) => sftp.metadata(path).await.is_ok(),
+            RemoteIo::Scp(session) => session
+                .exec(&format!("test -e {}", shell::quote(path)))
+                .await
+                .is_ok_and(|(status, _)| status == 0),
+        }
+    }
+
+    /// Modification time in seconds since the epoch; `None` if missing or unknown.
+    async fn mtime(&self, path: &str) -> Option<u64> {
+        match self {
+            RemoteIo::Sftp(sftp) => sftp.metadata(path).await.ok()?.mtime,
+            RemoteIo::Scp(session) => {
+                let quoted = shell::quote(path);
+                // GNU stat first, then the BSD spelling.
+                let command =
+                    format!("stat -c %Y {quoted} 2>/dev/null || stat -f %m {quoted} 2>/dev/null");
+                match session.exec(&command).await {
+                    Ok((0, output)) => String::from_utf8_lossy(&output).trim().parse().ok(),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+async fn open_remote_io(
+    session_manager: &SessionManager,
+    session_id: Uuid,
+) -> Result<RemoteIo, CoreError> {
+    let handle = session_manager
+        .get_session(session_id)
+        .ok_or_else(|| CoreError::Invalid("session not found".to_string()))?;
+    let backend = session_manager.transfer_backend(session_id).await;
+    if backend == TransferBackend::Scp {
+        return Ok(RemoteIo::Scp(handle.session));
+    }
+    match handle.session.open_sftp().await {
+        Ok(sftp) => Ok(RemoteIo::Sftp(Box::new(sftp))),
+        Err(err) if backend == TransferBackend::Auto => {
+            warn!(%session_id, error = %err, "sftp unavailable, falling back to scp");
+            Ok(RemoteIo::Scp(handle.session))
+        }
+        Err(err) => Err(CoreError::Ssh(err.to_string())),
+    }
 }
 
 async fn copy_local_to_remote(
     job: &mut TransferJob,
     file: &TransferFile,
-    sftp: &SftpClient,
+    sftp: &dyn RemoteFs,
     ctx: &mut CopyContext<'_>,
 ) -> Result<(), CoreError> {
     let src = PathBuf::from(&file.source_path);
     let dest = &file.dest_path;
     if file.is_dir {
-        sftp.create_dir_all(dest)
-            .await
-            .map_err(|e| CoreError::Ssh(e.to_string()))?;
+        sftp.create_dir_all(dest).await?;
         return Ok(());
     }
 
     let mut local = tokio::fs::File::open(&src).await?;
     let mut remote = sftp
-        .open_write(dest, job.options.overwrite == OverwriteMode::Replace)
-        .await
-        .map_err(|e| CoreError::Ssh(e.to_string()))?;
+        .open_write(
+            dest,
+            matches!(
+                job.options.overwrite,
+                OverwriteMode::Replace | OverwriteMode::IfNewer
+            ),
+        )
+        .await?;
 
     let mut buf = vec![0u8; ctx.cfg.buffer_size];
     let mut hasher = Sha256::new();
     loop {
+        wait_for_control(job, ctx).await?;
         let n = local.read(&mut buf).await?;
         if n == 0 {
             break;
@@ -253,11 +754,12 @@ async fn copy_local_to_remote(
             ctx.last_bytes,
             false,
         );
+        throttle_chunk(ctx, n).await;
     }
 
     if job.options.verify_checksum {
         let local_hash = hasher.finalize();
-        let remote_hash = hash_remote(sftp, dest).await?;
+        let remote_hash = hash_remote_file(sftp, dest, HashAlgorithm::Sha256).await?;
         if local_hash.as_slice() != remote_hash.as_slice() {
             return Err(CoreError::Invalid("checksum mismatch".to_string()));
         }
@@ -269,7 +771,7 @@ async fn copy_local_to_remote(
 async fn copy_remote_to_local(
     job: &mut TransferJob,
     file: &TransferFile,
-    sftp: &SftpClient,
+    sftp: &dyn RemoteFs,
     ctx: &mut CopyContext<'_>,
 ) -> Result<(), CoreError> {
     let dest = PathBuf::from(&file.dest_path);
@@ -282,15 +784,104 @@ async fn copy_remote_to_local(
         tokio::fs::create_dir_all(parent).await?;
     }
 
-    let mut remote = sftp
-        .open_read(&file.source_path)
-        .await
-        .map_err(|e| CoreError::Ssh(e.to_string()))?;
-    let mut local = tokio::fs::File::create(&dest).await?;
+    // Downloads land in `<name>.part` and are renamed into place once complete, so resume
+    // only ever continues bytes an earlier run of this copy wrote, never an unrelated file.
+    let part = partial_path(&dest);
+    let mut offset = if job.options.resume {
+        resume_offset(file, sftp, &part).await?
+    } else {
+        0
+    };
+    loop {
+        let counted_before = job.progress.bytes_transferred;
+        let stream_hash = download_from(job, file, sftp, ctx, &part, offset).await?;
+        if !job.options.verify_checksum {
+            break;
+        }
+        // A resumed download only streamed the tail, so compare whole files on both sides.
+        let expected = match stream_hash {
+            Some(hash) => hash,
+            None => hash_remote_file(sftp, &file.source_path, HashAlgorithm::Sha256).await?,
+        };
+        let local_hash = hash_local_file(&part, HashAlgorithm::Sha256).await?;
+        if expected.as_slice() == local_hash.as_slice() {
+            break;
+        }
+        if offset == 0 {
+            let _ = tokio::fs::remove_file(&part).await;
+            return Err(CoreError::Invalid("checksum mismatch".to_string()));
+        }
+        warn!(
+            job_id = %job.id,
+            path = %file.source_path,
+            "resumed download failed verification, restarting"
+        );
+        job.progress.bytes_transferred = counted_before;
+        offset = 0;
+    }
+    tokio::fs::rename(&part, &dest).await?;
+    Ok(())
+}
+
+/// Where a download is staged until it completes: `<name>.part` next to the destination.
+fn partial_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest.with_file_name(name)
+}
+
+/// Size of a partial download to continue from, or zero when there is nothing usable
+/// (missing, not a regular file, or larger than the remote source).
+async fn resume_offset(
+    file: &TransferFile,
+    sftp: &dyn RemoteFs,
+    part: &Path,
+) -> Result<u64, CoreError> {
+    let local_len = match tokio::fs::metadata(part).await {
+        Ok(meta) if meta.is_file() => meta.len(),
+        _ => return Ok(0),
+    };
+    if local_len == 0 {
+        return Ok(0);
+    }
+    let remote_len = sftp
+        .metadata(&file.source_path)
+        .await?
+        .size
+        .unwrap_or(file.size);
+    Ok(if local_len <= remote_len {
+        local_len
+    } else {
+        0
+    })
+}
+
+/// Copies the remote file into `dest` starting at `offset`, returning the hash of the
+/// streamed bytes when they cover the whole file.
+async fn download_from(
+    job: &mut TransferJob,
+    file: &TransferFile,
+    sftp: &dyn RemoteFs,
+    ctx: &mut CopyContext<'_>,
+    dest: &Path,
+    offset: u64,
+) -> Result<Option<Vec<u8>>, CoreError> {
+    let mut remote = sftp.open_read_at(&file.source_path, offset).await?;
+    let mut local = if offset > 0 {
+        info!(job_id = %job.id, path = %file.source_path, offset, "resuming download");
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(dest)
+            .await?
+    } else {
+        tokio::fs::File::create(dest).await?
+    };
+    job.progress.bytes_transferred += offset;
     let mut buf = vec![0u8; ctx.cfg.buffer_size];
     let mut hasher = Sha256::new();
 
     loop {
+        wait_for_control(job, ctx).await?;
         let n = remote
             .read(&mut buf)
             .await
@@ -311,19 +902,168 @@ async fn copy_remote_to_local(
             ctx.last_bytes,
             false,
         );
+        throttle_chunk(ctx, n).await;
     }
+    local.flush().await?;
 
-    if job.options.verify_checksum {
-        let remote_hash = hasher.finalize();
-        let local_hash = hash_local(&dest).await?;
-        if remote_hash.as_slice() != local_hash.as_slice() {
-            return Err(CoreError::Invalid("checksum mismatch".to_string()));
+    Ok((offset == 0).then(|| hasher.finalize().to_vec()))
+}
+
+/// Uploads over `scp -t`. SCP has no random access or remote hashing, so `resume` and
+/// `verify_checksum` only apply to SFTP.
+async fn scp_upload(
+    job: &mut TransferJob,
+    file: &TransferFile,
+    session: &SshSession,
+    ctx: &mut CopyContext<'_>,
+) -> Result<(), CoreError> {
+    if file.is_dir {
+        let (status, output) = session
+            .exec(&format!("mkdir -p -- {}", shell::quote(&file.dest_path)))
+            .await
+            .map_err(|e| CoreError::Ssh(e.to_string()))?;
+        if status != 0 {
+            return Err(CoreError::Ssh(
+                String::from_utf8_lossy(&output).trim().to_string(),
+            ));
+        }
+        return Ok(());
+    }
+
+    let mut local = tokio::fs::File::open(&file.source_path).await?;
+    let meta = local.metadata().await?;
+    let mut remaining = meta.len();
+    let mode = if job.options.preserve_permissions {
+        local_mode(&meta)
+    } else {
+        0o644
+    };
+    let mut upload = session
+        .open_scp_upload(&file.dest_path, mode, remaining)
+        .await
+        .map_err(|e| CoreError::Ssh(e.to_string()))?;
+
+    let mut buf = vec![0u8; ctx.cfg.buffer_size];
+    while remaining > 0 {
+        wait_for_control(job, ctx).await?;
+        let want = buf.len().min(remaining.try_into().unwrap_or(usize::MAX));
+        let n = local.read(&mut buf[..want]).await?;
+        if n == 0 {
+            return Err(CoreError::Invalid(format!(
+                "{} shrank during upload",
+                file.source_path
+            )));
         }
+        upload
+            .write(&buf[..n])
+            .await
+            .map_err(|e| CoreError::Ssh(e.to_string()))?;
+        remaining -= n as u64;
+        job.progress.bytes_transferred += n as u64;
+        update_progress(
+            job,
+            ctx.bus,
+            ctx.started_at,
+            ctx.last_emit,
+            ctx.last_bytes,
+            false,
+        );
+        throttle_chunk(ctx, n).await;
     }
+    upload
+        .finish()
+        .await
+        .map_err(|e| CoreError::Ssh(e.to_string()))
+}
 
+async fn scp_download(
+    job: &mut TransferJob,
+    file: &TransferFile,
+    session: &SshSession,
+    ctx: &mut CopyContext<'_>,
+) -> Result<(), CoreError> {
+    let dest = PathBuf::from(&file.dest_path);
+    if file.is_dir {
+        tokio::fs::create_dir_all(&dest).await?;
+        return Ok(());
+    }
+    if let Some(parent) = dest.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let (download, header) = session
+        .open_scp_download(&file.source_path)
+        .await
+        .map_err(|e| CoreError::Ssh(e.to_string()))?;
+    if file.size == 0 {
+        job.progress.bytes_total += header.size;
+    }
+    // Stage into `<name>.part` like SFTP downloads, so a failed or cancelled copy never
+    // truncates an existing destination. SCP cannot seek, so the part file is not resumed.
+    let part = partial_path(&dest);
+    if let Err(err) = scp_receive(job, ctx, download, &part).await {
+        let _ = tokio::fs::remove_file(&part).await;
+        return Err(err);
+    }
+
+    #[cfg(unix)]
+    if job.options.preserve_permissions {
+        use std::os::unix::fs::PermissionsExt;
+        let perms = std::fs::Permissions::from_mode(header.mode & 0o7777);
+        tokio::fs::set_permissions(&part, perms).await?;
+    }
+    tokio::fs::rename(&part, &dest).await?;
     Ok(())
 }
 
+/// Streams the SCP file body into `part`, truncating whatever was there.
+async fn scp_receive(
+    job: &mut TransferJob,
+    ctx: &mut CopyContext<'_>,
+    mut download: ScpDownload<ScpChannel>,
+    part: &Path,
+) -> Result<(), CoreError> {
+    let mut local = tokio::fs::File::create(part).await?;
+    let mut buf = vec![0u8; ctx.cfg.buffer_size];
+    loop {
+        wait_for_control(job, ctx).await?;
+        let n = download
+            .read(&mut buf)
+            .await
+            .map_err(|e| CoreError::Ssh(e.to_string()))?;
+        if n == 0 {
+            break;
+        }
+        local.write_all(&buf[..n]).await?;
+        job.progress.bytes_transferred += n as u64;
+        update_progress(
+            job,
+            ctx.bus,
+            ctx.started_at,
+            ctx.last_emit,
+            ctx.last_bytes,
+            false,
+        );
+        throttle_chunk(ctx, n).await;
+    }
+    local.flush().await?;
+    download
+        .finish()
+        .await
+        .map_err(|e| CoreError::Ssh(e.to_string()))
+}
+
+#[cfg(unix)]
+fn local_mode(meta: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode()
+}
+
+#[cfg(not(unix))]
+fn local_mode(_meta: &std::fs::Metadata) -> u32 {
+    0o644
+}
+
 async fn copy_local_to_local(
     job: &mut TransferJob,
     file: &TransferFile,
@@ -351,6 +1091,69 @@ async fn copy_local_to_local(
     Ok(())
 }
 
+/// Sleeps off any throughput debt, waking early if the job is paused or cancelled.
+async fn throttle_chunk(ctx: &mut CopyContext<'_>, bytes: usize) {
+    let Some(throttle) = ctx.throttle.as_mut() else {
+        return;
+    };
+    let delay = throttle.consume(bytes as u64, Instant::now());
+    if delay.is_zero() {
+        return;
+    }
+    tokio::select! {
+        _ = tokio::time::sleep(delay) => {}
+        _ = ctx.control.changed() => {}
+    }
+}
+
+/// Blocks while the job is paused and fails with `CoreError::Cancelled` once it is cancelled.
+async fn wait_for_control(
+    job: &mut TransferJob,
+    ctx: &mut CopyContext<'_>,
+) -> Result<(), CoreError> {
+    loop {
+        let signal = *ctx.control.borrow_and_update();
+        match signal {
+            ControlSignal::Run => {
+                if matches!(job.state, TransferState::Paused) {
+                    job.state = TransferState::InProgress;
+                    info!(job_id = %job.id, "transfer resumed");
+                    update_progress(
+                        job,
+                        ctx.bus,
+                        ctx.started_at,
+                        ctx.last_emit,
+                        ctx.last_bytes,
+                        true,
+                    );
+                }
+                return Ok(());
+            }
+            ControlSignal::Cancel => {
+                info!(job_id = %job.id, "transfer cancelled");
+                return Err(CoreError::Cancelled);
+            }
+            ControlSignal::Pause => {
+                if !matches!(job.state, TransferState::Paused) {
+                    job.state = TransferState::Paused;
+                    info!(job_id = %job.id, "transfer paused");
+                    update_progress(
+                        job,
+                        ctx.bus,
+                        ctx.started_at,
+                        ctx.last_emit,
+                        ctx.last_bytes,
+                        true,
+                    );
+                }
+                if ctx.control.changed().await.is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
 fn update_progress(
     job: &mut TransferJob,
     bus: &EventBus,
@@ -385,6 +1188,7 @@ fn update_progress(
     if should_emit {
         bus.send(Event::TransferProgress {
             job_id: job.id,
+            state: job.state.clone(),
             progress: job.progress.clone(),
         });
         *last_emit = now;
@@ -392,36 +1196,459 @@ fn update_progress(
     }
 }
 
-async fn hash_remote(sftp: &SftpClient, path: &str) -> Result<Vec<u8>, CoreError> {
-    let mut remote = sftp
-        .open_read(path)
+/// Binary units with one decimal, e.g. `1.5MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const KIB: f64 = 1024.0;
+    const MIB: f64 = KIB * 1024.0;
+    const GIB: f64 = MIB * 1024.0;
+    let b = bytes as f64;
+    if b >= GIB {
+        format!("{:.1}GiB", b / GIB)
+    } else if b >= MIB {
+        format!("{:.1}MiB", b / MIB)
+    } else if b >= KIB {
+        format!("{:.1}KiB", b / KIB)
+    } else {
+        format!("{}B", bytes)
+    }
+}
+
+/// Short countdown form for ETAs: `1h05m`, `3m07s`, `42s`.
+pub fn format_duration(secs: u64) -> String {
+    let hours = secs / 3600;
+    let minutes = (secs % 3600) / 60;
+    let seconds = secs % 60;
+    if hours > 0 {
+        format!("{hours}h{minutes:02}m")
+    } else if minutes > 0 {
+        format!("{minutes}m{seconds:02}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_fs::MemoryFs;
+
+    #[test]
+    fn expands_local_directory_recursively() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("src");
+        std::fs::create_dir_all(root.join("nested/empty")).unwrap();
+        std::fs::write(root.join("a.txt"), b"hello").unwrap();
+        std::fs::write(root.join("nested/b.txt"), b"abc").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&root, root.join("nested/loop")).unwrap();
+
+        let files = expand_local_tree(&root, "/srv/dst", true).unwrap();
+        let mut dests: Vec<_> = files
+            .iter()
+            .map(|f| (f.dest_path.as_str(), f.is_dir, f.size))
+            .collect();
+        dests.sort();
+        assert_eq!(
+            dests,
+            vec![
+                ("/srv/dst", true, 0),
+                ("/srv/dst/a.txt", false, 5),
+                ("/srv/dst/nested", true, 0),
+                ("/srv/dst/nested/b.txt", false, 3),
+                ("/srv/dst/nested/empty", true, 0),
+            ]
+        );
+        let total: u64 = files.iter().map(|f| f.size).sum();
+        assert_eq!(total, 8);
+    }
+
+    fn local_job() -> TransferJob {
+        TransferJob {
+            id: Uuid::new_v4(),
+            source: TransferEndpoint::Local {
+                path: PathBuf::from("/tmp/src"),
+            },
+            dest: TransferEndpoint::Local {
+                path: PathBuf::from("/tmp/dst"),
+            },
+            files: Vec::new(),
+            options: TransferOptions {
+                overwrite: OverwriteMode::Replace,
+                preserve_permissions: false,
+                preserve_times: false,
+                verify_checksum: false,
+                resume: false,
+                buffer_size: 1024,
+                max_bytes_per_sec: None,
+                continue_on_error: false,
+            },
+            state: TransferState::InProgress,
+            progress: TransferProgress::default(),
+            results: Vec::new(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn paused_job_waits_for_resume_or_cancel() {
+        let cfg = TransferConfig::default();
+        let bus = EventBus::new(16);
+        let mut events = bus.subscribe();
+        let mut job = local_job();
+        let (control_tx, mut control_rx) = watch::channel(ControlSignal::Pause);
+        let mut last_emit = Instant::now();
+        let mut last_bytes = 0;
+        let mut throttle = None;
+        let mut ctx = CopyContext {
+            cfg: &cfg,
+            bus: &bus,
+            started_at: Instant::now(),
+            last_emit: &mut last_emit,
+            last_bytes: &mut last_bytes,
+            control: &mut control_rx,
+            throttle: &mut throttle,
+        };
+
+        let resume = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            control_tx.send_replace(ControlSignal::Run);
+            control_tx
+        });
+        wait_for_control(&mut job, &mut ctx).await.unwrap();
+        assert_eq!(job.state, TransferState::InProgress);
+        let states: Vec<_> = std::iter::from_fn(|| match events.try_recv() {
+            Ok(Event::TransferProgress { state, .. }) => Some(state),
+            _ => None,
+        })
+        .collect();
+        assert_eq!(
+            states,
+            vec![TransferState::Paused, TransferState::InProgress]
+        );
+
+        let control_tx = resume.await.unwrap();
+        control_tx.send_replace(ControlSignal::Cancel);
+        assert!(matches!(
+            wait_for_control(&mut job, &mut ctx).await,
+            Err(CoreError::Cancelled)
+        ));
+    }
+
+    #[tokio::test]
+    async fn stops_or_continues_after_a_failed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        let dst = dir.path().join("dst");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::create_dir_all(&dst).unwrap();
+        std::fs::write(src.join("a.txt"), b"new").unwrap();
+        std::fs::write(src.join("b.txt"), b"new").unwrap();
+        std::fs::write(dst.join("b.txt"), b"old").unwrap();
+        let file = |name: &str| TransferFile {
+            source_path: src.join(name).to_string_lossy().to_string(),
+            dest_path: dst.join(name).to_string_lossy().to_string(),
+            size: 3,
+            is_dir: false,
+        };
+
+        let bus = EventBus::new(64);
+        let manager = SessionManager::new(
+            crate::ConnectionStore::new(dir.path().join("db.sqlite")),
+            catsolle_keychain::KeychainManager::new(
+                "catsolle-test",
+                dir.path().join("secrets"),
+                false,
+            ),
+            catsolle_config::AppConfig::default(),
+            bus.clone(),
+        );
+        let mut job = local_job();
+        job.options.overwrite = OverwriteMode::Skip;
+        job.files = vec![file("missing.txt"), file("a.txt"), file("b.txt")];
+        let (_control_tx, mut control_rx) = watch::channel(ControlSignal::Run);
+        let cfg = TransferConfig::default();
+
+        let stopped = process_job(&mut job, &manager, &bus, &cfg, &mut control_rx).await;
+        assert!(stopped.is_err());
+        assert_eq!(job.results.len(), 1);
+        assert!(!dst.join("a.txt").exists());
+
+        job.results.clear();
+        job.progress = TransferProgress::default();
+        job.options.continue_on_error = true;
+        process_job(&mut job, &manager, &bus, &cfg, &mut control_rx)
+            .await
+            .unwrap();
+
+        let outcomes: Vec<_> = job.results.iter().map(|r| &r.outcome).collect();
+        assert!(matches!(outcomes[0], FileOutcome::Failed { .. }));
+        assert_eq!(outcomes[1..], [&FileOutcome::Copied, &FileOutcome::Skipped]);
+        assert_eq!(job.progress.files_completed, 3);
+        assert_eq!(std::fs::read(dst.join("a.txt")).unwrap(), b"new");
+        assert_eq!(std::fs::read(dst.join("b.txt")).unwrap(), b"old");
+    }
+
+    #[tokio::test]
+    async fn if_newer_skips_up_to_date_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        let dst = dir.path().join("dst");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::create_dir_all(&dst).unwrap();
+        let set_mtime = |path: &Path, secs: u64| {
+            let file = std::fs::File::options().write(true).open(path).unwrap();
+            file.set_modified(std::time::UNIX_EPOCH + Duration::from_secs(secs))
+                .unwrap();
+        };
+        for (name, src_secs, dst_secs) in [
+            ("stale.txt", 2000, Some(1000)),
+            ("fresh.txt", 1000, Some(2000)),
+            ("new.txt", 1000, None),
+        ] {
+            std::fs::write(src.join(name), b"src").unwrap();
+            set_mtime(&src.join(name), src_secs);
+            if let Some(secs) = dst_secs {
+                std::fs::write(dst.join(name), b"dst").unwrap();
+                set_mtime(&dst.join(name), secs);
+            }
+        }
+        let file = |name: &str| TransferFile {
+            source_path: src.join(name).to_string_lossy().to_string(),
+            dest_path: dst.join(name).to_string_lossy().to_string(),
+            size: 3,
+            is_dir: false,
+        };
+
+        let bus = EventBus::new(64);
+        let manager = SessionManager::new(
+            crate::ConnectionStore::new(dir.path().join("db.sqlite")),
+            catsolle_keychain::KeychainManager::new(
+                "catsolle-test",
+                dir.path().join("secrets"),
+                false,
+            ),
+            catsolle_config::AppConfig::default(),
+            bus.clone(),
+        );
+        let mut job = local_job();
+        job.options.overwrite = OverwriteMode::IfNewer;
+        job.files = vec![file("stale.txt"), file("fresh.txt"), file("new.txt")];
+        let (_control_tx, mut control_rx) = watch::channel(ControlSignal::Run);
+        process_job(
+            &mut job,
+            &manager,
+            &bus,
+            &TransferConfig::default(),
+            &mut control_rx,
+        )
         .await
-        .map_err(|e| CoreError::Ssh(e.to_string()))?;
-    let mut hasher = Sha256::new();
-    let mut buf = vec![0u8; 1024 * 128];
-    loop {
-        let n = remote
-            .read(&mut buf)
+        .unwrap();
+
+        let outcomes: Vec<_> = job.results.iter().map(|r| &r.outcome).collect();
+        assert_eq!(
+            outcomes,
+            [
+                &FileOutcome::Copied,
+                &FileOutcome::Skipped,
+                &FileOutcome::Copied
+            ]
+        );
+        assert_eq!(std::fs::read(dst.join("stale.txt")).unwrap(), b"src");
+        assert_eq!(std::fs::read(dst.join("fresh.txt")).unwrap(), b"dst");
+        assert!(source_is_newer(Some(2), None));
+        assert!(!source_is_newer(Some(2), Some(2)));
+    }
+
+    fn remote_job(upload: bool) -> TransferJob {
+        let remote = TransferEndpoint::Remote {
+            session_id: Uuid::new_v4(),
+            path: "/srv".to_string(),
+        };
+        let local = TransferEndpoint::Local {
+            path: PathBuf::from("/tmp"),
+        };
+        let mut job = local_job();
+        (job.source, job.dest) = if upload {
+            (local, remote)
+        } else {
+            (remote, local)
+        };
+        job.options.verify_checksum = true;
+        job
+    }
+
+    async fn run_with(job: &mut TransferJob, fs: &MemoryFs) -> Result<(), CoreError> {
+        let remote = RemoteIo::Sftp(Box::new(fs.clone()));
+        let (_control_tx, mut control_rx) = watch::channel(ControlSignal::Run);
+        let bus = EventBus::new(256);
+        let cfg = TransferConfig {
+            buffer_size: 4,
+            ..TransferConfig::default()
+        };
+        run_job(job, Some(&remote), &bus, &cfg, &mut control_rx).await
+    }
+
+    #[tokio::test]
+    async fn uploads_and_downloads_through_remote_fs() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        std::fs::create_dir_all(src.join("nested")).unwrap();
+        std::fs::write(src.join("a.txt"), b"hello world").unwrap();
+        std::fs::write(src.join("nested/b.txt"), b"abc").unwrap();
+        let fs = MemoryFs::default();
+
+        let mut upload = remote_job(true);
+        upload.files = expand_local_tree(&src, "/srv/dst", true).unwrap();
+        let bus = EventBus::new(256);
+        let mut events = bus.subscribe();
+        let remote = RemoteIo::Sftp(Box::new(fs.clone()));
+        let (_control_tx, mut control_rx) = watch::channel(ControlSignal::Run);
+        let cfg = TransferConfig {
+            buffer_size: 4,
+            ..TransferConfig::default()
+        };
+        run_job(&mut upload, Some(&remote), &bus, &cfg, &mut control_rx)
             .await
-            .map_err(|e| CoreError::Ssh(e.to_string()))?;
-        if n == 0 {
-            break;
+            .unwrap();
+        assert!(fs.is_dir("/srv/dst/nested"));
+        assert_eq!(fs.file("/srv/dst/a.txt").unwrap(), b"hello world");
+        assert_eq!(fs.file("/srv/dst/nested/b.txt").unwrap(), b"abc");
+        assert_eq!(upload.progress.bytes_transferred, 14);
+        assert_eq!(upload.progress.files_completed, upload.files.len());
+        let last = std::iter::from_fn(|| match events.try_recv() {
+            Ok(Event::TransferProgress { progress, .. }) => Some(progress),
+            _ => None,
+        })
+        .last()
+        .unwrap();
+        assert_eq!(last.bytes_transferred, 14);
+        assert_eq!(last.bytes_total, 14);
+
+        let back = dir.path().join("back");
+        let mut download = remote_job(false);
+        download.files = expand_remote_tree(&fs, "/srv/dst", &back.to_string_lossy(), false)
+            .await
+            .unwrap();
+        run_with(&mut download, &fs).await.unwrap();
+        assert_eq!(std::fs::read(back.join("a.txt")).unwrap(), b"hello world");
+        assert_eq!(std::fs::read(back.join("nested/b.txt")).unwrap(), b"abc");
+        assert!(download
+            .results
+            .iter()
+            .all(|r| r.outcome == FileOutcome::Copied));
+    }
+
+    #[tokio::test]
+    async fn upload_fails_when_checksums_differ() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("a.txt");
+        std::fs::write(&src, b"payload").unwrap();
+        let fs = MemoryFs::default().with_corrupt_writes();
+        let mut job = remote_job(true);
+        job.files = expand_local_tree(&src, "/srv/a.txt", true).unwrap();
+        let err = run_with(&mut job, &fs).await.unwrap_err();
+        assert_eq!(err.to_string(), "invalid data: checksum mismatch");
+        assert!(matches!(job.results[0].outcome, FileOutcome::Failed { .. }));
+    }
+
+    #[tokio::test]
+    async fn resumes_partial_downloads_and_restarts_bad_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("big.bin");
+        let fs = MemoryFs::default().with_file("/srv/big.bin", b"0123456789", None);
+        let file = TransferFile {
+            source_path: "/srv/big.bin".to_string(),
+            dest_path: dest.to_string_lossy().to_string(),
+            size: 10,
+            is_dir: false,
+        };
+
+        let part = dir.path().join("big.bin.part");
+
+        std::fs::write(&part, b"0123").unwrap();
+        let mut job = remote_job(false);
+        job.options.resume = true;
+        job.files = vec![file.clone()];
+        run_with(&mut job, &fs).await.unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), b"0123456789");
+        assert_eq!(job.progress.bytes_transferred, 10);
+        assert!(!part.exists());
+
+        std::fs::write(&part, b"xxxx").unwrap();
+        let mut job = remote_job(false);
+        job.options.resume = true;
+        job.files = vec![file];
+        run_with(&mut job, &fs).await.unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), b"0123456789");
+        assert_eq!(job.progress.bytes_transferred, 10);
+    }
+
+    #[tokio::test]
+    async fn resume_never_appends_to_an_existing_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("notes.txt");
+        std::fs::write(&dest, b"old").unwrap();
+        let fs = MemoryFs::default().with_file("/srv/notes.txt", b"new notes", None);
+        for verify_checksum in [false, true] {
+            let mut job = remote_job(false);
+            job.options.resume = true;
+            job.options.verify_checksum = verify_checksum;
+            job.files = vec![TransferFile {
+                source_path: "/srv/notes.txt".to_string(),
+                dest_path: dest.to_string_lossy().to_string(),
+                size: 9,
+                is_dir: false,
+            }];
+            run_with(&mut job, &fs).await.unwrap();
+            assert_eq!(std::fs::read(&dest).unwrap(), b"new notes");
         }
-        hasher.update(&buf[..n]);
     }
-    Ok(hasher.finalize().to_vec())
-}
 
-async fn hash_local(path: &Path) -> Result<Vec<u8>, CoreError> {
-    let mut file = tokio::fs::File::open(path).await?;
-    let mut hasher = Sha256::new();
-    let mut buf = vec![0u8; 1024 * 128];
-    loop {
-        let n = file.read(&mut buf).await?;
-        if n == 0 {
-            break;
+    #[tokio::test]
+    async fn skips_existing_or_newer_remote_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("a.txt");
+        std::fs::write(&src, b"local").unwrap();
+        let fs = MemoryFs::default().with_file("/srv/a.txt", b"remote", Some(u64::MAX));
+        let file = TransferFile {
+            source_path: src.to_string_lossy().to_string(),
+            dest_path: "/srv/a.txt".to_string(),
+            size: 5,
+            is_dir: false,
+        };
+
+        for overwrite in [OverwriteMode::Skip, OverwriteMode::IfNewer] {
+            let mut job = remote_job(true);
+            job.options.overwrite = overwrite;
+            job.files = vec![file.clone()];
+            run_with(&mut job, &fs).await.unwrap();
+            assert_eq!(job.results[0].outcome, FileOutcome::Skipped);
         }
-        hasher.update(&buf[..n]);
+        assert_eq!(fs.file("/srv/a.txt").unwrap(), b"remote");
+
+        let mut job = remote_job(true);
+        job.files = vec![file];
+        run_with(&mut job, &fs).await.unwrap();
+        assert_eq!(fs.file("/srv/a.txt").unwrap(), b"local");
+    }
+
+    #[test]
+    fn throttle_allows_one_buffer_burst_then_holds_rate() {
+        let start = Instant::now();
+        let mut throttle = Throttle::new(1000, 500, start);
+        assert_eq!(throttle.consume(500, start), Duration::ZERO);
+        assert_eq!(throttle.consume(250, start), Duration::from_millis(250));
+        let later = start + Duration::from_millis(250);
+        assert_eq!(throttle.consume(0, later), Duration::ZERO);
+        let idle = later + Duration::from_secs(10);
+        assert_eq!(throttle.consume(500, idle), Duration::ZERO);
+        assert_eq!(throttle.consume(500, idle), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn joins_relative_remote_paths() {
+        let parts = vec!["a".to_string(), "b".to_string()];
+        assert_eq!(join_relative("/", &parts, true), "/a/b");
+        assert_eq!(join_relative("/home/u/", &parts, true), "/home/u/a/b");
     }
-    Ok(hasher.finalize().to_vec())
 }
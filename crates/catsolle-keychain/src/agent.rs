@@ -55,6 +55,21 @@ impl AgentManager {
         Ok(())
     }
 
+    /// Drops the loaded identity with this SHA256 fingerprint; the `SHA256:` prefix is
+    /// optional.
+    pub async fn remove_by_fingerprint(&mut self, fingerprint: &str) -> Result<AgentKey> {
+        let wanted = fingerprint.trim();
+        let wanted = wanted.strip_prefix("SHA256:").unwrap_or(wanted);
+        let key = self
+            .list_identities()
+            .await?
+            .into_iter()
+            .find(|key| key.fingerprint.strip_prefix("SHA256:") == Some(wanted))
+            .ok_or_else(|| anyhow::anyhow!("no agent identity with fingerprint {fingerprint}"))?;
+        self.remove_identity(&key.public_key).await?;
+        Ok(key)
+    }
+
     pub async fn remove_all(&mut self) -> Result<()> {
         self.client.remove_all_identities().await?;
         Ok(())
@@ -2,7 +2,7 @@ use anyhow::Result;
 use getrandom::getrandom;
 use rand_core::{CryptoRng, RngCore};
 use ssh_key::private::{EcdsaKeypair, Ed25519Keypair, KeypairData, RsaKeypair};
-use ssh_key::{EcdsaCurve, LineEnding, PrivateKey};
+use ssh_key::{EcdsaCurve, LineEnding, PrivateKey, PublicKey};
 use std::fs;
 use std::path::{Path, PathBuf};
 use zeroize::Zeroizing;
@@ -21,6 +21,15 @@ pub struct GeneratedKey {
     pub fingerprint: String,
 }
 
+/// Identity of an existing key as `ssh-keygen -l` would print it.
+#[derive(Clone, Debug)]
+pub struct KeyFingerprint {
+    pub algorithm: String,
+    /// `SHA256:` followed by the base64 digest.
+    pub fingerprint: String,
+    pub comment: String,
+}
+
 #[derive(Clone, Debug)]
 pub struct KeyManager {
     base_dir: PathBuf,
@@ -51,6 +60,8 @@ impl KeyManager {
 
         let comment = comment.unwrap_or_else(|| name.to_string());
         let key = PrivateKey::new(key_data, comment)?;
+        // Encrypting moves the comment into the encrypted part, so take the public half first.
+        let public_key = key.public_key().to_openssh()?;
         let key = if let Some(pass) = passphrase.as_ref() {
             key.encrypt(&mut rng, pass.as_bytes())?
         } else {
@@ -60,7 +71,6 @@ impl KeyManager {
         let private_path = self.base_dir.join(name);
         let public_path = self.base_dir.join(format!("{}.pub", name));
         let private_pem = key.to_openssh(LineEnding::LF)?;
-        let public_key = key.public_key().to_openssh()?;
         fs::write(&private_path, private_pem.as_bytes())?;
         fs::write(&public_path, public_key.as_bytes())?;
         set_private_permissions(&private_path);
@@ -82,6 +92,49 @@ impl KeyManager {
         }
         Ok(key)
     }
+
+    /// Reads a public key, or the public half of a private key, which works without the
+    /// passphrase even when the private key is encrypted. Encrypted keys keep their comment
+    /// in the encrypted part, so it comes back empty for them.
+    pub fn fingerprint(path: &Path) -> Result<KeyFingerprint> {
+        let data = fs::read_to_string(path)?;
+        let public = match PublicKey::from_openssh(data.trim()) {
+            Ok(public) => public,
+            Err(_) => PrivateKey::from_openssh(&data)
+                .map_err(|_| anyhow::anyhow!("{} is not an OpenSSH key", path.display()))?
+                .public_key()
+                .clone(),
+        };
+        Ok(KeyFingerprint {
+            algorithm: public.algorithm().as_str().to_string(),
+            fingerprint: public.fingerprint(HashAlg::Sha256).to_string(),
+            comment: public.comment().to_string(),
+        })
+    }
+
+    /// Private and public key files that make up the pair called `name`, if present.
+    pub fn key_files(&self, name: &str) -> Result<Vec<PathBuf>> {
+        if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+            anyhow::bail!("invalid key name: {name}");
+        }
+        let files = [
+            self.base_dir.join(name),
+            self.base_dir.join(format!("{}.pub", name)),
+        ];
+        Ok(files.into_iter().filter(|path| path.is_file()).collect())
+    }
+
+    /// Deletes the key pair called `name` and returns the files removed.
+    pub fn remove(&self, name: &str) -> Result<Vec<PathBuf>> {
+        let files = self.key_files(name)?;
+        if files.is_empty() {
+            anyhow::bail!("no key named {name} in {}", self.base_dir.display());
+        }
+        for path in &files {
+            fs::remove_file(path)?;
+        }
+        Ok(files)
+    }
 }
 
 struct OsRng;
@@ -119,3 +172,37 @@ fn set_private_permissions(path: &Path) {
 fn set_private_permissions(_path: &Path) {}
 
 use ssh_key::HashAlg;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn fingerprint_and_remove_pair() {
+        let dir = TempDir::new().unwrap();
+        let manager = KeyManager::new(dir.path().to_path_buf());
+        let key = manager
+            .generate(
+                "id_test",
+                KeyAlgorithm::Ed25519,
+                Some(Zeroizing::new("secret".to_string())),
+                Some("me@host".to_string()),
+            )
+            .unwrap();
+
+        let private = KeyManager::fingerprint(&key.private_key_path).unwrap();
+        let public = KeyManager::fingerprint(&key.public_key_path).unwrap();
+        assert_eq!(private.fingerprint, key.fingerprint);
+        assert_eq!(public.fingerprint, key.fingerprint);
+        assert_eq!(private.algorithm, "ssh-ed25519");
+        assert_eq!(public.comment, "me@host");
+
+        assert!(manager.key_files("../id_test").is_err());
+        let removed = manager.remove("id_test").unwrap();
+        assert_eq!(removed.len(), 2);
+        assert!(!key.private_key_path.exists());
+        assert!(!key.public_key_path.exists());
+        assert!(manager.remove("id_test").is_err());
+    }
+}
@@ -3,5 +3,5 @@ pub mod keys;
 pub mod store;
 
 pub use agent::{AgentKey, AgentManager};
-pub use keys::{GeneratedKey, KeyAlgorithm, KeyManager};
+pub use keys::{GeneratedKey, KeyAlgorithm, KeyFingerprint, KeyManager};
 pub use store::{KeychainManager, SecretError, SecretRef};
@@ -18,6 +18,8 @@ pub enum SecretError {
     Crypto(String),
     #[error("not found")]
     NotFound,
+    #[error("master password required")]
+    MasterPasswordRequired,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -53,8 +55,7 @@ impl KeychainManager {
             }
         }
         if self.fallback_enabled {
-            let master = master
-                .ok_or_else(|| SecretError::Crypto("master password required".to_string()))?;
+            let master = master.ok_or(SecretError::MasterPasswordRequired)?;
             let mut map = self.read_fallback(master).unwrap_or_default();
             map.insert(id.to_string(), secret.to_string());
             self.write_fallback(master, &map)?;
@@ -75,8 +76,7 @@ impl KeychainManager {
             }
         }
         if self.fallback_enabled {
-            let master = master
-                .ok_or_else(|| SecretError::Crypto("master password required".to_string()))?;
+            let master = master.ok_or(SecretError::MasterPasswordRequired)?;
             let map = self.read_fallback(master).unwrap_or_default();
             Ok(map.get(id).map(|v| Zeroizing::new(v.clone())))
         } else {
@@ -89,8 +89,7 @@ impl KeychainManager {
             let _ = entry.delete_password();
         }
         if self.fallback_enabled {
-            let master = master
-                .ok_or_else(|| SecretError::Crypto("master password required".to_string()))?;
+            let master = master.ok_or(SecretError::MasterPasswordRequired)?;
             let mut map = self.read_fallback(master).unwrap_or_default();
             map.remove(id);
             self.write_fallback(master, &map)?;
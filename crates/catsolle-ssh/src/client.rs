@@ -1,26 +1,45 @@
 use crate::config::{
-    AuthMethod, HostKeyPolicy, JumpHost, KeyboardInteractiveHandler, ProxyConfig, SshConnectConfig,
+    AuthMethod, HostKeyPolicy, JumpHost, KeyboardInteractiveHandler, NewHostKey, ProxyConfig,
+    SshConnectConfig,
+};
+use crate::error::{Result, SshError};
+use crate::forward::{
+    socks5_accept, socks5_reply, DynamicForward, LocalForward, RemoteForward, RemoteForwardTargets,
+    REPLY_GENERAL_FAILURE, REPLY_SUCCEEDED,
 };
 use crate::known_hosts::{KnownHostResult, KnownHosts};
 use crate::proxy::connect_via_proxy;
+use crate::scp::{ScpChannel, ScpDownload, ScpFileHeader, ScpUpload};
 use crate::sftp::SftpClient;
-use anyhow::Result;
+use crate::shell;
 use russh::client::{Config as ClientConfig, Handle};
 use russh::keys::key::PrivateKeyWithHashAlg;
-use russh::keys::Algorithm;
 use russh::keys::{load_openssh_certificate, load_secret_key};
+use russh::keys::{Algorithm, HashAlg};
 use russh::{client, ChannelMsg, ChannelWriteHalf};
+use std::future::Future;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
-use tokio::sync::{mpsc, Mutex};
-use tracing::warn;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tracing::{debug, warn};
 
 trait AsyncStream: AsyncRead + AsyncWrite {}
 impl<T: AsyncRead + AsyncWrite + ?Sized> AsyncStream for T {}
 type BoxedStream = Box<dyn AsyncStream + Unpin + Send>;
 
+const SHELL_PENDING_MAX_BYTES: usize = 8 * 1024 * 1024;
+const SFTP_OPEN_ATTEMPTS: u32 = 3;
+const SFTP_RETRY_BASE_MS: u64 = 200;
+/// Remote forwards listen on the server's loopback only, like `ssh -R` without a bind address.
+const REMOTE_FORWARD_BIND: &str = "localhost";
+/// Exit status `exec` reports for a command killed by a signal or one that never sent an
+/// exit status, as `ssh` does.
+pub const EXEC_FAILED_STATUS: i32 = 255;
+
 #[derive(Clone)]
 pub struct SshClient;
 
@@ -33,12 +52,42 @@ struct SessionInner {
     handle: Handle<ClientHandler>,
     jump_handles: Vec<Handle<ClientHandler>>,
     config: SshConnectConfig,
+    remote_forwards: RemoteForwardTargets,
+    close_reason: CloseReasonSlot,
+    shell_end: CloseReasonSlot,
+}
+
+/// Why a session ended.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CloseReason {
+    /// The shell exited, or the server closed its channel.
+    Eof,
+    /// The server, or a jump host, stopped answering keepalives.
+    KeepaliveTimeout,
+    /// The connection failed or the server dropped it.
+    Transport(String),
 }
 
+/// First reason any hop of the chain reported for going down, or how the last shell
+/// channel ended.
+type CloseReasonSlot = Arc<std::sync::Mutex<Option<CloseReason>>>;
+
 pub struct SshShell {
     writer: ChannelWriteHalf<russh::client::Msg>,
     output: mpsc::Receiver<Vec<u8>>,
     exit_status: Option<tokio::sync::oneshot::Receiver<Option<u32>>>,
+    env: ShellEnv,
+}
+
+/// Names of the `SshConnectConfig::env` variables, grouped by how they reached the shell.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShellEnv {
+    /// Accepted through SetEnv.
+    pub sent: Vec<String>,
+    /// Refused through SetEnv and exported in the shell instead.
+    pub exported: Vec<String>,
+    /// Refused and left out.
+    pub refused: Vec<String>,
 }
 
 impl SshClient {
@@ -62,19 +111,28 @@ impl SshClient {
         });
 
         let mut jump_handles: Vec<Handle<ClientHandler>> = Vec::new();
+        let remote_forwards = RemoteForwardTargets::default();
+        let close_reason = CloseReasonSlot::default();
 
+        let last = chain.len() - 1;
         for (idx, hop) in chain.iter().enumerate() {
             let handler = ClientHandler {
                 host: hop.host.clone(),
                 port: hop.port,
                 policy: cfg.host_key_policy.clone(),
                 known_hosts: known_hosts.clone(),
+                // Jump hosts never get the agent, only the target the user asked for.
+                agent_forwarding: idx == last && cfg.agent_forwarding,
+                remote_forwards: remote_forwards.clone(),
+                close_reason: close_reason.clone(),
             };
 
             let mut handle = if idx == 0 {
                 let sock =
                     connect_socket(&cfg.proxy, &hop.host, hop.port, cfg.connect_timeout_ms).await?;
-                client::connect_stream(base_config.clone(), sock, handler).await?
+                client::connect_stream(base_config.clone(), sock, handler)
+                    .await
+                    .map_err(|err| host_key_error(err, &hop.host, hop.port))?
             } else {
                 let prev = jump_handles
                     .last_mut()
@@ -82,7 +140,9 @@ impl SshClient {
                 let channel = prev
                     .channel_open_direct_tcpip(&hop.host, hop.port as u32, "127.0.0.1", 0)
                     .await?;
-                client::connect_stream(base_config.clone(), channel.into_stream(), handler).await?
+                client::connect_stream(base_config.clone(), channel.into_stream(), handler)
+                    .await
+                    .map_err(|err| host_key_error(err, &hop.host, hop.port))?
             };
 
             authenticate(
@@ -104,6 +164,9 @@ impl SshClient {
                 handle,
                 jump_handles,
                 config: cfg,
+                remote_forwards,
+                close_reason,
+                shell_end: CloseReasonSlot::default(),
             })),
         };
         Ok(session)
@@ -112,49 +175,130 @@ impl SshClient {
 
 impl SshSession {
     pub async fn open_shell(&self) -> Result<SshShell> {
+        let (width, height) = {
+            let inner = self.inner.lock().await;
+            (inner.config.term_width, inner.config.term_height)
+        };
+        self.open_shell_with_size(width, height).await
+    }
+
+    /// Opens a shell whose PTY starts at `width`x`height` instead of the configured size,
+    /// so programs launched right away render for the real viewport.
+    pub async fn open_shell_with_size(&self, width: u32, height: u32) -> Result<SshShell> {
         let inner = self.inner.lock().await;
-        let channel = inner.handle.channel_open_session().await?;
+        let mut channel = inner.handle.channel_open_session().await?;
         if inner.config.request_pty {
             channel
-                .request_pty(
-                    true,
-                    &inner.config.term,
-                    inner.config.term_width,
-                    inner.config.term_height,
-                    0,
-                    0,
-                    &[],
-                )
+                .request_pty(true, &inner.config.term, width, height, 0, 0, &[])
                 .await?;
         }
         for (k, v) in &inner.config.env {
             channel.set_env(true, k, v).await?;
         }
+        if inner.config.agent_forwarding {
+            channel.agent_forward(true).await?;
+        }
+        // Replies come in request order; a shell without a PTY still works, so only the
+        // SetEnv answers matter.
+        if inner.config.request_pty {
+            wait_request_reply(&mut channel).await?;
+        }
+        let mut env = ShellEnv::default();
+        let mut refused = Vec::new();
+        for (k, v) in &inner.config.env {
+            if wait_request_reply(&mut channel).await? {
+                env.sent.push(k.clone());
+            } else {
+                refused.push((k.clone(), v.clone()));
+            }
+        }
+        if inner.config.agent_forwarding {
+            if wait_request_reply(&mut channel).await? {
+                debug!("server accepted agent forwarding");
+            } else {
+                warn!("server refused agent forwarding");
+            }
+        }
         channel.request_shell(true).await?;
+        if !refused.is_empty() {
+            debug!(count = refused.len(), "server refused SetEnv");
+        }
+        if inner.config.env_export_fallback {
+            let (line, exported) = export_line(&refused);
+            if let Some(line) = line {
+                channel.data(line.as_bytes()).await?;
+            }
+            refused.retain(|(k, _)| !exported.contains(k));
+            env.exported = exported;
+        }
+        env.refused = refused.into_iter().map(|(k, _)| k).collect();
 
         let (mut reader, writer) = channel.split();
         let (tx, rx) = mpsc::channel(1024);
         let (exit_tx, exit_rx) = tokio::sync::oneshot::channel();
+        let shell_end = inner.shell_end.clone();
+        *shell_end.lock().unwrap_or_else(|e| e.into_inner()) = None;
 
         tokio::spawn(async move {
             let mut exit_status: Option<u32> = None;
-            while let Some(msg) = reader.wait().await {
+            // A shell that really ended reports its exit or closes the channel; output that just
+            // stops means the connection under it went away.
+            let mut exited = false;
+            // Output the receiver has not taken yet. Reading from the SSH channel keeps going
+            // while this backlog grows, so a slow consumer cannot stall the connection.
+            let mut pending: Vec<u8> = Vec::new();
+            loop {
+                let msg = if pending.is_empty() {
+                    reader.wait().await
+                } else if pending.len() >= SHELL_PENDING_MAX_BYTES {
+                    let _ = tx.send(std::mem::take(&mut pending)).await;
+                    continue;
+                } else {
+                    tokio::select! {
+                        biased;
+                        permit = tx.reserve() => {
+                            let chunk = std::mem::take(&mut pending);
+                            if let Ok(permit) = permit {
+                                permit.send(chunk);
+                            }
+                            continue;
+                        }
+                        msg = reader.wait() => msg,
+                    }
+                };
+                let Some(msg) = msg else {
+                    break;
+                };
                 match msg {
                     ChannelMsg::Data { data } => {
-                        let _ = tx.send(data.to_vec()).await;
+                        queue_output(&tx, &mut pending, &data);
                     }
                     ChannelMsg::ExtendedData { data, .. } => {
-                        let _ = tx.send(data.to_vec()).await;
+                        queue_output(&tx, &mut pending, &data);
                     }
                     ChannelMsg::ExitStatus {
                         exit_status: status,
                     } => {
                         exit_status = Some(status);
+                        exited = true;
+                    }
+                    ChannelMsg::ExitSignal { .. } => exited = true,
+                    // OpenSSH sends the exit status after EOF, so keep reading until Close.
+                    ChannelMsg::Close => {
+                        exited = true;
+                        break;
                     }
-                    ChannelMsg::Eof => break,
                     _ => {}
                 }
             }
+            *shell_end.lock().unwrap_or_else(|e| e.into_inner()) = Some(if exited {
+                CloseReason::Eof
+            } else {
+                CloseReason::Transport("shell channel ended without an exit status".to_string())
+            });
+            if !pending.is_empty() {
+                let _ = tx.send(pending).await;
+            }
             let _ = exit_tx.send(exit_status);
         });
 
@@ -162,34 +306,270 @@ impl SshSession {
             writer,
             output: rx,
             exit_status: Some(exit_rx),
+            env,
         })
     }
 
+    /// Opens an SFTP subsystem channel, retrying with exponential backoff since busy
+    /// servers sometimes refuse a new channel for a moment.
     pub async fn open_sftp(&self) -> Result<SftpClient> {
-        let inner = self.inner.lock().await;
-        let channel = inner.handle.channel_open_session().await?;
+        let mut delay = Duration::from_millis(SFTP_RETRY_BASE_MS);
+        let mut attempt = 1;
+        loop {
+            let err = match self.try_open_sftp().await {
+                Ok(sftp) => return Ok(sftp),
+                Err(err) => err,
+            };
+            if attempt >= SFTP_OPEN_ATTEMPTS || self.is_closed().await {
+                return Err(SshError::SftpUnavailable {
+                    attempts: attempt,
+                    source: Box::new(err),
+                });
+            }
+            debug!(attempt, error = %err, "sftp open failed, retrying");
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+            attempt += 1;
+        }
+    }
+
+    async fn try_open_sftp(&self) -> Result<SftpClient> {
+        let channel = {
+            let inner = self.inner.lock().await;
+            inner.handle.channel_open_session().await?
+        };
         channel.request_subsystem(true, "sftp").await?;
         let stream = channel.into_stream();
         SftpClient::new(stream).await
     }
 
+    /// Starts `scp -t` for `path` and announces one file of `size` bytes, for servers
+    /// that refuse the SFTP subsystem.
+    pub async fn open_scp_upload(
+        &self,
+        path: &str,
+        mode: u32,
+        size: u64,
+    ) -> Result<ScpUpload<ScpChannel>> {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let stream = self
+            .exec_stream(&format!("scp -t -- {}", shell::quote(path)))
+            .await?;
+        ScpUpload::start(stream, mode, size, name).await
+    }
+
+    /// Starts `scp -f` for `path` and reads the file header.
+    pub async fn open_scp_download(
+        &self,
+        path: &str,
+    ) -> Result<(ScpDownload<ScpChannel>, ScpFileHeader)> {
+        let stream = self
+            .exec_stream(&format!("scp -f -- {}", shell::quote(path)))
+            .await?;
+        ScpDownload::start(stream).await
+    }
+
+    async fn exec_stream(&self, command: &str) -> Result<ScpChannel> {
+        let channel = {
+            let inner = self.inner.lock().await;
+            inner.handle.channel_open_session().await?
+        };
+        channel.exec(true, command).await?;
+        Ok(channel.into_stream())
+    }
+
+    /// Binds `bind_addr` and serves SOCKS5 CONNECT requests through `direct-tcpip`
+    /// channels on this session until the returned handle is dropped.
+    pub async fn open_dynamic_forward(&self, bind_addr: SocketAddr) -> Result<DynamicForward> {
+        let listener = TcpListener::bind(bind_addr).await?;
+        let local_addr = listener.local_addr()?;
+        let session = self.clone();
+        let task = spawn_forward_listener(listener, "dynamic", move |stream, peer| {
+            let session = session.clone();
+            async move { session.forward_socks_client(stream, peer).await }
+        });
+        Ok(DynamicForward::new(local_addr, task))
+    }
+
+    /// Binds `local` and pipes every accepted connection to `remote_host:remote_port`
+    /// through a `direct-tcpip` channel until the returned handle is dropped.
+    pub async fn open_local_forward(
+        &self,
+        local: SocketAddr,
+        remote_host: String,
+        remote_port: u16,
+    ) -> Result<LocalForward> {
+        let listener = TcpListener::bind(local).await?;
+        let local_addr = listener.local_addr()?;
+        let session = self.clone();
+        let remote_host = Arc::new(remote_host);
+        let task = spawn_forward_listener(listener, "local", move |stream, peer| {
+            let session = session.clone();
+            let remote_host = remote_host.clone();
+            async move {
+                session
+                    .forward_tcp_client(stream, peer, &remote_host, remote_port)
+                    .await
+            }
+        });
+        Ok(LocalForward::new(local_addr, task))
+    }
+
+    /// Asks the server to listen on `remote_bind_port` of its loopback interface and pipes
+    /// every connection it forwards back to `local_host:local_port`, until the returned
+    /// handle is dropped. Port 0 lets the server pick one.
+    pub async fn open_remote_forward(
+        &self,
+        remote_bind_port: u16,
+        local_host: String,
+        local_port: u16,
+    ) -> Result<RemoteForward> {
+        let mut inner = self.inner.lock().await;
+        let targets = inner.remote_forwards.clone();
+        let requested = u32::from(remote_bind_port);
+        // Registered up front so connections arriving before the reply find their target.
+        targets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(requested, (local_host.clone(), local_port));
+        let port = match inner
+            .handle
+            .tcpip_forward(REMOTE_FORWARD_BIND, requested)
+            .await
+        {
+            Ok(port) if requested == 0 => port,
+            Ok(_) => requested,
+            Err(err) => {
+                targets
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .remove(&requested);
+                return Err(err.into());
+            }
+        };
+        if port != requested {
+            let mut map = targets.lock().unwrap_or_else(|e| e.into_inner());
+            map.remove(&requested);
+            map.insert(port, (local_host, local_port));
+        }
+        drop(inner);
+
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        let session = self.clone();
+        tokio::spawn(async move {
+            let _ = cancel_rx.await;
+            targets
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&port);
+            let inner = session.inner.lock().await;
+            if let Err(err) = inner
+                .handle
+                .cancel_tcpip_forward(REMOTE_FORWARD_BIND, port)
+                .await
+            {
+                debug!(port, error = %err, "cancel remote forward failed");
+            }
+        });
+        Ok(RemoteForward::new(port as u16, cancel_tx))
+    }
+
+    async fn forward_tcp_client(
+        &self,
+        mut stream: TcpStream,
+        peer: SocketAddr,
+        host: &str,
+        port: u16,
+    ) -> Result<()> {
+        let channel = {
+            let inner = self.inner.lock().await;
+            inner
+                .handle
+                .channel_open_direct_tcpip(
+                    host,
+                    port as u32,
+                    peer.ip().to_string(),
+                    peer.port() as u32,
+                )
+                .await?
+        };
+        let mut remote = channel.into_stream();
+        tokio::io::copy_bidirectional(&mut stream, &mut remote).await?;
+        Ok(())
+    }
+
+    async fn forward_socks_client(&self, mut stream: TcpStream, peer: SocketAddr) -> Result<()> {
+        let (host, port) = socks5_accept(&mut stream).await?;
+        let channel = {
+            let inner = self.inner.lock().await;
+            inner
+                .handle
+                .channel_open_direct_tcpip(
+                    host.as_str(),
+                    port as u32,
+                    peer.ip().to_string(),
+                    peer.port() as u32,
+                )
+                .await
+        };
+        let channel = match channel {
+            Ok(channel) => channel,
+            Err(err) => {
+                socks5_reply(&mut stream, REPLY_GENERAL_FAILURE).await?;
+                return Err(err.into());
+            }
+        };
+        socks5_reply(&mut stream, REPLY_SUCCEEDED).await?;
+        let mut remote = channel.into_stream();
+        tokio::io::copy_bidirectional(&mut stream, &mut remote).await?;
+        Ok(())
+    }
+
+    /// Runs `command` and returns its exit status with stdout and stderr interleaved.
     pub async fn exec(&self, command: &str) -> Result<(i32, Vec<u8>)> {
+        let mut output = Vec::new();
+        let status = self
+            .run_command(command, |_, data| output.extend_from_slice(data))
+            .await?;
+        Ok((status, output))
+    }
+
+    /// `exec`, keeping the command's stderr apart from its stdout.
+    pub async fn exec_split(&self, command: &str) -> Result<(i32, Vec<u8>, Vec<u8>)> {
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let status = self
+            .run_command(command, |is_stderr, data| {
+                if is_stderr {
+                    stderr.extend_from_slice(data);
+                } else {
+                    stdout.extend_from_slice(data);
+                }
+            })
+            .await?;
+        Ok((status, stdout, stderr))
+    }
+
+    /// Feeds the output of `command` to `sink` (`true` for stderr) and returns its exit
+    /// status, or `EXEC_FAILED_STATUS` when it was killed by a signal or never reported one.
+    async fn run_command(&self, command: &str, mut sink: impl FnMut(bool, &[u8])) -> Result<i32> {
         let inner = self.inner.lock().await;
         let channel = inner.handle.channel_open_session().await?;
         channel.exec(true, command).await?;
         let (mut reader, _) = channel.split();
-        let mut output = Vec::new();
-        let mut status = 0;
+        let mut status = None;
         while let Some(msg) = reader.wait().await {
             match msg {
-                ChannelMsg::Data { data } => output.extend_from_slice(&data),
-                ChannelMsg::ExtendedData { data, .. } => output.extend_from_slice(&data),
-                ChannelMsg::ExitStatus { exit_status } => status = exit_status as i32,
-                ChannelMsg::Eof => break,
+                ChannelMsg::Data { data } => sink(false, &data),
+                ChannelMsg::ExtendedData { data, .. } => sink(true, &data),
+                ChannelMsg::ExitStatus { exit_status } => status = Some(exit_status as i32),
+                ChannelMsg::ExitSignal { .. } => status = Some(EXEC_FAILED_STATUS),
+                // OpenSSH reports the exit status after EOF, so wait for the close.
+                ChannelMsg::Close => break,
                 _ => {}
             }
         }
-        Ok((status, output))
+        Ok(status.unwrap_or(EXEC_FAILED_STATUS))
     }
 
     pub async fn send_startup_commands(&self) -> Result<()> {
@@ -210,6 +590,27 @@ impl SshSession {
         }
         inner.jump_handles.iter().any(|handle| handle.is_closed())
     }
+
+    /// Why the session ended; `None` while both the connection and the shell are up. A shell
+    /// that exited counts as `Eof` even if the connection is gone by now.
+    pub async fn close_reason(&self) -> Option<CloseReason> {
+        let closed = self.is_closed().await;
+        let inner = self.inner.lock().await;
+        let shell_end = inner
+            .shell_end
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        if shell_end == Some(CloseReason::Eof) || !closed {
+            return shell_end;
+        }
+        let reason = inner
+            .close_reason
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        Some(reason.unwrap_or_else(|| CloseReason::Transport("connection closed".to_string())))
+    }
 }
 
 impl SshShell {
@@ -219,6 +620,10 @@ impl SshShell {
         Ok(())
     }
 
+    pub fn env(&self) -> &ShellEnv {
+        &self.env
+    }
+
     pub async fn resize(&mut self, width: u32, height: u32) -> Result<()> {
         self.writer.window_change(width, height, 0, 0).await?;
         Ok(())
@@ -228,6 +633,11 @@ impl SshShell {
         self.output.recv().await
     }
 
+    /// Returns already buffered output without waiting.
+    pub fn try_read(&mut self) -> Option<Vec<u8>> {
+        self.output.try_recv().ok()
+    }
+
     pub async fn wait_exit(&mut self) -> Option<u32> {
         if let Some(rx) = self.exit_status.take() {
             rx.await.ok().flatten()
@@ -237,16 +647,162 @@ impl SshShell {
     }
 }
 
+/// Waits for the server's answer to the oldest channel request sent with `want_reply`.
+async fn wait_request_reply(channel: &mut russh::Channel<client::Msg>) -> Result<bool> {
+    loop {
+        match channel.wait().await {
+            Some(ChannelMsg::Success) => return Ok(true),
+            Some(ChannelMsg::Failure) => return Ok(false),
+            Some(_) => {}
+            None => return Err(SshError::Protocol(russh::Error::Disconnect)),
+        }
+    }
+}
+
+/// An `export` command for the variables with names a shell accepts, and those names. The
+/// leading space keeps it out of the history of shells that ignore such lines.
+fn export_line(vars: &[(String, String)]) -> (Option<String>, Vec<String>) {
+    let valid = |name: &str| {
+        let mut chars = name.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    let vars: Vec<_> = vars.iter().filter(|(k, _)| valid(k)).collect();
+    if vars.is_empty() {
+        return (None, Vec::new());
+    }
+    let assignments: Vec<String> = vars
+        .iter()
+        .map(|(k, v)| format!("{k}={}", shell::quote(v)))
+        .collect();
+    let line = format!(" export {}\n", assignments.join(" "));
+    (
+        Some(line),
+        vars.into_iter().map(|(k, _)| k.clone()).collect(),
+    )
+}
+
+/// Hands `data` to the output channel without waiting, appending it to `pending` when the
+/// channel is full or earlier output is still queued so byte order is preserved.
+fn queue_output(tx: &mpsc::Sender<Vec<u8>>, pending: &mut Vec<u8>, data: &[u8]) {
+    if !pending.is_empty() {
+        pending.extend_from_slice(data);
+        return;
+    }
+    if let Err(mpsc::error::TrySendError::Full(chunk)) = tx.try_send(data.to_vec()) {
+        *pending = chunk;
+    }
+}
+
 #[derive(Clone)]
 struct ClientHandler {
     host: String,
     port: u16,
     policy: HostKeyPolicy,
     known_hosts: Option<Arc<Mutex<KnownHosts>>>,
+    agent_forwarding: bool,
+    remote_forwards: RemoteForwardTargets,
+    close_reason: CloseReasonSlot,
 }
 
 impl client::Handler for ClientHandler {
-    type Error = anyhow::Error;
+    type Error = SshError;
+
+    fn server_channel_open_agent_forward(
+        &mut self,
+        channel: russh::Channel<client::Msg>,
+        _session: &mut client::Session,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send {
+        let enabled = self.agent_forwarding;
+        let host = self.host.clone();
+        async move {
+            if !enabled {
+                warn!(%host, "agent channel opened without forwarding enabled, dropped");
+                return Ok(());
+            }
+            tokio::spawn(async move {
+                let result = async {
+                    let mut agent = connect_agent_stream().await?;
+                    let mut remote = channel.into_stream();
+                    tokio::io::copy_bidirectional(&mut agent, &mut remote).await?;
+                    Ok::<_, SshError>(())
+                }
+                .await;
+                if let Err(err) = result {
+                    debug!(%host, error = %err, "forwarded agent connection ended");
+                }
+            });
+            Ok(())
+        }
+    }
+
+    fn disconnected(
+        &mut self,
+        reason: client::DisconnectReason<Self::Error>,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send {
+        let slot = self.close_reason.clone();
+        let host = self.host.clone();
+        async move {
+            // A timeout after the handshake can only come from the keepalive check.
+            let (recorded, result) = match reason {
+                client::DisconnectReason::ReceivedDisconnect(info) => (
+                    CloseReason::Transport(format!("disconnected by server: {}", info.message)),
+                    Ok(()),
+                ),
+                client::DisconnectReason::Error(SshError::Timeout) => {
+                    (CloseReason::KeepaliveTimeout, Err(SshError::Timeout))
+                }
+                client::DisconnectReason::Error(err) => {
+                    (CloseReason::Transport(err.to_string()), Err(err))
+                }
+            };
+            debug!(%host, reason = ?recorded, "connection closed");
+            slot.lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get_or_insert(recorded);
+            result
+        }
+    }
+
+    fn server_channel_open_forwarded_tcpip(
+        &mut self,
+        channel: russh::Channel<client::Msg>,
+        connected_address: &str,
+        connected_port: u32,
+        originator_address: &str,
+        originator_port: u32,
+        _session: &mut client::Session,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send {
+        let target = self
+            .remote_forwards
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&connected_port)
+            .cloned();
+        let origin = format!("{originator_address}:{originator_port}");
+        let listen = format!("{connected_address}:{connected_port}");
+        async move {
+            let Some((host, port)) = target else {
+                warn!(%listen, %origin, "forwarded connection for an unknown port dropped");
+                return Ok(());
+            };
+            tokio::spawn(async move {
+                let result = async {
+                    let mut local = TcpStream::connect((host.as_str(), port)).await?;
+                    let mut remote = channel.into_stream();
+                    tokio::io::copy_bidirectional(&mut local, &mut remote).await?;
+                    Ok::<_, std::io::Error>(())
+                }
+                .await;
+                if let Err(err) = result {
+                    debug!(%listen, %origin, error = %err, "remote forward connection ended");
+                }
+            });
+            Ok(())
+        }
+    }
 
     fn check_server_key(
         &mut self,
@@ -262,23 +818,51 @@ impl client::Handler for ClientHandler {
                     warn!("accepting any host key for {}:{}", host, port);
                     Ok(true)
                 }
-                HostKeyPolicy::Strict | HostKeyPolicy::AcceptNew => {
-                    let Some(kh) = known_hosts else {
+                HostKeyPolicy::Strict
+                | HostKeyPolicy::AcceptNew
+                | HostKeyPolicy::AcceptNewInteractive(_) => {
+                    let Some(known_hosts) = known_hosts else {
                         warn!("known_hosts not configured for {}:{}", host, port);
                         return Ok(false);
                     };
-                    let mut kh = kh.lock().await;
+                    let mut kh = known_hosts.lock().await;
                     match kh.check(&host, port, server_public_key) {
                         KnownHostResult::Match => Ok(true),
-                        KnownHostResult::NotFound => {
-                            if let HostKeyPolicy::AcceptNew = policy {
+                        KnownHostResult::NotFound => match policy {
+                            HostKeyPolicy::AcceptNew => {
                                 kh.add(&host, port, server_public_key, "catsolle")?;
                                 Ok(true)
-                            } else {
-                                Ok(false)
                             }
+                            HostKeyPolicy::AcceptNewInteractive(prompts) => {
+                                // Other hops must not wait on the lock while the user decides.
+                                drop(kh);
+                                if !confirm_new_host_key(&prompts, &host, port, server_public_key)
+                                    .await
+                                {
+                                    return Ok(false);
+                                }
+                                known_hosts.lock().await.add(
+                                    &host,
+                                    port,
+                                    server_public_key,
+                                    "catsolle",
+                                )?;
+                                Ok(true)
+                            }
+                            _ => Ok(false),
+                        },
+                        KnownHostResult::Mismatch => {
+                            warn!("host key for {}:{} has changed", host, port);
+                            Err(SshError::HostKeyChanged {
+                                host,
+                                port,
+                                fingerprint: server_public_key
+                                    .fingerprint(HashAlg::Sha256)
+                                    .to_string(),
+                                key: Box::new(server_public_key.clone()),
+                            })
                         }
-                        KnownHostResult::Mismatch | KnownHostResult::Revoked => Ok(false),
+                        KnownHostResult::Revoked => Ok(false),
                     }
                 }
             }
@@ -303,10 +887,10 @@ async fn connect_socket(
     let fut = async {
         if let Some(proxy) = proxy {
             let stream = connect_via_proxy(proxy, host, port).await?;
-            Ok::<BoxedStream, anyhow::Error>(Box::new(stream))
+            Ok::<BoxedStream, SshError>(Box::new(stream))
         } else {
             let stream = tokio::net::TcpStream::connect((host, port)).await?;
-            Ok::<BoxedStream, anyhow::Error>(Box::new(stream))
+            Ok::<BoxedStream, SshError>(Box::new(stream))
         }
     };
     let stream = tokio::time::timeout(Duration::from_millis(timeout_ms), fut).await??;
@@ -347,7 +931,7 @@ async fn authenticate(
             if authenticate_with_agent(handle, &user).await? {
                 Ok(())
             } else {
-                Err(anyhow::anyhow!("agent authentication failed"))
+                Err(SshError::AgentAuthFailed)
             }
         }
         AuthMethod::KeyboardInteractive => {
@@ -358,7 +942,7 @@ async fn authenticate(
                 match response {
                     russh::client::KeyboardInteractiveAuthResponse::Success => return Ok(()),
                     russh::client::KeyboardInteractiveAuthResponse::Failure { .. } => {
-                        return Err(anyhow::anyhow!("keyboard-interactive failed"));
+                        return Err(SshError::AuthFailed);
                     }
                     russh::client::KeyboardInteractiveAuthResponse::InfoRequest {
                         name: _,
@@ -373,7 +957,9 @@ async fn authenticate(
                             })
                             .collect::<Vec<_>>();
                         let Some(handler) = keyboard.clone() else {
-                            return Err(anyhow::anyhow!("keyboard-interactive handler missing"));
+                            return Err(
+                                anyhow::anyhow!("keyboard-interactive handler missing").into()
+                            );
                         };
                         let answers = handler.respond(prompts).await?;
                         response = handle
@@ -389,7 +975,8 @@ async fn authenticate(
             passphrase,
         } => {
             let key = load_private_key(private_key_path, passphrase.as_ref().map(|v| v.as_str()))?;
-            let cert = load_openssh_certificate(cert_path)?;
+            let cert =
+                load_openssh_certificate(cert_path).map_err(|e| SshError::Key(e.to_string()))?;
             let res = handle
                 .authenticate_openssh_cert(user.clone(), Arc::new(key), cert)
                 .await?;
@@ -401,17 +988,77 @@ async fn authenticate(
 fn ensure_auth(res: russh::client::AuthResult) -> Result<()> {
     match res {
         russh::client::AuthResult::Success => Ok(()),
-        russh::client::AuthResult::Failure { .. } => Err(anyhow::anyhow!("authentication failed")),
+        russh::client::AuthResult::Failure { .. } => Err(SshError::AuthFailed),
     }
 }
 
 fn load_private_key(path: &PathBuf, passphrase: Option<&str>) -> Result<russh::keys::PrivateKey> {
-    load_secret_key(path, passphrase).map_err(|e| anyhow::anyhow!(e))
+    Ok(load_secret_key(path, passphrase)?)
+}
+
+/// Hands an unknown host key to the UI and waits for its answer; a closed channel on
+/// either side counts as a refusal.
+async fn confirm_new_host_key(
+    prompts: &mpsc::Sender<NewHostKey>,
+    host: &str,
+    port: u16,
+    key: &russh::keys::PublicKey,
+) -> bool {
+    let (reply, decision) = oneshot::channel();
+    let prompt = NewHostKey {
+        host: host.to_string(),
+        port,
+        key_type: key.algorithm().as_str().to_string(),
+        fingerprint: key.fingerprint(HashAlg::Sha256).to_string(),
+        reply,
+    };
+    if prompts.send(prompt).await.is_err() {
+        warn!("no one to confirm the new host key for {}:{}", host, port);
+        return false;
+    }
+    decision.await.unwrap_or(false)
+}
+
+/// russh reports a host key refused by `check_server_key` as `UnknownKey`.
+fn host_key_error(err: SshError, host: &str, port: u16) -> SshError {
+    match err {
+        SshError::Protocol(russh::Error::UnknownKey) => SshError::HostKeyRejected {
+            host: host.to_string(),
+            port,
+        },
+        other => other,
+    }
+}
+
+/// Raw connection to the local agent, for piping forwarded agent channels into.
+async fn connect_agent_stream() -> Result<BoxedStream> {
+    #[cfg(unix)]
+    let stream = {
+        let path = std::env::var("SSH_AUTH_SOCK")
+            .map_err(|_| SshError::AgentUnavailable("SSH_AUTH_SOCK is not set".to_string()))?;
+        tokio::net::UnixStream::connect(path)
+            .await
+            .map_err(|e| SshError::AgentUnavailable(e.to_string()))?
+    };
+
+    #[cfg(windows)]
+    let stream = {
+        use tokio::net::windows::named_pipe::ClientOptions;
+        let sock = std::env::var("SSH_AUTH_SOCK")
+            .unwrap_or_else(|_| "\\\\.\\pipe\\openssh-ssh-agent".to_string());
+        ClientOptions::new()
+            .open(sock)
+            .map_err(|e| SshError::AgentUnavailable(e.to_string()))?
+    };
+
+    Ok(Box::new(stream))
 }
 
 async fn authenticate_with_agent(handle: &mut Handle<ClientHandler>, user: &str) -> Result<bool> {
     #[cfg(unix)]
-    let mut client = russh::keys::agent::client::AgentClient::connect_env().await?;
+    let mut client = russh::keys::agent::client::AgentClient::connect_env()
+        .await
+        .map_err(|e| SshError::AgentUnavailable(e.to_string()))?;
 
     #[cfg(windows)]
     let mut client = {
@@ -431,10 +1078,97 @@ async fn authenticate_with_agent(handle: &mut Handle<ClientHandler>, user: &str)
         };
         let res = handle
             .authenticate_publickey_with(user.to_string(), key, hash, &mut client)
-            .await?;
+            .await
+            .map_err(|e| SshError::AgentUnavailable(e.to_string()))?;
         if matches!(res, russh::client::AuthResult::Success) {
             return Ok(true);
         }
     }
     Ok(false)
 }
+
+/// Accepts connections on `listener` and serves each one on its own task; `kind` only
+/// labels log lines.
+fn spawn_forward_listener<F, Fut>(
+    listener: TcpListener,
+    kind: &'static str,
+    serve: F,
+) -> tokio::task::JoinHandle<()>
+where
+    F: Fn(TcpStream, SocketAddr) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    warn!(kind, error = %err, "forward accept failed");
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+            let fut = serve(stream, peer);
+            tokio::spawn(async move {
+                if let Err(err) = fut.await {
+                    debug!(kind, peer = %peer, error = %err, "forward connection closed");
+                }
+            });
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_output_spills_into_pending_when_full() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let mut pending = Vec::new();
+        queue_output(&tx, &mut pending, b"one");
+        queue_output(&tx, &mut pending, b"two");
+        queue_output(&tx, &mut pending, b"three");
+        assert_eq!(pending, b"twothree");
+        assert_eq!(rx.try_recv().unwrap(), b"one");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn exports_refused_variables() {
+        let vars = vec![
+            ("APP_ENV".to_string(), "it's prod".to_string()),
+            ("1BAD".to_string(), "x".to_string()),
+            ("LANG".to_string(), "C.UTF-8".to_string()),
+        ];
+        let (line, exported) = export_line(&vars);
+        assert_eq!(
+            line.as_deref(),
+            Some(" export APP_ENV='it'\\''s prod' LANG=C.UTF-8\n")
+        );
+        assert_eq!(exported, vec!["APP_ENV", "LANG"]);
+        assert_eq!(export_line(&vars[1..2]), (None, Vec::new()));
+    }
+
+    #[tokio::test]
+    async fn new_host_keys_wait_for_the_ui() {
+        let key: russh::keys::PublicKey =
+            "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIB9dG4kjRhQTtWTVzd2t27+t0DEHBPW7iOD23TUiYLio"
+                .parse()
+                .unwrap();
+        let (tx, mut rx) = mpsc::channel::<NewHostKey>(1);
+        let ui = tokio::spawn(async move {
+            let prompt = rx.recv().await.unwrap();
+            assert_eq!((prompt.host.as_str(), prompt.port), ("example.com", 2222));
+            assert_eq!(prompt.key_type, "ssh-ed25519");
+            assert!(prompt.fingerprint.starts_with("SHA256:"));
+            prompt.reply.send(true).unwrap();
+            let prompt = rx.recv().await.unwrap();
+            drop(prompt.reply);
+        });
+        assert!(confirm_new_host_key(&tx, "example.com", 2222, &key).await);
+        assert!(!confirm_new_host_key(&tx, "example.com", 2222, &key).await);
+        ui.await.unwrap();
+        assert!(!confirm_new_host_key(&tx, "example.com", 2222, &key).await);
+    }
+}
@@ -1,5 +1,6 @@
 use async_trait::async_trait;
 use std::path::PathBuf;
+use tokio::sync::{mpsc, oneshot};
 use zeroize::Zeroizing;
 
 #[derive(Clone, Debug)]
@@ -19,6 +20,8 @@ pub struct SshConnectConfig {
     pub term_width: u32,
     pub term_height: u32,
     pub env: Vec<(String, String)>,
+    /// Exports the variables the server refuses through SetEnv in the shell instead.
+    pub env_export_fallback: bool,
     pub startup_commands: Vec<String>,
     pub agent_forwarding: bool,
     pub x11_forwarding: bool,
@@ -54,9 +57,24 @@ pub struct JumpHost {
 pub enum HostKeyPolicy {
     Strict,
     AcceptNew,
+    /// Like `AcceptNew`, but an unknown key is sent to the UI and only saved once it is
+    /// confirmed there.
+    AcceptNewInteractive(mpsc::Sender<NewHostKey>),
     InsecureAcceptAny,
 }
 
+/// A host key seen for the first time under `HostKeyPolicy::AcceptNewInteractive`. The
+/// handshake waits until `true` (trust and save it) or `false` arrives on `reply`; dropping
+/// it counts as `false`.
+#[derive(Debug)]
+pub struct NewHostKey {
+    pub host: String,
+    pub port: u16,
+    pub key_type: String,
+    pub fingerprint: String,
+    pub reply: oneshot::Sender<bool>,
+}
+
 #[derive(Clone, Debug)]
 pub struct ProxyConfig {
     pub proxy_type: ProxyType,
@@ -0,0 +1,90 @@
+use russh::keys::PublicKey;
+use thiserror::Error;
+
+/// Errors surfaced by the SSH layer, classified so callers can react without parsing text.
+#[derive(Debug, Error)]
+pub enum SshError {
+    #[error("authentication failed")]
+    AuthFailed,
+    #[error("ssh agent unavailable: {0}")]
+    AgentUnavailable(String),
+    #[error("agent authentication failed")]
+    AgentAuthFailed,
+    #[error("host key for {host}:{port} was rejected")]
+    HostKeyRejected { host: String, port: u16 },
+    /// The server offered a different key than the one in known_hosts. `key` is what it
+    /// offers now, so the caller can replace the stored one after asking the user.
+    #[error("host key for {host}:{port} has changed, the server now offers {fingerprint}")]
+    HostKeyChanged {
+        host: String,
+        port: u16,
+        fingerprint: String,
+        key: Box<PublicKey>,
+    },
+    #[error("connection timed out")]
+    Timeout,
+    #[error("key error: {0}")]
+    Key(String),
+    #[error("proxy error: {0}")]
+    Proxy(String),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("sftp error: {0}")]
+    Sftp(#[from] russh_sftp::client::error::Error),
+    #[error("sftp unavailable after {attempts} attempts: {source}")]
+    SftpUnavailable {
+        attempts: u32,
+        source: Box<SshError>,
+    },
+    #[error("scp error: {0}")]
+    Scp(String),
+    #[error("destination not writable: {path} ({reason})")]
+    NotWritable { path: String, reason: String },
+    #[error("ssh error: {0}")]
+    Protocol(russh::Error),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+pub type Result<T, E = SshError> = std::result::Result<T, E>;
+
+impl From<russh::Error> for SshError {
+    fn from(err: russh::Error) -> Self {
+        match err {
+            russh::Error::ConnectionTimeout | russh::Error::KeepaliveTimeout => SshError::Timeout,
+            russh::Error::IO(err) => SshError::Io(err),
+            other => SshError::Protocol(other),
+        }
+    }
+}
+
+impl From<russh::keys::Error> for SshError {
+    fn from(err: russh::keys::Error) -> Self {
+        SshError::Key(err.to_string())
+    }
+}
+
+impl From<tokio::time::error::Elapsed> for SshError {
+    fn from(_: tokio::time::error::Elapsed) -> Self {
+        SshError::Timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_russh_errors() {
+        assert!(matches!(
+            SshError::from(russh::Error::KeepaliveTimeout),
+            SshError::Timeout
+        ));
+        assert!(matches!(
+            SshError::from(russh::Error::IO(
+                std::io::ErrorKind::ConnectionRefused.into()
+            )),
+            SshError::Io(_)
+        ));
+    }
+}
@@ -0,0 +1,207 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+const SOCKS_VERSION: u8 = 0x05;
+const METHOD_NO_AUTH: u8 = 0x00;
+const METHOD_UNACCEPTABLE: u8 = 0xff;
+const CMD_CONNECT: u8 = 0x01;
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_DOMAIN: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+
+pub(crate) const REPLY_SUCCEEDED: u8 = 0x00;
+pub(crate) const REPLY_GENERAL_FAILURE: u8 = 0x01;
+const REPLY_COMMAND_NOT_SUPPORTED: u8 = 0x07;
+const REPLY_ADDRESS_NOT_SUPPORTED: u8 = 0x08;
+
+/// Local SOCKS5 listener tunnelling each connection over the SSH session, like `ssh -D`.
+/// The listener stops when this handle is dropped.
+pub struct DynamicForward {
+    local_addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl DynamicForward {
+    pub(crate) fn new(local_addr: SocketAddr, task: JoinHandle<()>) -> Self {
+        Self { local_addr, task }
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for DynamicForward {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Local TCP listener piping each connection to a fixed host and port reachable from the
+/// server, like `ssh -L`. The listener stops when this handle is dropped.
+pub struct LocalForward {
+    local_addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl LocalForward {
+    pub(crate) fn new(local_addr: SocketAddr, task: JoinHandle<()>) -> Self {
+        Self { local_addr, task }
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for LocalForward {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Port on the server whose connections come back as `forwarded-tcpip` channels and are
+/// piped to a local target, like `ssh -R`. The server stops listening when this handle is
+/// dropped.
+pub struct RemoteForward {
+    remote_port: u16,
+    _cancel: oneshot::Sender<()>,
+}
+
+impl RemoteForward {
+    pub(crate) fn new(remote_port: u16, cancel: oneshot::Sender<()>) -> Self {
+        Self {
+            remote_port,
+            _cancel: cancel,
+        }
+    }
+
+    /// The port the server listens on; the one it picked when 0 was requested.
+    pub fn remote_port(&self) -> u16 {
+        self.remote_port
+    }
+}
+
+/// Local targets of the session's remote forwards, keyed by the server port.
+pub(crate) type RemoteForwardTargets = Arc<Mutex<HashMap<u32, (String, u16)>>>;
+
+/// Server side of a SOCKS5 greeting and CONNECT request without authentication.
+/// Returns the requested target; unsupported requests are answered and rejected.
+pub(crate) async fn socks5_accept<S>(stream: &mut S) -> Result<(String, u16)>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header).await?;
+    if header[0] != SOCKS_VERSION {
+        anyhow::bail!("invalid socks version {}", header[0]);
+    }
+    let mut methods = vec![0u8; header[1] as usize];
+    stream.read_exact(&mut methods).await?;
+    if !methods.contains(&METHOD_NO_AUTH) {
+        stream
+            .write_all(&[SOCKS_VERSION, METHOD_UNACCEPTABLE])
+            .await?;
+        anyhow::bail!("socks5 client requires authentication");
+    }
+    stream.write_all(&[SOCKS_VERSION, METHOD_NO_AUTH]).await?;
+
+    let mut request = [0u8; 4];
+    stream.read_exact(&mut request).await?;
+    if request[0] != SOCKS_VERSION {
+        anyhow::bail!("invalid socks version {}", request[0]);
+    }
+    if request[1] != CMD_CONNECT {
+        socks5_reply(stream, REPLY_COMMAND_NOT_SUPPORTED).await?;
+        anyhow::bail!("socks5 command {} unsupported", request[1]);
+    }
+    let host = match request[3] {
+        ATYP_IPV4 => {
+            let mut addr = [0u8; 4];
+            stream.read_exact(&mut addr).await?;
+            Ipv4Addr::from(addr).to_string()
+        }
+        ATYP_DOMAIN => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len).await?;
+            let mut name = vec![0u8; len[0] as usize];
+            stream.read_exact(&mut name).await?;
+            String::from_utf8(name)?
+        }
+        ATYP_IPV6 => {
+            let mut addr = [0u8; 16];
+            stream.read_exact(&mut addr).await?;
+            Ipv6Addr::from(addr).to_string()
+        }
+        other => {
+            socks5_reply(stream, REPLY_ADDRESS_NOT_SUPPORTED).await?;
+            anyhow::bail!("socks5 address type {other} unsupported");
+        }
+    };
+    let mut port = [0u8; 2];
+    stream.read_exact(&mut port).await?;
+    Ok((host, u16::from_be_bytes(port)))
+}
+
+pub(crate) async fn socks5_reply<S>(stream: &mut S, reply: u8) -> Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    stream
+        .write_all(&[SOCKS_VERSION, reply, 0x00, ATYP_IPV4, 0, 0, 0, 0, 0, 0])
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn accepts_socks5_connect_to_domain() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let accept = tokio::spawn(async move {
+            let target = socks5_accept(&mut server).await.unwrap();
+            socks5_reply(&mut server, REPLY_SUCCEEDED).await.unwrap();
+            target
+        });
+
+        client.write_all(&[0x05, 0x01, 0x00]).await.unwrap();
+        let mut choice = [0u8; 2];
+        client.read_exact(&mut choice).await.unwrap();
+        assert_eq!(choice, [0x05, 0x00]);
+
+        let mut request = vec![0x05, 0x01, 0x00, 0x03, 11];
+        request.extend_from_slice(b"example.com");
+        request.extend_from_slice(&443u16.to_be_bytes());
+        client.write_all(&request).await.unwrap();
+        let mut reply = [0u8; 10];
+        client.read_exact(&mut reply).await.unwrap();
+        assert_eq!(reply[1], REPLY_SUCCEEDED);
+
+        assert_eq!(accept.await.unwrap(), ("example.com".to_string(), 443));
+    }
+
+    #[tokio::test]
+    async fn rejects_socks5_bind_requests() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let accept = tokio::spawn(async move { socks5_accept(&mut server).await });
+
+        client.write_all(&[0x05, 0x01, 0x00]).await.unwrap();
+        let mut choice = [0u8; 2];
+        client.read_exact(&mut choice).await.unwrap();
+        client
+            .write_all(&[0x05, 0x02, 0x00, 0x01, 127, 0, 0, 1, 0, 80])
+            .await
+            .unwrap();
+        let mut reply = [0u8; 10];
+        client.read_exact(&mut reply).await.unwrap();
+        assert_eq!(reply[1], REPLY_COMMAND_NOT_SUPPORTED);
+        assert!(accept.await.unwrap().is_err());
+    }
+}
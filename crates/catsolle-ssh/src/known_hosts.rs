@@ -2,7 +2,7 @@ use hmac::{Hmac, Mac};
 use russh::keys::ssh_key::known_hosts::{
     Entry, HostPatterns, KnownHosts as KnownHostsFile, Marker,
 };
-use russh::keys::PublicKey;
+use russh::keys::{HashAlg, PublicKey};
 use sha1::Sha1;
 use std::fs;
 use std::path::PathBuf;
@@ -21,6 +21,15 @@ pub enum KnownHostResult {
     Revoked,
 }
 
+/// One host pattern of a known_hosts line together with its key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownHostEntry {
+    pub host: String,
+    pub port: u16,
+    pub key_type: String,
+    pub fingerprint: String,
+}
+
 impl KnownHosts {
     pub fn load(path: PathBuf) -> anyhow::Result<Self> {
         let entries = if path.exists() {
@@ -31,24 +40,31 @@ impl KnownHosts {
         Ok(Self { path, entries })
     }
 
+    /// `Match` when any stored key for the host is `key`. Hosts usually have one key per
+    /// algorithm, so only a stored key of the same algorithm that differs is a `Mismatch`;
+    /// a host known by other key types only is `NotFound`.
     pub fn check(&self, host: &str, port: u16, key: &PublicKey) -> KnownHostResult {
         let host_for_match = host_to_pattern(host, port);
+        let mut mismatch = false;
         for entry in &self.entries {
-            if let Some(marker) = entry.marker() {
-                if marker == &Marker::Revoked
-                    && host_matches(entry.host_patterns(), &host_for_match, host, port)
-                {
-                    return KnownHostResult::Revoked;
-                }
+            if !host_matches(entry.host_patterns(), &host_for_match, host, port) {
+                continue;
             }
-            if host_matches(entry.host_patterns(), &host_for_match, host, port) {
-                if entry.public_key() == key {
-                    return KnownHostResult::Match;
-                }
-                return KnownHostResult::Mismatch;
+            if entry.marker() == Some(&Marker::Revoked) {
+                return KnownHostResult::Revoked;
+            }
+            if entry.public_key() == key {
+                return KnownHostResult::Match;
             }
+            if entry.public_key().algorithm() == key.algorithm() {
+                mismatch = true;
+            }
+        }
+        if mismatch {
+            KnownHostResult::Mismatch
+        } else {
+            KnownHostResult::NotFound
         }
-        KnownHostResult::NotFound
     }
 
     pub fn add(
@@ -71,6 +87,126 @@ impl KnownHosts {
         self.entries = KnownHostsFile::read_file(&self.path)?;
         Ok(())
     }
+
+    /// Forgets the keys of `key`'s algorithm stored for `host:port` and trusts `key`
+    /// instead. Keys of other types, revoked markers, comments and entries of other hosts
+    /// are kept as written.
+    pub fn replace(
+        &mut self,
+        host: &str,
+        port: u16,
+        key: &PublicKey,
+        comment: &str,
+    ) -> anyhow::Result<()> {
+        self.remove_where(host, port, |stored| stored.algorithm() == key.algorithm())?;
+        self.add(host, port, key, comment)
+    }
+
+    /// Stored keys by host pattern, in file order. Hashed names cannot be shown and revoked
+    /// markers are not trusted keys, so both are left out.
+    pub fn entries(&self) -> Vec<KnownHostEntry> {
+        let mut out = Vec::new();
+        for entry in &self.entries {
+            if entry.marker() == Some(&Marker::Revoked) {
+                continue;
+            }
+            let HostPatterns::Patterns(patterns) = entry.host_patterns() else {
+                continue;
+            };
+            let key = entry.public_key();
+            for pattern in patterns.iter().filter(|p| !p.starts_with('!')) {
+                let (host, port) = pattern_to_host(pattern);
+                out.push(KnownHostEntry {
+                    host,
+                    port,
+                    key_type: key.algorithm().as_str().to_string(),
+                    fingerprint: key.fingerprint(HashAlg::Sha256).to_string(),
+                });
+            }
+        }
+        out
+    }
+
+    /// Stops trusting any key for `host:port`, hashed lines included. Only the matching
+    /// pattern is taken off a shared line, so the other hosts on it stay trusted; wildcards
+    /// that match get a `!host` exception instead. A line goes once no pattern is left.
+    /// Revoked markers and comments stay. Returns how many lines changed.
+    pub fn remove(&mut self, host: &str, port: u16) -> anyhow::Result<usize> {
+        self.remove_where(host, port, |_| true)
+    }
+
+    /// `remove`, limited to lines whose key passes `filter`.
+    fn remove_where(
+        &mut self,
+        host: &str,
+        port: u16,
+        filter: impl Fn(&PublicKey) -> bool,
+    ) -> anyhow::Result<usize> {
+        if !self.path.exists() {
+            return Ok(0);
+        }
+        let host_for_match = host_to_pattern(host, port);
+        let content = fs::read_to_string(&self.path)?;
+        let mut changed = 0;
+        let mut kept = String::new();
+        for line in content.lines() {
+            let rewritten = match line.trim().parse::<Entry>() {
+                Ok(entry)
+                    if entry.marker() != Some(&Marker::Revoked)
+                        && filter(entry.public_key())
+                        && host_matches(entry.host_patterns(), &host_for_match, host, port) =>
+                {
+                    changed += 1;
+                    without_host(line, entry.host_patterns(), &host_for_match)
+                }
+                _ => Some(line.to_string()),
+            };
+            if let Some(line) = rewritten {
+                kept.push_str(&line);
+                kept.push('\n');
+            }
+        }
+        if changed > 0 {
+            fs::write(&self.path, kept)?;
+            self.entries = KnownHostsFile::read_file(&self.path)?;
+        }
+        Ok(changed)
+    }
+}
+
+/// `line` with `host_for_match` taken out of its host patterns, or `None` when nothing
+/// would be left. Hashed lines name a single host, so they always go.
+fn without_host(line: &str, patterns: &HostPatterns, host_for_match: &str) -> Option<String> {
+    let HostPatterns::Patterns(patterns) = patterns else {
+        return None;
+    };
+    let mut kept: Vec<String> = patterns
+        .iter()
+        .filter(|pattern| pattern.as_str() != host_for_match)
+        .cloned()
+        .collect();
+    if !kept.iter().any(|pattern| !pattern.starts_with('!')) {
+        return None;
+    }
+    if match_plain_patterns(&kept, host_for_match) {
+        kept.push(format!("!{host_for_match}"));
+    }
+    // The patterns are the first field, or the second after a marker such as
+    // `@cert-authority`; everything around them is kept byte for byte.
+    let line = line.trim_start();
+    let start = if line.starts_with('@') {
+        let marker_end = line.find(char::is_whitespace)?;
+        marker_end + line[marker_end..].find(|c: char| !c.is_whitespace())?
+    } else {
+        0
+    };
+    let end = start + line[start..].find(char::is_whitespace)?;
+    Some(format!(
+        "{}{}{}",
+        &line[..start],
+        kept.join(","),
+        &line[end..]
+    ))
 }
 
 fn host_to_pattern(host: &str, port: u16) -> String {
@@ -81,6 +217,15 @@ fn host_to_pattern(host: &str, port: u16) -> String {
     }
 }
 
+/// Inverse of `host_to_pattern`.
+fn pattern_to_host(pattern: &str) -> (String, u16) {
+    pattern
+        .strip_prefix('[')
+        .and_then(|rest| rest.rsplit_once("]:"))
+        .and_then(|(host, port)| Some((host.to_string(), port.parse().ok()?)))
+        .unwrap_or_else(|| (pattern.to_string(), 22))
+}
+
 fn host_matches(patterns: &HostPatterns, host_for_match: &str, host: &str, port: u16) -> bool {
     match patterns {
         HostPatterns::Patterns(list) => match_plain_patterns(list, host_for_match),
@@ -167,4 +312,150 @@ mod tests {
         assert!(!glob_match("*.example.com", "example.net"));
         assert!(glob_match("??.example.com", "ab.example.com"));
     }
+
+    #[test]
+    fn replaces_changed_keys() {
+        let old: PublicKey =
+            "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIB9dG4kjRhQTtWTVzd2t27+t0DEHBPW7iOD23TUiYLio"
+                .parse()
+                .unwrap();
+        let new: PublicKey =
+            "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAILM+rvN+ot98qgEN796jTiQfZfG1KaT0PtFDJ/XFSqti"
+                .parse()
+                .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("known_hosts");
+        let other = format!("other.example.com {}", old.to_openssh().unwrap());
+        fs::write(&path, format!("# keep me\n{other}\n")).unwrap();
+
+        let mut hosts = KnownHosts::load(path.clone()).unwrap();
+        hosts.add("example.com", 2222, &old, "catsolle").unwrap();
+        assert_eq!(
+            hosts.check("example.com", 2222, &new),
+            KnownHostResult::Mismatch
+        );
+
+        hosts
+            .replace("example.com", 2222, &new, "catsolle")
+            .unwrap();
+        assert_eq!(
+            hosts.check("example.com", 2222, &new),
+            KnownHostResult::Match
+        );
+        assert_eq!(
+            hosts.check("other.example.com", 22, &old),
+            KnownHostResult::Match
+        );
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# keep me\n"));
+        assert_eq!(content.matches("example.com").count(), 2);
+    }
+
+    #[test]
+    fn checks_every_key_type_of_a_host() {
+        let ed25519: PublicKey =
+            "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIB9dG4kjRhQTtWTVzd2t27+t0DEHBPW7iOD23TUiYLio"
+                .parse()
+                .unwrap();
+        let other_ed25519: PublicKey =
+            "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAILM+rvN+ot98qgEN796jTiQfZfG1KaT0PtFDJ/XFSqti"
+                .parse()
+                .unwrap();
+        let ecdsa: PublicKey = "ecdsa-sha2-nistp256 AAAAE2VjZHNhLXNoYTItbmlzdHAyNTYAAAAIbmlzdHAyNTYAAABBBKRhCzs8cW8qHMnM4W/HGEy4TJdtxV6jqUq9vzGqHqf7KfIGmF/4rK+1Th4uJDQ4M3Nws5T6uO4YtTk1DzxSBSg="
+            .parse()
+            .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("known_hosts");
+        fs::write(
+            &path,
+            format!(
+                "example.com {}\nexample.com {}\n",
+                ecdsa.to_openssh().unwrap(),
+                ed25519.to_openssh().unwrap()
+            ),
+        )
+        .unwrap();
+
+        let mut hosts = KnownHosts::load(path.clone()).unwrap();
+        assert_eq!(
+            hosts.check("example.com", 22, &ed25519),
+            KnownHostResult::Match
+        );
+        assert_eq!(
+            hosts.check("example.com", 22, &ecdsa),
+            KnownHostResult::Match
+        );
+        assert_eq!(
+            hosts.check("example.com", 22, &other_ed25519),
+            KnownHostResult::Mismatch
+        );
+
+        hosts
+            .replace("example.com", 22, &other_ed25519, "catsolle")
+            .unwrap();
+        assert_eq!(
+            hosts.check("example.com", 22, &ecdsa),
+            KnownHostResult::Match
+        );
+        assert_eq!(
+            hosts.check("example.com", 22, &other_ed25519),
+            KnownHostResult::Match
+        );
+        assert_eq!(
+            hosts.check("example.com", 22, &ed25519),
+            KnownHostResult::Mismatch
+        );
+    }
+
+    #[test]
+    fn lists_and_removes_entries() {
+        let key: PublicKey =
+            "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIB9dG4kjRhQTtWTVzd2t27+t0DEHBPW7iOD23TUiYLio"
+                .parse()
+                .unwrap();
+        let openssh = key.to_openssh().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("known_hosts");
+        fs::write(
+            &path,
+            format!(
+                "# keep me\nweb,[db]:2222 {openssh}\n@revoked old.example.com {openssh}\n\
+                 |1|F1E1KeoE/eEWhi10WpGv4OdiO6Y=|3988QV0VE8wmZL7suNrYQLITLCg= {openssh}\n\
+                 *.example.com {openssh}\n"
+            ),
+        )
+        .unwrap();
+
+        let mut hosts = KnownHosts::load(path.clone()).unwrap();
+        let entries = hosts.entries();
+        let listed: Vec<_> = entries.iter().map(|e| (e.host.as_str(), e.port)).collect();
+        assert_eq!(
+            listed,
+            vec![("web", 22), ("db", 2222), ("*.example.com", 22)]
+        );
+        assert_eq!(entries[0].key_type, "ssh-ed25519");
+        assert!(entries[0].fingerprint.starts_with("SHA256:"));
+
+        assert_eq!(hosts.remove("nope", 22).unwrap(), 0);
+        assert_eq!(hosts.remove("db", 2222).unwrap(), 1);
+        assert_eq!(hosts.check("db", 2222, &key), KnownHostResult::NotFound);
+        assert_eq!(hosts.check("web", 22, &key), KnownHostResult::Match);
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with(&format!("# keep me\nweb {openssh}\n@revoked")));
+        assert!(content.contains("|1|"));
+
+        assert_eq!(hosts.remove("web", 22).unwrap(), 1);
+        assert_eq!(hosts.check("web", 22, &key), KnownHostResult::NotFound);
+        assert_eq!(hosts.remove("a.example.com", 22).unwrap(), 1);
+        assert_eq!(
+            hosts.check("a.example.com", 22, &key),
+            KnownHostResult::NotFound
+        );
+        assert_eq!(
+            hosts.check("b.example.com", 22, &key),
+            KnownHostResult::Match
+        );
+        let listed: Vec<_> = hosts.entries().into_iter().map(|e| e.host).collect();
+        assert_eq!(listed, vec!["*.example.com"]);
+    }
 }
@@ -1,13 +1,21 @@
 pub mod client;
 pub mod config;
+pub mod error;
+pub mod forward;
 pub mod known_hosts;
 pub mod proxy;
+pub mod scp;
 pub mod sftp;
+pub mod shell;
 
-pub use client::{SshClient, SshSession, SshShell};
+pub use client::{CloseReason, ShellEnv, SshClient, SshSession, SshShell};
 pub use config::{
-    AuthMethod, HostKeyPolicy, JumpHost, KeyboardInteractiveHandler, ProxyConfig, ProxyType,
-    SshConnectConfig,
+    AuthMethod, HostKeyPolicy, JumpHost, KeyboardInteractiveHandler, NewHostKey, ProxyConfig,
+    ProxyType, SshConnectConfig,
 };
-pub use known_hosts::KnownHosts;
+pub use error::SshError;
+pub use forward::{DynamicForward, LocalForward, RemoteForward};
+pub use known_hosts::{KnownHostEntry, KnownHosts};
+pub use russh::keys::PublicKey;
+pub use scp::{ScpChannel, ScpDownload, ScpFileHeader, ScpUpload};
 pub use sftp::{SftpClient, SftpEntry};
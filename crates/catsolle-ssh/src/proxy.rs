@@ -1,5 +1,5 @@
 use crate::config::{ProxyConfig, ProxyType};
-use anyhow::Result;
+use crate::error::{Result, SshError};
 use base64::Engine;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
@@ -29,7 +29,7 @@ async fn socks5_handshake(
     let mut resp = [0u8; 2];
     stream.read_exact(&mut resp).await?;
     if resp[0] != 0x05 {
-        anyhow::bail!("invalid socks5 version");
+        return Err(SshError::Proxy("invalid socks5 version".to_string()));
     }
     match resp[1] {
         0x00 => {}
@@ -41,7 +41,7 @@ async fn socks5_handshake(
                 .map(|v| v.to_string())
                 .unwrap_or_default();
             if username.len() > 255 || password.len() > 255 {
-                anyhow::bail!("socks5 auth too long");
+                return Err(SshError::Proxy("socks5 auth too long".to_string()));
             }
             let mut auth = Vec::with_capacity(3 + username.len() + password.len());
             auth.push(0x01);
@@ -53,10 +53,14 @@ async fn socks5_handshake(
             let mut auth_resp = [0u8; 2];
             stream.read_exact(&mut auth_resp).await?;
             if auth_resp[1] != 0x00 {
-                anyhow::bail!("socks5 auth failed");
+                return Err(SshError::Proxy("socks5 auth failed".to_string()));
             }
         }
-        _ => anyhow::bail!("socks5 auth method unsupported"),
+        _ => {
+            return Err(SshError::Proxy(
+                "socks5 auth method unsupported".to_string(),
+            ))
+        }
     }
 
     let mut req = Vec::new();
@@ -84,7 +88,10 @@ async fn socks5_handshake(
     let mut header = [0u8; 4];
     stream.read_exact(&mut header).await?;
     if header[1] != 0x00 {
-        anyhow::bail!("socks5 connect failed: code {}", header[1]);
+        return Err(SshError::Proxy(format!(
+            "socks5 connect failed: code {}",
+            header[1]
+        )));
     }
     let atyp = header[3];
     match atyp {
@@ -102,7 +109,7 @@ async fn socks5_handshake(
             let mut addr = [0u8; 16];
             stream.read_exact(&mut addr).await?;
         }
-        _ => anyhow::bail!("socks5 invalid atyp"),
+        _ => return Err(SshError::Proxy("socks5 invalid atyp".to_string())),
     }
     let mut port_buf = [0u8; 2];
     stream.read_exact(&mut port_buf).await?;
@@ -144,7 +151,7 @@ async fn http_connect_handshake(
     let mut lines = resp.lines();
     let status = lines.next().unwrap_or("");
     if !status.contains("200") {
-        anyhow::bail!("http connect failed: {}", status);
+        return Err(SshError::Proxy(format!("http connect failed: {}", status)));
     }
     Ok(())
 }
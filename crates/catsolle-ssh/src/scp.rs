@@ -0,0 +1,251 @@
+use crate::error::{Result, SshError};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// Channel stream of an `scp -t`/`scp -f` exec request.
+pub type ScpChannel = russh::ChannelStream<russh::client::Msg>;
+
+const ACK_OK: u8 = 0;
+const ACK_WARNING: u8 = 1;
+const ACK_FATAL: u8 = 2;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScpFileHeader {
+    pub mode: u32,
+    pub size: u64,
+    pub name: String,
+}
+
+/// Sending side of the classic scp protocol for one regular file, talking to `scp -t`.
+pub struct ScpUpload<S> {
+    stream: BufReader<S>,
+    remaining: u64,
+}
+
+impl<S> ScpUpload<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Waits for the sink to get ready and announces the file.
+    pub async fn start(stream: S, mode: u32, size: u64, name: &str) -> Result<Self> {
+        let mut stream = BufReader::new(stream);
+        read_ack(&mut stream).await?;
+        let header = format!("C{:04o} {size} {name}\n", mode & 0o7777);
+        stream.get_mut().write_all(header.as_bytes()).await?;
+        read_ack(&mut stream).await?;
+        Ok(Self {
+            stream,
+            remaining: size,
+        })
+    }
+
+    pub async fn write(&mut self, data: &[u8]) -> Result<()> {
+        if data.len() as u64 > self.remaining {
+            return Err(SshError::Scp("more data than announced".to_string()));
+        }
+        self.stream.get_mut().write_all(data).await?;
+        self.remaining -= data.len() as u64;
+        Ok(())
+    }
+
+    pub async fn finish(mut self) -> Result<()> {
+        if self.remaining > 0 {
+            return Err(SshError::Scp(format!(
+                "{} bytes short of the announced size",
+                self.remaining
+            )));
+        }
+        self.stream.get_mut().write_all(&[ACK_OK]).await?;
+        read_ack(&mut self.stream).await?;
+        self.stream.get_mut().shutdown().await?;
+        Ok(())
+    }
+}
+
+/// Receiving side of the classic scp protocol for one regular file, talking to `scp -f`.
+pub struct ScpDownload<S> {
+    stream: BufReader<S>,
+    remaining: u64,
+}
+
+impl<S> ScpDownload<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Asks the source for the file and reads its header; timestamps are skipped.
+    pub async fn start(stream: S) -> Result<(Self, ScpFileHeader)> {
+        let mut stream = BufReader::new(stream);
+        stream.get_mut().write_all(&[ACK_OK]).await?;
+        loop {
+            let mut kind = [0u8; 1];
+            stream.read_exact(&mut kind).await?;
+            match kind[0] {
+                b'T' => {
+                    read_line(&mut stream).await?;
+                    stream.get_mut().write_all(&[ACK_OK]).await?;
+                }
+                b'C' => {
+                    let header = parse_file_header(&read_line(&mut stream).await?)?;
+                    stream.get_mut().write_all(&[ACK_OK]).await?;
+                    let download = Self {
+                        stream,
+                        remaining: header.size,
+                    };
+                    return Ok((download, header));
+                }
+                b'D' => return Err(SshError::Scp("source is a directory".to_string())),
+                ACK_WARNING | ACK_FATAL => {
+                    return Err(SshError::Scp(read_line(&mut stream).await?));
+                }
+                other => {
+                    return Err(SshError::Scp(format!(
+                        "unexpected scp message {other:#04x}"
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Reads file data into `buf`; returns 0 once the announced size is consumed.
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let want = buf
+            .len()
+            .min(self.remaining.try_into().unwrap_or(usize::MAX));
+        if want == 0 {
+            return Ok(0);
+        }
+        let n = self.stream.read(&mut buf[..want]).await?;
+        if n == 0 {
+            return Err(SshError::Scp("connection closed mid-file".to_string()));
+        }
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+
+    pub async fn finish(mut self) -> Result<()> {
+        read_ack(&mut self.stream).await?;
+        self.stream.get_mut().write_all(&[ACK_OK]).await?;
+        self.stream.get_mut().shutdown().await?;
+        Ok(())
+    }
+}
+
+async fn read_ack<S>(stream: &mut BufReader<S>) -> Result<()>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut ack = [0u8; 1];
+    stream.read_exact(&mut ack).await?;
+    match ack[0] {
+        ACK_OK => Ok(()),
+        ACK_WARNING | ACK_FATAL => Err(SshError::Scp(read_line(stream).await?)),
+        other => Err(SshError::Scp(format!("unexpected scp reply {other:#04x}"))),
+    }
+}
+
+async fn read_line<S>(stream: &mut BufReader<S>) -> Result<String>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut line = Vec::new();
+    stream.read_until(b'\n', &mut line).await?;
+    if line.pop() != Some(b'\n') {
+        return Err(SshError::Scp("connection closed".to_string()));
+    }
+    Ok(String::from_utf8_lossy(&line).trim().to_string())
+}
+
+/// Parses the `<mode> <size> <name>` part of a `C` message.
+fn parse_file_header(line: &str) -> Result<ScpFileHeader> {
+    let invalid = || SshError::Scp(format!("invalid scp header: {line}"));
+    let mut parts = line.splitn(3, ' ');
+    let mode = parts
+        .next()
+        .and_then(|v| u32::from_str_radix(v, 8).ok())
+        .ok_or_else(invalid)?;
+    let size = parts
+        .next()
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(invalid)?;
+    let name = parts.next().ok_or_else(invalid)?.to_string();
+    Ok(ScpFileHeader { mode, size, name })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn uploads_one_file() {
+        let (client, mut server) = tokio::io::duplex(64);
+        let sink = tokio::spawn(async move {
+            server.write_all(&[0]).await.unwrap();
+            let mut reader = BufReader::new(server);
+            let mut header = String::new();
+            reader.read_line(&mut header).await.unwrap();
+            reader.get_mut().write_all(&[0]).await.unwrap();
+            let mut body = [0u8; 6];
+            reader.read_exact(&mut body).await.unwrap();
+            reader.get_mut().write_all(&[0]).await.unwrap();
+            (header, body)
+        });
+
+        let mut upload = ScpUpload::start(client, 0o100644, 5, "a.txt")
+            .await
+            .unwrap();
+        upload.write(b"hello").await.unwrap();
+        assert!(upload.write(b"!").await.is_err());
+        upload.finish().await.unwrap();
+
+        let (header, body) = sink.await.unwrap();
+        assert_eq!(header, "C0644 5 a.txt\n");
+        assert_eq!(&body, b"hello\0");
+    }
+
+    #[tokio::test]
+    async fn downloads_one_file_and_reports_errors() {
+        let (client, mut server) = tokio::io::duplex(64);
+        let source = tokio::spawn(async move {
+            let mut ack = [0u8; 1];
+            server.read_exact(&mut ack).await.unwrap();
+            server.write_all(b"T1 0 1 0\n").await.unwrap();
+            server.read_exact(&mut ack).await.unwrap();
+            server.write_all(b"C0600 3 key file\n").await.unwrap();
+            server.read_exact(&mut ack).await.unwrap();
+            server.write_all(b"abc\0").await.unwrap();
+            server.read_exact(&mut ack).await.unwrap();
+        });
+
+        let (mut download, header) = ScpDownload::start(client).await.unwrap();
+        assert_eq!(
+            header,
+            ScpFileHeader {
+                mode: 0o600,
+                size: 3,
+                name: "key file".to_string(),
+            }
+        );
+        let mut buf = [0u8; 16];
+        let mut data = Vec::new();
+        loop {
+            let n = download.read(&mut buf).await.unwrap();
+            if n == 0 {
+                break;
+            }
+            data.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(data, b"abc");
+        download.finish().await.unwrap();
+        source.await.unwrap();
+
+        let (client, mut server) = tokio::io::duplex(64);
+        server
+            .write_all(b"\x01scp: /nope: No such file or directory\n")
+            .await
+            .unwrap();
+        let err = ScpDownload::start(client).await.err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "scp error: scp: /nope: No such file or directory"
+        );
+    }
+}
@@ -1,8 +1,12 @@
-use anyhow::Result;
+use crate::error::{Result, SshError};
 use russh_sftp::client::fs::{File, Metadata};
 use russh_sftp::client::SftpSession;
+use russh_sftp::extensions::Statvfs;
 use russh_sftp::protocol::OpenFlags;
-use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::io::{AsyncRead, AsyncSeekExt, AsyncWrite};
+
+/// `f_flag` bit marking a read-only mount in a statvfs@openssh.com reply.
+const ST_RDONLY: u64 = 0x1;
 
 #[derive(Debug, Clone)]
 pub struct SftpEntry {
@@ -10,6 +14,7 @@ pub struct SftpEntry {
     pub path: String,
     pub size: u64,
     pub is_dir: bool,
+    pub is_symlink: bool,
     pub modified: Option<u64>,
     pub permissions: Option<u32>,
 }
@@ -42,10 +47,51 @@ impl SftpClient {
         Ok(self.inner.metadata(path).await?)
     }
 
+    /// Fails early with `SshError::NotWritable` when `dir` obviously cannot take `bytes` of
+    /// new data. The nearest existing ancestor is checked, since missing directories get
+    /// created on the way. Free space and inodes are only known when the server supports
+    /// statvfs; the owner of a write bit is not, so the server still has the final word.
+    pub async fn check_writable(&self, dir: &str, bytes: u64) -> Result<()> {
+        let mut current = dir.to_string();
+        let meta = loop {
+            match self.inner.metadata(current.as_str()).await {
+                Ok(meta) => break meta,
+                Err(_) if current != "/" && current != "." => {
+                    current = match current.trim_end_matches('/').rsplit_once('/') {
+                        Some(("", _)) => "/".to_string(),
+                        Some((parent, _)) => parent.to_string(),
+                        None => ".".to_string(),
+                    };
+                }
+                Err(err) => return Err(err.into()),
+            }
+        };
+        let fs = self.inner.fs_info(current.as_str()).await.ok().flatten();
+        match write_blocker(&meta, fs.as_ref(), bytes) {
+            Some(reason) => Err(SshError::NotWritable {
+                path: current,
+                reason: reason.to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub async fn canonicalize(&self, path: &str) -> Result<String> {
+        Ok(self.inner.canonicalize(path).await?)
+    }
+
     pub async fn open_read(&self, path: &str) -> Result<File> {
         Ok(self.inner.open(path).await?)
     }
 
+    pub async fn open_read_at(&self, path: &str, offset: u64) -> Result<File> {
+        let mut file = self.inner.open(path).await?;
+        if offset > 0 {
+            file.seek(std::io::SeekFrom::Start(offset)).await?;
+        }
+        Ok(file)
+    }
+
     pub async fn open_write(&self, path: &str, truncate: bool) -> Result<File> {
         let mut flags = OpenFlags::WRITE | OpenFlags::CREATE;
         if truncate {
@@ -108,8 +154,85 @@ impl SftpClient {
             path,
             size: meta.size.unwrap_or(0),
             is_dir,
+            is_symlink: file_type.is_symlink(),
             modified: meta.mtime.map(|t| t as u64),
             permissions: meta.permissions,
         }
     }
 }
+
+fn write_blocker(dir: &Metadata, fs: Option<&Statvfs>, bytes: u64) -> Option<&'static str> {
+    if !dir.is_dir() {
+        return Some("not a directory");
+    }
+    if dir.permissions.is_some_and(|mode| mode & 0o222 == 0) {
+        return Some("no write permission");
+    }
+    let fs = fs?;
+    if fs.flags & ST_RDONLY != 0 {
+        return Some("read-only file system");
+    }
+    if fs.blocks_avail.saturating_mul(fs.fragment_size) < bytes {
+        return Some("not enough free space");
+    }
+    if fs.inodes > 0 && fs.inodes_avail == 0 {
+        return Some("no free inodes");
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use russh_sftp::protocol::FileAttributes;
+
+    fn dir(mode: u32) -> Metadata {
+        FileAttributes {
+            permissions: Some(0o040000 | mode),
+            ..FileAttributes::default()
+        }
+    }
+
+    fn fs(blocks_avail: u64, inodes_avail: u64, flags: u64) -> Statvfs {
+        Statvfs {
+            block_size: 4096,
+            fragment_size: 4096,
+            blocks: 1000,
+            blocks_free: blocks_avail,
+            blocks_avail,
+            inodes: 100,
+            inodes_free: inodes_avail,
+            inodes_avail,
+            fs_id: 0,
+            flags,
+            name_max: 255,
+        }
+    }
+
+    #[test]
+    fn finds_write_blockers() {
+        assert_eq!(write_blocker(&dir(0o755), None, u64::MAX), None);
+        assert_eq!(
+            write_blocker(&dir(0o555), None, 0),
+            Some("no write permission")
+        );
+        let file = FileAttributes {
+            permissions: Some(0o100644),
+            ..FileAttributes::default()
+        };
+        assert_eq!(write_blocker(&file, None, 0), Some("not a directory"));
+        assert_eq!(
+            write_blocker(&dir(0o755), Some(&fs(10, 5, ST_RDONLY)), 0),
+            Some("read-only file system")
+        );
+        assert_eq!(
+            write_blocker(&dir(0o755), Some(&fs(1, 5, 0)), 8192),
+            Some("not enough free space")
+        );
+        assert_eq!(
+            write_blocker(&dir(0o755), Some(&fs(10, 0, 0)), 0),
+            Some("no free inodes")
+        );
+        assert_eq!(write_blocker(&dir(0o755), Some(&fs(10, 5, 0)), 4096), None);
+    }
+}
@@ -0,0 +1,43 @@
+use std::borrow::Cow;
+
+/// Quotes `value` as a single POSIX `sh` word. Plain words are returned as-is; anything
+/// else is wrapped in single quotes, with embedded quotes spelled `'\''`.
+pub fn quote(value: &str) -> Cow<'_, str> {
+    let plain = !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"-_./:=+,@%".contains(&b));
+    if plain {
+        Cow::Borrowed(value)
+    } else {
+        Cow::Owned(format!("'{}'", value.replace('\'', "'\\''")))
+    }
+}
+
+/// Quotes every argument and joins them into one command line.
+pub fn join<S: AsRef<str>>(args: &[S]) -> String {
+    args.iter()
+        .map(|arg| quote(arg.as_ref()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_only_when_needed() {
+        assert_eq!(quote("/var/log/syslog"), "/var/log/syslog");
+        assert_eq!(quote(""), "''");
+        assert_eq!(quote("my file"), "'my file'");
+        assert_eq!(quote("it's"), "'it'\\''s'");
+        assert_eq!(quote("$(reboot)"), "'$(reboot)'");
+        assert_eq!(quote("-rf *"), "'-rf *'");
+    }
+
+    #[test]
+    fn joins_quoted_arguments() {
+        assert_eq!(join(&["ls", "-la", "a b", "c;d"]), "ls -la 'a b' 'c;d'");
+    }
+}
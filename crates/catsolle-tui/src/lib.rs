@@ -1,11 +1,20 @@
 use anyhow::Result;
-use catsolle_config::{AiConfig, AppConfig, ConfigManager, I18n};
+use async_trait::async_trait;
+use catsolle_config::{AiConfig, AppConfig, ConfigManager, I18n, TransferConfig, UiConfig};
+use catsolle_core::transfer::OverwriteMode;
 use catsolle_core::{
-    AuthMethod, Connection, ConnectionStore, Event as CoreEvent, EventBus, SessionManager,
-    TransferEndpoint, TransferFile, TransferJob, TransferOptions, TransferProgress, TransferQueue,
-    TransferState,
+    expand_home, expand_transfer_files, format_bytes, format_duration, AsciinemaRecorder,
+    AuthMethod, Bookmark, Connection, ConnectionGroup, ConnectionStore, CoreError,
+    Event as CoreEvent, EventBus, FileOutcome, FileResult, HashAlgorithm, JumpHost, RemoteFs,
+    SessionManager, TerminalEncoding, TransferEndpoint, TransferFile, TransferJob, TransferOptions,
+    TransferProgress, TransferQueue, TransferState,
+};
+use catsolle_ssh::{CloseReason, KnownHostEntry, NewHostKey, PublicKey, SshError};
+use crossterm::event::{
+    DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture, Event,
+    EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+    MouseEventKind,
 };
-use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use crossterm::execute;
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
@@ -16,15 +25,17 @@ use futures::{
     future::{pending, Either},
     StreamExt, TryStreamExt,
 };
-use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Position, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span, Text};
-use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap};
 use ratatui::Terminal;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::Write as _;
 use std::io::{self, Stdout};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
@@ -63,7 +74,12 @@ pub async fn run(
 ) -> Result<()> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableFocusChange,
+        EnableMouseCapture
+    )?;
     let backend = ratatui::backend::CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
@@ -79,7 +95,12 @@ pub async fn run(
     let res = run_app(&mut terminal, ctx).await;
 
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(
+        terminal.backend_mut(),
+        DisableFocusChange,
+        DisableMouseCapture,
+        LeaveAlternateScreen
+    )?;
     terminal.show_cursor()?;
     res
 }
@@ -89,9 +110,16 @@ async fn run_app(
     ctx: AppRunContext,
 ) -> Result<()> {
     let mut event_stream = EventStream::new();
-    let mut event_rx = ctx.bus.subscribe();
+    let (retained_events, mut event_rx) = ctx.bus.subscribe_with_snapshot();
     let (assistant_tx, mut assistant_rx) = mpsc::channel::<AssistantEvent>(16);
     let (connect_tx, mut connect_rx) = mpsc::channel::<ConnectEvent>(8);
+    let mut host_key_rx = if ctx.config.ssh.confirm_new_host_keys {
+        let (host_key_tx, host_key_rx) = mpsc::channel::<NewHostKey>(4);
+        ctx.sessions.set_host_key_prompts(Some(host_key_tx));
+        Some(host_key_rx)
+    } else {
+        None
+    };
     let mut tick_interval =
         tokio::time::interval(Duration::from_millis(CONNECT_ANIMATION_INTERVAL_MS));
     tick_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
@@ -108,12 +136,27 @@ async fn run_app(
         },
     )
     .await?;
+    for event in retained_events {
+        app.handle_bus_event(event).await?;
+    }
 
+    let frame_interval = Duration::from_millis(FRAME_INTERVAL_MS);
+    let mut last_draw: Option<Instant> = None;
+    let mut needs_redraw = true;
     loop {
-        terminal.draw(|f| app.draw(f))?;
+        if needs_redraw {
+            let now = Instant::now();
+            if last_draw.is_none_or(|at| now.duration_since(at) >= frame_interval) {
+                terminal.draw(|f| app.draw(f))?;
+                last_draw = Some(now);
+                needs_redraw = false;
+            }
+        }
         app.apply_pending_resize().await?;
 
         let capture_deadline = app.shell_capture_deadline();
+        let resize_deadline = app.shell_resize_deadline();
+        let reconnect_deadline = app.reconnect_deadline();
         let (shell_opt, shell_tool_rx) = (&mut app.shell, &mut app.shell_tool_rx);
         let output_fut = if shell_opt.is_some() {
             Either::Left(async { shell_opt.as_mut().unwrap().read().await })
@@ -122,12 +165,33 @@ async fn run_app(
         };
         let shell_req_fut = shell_tool_rx.recv();
         let connect_req_fut = connect_rx.recv();
+        let reconnect_fut = match reconnect_deadline {
+            Some(deadline) => Either::Left(tokio::time::sleep_until(deadline)),
+            None => Either::Right(pending::<()>()),
+        };
+        let host_key_fut = match host_key_rx.as_mut() {
+            Some(rx) => Either::Left(rx.recv()),
+            None => Either::Right(pending::<Option<NewHostKey>>()),
+        };
         let deadline_fut = if let Some(deadline) = capture_deadline {
             Either::Left(tokio::time::sleep_until(deadline))
         } else {
             Either::Right(pending::<()>())
         };
-        let tick_fut = if app.connecting.is_some() || app.assistant.busy || app.tool_busy {
+        let resize_fut = if let Some(deadline) = resize_deadline {
+            Either::Left(tokio::time::sleep_until(deadline))
+        } else {
+            Either::Right(pending::<()>())
+        };
+        let frame_fut = match (needs_redraw, last_draw) {
+            (true, Some(at)) => Either::Left(tokio::time::sleep_until(at + frame_interval)),
+            _ => Either::Right(pending::<()>()),
+        };
+        let tick_fut = if app.connecting.is_some()
+            || app.assistant.busy
+            || app.tool_busy
+            || app.bell_flash_until.is_some()
+        {
             Either::Left(tick_interval.tick())
         } else {
             Either::Right(pending::<Instant>())
@@ -137,15 +201,28 @@ async fn run_app(
             maybe_event = event_stream.next() => {
                 if let Some(Ok(event)) = maybe_event {
                     if app.handle_event(event).await? {
+                        app.save_conversation();
+                        app.stop_recording();
                         break;
                     }
                 }
             }
             maybe_output = output_fut => {
-                if let Some(data) = maybe_output {
+                if let Some(mut data) = maybe_output {
+                    // Coalesce everything already buffered so a fast producer costs one frame.
+                    if let Some(shell) = app.shell.as_mut() {
+                        while data.len() < SHELL_COALESCE_MAX_BYTES {
+                            let Some(more) = shell.try_read() else {
+                                break;
+                            };
+                            data.extend_from_slice(&more);
+                        }
+                    }
                     app.process_shell_output(&data);
                     app.auto_confirm_interactive().await?;
                     app.try_start_pending_shell_tool().await;
+                } else {
+                    app.handle_shell_closed().await?;
                 }
             }
             maybe_bus = event_rx.recv() => {
@@ -153,7 +230,12 @@ async fn run_app(
                     Ok(event) => {
                         app.handle_bus_event(event).await?;
                     }
-                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {
+                        // Dropped updates are superseded by the retained state.
+                        for event in ctx.bus.snapshot() {
+                            app.handle_bus_event(event).await?;
+                        }
+                    }
                     Err(tokio::sync::broadcast::error::RecvError::Closed) => {}
                 }
             }
@@ -172,6 +254,11 @@ async fn run_app(
                     app.handle_connect_event(event).await?;
                 }
             }
+            maybe_host_key = host_key_fut => {
+                if let Some(prompt) = maybe_host_key {
+                    app.pending_host_keys.push_back(prompt);
+                }
+            }
             _ = deadline_fut => {
                 app.finish_shell_capture(true);
                 app.try_start_pending_shell_tool().await;
@@ -179,7 +266,14 @@ async fn run_app(
             _ = tick_fut => {
                 app.handle_tick();
             }
+            _ = reconnect_fut => {
+                app.try_reconnect();
+            }
+            _ = resize_fut => {}
+            _ = frame_fut => {}
         }
+        app.prompt_new_host_key();
+        needs_redraw = true;
     }
 
     Ok(())
@@ -193,24 +287,53 @@ struct AppState {
     config: AppConfig,
     i18n: I18n,
     theme: Theme,
+    keymap: Keymap,
     ai_client: reqwest::Client,
     assistant: AssistantState,
     assistant_tx: mpsc::Sender<AssistantEvent>,
     connect_tx: mpsc::Sender<ConnectEvent>,
     connections: Vec<Connection>,
+    groups: Vec<ConnectionGroup>,
+    collapsed_groups: HashSet<Option<Uuid>>,
+    connection_list_mode: ConnectionListMode,
+    /// Collapsed auth method headers, by `AuthMethod::as_key`.
+    collapsed_auth: HashSet<&'static str>,
+    /// Fuzzy search over the connection list; while not empty the list is flat and ranked.
+    connection_query: String,
+    connection_query_editing: bool,
     selected: usize,
     mode: AppMode,
     terminal_parser: Parser,
+    /// Rows scrolled back from the live screen. The parser itself stays at offset 0 so
+    /// tools and prompt detection always read the live screen; only drawing looks back.
+    scrollback_offset: usize,
     shell: Option<catsolle_ssh::SshShell>,
+    /// Asciicast of the live shell while `recording.enabled`.
+    recorder: Option<AsciinemaRecorder>,
     shell_log: ShellLog,
     shell_capture: Option<ShellCapture>,
     shell_tool_tx: mpsc::Sender<ShellToolRequest>,
     shell_tool_rx: mpsc::Receiver<ShellToolRequest>,
     shell_display_tail: Vec<u8>,
+    hyperlinks: HyperlinkTracker,
+    bell_count: usize,
+    bell_flash_until: Option<Instant>,
+    last_bell_notification: Option<Instant>,
+    app_focused: bool,
     pending_shell_tools: VecDeque<ShellToolRequest>,
     left_panel: PanelState,
     right_panel: PanelState,
     active_panel_left: bool,
+    /// Left and right file panel areas from the last frame, used for mouse hit-testing.
+    panel_areas: [Rect; 2],
+    last_panel_click: Option<(bool, usize, Instant)>,
+    file_filter_editing: bool,
+    /// Mirrors directory changes of the active panel in the other one when possible.
+    sync_navigation: bool,
+    /// Colors entries that exist on one side only or differ from the other panel.
+    compare_panels: bool,
+    /// Assistant conversations of previously visited connections, without the hint.
+    conversations: HashMap<Uuid, Vec<AssistantMessage>>,
     input_focus: InputFocus,
     show_file_manager: bool,
     show_ai_panel: bool,
@@ -218,15 +341,21 @@ struct AppState {
     transfer_status: Option<TransferStatus>,
     completed_transfers: HashSet<Uuid>,
     pending_tools: VecDeque<ToolCall>,
+    /// Unknown host keys whose connections wait for the user, oldest first.
+    pending_host_keys: VecDeque<NewHostKey>,
+    stream_fallback: bool,
+    tool_batch: bool,
     tool_busy: bool,
     agent_steps_remaining: u32,
     overlay: Overlay,
     active_connection: Option<Connection>,
     connecting: Option<ConnectingState>,
+    reconnect: Option<ReconnectState>,
     last_interactive_signature: Option<String>,
     ai_spinner_frame: usize,
     terminal_size: Option<(u16, u16)>,
-    pending_shell_resize: Option<(u16, u16)>,
+    /// Latest PTY size and the instant it may be sent; each change pushes the instant back.
+    pending_shell_resize: Option<(u16, u16, Instant)>,
 }
 
 #[derive(Clone, Debug)]
@@ -271,6 +400,350 @@ impl Theme {
             selection_inactive_fg: Color::Black,
         }
     }
+
+    /// Palette for a `ui.theme` name; `neko-dark` is the kawaii palette.
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "neko-dark" | "kawaii" => Some(Self::kawaii()),
+            "neko-light" => Some(Self {
+                accent: Color::Magenta,
+                accent_soft: Color::Blue,
+                accent_alt: Color::Rgb(160, 100, 0),
+                text: Color::Black,
+                muted: Color::DarkGray,
+                error: Color::Red,
+                selection_bg: Color::Magenta,
+                selection_fg: Color::White,
+                selection_inactive_bg: Color::Blue,
+                selection_inactive_fg: Color::White,
+            }),
+            "mono" => Some(Self {
+                accent: Color::White,
+                accent_soft: Color::Gray,
+                accent_alt: Color::White,
+                text: Color::Reset,
+                muted: Color::DarkGray,
+                error: Color::White,
+                selection_bg: Color::White,
+                selection_fg: Color::Black,
+                selection_inactive_bg: Color::DarkGray,
+                selection_inactive_fg: Color::White,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Session actions that can be rebound, named as in the keybindings file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum KeyAction {
+    AiSettings,
+    ToggleAiPanel,
+    ToggleFileManager,
+    ToggleFocus,
+    ToggleTools,
+    ToggleAgent,
+    ToggleAuto,
+    Open,
+    NavigateUp,
+    Copy,
+    QuickSend,
+    SyncDirectory,
+    Compare,
+    SyncNavigation,
+    SwapPanels,
+    Delete,
+    Rename,
+    Sort,
+    Filter,
+    ToggleHidden,
+    Mkdir,
+    Bookmarks,
+    PauseTransfer,
+    ResumeTransfer,
+    CancelTransfer,
+}
+
+impl KeyAction {
+    const ALL: [KeyAction; 25] = [
+        KeyAction::AiSettings,
+        KeyAction::ToggleAiPanel,
+        KeyAction::ToggleFileManager,
+        KeyAction::ToggleFocus,
+        KeyAction::ToggleTools,
+        KeyAction::ToggleAgent,
+        KeyAction::ToggleAuto,
+        KeyAction::Open,
+        KeyAction::NavigateUp,
+        KeyAction::Copy,
+        KeyAction::QuickSend,
+        KeyAction::SyncDirectory,
+        KeyAction::Compare,
+        KeyAction::SyncNavigation,
+        KeyAction::SwapPanels,
+        KeyAction::Delete,
+        KeyAction::Rename,
+        KeyAction::Sort,
+        KeyAction::Filter,
+        KeyAction::ToggleHidden,
+        KeyAction::Mkdir,
+        KeyAction::Bookmarks,
+        KeyAction::PauseTransfer,
+        KeyAction::ResumeTransfer,
+        KeyAction::CancelTransfer,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            KeyAction::AiSettings => "ai_settings",
+            KeyAction::ToggleAiPanel => "toggle_ai_panel",
+            KeyAction::ToggleFileManager => "toggle_file_manager",
+            KeyAction::ToggleFocus => "toggle_focus",
+            KeyAction::ToggleTools => "toggle_tools",
+            KeyAction::ToggleAgent => "toggle_agent",
+            KeyAction::ToggleAuto => "toggle_auto",
+            KeyAction::Open => "open",
+            KeyAction::NavigateUp => "navigate_up",
+            KeyAction::Copy => "copy",
+            KeyAction::QuickSend => "quick_send",
+            KeyAction::SyncDirectory => "sync_directory",
+            KeyAction::Compare => "compare",
+            KeyAction::SyncNavigation => "sync_navigation",
+            KeyAction::SwapPanels => "swap_panels",
+            KeyAction::Delete => "delete",
+            KeyAction::Rename => "rename",
+            KeyAction::Sort => "sort",
+            KeyAction::Filter => "filter",
+            KeyAction::ToggleHidden => "toggle_hidden",
+            KeyAction::Mkdir => "mkdir",
+            KeyAction::Bookmarks => "bookmarks",
+            KeyAction::PauseTransfer => "pause_transfer",
+            KeyAction::ResumeTransfer => "resume_transfer",
+            KeyAction::CancelTransfer => "cancel_transfer",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            KeyAction::AiSettings => &["F9"],
+            KeyAction::ToggleAiPanel => &["F10"],
+            KeyAction::ToggleFileManager => &["F12"],
+            KeyAction::ToggleFocus => &["ctrl+t"],
+            KeyAction::ToggleTools => &["F6"],
+            KeyAction::ToggleAgent => &["F7"],
+            KeyAction::ToggleAuto => &["F8"],
+            KeyAction::Open => &["enter"],
+            KeyAction::NavigateUp => &["backspace"],
+            KeyAction::Copy => &["F5"],
+            KeyAction::QuickSend => &["u"],
+            KeyAction::SyncDirectory => &["S"],
+            KeyAction::Compare => &["c"],
+            KeyAction::SyncNavigation => &["="],
+            KeyAction::SwapPanels => &["ctrl+u"],
+            KeyAction::Delete => &["delete"],
+            KeyAction::Rename => &["r"],
+            KeyAction::Sort => &["s"],
+            KeyAction::Filter => &["/"],
+            KeyAction::ToggleHidden => &["."],
+            KeyAction::Mkdir => &["m"],
+            KeyAction::Bookmarks => &["b"],
+            KeyAction::PauseTransfer => &["F6"],
+            KeyAction::ResumeTransfer => &["F7"],
+            KeyAction::CancelTransfer => &[],
+        }
+    }
+
+    /// Global actions work whatever has focus; the rest only in the file panels.
+    fn is_global(self) -> bool {
+        matches!(
+            self,
+            KeyAction::AiSettings
+                | KeyAction::ToggleAiPanel
+                | KeyAction::ToggleFileManager
+                | KeyAction::ToggleFocus
+                | KeyAction::ToggleTools
+                | KeyAction::ToggleAgent
+                | KeyAction::ToggleAuto
+        )
+    }
+}
+
+/// A key with the modifiers that matter. Shift is folded into the character for
+/// printable keys, so `S` and `shift+s` are the same binding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::Char(c.to_ascii_lowercase())
+            }
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            other => other,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Parses descriptors like `F5`, `ctrl+t`, `shift+F6`, `alt+enter`, `S` or `ctrl++`.
+    fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        let (mods, key) = if let Some(mods) = spec.strip_suffix("++") {
+            (mods, "+")
+        } else {
+            spec.rsplit_once('+').unwrap_or(("", spec))
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for part in mods.split('+').filter(|part| !part.is_empty()) {
+            modifiers |= match part.trim().to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(anyhow::anyhow!("unknown modifier `{part}` in `{spec}`")),
+            };
+        }
+        let lower = key.to_ascii_lowercase();
+        let code = match lower.as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" | "ins" => KeyCode::Insert,
+            "tab" => KeyCode::Tab,
+            "esc" | "escape" => KeyCode::Esc,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" | "pgup" => KeyCode::PageUp,
+            "pagedown" | "pgdn" => KeyCode::PageDown,
+            _ => match lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n) if (1..=24).contains(&n) => KeyCode::F(n),
+                _ => {
+                    let mut chars = key.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => KeyCode::Char(c),
+                        _ => return Err(anyhow::anyhow!("unknown key `{key}` in `{spec}`")),
+                    }
+                }
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+/// One key or several for an action in the keybindings file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeySpecs {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Key to action lookup for the session screen. A keybindings file lists action names
+/// with one key or a list of keys; listed actions lose their default keys (an empty
+/// list unbinds), and a key taken by a listed action no longer triggers its default ones.
+/// A key may carry a file panel action and a global one, like F6 pausing a transfer in
+/// the panels and toggling AI tools everywhere else.
+#[derive(Clone, Debug)]
+struct Keymap {
+    bindings: HashMap<KeyBinding, Vec<KeyAction>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::parse("").expect("default keybindings parse")
+    }
+}
+
+impl Keymap {
+    fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| anyhow::anyhow!("{}: {err}", path.display()))?;
+        Self::parse(&content)
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let table: BTreeMap<String, KeySpecs> = toml::from_str(content)?;
+        let mut custom = Vec::new();
+        for (name, specs) in table {
+            let action = KeyAction::from_name(&name)
+                .ok_or_else(|| anyhow::anyhow!("unknown action `{name}`"))?;
+            let specs = match specs {
+                KeySpecs::One(spec) => vec![spec],
+                KeySpecs::Many(specs) => specs,
+            };
+            let keys = specs
+                .iter()
+                .map(|spec| KeyBinding::parse(spec))
+                .collect::<Result<Vec<_>>>()?;
+            custom.push((action, keys));
+        }
+        let mut bindings: HashMap<KeyBinding, Vec<KeyAction>> = HashMap::new();
+        for action in KeyAction::ALL {
+            if custom.iter().any(|(custom, _)| *custom == action) {
+                continue;
+            }
+            for spec in action.default_keys() {
+                bindings
+                    .entry(KeyBinding::parse(spec)?)
+                    .or_default()
+                    .push(action);
+            }
+        }
+        let mut claimed = HashSet::new();
+        for (action, keys) in custom {
+            for key in keys {
+                let actions = bindings.entry(key).or_default();
+                if claimed.insert(key) {
+                    actions.clear();
+                }
+                actions.push(action);
+            }
+        }
+        Ok(Self { bindings })
+    }
+
+    /// The action `key` triggers; in the file panels their own actions win over global ones.
+    fn action(&self, key: &KeyEvent, files: bool) -> Option<KeyAction> {
+        let actions = self
+            .bindings
+            .get(&KeyBinding::new(key.code, key.modifiers))?;
+        actions
+            .iter()
+            .find(|action| files && !action.is_global())
+            .or_else(|| actions.iter().find(|action| action.is_global()))
+            .copied()
+    }
+}
+
+/// Themes the cycle key walks through, in order.
+const THEME_NAMES: [&str; 3] = ["neko-dark", "neko-light", "mono"];
+
+/// Theme after `current`; unknown names start over at the first one.
+fn next_theme_name(current: &str) -> &'static str {
+    let pos = THEME_NAMES
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(current.trim()));
+    match pos {
+        Some(pos) => THEME_NAMES[(pos + 1) % THEME_NAMES.len()],
+        None => THEME_NAMES[0],
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -281,11 +754,44 @@ enum Overlay {
         input: String,
         error: Option<String>,
     },
-    Edit {
+    Group {
+        id: Uuid,
+        input: String,
+        error: Option<String>,
+    },
+    Locale {
+        id: Uuid,
+        input: String,
+        error: Option<String>,
+    },
+    TransferDirs {
         id: Uuid,
         input: String,
         error: Option<String>,
     },
+    Delete {
+        kind: PanelKind,
+        path: String,
+        name: String,
+        is_dir: bool,
+    },
+    Rename {
+        kind: PanelKind,
+        dir: String,
+        from: String,
+        input: String,
+        error: Option<String>,
+    },
+    Mkdir {
+        kind: PanelKind,
+        dir: String,
+        input: String,
+        error: Option<String>,
+    },
+    Edit {
+        id: Uuid,
+        state: Box<ConnectionFormState>,
+    },
     Password {
         id: Uuid,
         state: PasswordOverlayState,
@@ -293,6 +799,49 @@ enum Overlay {
     AiSettings {
         state: Box<AiSettingsState>,
     },
+    /// Asks before running the call at the front of `pending_tools`.
+    ToolApproval,
+    /// The server of `conn` (or one of its jump hosts) offered a key that differs from
+    /// known_hosts; the connection waits until the user aborts or replaces the stored key.
+    HostKeyChanged {
+        conn: Box<Connection>,
+        host: String,
+        port: u16,
+        fingerprint: String,
+        key: Box<PublicKey>,
+    },
+    /// Asks whether to trust the key at the front of `pending_host_keys`, which known_hosts
+    /// has never seen.
+    UnknownHostKey,
+    /// Bookmarks for the panel of `kind`: local ones, or those of the active connection.
+    Bookmarks {
+        kind: PanelKind,
+        items: Vec<Bookmark>,
+        selected: usize,
+        error: Option<String>,
+    },
+    /// Host keys stored in known_hosts.
+    KnownHosts {
+        items: Vec<KnownHostEntry>,
+        selected: usize,
+        error: Option<String>,
+    },
+    /// Jump hosts of a connection, in connect order; `input` is set while a hop is typed.
+    JumpHosts {
+        id: Uuid,
+        items: Vec<JumpHost>,
+        selected: usize,
+        input: Option<String>,
+        error: Option<String>,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum JumpHostError {
+    Invalid,
+    /// The hop is the connection's own host.
+    SameAsTarget,
+    KeyMissing(PathBuf),
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -320,6 +869,8 @@ struct PasswordOverlayState {
 #[derive(Clone, Debug)]
 struct AiSettingsState {
     selected: usize,
+    /// Index into `TOOLS` for the allowed-tools row.
+    tool_cursor: usize,
     editing: bool,
     input: String,
     error: Option<String>,
@@ -340,8 +891,11 @@ struct AiConfigDraft {
     streaming: bool,
     agent_enabled: bool,
     auto_mode: bool,
+    continue_after_tool: bool,
     max_steps: String,
     tools_enabled: bool,
+    native_tools: bool,
+    blocked_tools: BTreeSet<String>,
     system_prompt: String,
 }
 
@@ -359,12 +913,15 @@ enum AiSettingsField {
     Streaming,
     Agent,
     AutoMode,
+    ContinueAfterTool,
     MaxSteps,
     Tools,
+    NativeTools,
+    AllowedTools,
     SystemPrompt,
 }
 
-const AI_SETTINGS_FIELDS: [AiSettingsField; 15] = [
+const AI_SETTINGS_FIELDS: [AiSettingsField; 18] = [
     AiSettingsField::Enabled,
     AiSettingsField::Provider,
     AiSettingsField::Endpoint,
@@ -377,11 +934,61 @@ const AI_SETTINGS_FIELDS: [AiSettingsField; 15] = [
     AiSettingsField::Streaming,
     AiSettingsField::Agent,
     AiSettingsField::AutoMode,
+    AiSettingsField::ContinueAfterTool,
     AiSettingsField::MaxSteps,
     AiSettingsField::Tools,
+    AiSettingsField::NativeTools,
+    AiSettingsField::AllowedTools,
     AiSettingsField::SystemPrompt,
 ];
 
+/// Edit form of one connection; text fields are edited through `input` like in the AI
+/// settings form.
+#[derive(Clone, Debug)]
+struct ConnectionFormState {
+    selected: usize,
+    editing: bool,
+    input: String,
+    error: Option<String>,
+    draft: ConnectionDraft,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ConnectionDraft {
+    name: String,
+    /// `user@host:port`.
+    target: String,
+    /// An `AuthMethod::as_key` value from `EDIT_AUTH_KINDS`.
+    auth: &'static str,
+    key_path: String,
+    cert_path: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ConnectionField {
+    Name,
+    Target,
+    Auth,
+    KeyPath,
+    CertPath,
+}
+
+const CONNECTION_FIELDS: [ConnectionField; 5] = [
+    ConnectionField::Name,
+    ConnectionField::Target,
+    ConnectionField::Auth,
+    ConnectionField::KeyPath,
+    ConnectionField::CertPath,
+];
+
+const EDIT_AUTH_KINDS: [&str; 5] = [
+    "agent",
+    "key",
+    "password",
+    "certificate",
+    "keyboard-interactive",
+];
+
 #[derive(Clone, Debug)]
 struct PanelState {
     kind: PanelKind,
@@ -389,6 +996,13 @@ struct PanelState {
     entries: Vec<FileEntry>,
     selected: usize,
     scroll: usize,
+    sort: SortMode,
+    /// Unfiltered listing; `entries` is the subset matching `filter` and `show_hidden`.
+    all_entries: Vec<FileEntry>,
+    filter: String,
+    show_hidden: bool,
+    /// Why the panel cannot be listed (e.g. the server refuses SFTP); drawn instead of entries.
+    unavailable: Option<String>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -397,11 +1011,22 @@ enum PanelKind {
     Remote,
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum SortMode {
+    #[default]
+    DirsFirst,
+    NameAsc,
+    NameDesc,
+    SizeDesc,
+}
+
 #[derive(Clone, Debug)]
 struct FileEntry {
     name: String,
     is_dir: bool,
     size: u64,
+    /// Modification time in seconds since the epoch, when known.
+    modified: Option<u64>,
 }
 
 #[derive(Clone, Debug)]
@@ -411,9 +1036,16 @@ struct AssistantState {
     scroll: usize,
     busy: bool,
     stream_index: Option<usize>,
+    question: Option<String>,
+    /// Prompts submitted in this session, oldest first, capped at `PROMPT_HISTORY_MAX`.
+    history: Vec<String>,
+    /// Entry shown while recalling history; `None` when editing a fresh prompt.
+    history_index: Option<usize>,
+    /// Unsent input stashed when history recall starts.
+    history_draft: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct AssistantMessage {
     role: AssistantRole,
     content: String,
@@ -432,7 +1064,8 @@ struct ToolResult {
     output: String,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 enum AssistantRole {
     User,
     Assistant,
@@ -445,15 +1078,35 @@ enum AssistantRole {
 enum AssistantEvent {
     Start,
     Delta(String),
-    Done(String),
+    StreamFallback(String),
+    Done(AiReply),
     Error(String),
     ToolResult(ToolResult),
 }
 
-#[derive(Clone, Debug)]
+/// A finished model reply. `tool_calls` holds calls made through native function calling;
+/// `@tool` lines in `content` are parsed separately.
+#[derive(Clone, Debug, Default)]
+struct AiReply {
+    content: String,
+    tool_calls: Vec<ToolCall>,
+}
+
+impl From<String> for AiReply {
+    fn from(content: String) -> Self {
+        Self {
+            content,
+            tool_calls: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
 enum ConnectEvent {
     Success { session_id: Uuid, conn: Connection },
-    Failure { conn: Connection, error: String },
+    Failure { conn: Connection, error: CoreError },
+    Reconnected { session_id: Uuid },
+    ReconnectFailed { error: CoreError },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -464,6 +1117,7 @@ enum AssistantCommand {
     Auto(bool),
     Steps(u32),
     Run,
+    RunAll,
     Skip,
     Clear,
     Status,
@@ -509,6 +1163,22 @@ struct ConnectingState {
     frame: usize,
 }
 
+/// A session whose connection dropped and is being re-established.
+struct ReconnectState {
+    conn: Connection,
+    attempt: u32,
+    /// When the next attempt starts; `None` while one is running.
+    retry_at: Option<Instant>,
+}
+
+struct SessionAreas {
+    header: Rect,
+    terminal: Rect,
+    files: Option<Rect>,
+    ai: Option<Rect>,
+    footer: Rect,
+}
+
 struct ShellCapture {
     start_offset: u64,
     max_bytes: usize,
@@ -538,9 +1208,71 @@ struct ShellLogSlice {
 
 #[derive(Clone, Debug)]
 struct TransferStatus {
+    job_id: Uuid,
+    state: TransferState,
     progress: TransferProgress,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct TerminalLink {
+    row: u16,
+    start_col: u16,
+    end_col: u16,
+    text: String,
+    uri: String,
+}
+
+#[derive(Default)]
+struct HyperlinkTracker {
+    active: Option<String>,
+    pending: Vec<u8>,
+    links: Vec<TerminalLink>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RelativeAge {
+    JustNow,
+    Minutes(i64),
+    Hours(i64),
+    Yesterday,
+    Days(i64),
+    Weeks(i64),
+}
+
+/// One line of the connection list: a group header (`None` is the ungrouped bucket), an
+/// auth method header (an `AuthMethod::as_key` value) or an index into
+/// `AppState::connections`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ConnectionRow {
+    Group(Option<Uuid>),
+    Auth(&'static str),
+    Connection(usize),
+}
+
+/// How the connection list is organized.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum ConnectionListMode {
+    #[default]
+    Groups,
+    AuthMethod,
+}
+
+/// Auth method headers in display order, weakest first so connections worth moving to
+/// keys sit at the top.
+const AUTH_KIND_ORDER: [&str; 5] = [
+    "password",
+    "keyboard-interactive",
+    "key",
+    "certificate",
+    "agent",
+];
+
+#[derive(Debug, PartialEq, Eq)]
+enum TerminalChunk {
+    Text(Vec<u8>),
+    Link(Option<String>),
+}
+
 impl AppState {
     async fn new(
         store: ConnectionStore,
@@ -552,7 +1284,8 @@ impl AppState {
         channels: AppChannels,
     ) -> Result<Self> {
         let connections = store.list_connections().unwrap_or_default();
-        let mut parser = Parser::new(24, 80, 0);
+        let groups = store.list_groups().unwrap_or_default();
+        let mut parser = Parser::new(24, 80, TERMINAL_SCROLLBACK_LINES);
         parser.process(b"");
         let mut client_builder = reqwest::Client::builder();
         if config.ai.timeout_ms > 0 {
@@ -560,6 +1293,18 @@ impl AppState {
         }
         let ai_client = client_builder.build()?;
         let assistant = AssistantState::new(&i18n);
+        let conversations = load_conversations(&config_manager.paths.conversations_file);
+        let theme = Theme::from_name(&config.ui.theme).unwrap_or_else(Theme::kawaii);
+        let (keymap, keymap_error) = match config.ui.keybindings.as_deref() {
+            Some(path) => match Keymap::load(path) {
+                Ok(keymap) => (keymap, None),
+                Err(err) => {
+                    warn!(error = %err, path = %path.display(), "keybindings not loaded");
+                    (Keymap::default(), Some(err.to_string()))
+                }
+            },
+            None => (Keymap::default(), None),
+        };
         let (shell_tool_tx, shell_tool_rx) = mpsc::channel::<ShellToolRequest>(8);
         let mut state = Self {
             store,
@@ -568,25 +1313,45 @@ impl AppState {
             config_manager,
             config,
             i18n,
-            theme: Theme::kawaii(),
+            theme,
+            keymap,
             ai_client,
             assistant,
             assistant_tx: channels.assistant_tx,
             connect_tx: channels.connect_tx,
             connections,
+            groups,
+            collapsed_groups: HashSet::new(),
+            connection_list_mode: ConnectionListMode::default(),
+            collapsed_auth: HashSet::new(),
+            connection_query: String::new(),
+            connection_query_editing: false,
             selected: 0,
             mode: AppMode::Connections,
             terminal_parser: parser,
+            scrollback_offset: 0,
             shell: None,
+            recorder: None,
             shell_log: ShellLog::new(SHELL_LOG_MAX_BYTES),
             shell_capture: None,
             shell_tool_tx,
             shell_tool_rx,
             shell_display_tail: Vec::new(),
+            hyperlinks: HyperlinkTracker::default(),
+            bell_count: 0,
+            bell_flash_until: None,
+            last_bell_notification: None,
+            app_focused: true,
             pending_shell_tools: VecDeque::new(),
             left_panel: PanelState::local_default(),
             right_panel: PanelState::remote_default(),
             active_panel_left: true,
+            panel_areas: [Rect::default(); 2],
+            last_panel_click: None,
+            file_filter_editing: false,
+            sync_navigation: false,
+            compare_panels: false,
+            conversations,
             input_focus: InputFocus::Files,
             show_file_manager: true,
             show_ai_panel: false,
@@ -594,21 +1359,42 @@ impl AppState {
             transfer_status: None,
             completed_transfers: HashSet::new(),
             pending_tools: VecDeque::new(),
+            pending_host_keys: VecDeque::new(),
+            stream_fallback: false,
+            tool_batch: false,
             tool_busy: false,
             agent_steps_remaining: 0,
             overlay: Overlay::None,
             active_connection: None,
             connecting: None,
+            reconnect: None,
             last_interactive_signature: None,
             ai_spinner_frame: 0,
             terminal_size: None,
             pending_shell_resize: None,
         };
+        state.left_panel.show_hidden = state.config.ui.show_hidden_files;
+        state.right_panel.show_hidden = state.config.ui.show_hidden_files;
+        if state.config.ui.swap_panels {
+            std::mem::swap(&mut state.left_panel, &mut state.right_panel);
+        }
+        if Theme::from_name(&state.config.ui.theme).is_none() {
+            let mut args = FluentArgs::new();
+            args.set("name", state.config.ui.theme.clone());
+            state.set_status(state.i18n.tr_args("status-theme-unknown", &args));
+        }
+        if let Some(error) = keymap_error {
+            let mut args = FluentArgs::new();
+            args.set("error", error);
+            state.set_status(state.i18n.tr_args("status-keymap-error", &args));
+        }
         state.auto_import_if_empty()?;
+        state.warn_password_connections();
         Ok(state)
     }
 
     fn draw(&mut self, f: &mut ratatui::Frame<'_>) {
+        self.panel_areas = [Rect::default(); 2];
         match self.mode {
             AppMode::Connections => self.draw_connections(f),
             AppMode::Session { .. } => self.draw_session(f),
@@ -643,7 +1429,19 @@ impl AppState {
     }
 
     fn draw_session(&mut self, f: &mut ratatui::Frame<'_>) {
-        let size = f.area();
+        let areas = self.session_areas(f.area());
+        self.draw_header(f, areas.header, self.session_title());
+        self.draw_terminal(f, areas.terminal);
+        if let Some(area) = areas.files {
+            self.draw_file_manager(f, area);
+        }
+        if let Some(area) = areas.ai {
+            self.draw_ai_panel(f, area);
+        }
+        self.draw_footer(f, areas.footer);
+    }
+
+    fn session_areas(&self, size: Rect) -> SessionAreas {
         let layout = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
@@ -652,36 +1450,36 @@ impl AppState {
                 Constraint::Length(3),
             ])
             .split(size);
-        self.draw_header(f, layout[0], self.session_title());
-        if self.show_ai_panel {
+        let (main, ai) = if self.show_ai_panel {
             let body = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
                 .split(layout[1]);
-            self.draw_session_main(f, body[0]);
-            self.draw_ai_panel(f, body[1]);
+            (body[0], Some(body[1]))
         } else {
-            self.draw_session_main(f, layout[1]);
-        }
-        self.draw_footer(f, layout[2]);
-    }
-
-    fn draw_session_main(&mut self, f: &mut ratatui::Frame<'_>, area: Rect) {
-        if self.show_file_manager {
+            (layout[1], None)
+        };
+        let (terminal, files) = if self.show_file_manager {
             let body = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
-                .split(area);
-            self.draw_terminal(f, body[0]);
-            self.draw_file_manager(f, body[1]);
+                .split(main);
+            (body[0], Some(body[1]))
         } else {
-            self.draw_terminal(f, area);
+            (main, None)
+        };
+        SessionAreas {
+            header: layout[0],
+            terminal,
+            files,
+            ai,
+            footer: layout[2],
         }
     }
 
     fn draw_terminal(&mut self, f: &mut ratatui::Frame<'_>, area: Rect) {
         let theme = self.theme;
-        let mut block = Block::default().borders(Borders::ALL).title("terminal");
+        let mut block = terminal_block();
         let border = if matches!(self.input_focus, InputFocus::Terminal) {
             theme.accent
         } else {
@@ -690,7 +1488,9 @@ impl AppState {
         block = block.border_style(Style::default().fg(border));
         let inner = block.inner(area);
         self.update_terminal_size(inner);
+        self.terminal_parser.set_scrollback(self.scrollback_offset);
         let text = self.terminal_text(inner);
+        self.terminal_parser.set_scrollback(0);
         let paragraph = Paragraph::new(text).block(block);
         f.render_widget(paragraph, area);
     }
@@ -705,9 +1505,11 @@ impl AppState {
         let right_visible = panel_visible_rows(chunks[1]);
         self.left_panel.ensure_visible(left_visible);
         self.right_panel.ensure_visible(right_visible);
+        self.panel_areas = [chunks[0], chunks[1]];
         self.draw_panel(
             f,
             &self.left_panel,
+            &self.right_panel,
             chunks[0],
             self.active_panel_left,
             focus,
@@ -715,6 +1517,7 @@ impl AppState {
         self.draw_panel(
             f,
             &self.right_panel,
+            &self.left_panel,
             chunks[1],
             !self.active_panel_left,
             focus,
@@ -794,15 +1597,27 @@ impl AppState {
         } else {
             theme.muted
         };
+        let title = if self.assistant.question.is_some() {
+            self.i18n.tr("ai-input-answer")
+        } else {
+            self.i18n.tr("ai-input-title")
+        };
         let block = Block::default()
             .borders(Borders::ALL)
-            .title(self.i18n.tr("ai-input-title"))
+            .title(title)
             .border_style(Style::default().fg(border));
         let text = if !self.config.ai.enabled {
             Line::from(Span::styled(
                 self.i18n.tr("ai-disabled"),
                 Style::default().fg(theme.muted),
             ))
+        } else if let (Some(question), true) =
+            (&self.assistant.question, self.assistant.input.is_empty())
+        {
+            Line::from(Span::styled(
+                question.clone(),
+                Style::default().fg(theme.accent_alt),
+            ))
         } else if self.assistant.input.is_empty() {
             Line::from(Span::styled(
                 self.i18n.tr("ai-placeholder"),
@@ -825,12 +1640,36 @@ impl AppState {
         &self,
         f: &mut ratatui::Frame<'_>,
         panel: &PanelState,
+        other: &PanelState,
         area: Rect,
         active: bool,
         focus: bool,
     ) {
         let theme = self.theme;
-        let title = format!("{}: {}", panel.kind.label(), panel.path);
+        let mut title = format!(
+            "{}: {} · {}",
+            panel.kind.label(),
+            panel.path,
+            self.i18n.tr(panel.sort.label_key())
+        );
+        let editing = active && self.file_filter_editing;
+        if editing || !panel.filter.is_empty() {
+            let mut args = FluentArgs::new();
+            args.set("filter", panel.filter.clone());
+            title.push_str(" · ");
+            title.push_str(&self.i18n.tr_args("panel-filter", &args));
+            if editing {
+                title.push('_');
+            }
+        }
+        if self.sync_navigation {
+            title.push_str(" · ");
+            title.push_str(&self.i18n.tr("panel-sync"));
+        }
+        if self.compare_panels {
+            title.push_str(" · ");
+            title.push_str(&self.i18n.tr("panel-compare"));
+        }
         let mut block = Block::default().borders(Borders::ALL).title(title);
         let border = if active {
             if focus {
@@ -842,9 +1681,24 @@ impl AppState {
             theme.muted
         };
         block = block.border_style(Style::default().fg(border));
+        if let Some(reason) = &panel.unavailable {
+            let paragraph = Paragraph::new(reason.clone())
+                .style(Style::default().fg(theme.muted))
+                .wrap(Wrap { trim: true })
+                .block(block);
+            f.render_widget(paragraph, area);
+            return;
+        }
         let visible = panel_visible_rows(area);
         let start = panel.scroll;
         let end = (start + visible).min(panel.entries.len());
+        let counterparts: Option<HashMap<&str, &FileEntry>> = self.compare_panels.then(|| {
+            other
+                .all_entries
+                .iter()
+                .map(|e| (e.name.as_str(), e))
+                .collect()
+        });
         let items: Vec<ListItem> = panel
             .entries
             .iter()
@@ -868,7 +1722,15 @@ impl AppState {
                             .bg(theme.selection_inactive_bg)
                     }
                 } else {
-                    Style::default().fg(theme.text)
+                    let color = match counterparts
+                        .as_ref()
+                        .map(|others| entry_diff(e, others.get(e.name.as_str()).copied()))
+                    {
+                        Some(EntryDiff::OnlyHere) => theme.accent_alt,
+                        Some(EntryDiff::Differs) => theme.error,
+                        Some(EntryDiff::Same) | None => theme.text,
+                    };
+                    Style::default().fg(color)
                 };
                 ListItem::new(Line::from(Span::styled(name, style)))
             })
@@ -889,9 +1751,16 @@ impl AppState {
             spans.push(Span::raw("  "));
             spans.push(Span::styled(status, Style::default().fg(theme.accent_soft)));
         }
+        let border_style = if self.bell_flash_until.is_some() {
+            Style::default()
+                .fg(theme.accent_alt)
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED)
+        } else {
+            Style::default().fg(theme.accent_soft)
+        };
         let block = Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(theme.accent_soft));
+            .border_style(border_style);
         let paragraph = Paragraph::new(Text::from(Line::from(spans))).block(block);
         f.render_widget(paragraph, area);
     }
@@ -910,60 +1779,392 @@ impl AppState {
 
     fn draw_connection_list(&self, f: &mut ratatui::Frame<'_>, area: Rect) {
         let theme = self.theme;
+        let mut title = format!(
+            "{} ({})",
+            self.i18n.tr("connections"),
+            self.i18n.tr(self.connection_list_mode.label_key())
+        );
+        let searching = self.searching_connections();
+        if self.connection_query_editing || searching {
+            let mut args = FluentArgs::new();
+            args.set("query", self.connection_query.clone());
+            title.push_str(" · ");
+            title.push_str(&self.i18n.tr_args("connections-search", &args));
+            if self.connection_query_editing {
+                title.push('_');
+            }
+        }
         let block = Block::default()
             .borders(Borders::ALL)
-            .title(self.i18n.tr("connections"))
+            .title(title)
             .border_style(Style::default().fg(theme.accent_soft));
+        let grouped = !searching
+            && (!self.groups.is_empty()
+                || self.connection_list_mode == ConnectionListMode::AuthMethod);
+        let selected_style = Style::default()
+            .fg(theme.selection_fg)
+            .bg(theme.selection_bg)
+            .add_modifier(Modifier::BOLD);
         let items: Vec<ListItem> = self
-            .connections
-            .iter()
+            .connection_rows()
+            .into_iter()
             .enumerate()
-            .map(|(i, c)| {
-                let style = if i == self.selected {
-                    Style::default()
-                        .fg(theme.selection_fg)
-                        .bg(theme.selection_bg)
-                        .add_modifier(Modifier::BOLD)
-                } else {
-                    Style::default().fg(theme.text)
-                };
-                ListItem::new(Line::from(vec![Span::styled(
-                    format!("{}@{}:{}", c.username, c.host, c.port),
-                    style,
-                )]))
+            .map(|(i, row)| match row {
+                ConnectionRow::Group(id) => {
+                    let style = if i == self.selected {
+                        selected_style
+                    } else {
+                        Style::default()
+                            .fg(theme.accent)
+                            .add_modifier(Modifier::BOLD)
+                    };
+                    let marker = if self.collapsed_groups.contains(&id) {
+                        "+"
+                    } else {
+                        "-"
+                    };
+                    let count = self
+                        .connections
+                        .iter()
+                        .filter(|c| self.effective_group(c) == id)
+                        .count();
+                    ListItem::new(Line::from(Span::styled(
+                        format!("{marker} {} ({count})", self.group_label(id)),
+                        style,
+                    )))
+                }
+                ConnectionRow::Auth(kind) => {
+                    let style = if i == self.selected {
+                        selected_style
+                    } else {
+                        Style::default()
+                            .fg(theme.accent)
+                            .add_modifier(Modifier::BOLD)
+                    };
+                    let marker = if self.collapsed_auth.contains(kind) {
+                        "+"
+                    } else {
+                        "-"
+                    };
+                    let count = self
+                        .connections
+                        .iter()
+                        .filter(|c| c.auth_method.as_key() == kind)
+                        .count();
+                    ListItem::new(Line::from(Span::styled(
+                        format!("{marker} {} ({count})", self.auth_kind_label(kind)),
+                        style,
+                    )))
+                }
+                ConnectionRow::Connection(idx) => {
+                    let c = &self.connections[idx];
+                    let style = if i == self.selected {
+                        selected_style
+                    } else {
+                        Style::default().fg(theme.text)
+                    };
+                    let indent = if grouped { "  " } else { "" };
+                    let star = if c.is_favorite { "★ " } else { "" };
+                    let mut spans = vec![Span::styled(
+                        format!("{indent}{star}{}@{}:{}", c.username, c.host, c.port),
+                        style,
+                    )];
+                    if let Some(last) = c.last_connected_at {
+                        spans.push(Span::styled(
+                            format!("  {}", self.last_connected_label(last)),
+                            Style::default().fg(theme.muted),
+                        ));
+                    }
+                    ListItem::new(Line::from(spans))
+                }
             })
             .collect();
         let list = List::new(items).block(block);
-        f.render_widget(list, area);
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        f.render_stateful_widget(list, area, &mut state);
     }
 
-    fn draw_connection_details(&self, f: &mut ratatui::Frame<'_>, area: Rect) {
-        let theme = self.theme;
-        let block = Block::default()
-            .borders(Borders::ALL)
-            .title(self.i18n.tr("label-details"))
-            .border_style(Style::default().fg(theme.accent_soft));
-        let lines = if let Some(conn) = self.connections.get(self.selected) {
-            let last = conn
-                .last_connected_at
-                .map(|v| v.to_rfc3339())
-                .unwrap_or_else(|| self.i18n.tr("label-never"));
-            let tags = if conn.tags.is_empty() {
-                self.i18n.tr("label-none")
-            } else {
-                conn.tags
-                    .iter()
-                    .map(|t| t.name.clone())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            };
-            vec![
-                Line::from(Span::styled(
-                    conn.name.clone(),
-                    Style::default()
-                        .fg(theme.accent)
-                        .add_modifier(Modifier::BOLD),
-                )),
+    fn connection_rows(&self) -> Vec<ConnectionRow> {
+        if self.searching_connections() {
+            return search_connection_rows(&self.connections, self.connection_query.trim());
+        }
+        match self.connection_list_mode {
+            ConnectionListMode::Groups => {
+                connection_rows(&self.groups, &self.connections, &self.collapsed_groups)
+            }
+            ConnectionListMode::AuthMethod => {
+                connection_rows_by_auth(&self.connections, &self.collapsed_auth)
+            }
+        }
+    }
+
+    fn searching_connections(&self) -> bool {
+        !self.connection_query.trim().is_empty()
+    }
+
+    /// Clears the search, keeping the highlighted connection selected in the full list.
+    fn clear_connection_search(&mut self) {
+        let selected = self.selected_connection().map(|c| c.id);
+        self.connection_query_editing = false;
+        self.connection_query.clear();
+        self.selected = 0;
+        if let Some(id) = selected {
+            self.select_connection(id);
+        }
+    }
+
+    /// Keys while typing a connection search; the selection follows the ranked matches.
+    async fn handle_connection_search_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Esc => self.clear_connection_search(),
+            KeyCode::Enter => {
+                self.connection_query_editing = false;
+                if let Some(conn) = self.selected_connection().cloned() {
+                    self.start_connection(conn).await?;
+                }
+            }
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down if self.selected + 1 < self.connection_rows().len() => {
+                self.selected += 1;
+            }
+            KeyCode::Backspace => {
+                self.connection_query.pop();
+                self.selected = 0;
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.connection_query.push(c);
+                self.selected = 0;
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// Switches between the group and auth method views, keeping the selected connection.
+    fn cycle_connection_list_mode(&mut self) {
+        let selected = self.selected_connection().map(|c| c.id);
+        self.connection_list_mode = self.connection_list_mode.next();
+        self.selected = 0;
+        if let Some(id) = selected {
+            self.select_connection(id);
+        }
+        let mut args = FluentArgs::new();
+        args.set("mode", self.i18n.tr(self.connection_list_mode.label_key()));
+        self.set_status(self.i18n.tr_args("status-list-mode", &args));
+    }
+
+    fn selected_connection(&self) -> Option<&Connection> {
+        match self.connection_rows().get(self.selected) {
+            Some(ConnectionRow::Connection(idx)) => self.connections.get(*idx),
+            _ => None,
+        }
+    }
+
+    fn select_connection(&mut self, id: Uuid) {
+        if let Some(conn) = self.connections.iter().find(|c| c.id == id) {
+            let group = self.effective_group(conn);
+            self.collapsed_groups.remove(&group);
+            self.collapsed_auth.remove(conn.auth_method.as_key());
+        }
+        if let Some(pos) = self.connection_rows().iter().position(
+            |row| matches!(row, ConnectionRow::Connection(idx) if self.connections[*idx].id == id),
+        ) {
+            self.selected = pos;
+        }
+    }
+
+    /// Group a connection is listed under; ids of deleted groups fall back to ungrouped.
+    fn effective_group(&self, conn: &Connection) -> Option<Uuid> {
+        conn.group_id
+            .filter(|id| self.groups.iter().any(|g| g.id == *id))
+    }
+
+    fn group_label(&self, id: Option<Uuid>) -> String {
+        id.and_then(|id| self.groups.iter().find(|g| g.id == id))
+            .map(|g| g.name.clone())
+            .unwrap_or_else(|| self.i18n.tr("group-ungrouped"))
+    }
+
+    fn open_group_overlay(&mut self, id: Uuid) {
+        if let Some(conn) = self.connections.iter().find(|c| c.id == id) {
+            let input = self
+                .effective_group(conn)
+                .map(|group| self.group_label(Some(group)))
+                .unwrap_or_default();
+            self.overlay = Overlay::Group {
+                id,
+                input,
+                error: None,
+            };
+        }
+    }
+
+    fn assign_connection_group(&mut self, id: Uuid, input: &str) -> Result<(), String> {
+        let name = input.trim();
+        let group = if name.is_empty() {
+            None
+        } else if let Some(group) = self
+            .groups
+            .iter()
+            .find(|g| g.name.eq_ignore_ascii_case(name))
+        {
+            Some(group.clone())
+        } else {
+            Some(
+                self.store
+                    .create_group(name)
+                    .map_err(|_| self.i18n.tr("status-connection-error"))?,
+            )
+        };
+        self.store
+            .assign_group(id, group.as_ref().map(|g| g.id))
+            .map_err(|_| self.i18n.tr("status-connection-error"))?;
+        self.reload_connections();
+        self.select_connection(id);
+        let mut args = FluentArgs::new();
+        args.set(
+            "name",
+            self.connections
+                .iter()
+                .find(|c| c.id == id)
+                .map(|c| c.name.clone())
+                .unwrap_or_default(),
+        );
+        match group {
+            Some(group) => {
+                args.set("group", group.name);
+                self.set_status(self.i18n.tr_args("status-group-assigned", &args));
+            }
+            None => self.set_status(self.i18n.tr_args("status-group-cleared", &args)),
+        }
+        Ok(())
+    }
+
+    fn open_locale_overlay(&mut self, id: Uuid) {
+        if let Some(conn) = self.connections.iter().find(|c| c.id == id) {
+            self.overlay = Overlay::Locale {
+                id,
+                input: conn.locale().unwrap_or_default().to_string(),
+                error: None,
+            };
+        }
+    }
+
+    fn open_transfer_dirs_overlay(&mut self, id: Uuid) {
+        if let Some(conn) = self.connections.iter().find(|c| c.id == id) {
+            self.overlay = Overlay::TransferDirs {
+                id,
+                input: format!(
+                    "{}|{}",
+                    conn.transfer_local_dir.as_deref().unwrap_or_default(),
+                    conn.transfer_remote_dir.as_deref().unwrap_or_default()
+                ),
+                error: None,
+            };
+        }
+    }
+
+    fn set_transfer_dirs(&mut self, id: Uuid, input: &str) -> Result<(), String> {
+        let (local, remote) =
+            parse_transfer_dirs(input).ok_or_else(|| self.i18n.tr("prompt-transfer-dirs-error"))?;
+        let mut conn = self
+            .store
+            .get_connection(id)
+            .map_err(|_| self.i18n.tr("status-connection-error"))?;
+        conn.transfer_local_dir = local;
+        conn.transfer_remote_dir = remote;
+        conn.updated_at = chrono::Utc::now();
+        self.store
+            .update_connection(&conn)
+            .map_err(|_| self.i18n.tr("status-connection-error"))?;
+        self.reload_connections();
+        let mut args = FluentArgs::new();
+        args.set("name", conn.name.clone());
+        args.set("dirs", self.transfer_dirs_label(&conn));
+        self.set_status(self.i18n.tr_args("status-transfer-dirs-set", &args));
+        Ok(())
+    }
+
+    fn transfer_dirs_label(&self, conn: &Connection) -> String {
+        let none = self.i18n.tr("label-none");
+        format!(
+            "{} ⇄ {}",
+            conn.transfer_local_dir.as_deref().unwrap_or(&none),
+            conn.transfer_remote_dir.as_deref().unwrap_or(&none)
+        )
+    }
+
+    fn set_connection_locale(&mut self, id: Uuid, input: &str) -> Result<(), String> {
+        let locale = input.trim();
+        if !locale
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_.@-".contains(c))
+        {
+            return Err(self.i18n.tr("prompt-locale-error"));
+        }
+        let mut conn = self
+            .store
+            .get_connection(id)
+            .map_err(|_| self.i18n.tr("status-connection-error"))?;
+        conn.set_locale((!locale.is_empty()).then_some(locale));
+        conn.updated_at = chrono::Utc::now();
+        self.store
+            .update_connection(&conn)
+            .map_err(|_| self.i18n.tr("status-connection-error"))?;
+        self.reload_connections();
+        let mut args = FluentArgs::new();
+        args.set("name", conn.name.clone());
+        args.set("locale", self.locale_label(&conn));
+        self.set_status(self.i18n.tr_args("status-locale-set", &args));
+        Ok(())
+    }
+
+    fn locale_label(&self, conn: &Connection) -> String {
+        match conn.locale() {
+            Some(locale) => locale.to_string(),
+            None if self.config.ssh.locale.is_empty() => self.i18n.tr("label-none"),
+            None => {
+                let mut args = FluentArgs::new();
+                args.set("locale", self.config.ssh.locale.clone());
+                self.i18n.tr_args("label-locale-default", &args)
+            }
+        }
+    }
+
+    fn draw_connection_details(&self, f: &mut ratatui::Frame<'_>, area: Rect) {
+        let theme = self.theme;
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(self.i18n.tr("label-details"))
+            .border_style(Style::default().fg(theme.accent_soft));
+        let lines = if let Some(conn) = self.selected_connection() {
+            let last = conn
+                .last_connected_at
+                .map(|v| self.last_connected_label(v))
+                .unwrap_or_else(|| self.i18n.tr("label-never"));
+            let tags = if conn.tags.is_empty() {
+                self.i18n.tr("label-none")
+            } else {
+                conn.tags
+                    .iter()
+                    .map(|t| t.name.clone())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            let mut title = vec![Span::styled(
+                conn.name.clone(),
+                Style::default()
+                    .fg(theme.accent)
+                    .add_modifier(Modifier::BOLD),
+            )];
+            if conn.is_favorite {
+                title.push(Span::styled(
+                    format!("  ★ {}", self.i18n.tr("label-favorite")),
+                    Style::default().fg(theme.accent_alt),
+                ));
+            }
+            vec![
+                Line::from(title),
                 Line::from(""),
                 Line::from(format!("{}: {}", self.i18n.tr("label-host"), conn.host)),
                 Line::from(format!("{}: {}", self.i18n.tr("label-port"), conn.port)),
@@ -975,6 +2176,26 @@ impl AppState {
                 )),
                 Line::from(format!("{}: {}", self.i18n.tr("label-last"), last)),
                 Line::from(format!("{}: {}", self.i18n.tr("label-tags"), tags)),
+                Line::from(format!(
+                    "{}: {}",
+                    self.i18n.tr("label-encoding"),
+                    conn.terminal_encoding.as_key()
+                )),
+                Line::from(format!(
+                    "{}: {}",
+                    self.i18n.tr("label-backend"),
+                    conn.transfer_backend.as_key()
+                )),
+                Line::from(format!(
+                    "{}: {}",
+                    self.i18n.tr("label-locale"),
+                    self.locale_label(conn)
+                )),
+                Line::from(format!(
+                    "{}: {}",
+                    self.i18n.tr("label-transfer-dirs"),
+                    self.transfer_dirs_label(conn)
+                )),
             ]
         } else {
             vec![Line::from(self.i18n.tr("empty-details"))]
@@ -1020,9 +2241,83 @@ impl AppState {
                 let area = centered_rect(70, 40, f.area());
                 self.draw_quick_add_overlay(f, area, input, error.as_deref());
             }
-            Overlay::Edit { input, error, .. } => {
-                let area = centered_rect(70, 40, f.area());
-                self.draw_edit_overlay(f, area, input, error.as_deref());
+            Overlay::Group { input, error, .. } => {
+                let area = centered_rect(60, 30, f.area());
+                self.draw_input_overlay(
+                    f,
+                    area,
+                    self.i18n.tr("prompt-group"),
+                    self.i18n.tr("prompt-group-hint"),
+                    input,
+                    error.as_deref(),
+                );
+            }
+            Overlay::Delete { name, is_dir, .. } => {
+                let area = centered_rect(60, 25, f.area());
+                let mut args = FluentArgs::new();
+                args.set("name", name.clone());
+                let message = if *is_dir {
+                    self.i18n.tr_args("prompt-delete-dir", &args)
+                } else {
+                    self.i18n.tr_args("prompt-delete-file", &args)
+                };
+                self.draw_confirm_overlay(f, area, self.i18n.tr("prompt-delete"), message);
+            }
+            Overlay::Rename {
+                from, input, error, ..
+            } => {
+                let area = centered_rect(60, 30, f.area());
+                let mut args = FluentArgs::new();
+                args.set("name", from.clone());
+                self.draw_input_overlay(
+                    f,
+                    area,
+                    self.i18n.tr("prompt-rename"),
+                    self.i18n.tr_args("prompt-rename-hint", &args),
+                    input,
+                    error.as_deref(),
+                );
+            }
+            Overlay::Mkdir {
+                dir, input, error, ..
+            } => {
+                let area = centered_rect(60, 30, f.area());
+                let mut args = FluentArgs::new();
+                args.set("path", dir.clone());
+                self.draw_input_overlay(
+                    f,
+                    area,
+                    self.i18n.tr("prompt-mkdir"),
+                    self.i18n.tr_args("prompt-mkdir-hint", &args),
+                    input,
+                    error.as_deref(),
+                );
+            }
+            Overlay::Locale { input, error, .. } => {
+                let area = centered_rect(60, 30, f.area());
+                self.draw_input_overlay(
+                    f,
+                    area,
+                    self.i18n.tr("prompt-locale"),
+                    self.i18n.tr("prompt-locale-hint"),
+                    input,
+                    error.as_deref(),
+                );
+            }
+            Overlay::TransferDirs { input, error, .. } => {
+                let area = centered_rect(60, 30, f.area());
+                self.draw_input_overlay(
+                    f,
+                    area,
+                    self.i18n.tr("prompt-transfer-dirs"),
+                    self.i18n.tr("prompt-transfer-dirs-hint"),
+                    input,
+                    error.as_deref(),
+                );
+            }
+            Overlay::Edit { state, .. } => {
+                let area = centered_rect(70, 50, f.area());
+                self.draw_edit_overlay(f, area, state);
             }
             Overlay::Password { state, .. } => {
                 let area = centered_rect(70, 45, f.area());
@@ -1032,61 +2327,137 @@ impl AppState {
                 let area = centered_rect(80, 70, f.area());
                 self.draw_ai_settings_overlay(f, area, state);
             }
+            Overlay::ToolApproval => {
+                if let Some(call) = self.pending_tools.front() {
+                    let area = centered_rect(70, 60, f.area());
+                    self.draw_tool_approval_overlay(f, area, call);
+                }
+            }
+            Overlay::HostKeyChanged {
+                host,
+                port,
+                fingerprint,
+                key,
+                ..
+            } => {
+                let area = centered_rect(70, 50, f.area());
+                self.draw_host_key_changed_overlay(f, area, host, *port, fingerprint, key);
+            }
+            Overlay::UnknownHostKey => {
+                if let Some(prompt) = self.pending_host_keys.front() {
+                    let area = centered_rect(70, 50, f.area());
+                    self.draw_unknown_host_key_overlay(f, area, prompt);
+                }
+            }
+            Overlay::Bookmarks {
+                kind,
+                items,
+                selected,
+                error,
+            } => {
+                let area = centered_rect(60, 50, f.area());
+                self.draw_bookmarks_overlay(f, area, *kind, items, *selected, error.as_deref());
+            }
+            Overlay::KnownHosts {
+                items,
+                selected,
+                error,
+            } => {
+                let area = centered_rect(80, 60, f.area());
+                self.draw_known_hosts_overlay(f, area, items, *selected, error.as_deref());
+            }
+            Overlay::JumpHosts {
+                items,
+                selected,
+                input,
+                error,
+                ..
+            } => {
+                let area = centered_rect(70, 50, f.area());
+                self.draw_jump_hosts_overlay(
+                    f,
+                    area,
+                    items,
+                    *selected,
+                    input.as_deref(),
+                    error.as_deref(),
+                );
+            }
         }
     }
 
-    fn draw_help_overlay(&self, f: &mut ratatui::Frame<'_>, area: Rect) {
+    fn draw_host_key_changed_overlay(
+        &self,
+        f: &mut ratatui::Frame<'_>,
+        area: Rect,
+        host: &str,
+        port: u16,
+        fingerprint: &str,
+        key: &PublicKey,
+    ) {
         let theme = self.theme;
         let block = Block::default()
             .borders(Borders::ALL)
-            .title(self.i18n.tr("help-title"))
-            .border_style(Style::default().fg(theme.accent));
-        let text = Text::from(vec![
-            Line::from(self.i18n.tr("help-connections")),
-            Line::from(""),
-            Line::from(self.i18n.tr("help-session")),
-            Line::from(self.i18n.tr("help-assistant")),
-            Line::from(self.i18n.tr("help-ai-settings")),
+            .title(self.i18n.tr("prompt-host-key-changed"))
+            .border_style(Style::default().fg(theme.error));
+        let mut args = FluentArgs::new();
+        args.set("host", format!("{host}:{port}"));
+        let lines = vec![
+            Line::from(Span::styled(
+                self.i18n.tr_args("prompt-host-key-changed-warning", &args),
+                Style::default()
+                    .fg(theme.error)
+                    .add_modifier(Modifier::BOLD),
+            )),
             Line::from(""),
-            Line::from(self.i18n.tr("help-quick-add")),
+            Line::from(format!(
+                "{}: {}",
+                self.i18n.tr("label-key-type"),
+                key.algorithm().as_str()
+            )),
+            Line::from(format!(
+                "{}: {}",
+                self.i18n.tr("label-fingerprint"),
+                fingerprint
+            )),
             Line::from(""),
-            Line::from(self.i18n.tr("help-edit")),
-            Line::from(self.i18n.tr("help-password")),
-        ]);
+            Line::from(self.i18n.tr("prompt-host-key-changed-hint")),
+        ];
         f.render_widget(Clear, area);
-        let paragraph = Paragraph::new(text)
+        let paragraph = Paragraph::new(Text::from(lines))
             .block(block)
             .wrap(Wrap { trim: true })
             .style(Style::default().fg(theme.text));
         f.render_widget(paragraph, area);
     }
 
-    fn draw_quick_add_overlay(
+    fn draw_unknown_host_key_overlay(
         &self,
         f: &mut ratatui::Frame<'_>,
         area: Rect,
-        input: &str,
-        error: Option<&str>,
+        prompt: &NewHostKey,
     ) {
         let theme = self.theme;
         let block = Block::default()
             .borders(Borders::ALL)
-            .title(self.i18n.tr("prompt-new-connection"))
-            .border_style(Style::default().fg(theme.accent));
-        let mut lines = vec![
-            Line::from(self.i18n.tr("prompt-new-connection-hint")),
+            .title(self.i18n.tr("prompt-unknown-host-key"))
+            .border_style(Style::default().fg(theme.accent_alt));
+        let mut args = FluentArgs::new();
+        args.set("host", format!("{}:{}", prompt.host, prompt.port));
+        let lines = vec![
+            Line::from(self.i18n.tr_args("prompt-unknown-host-key-text", &args)),
             Line::from(""),
-            Line::from(Span::styled(
-                format!("> {}", input),
-                Style::default().fg(theme.accent_alt),
+            Line::from(format!(
+                "{}: {}",
+                self.i18n.tr("label-key-type"),
+                prompt.key_type
+            )),
+            Line::from(format!(
+                "{}: {}",
+                self.i18n.tr("label-fingerprint"),
+                prompt.fingerprint
             )),
         ];
-        if let Some(error) = error {
-            lines.push(Line::from(Span::styled(
-                error.to_string(),
-                Style::default().fg(theme.error),
-            )));
-        }
         f.render_widget(Clear, area);
         let paragraph = Paragraph::new(Text::from(lines))
             .block(block)
@@ -1095,20 +2466,233 @@ impl AppState {
         f.render_widget(paragraph, area);
     }
 
-    fn draw_edit_overlay(
+    fn draw_bookmarks_overlay(
         &self,
         f: &mut ratatui::Frame<'_>,
         area: Rect,
-        input: &str,
+        kind: PanelKind,
+        items: &[Bookmark],
+        selected: usize,
         error: Option<&str>,
     ) {
-        let theme = self.theme;
-        let block = Block::default()
-            .borders(Borders::ALL)
-            .title(self.i18n.tr("prompt-edit-connection"))
+        let rows = items
+            .iter()
+            .map(|bookmark| (bookmark.name.clone(), format!("  {}", bookmark.path)))
+            .collect::<Vec<_>>();
+        let title = format!("{}: {}", self.i18n.tr("prompt-bookmarks"), kind.label());
+        self.draw_list_overlay(
+            f,
+            area,
+            title,
+            rows,
+            &self.i18n.tr("prompt-bookmarks-empty"),
+            selected,
+            error,
+        );
+    }
+
+    fn draw_known_hosts_overlay(
+        &self,
+        f: &mut ratatui::Frame<'_>,
+        area: Rect,
+        items: &[KnownHostEntry],
+        selected: usize,
+        error: Option<&str>,
+    ) {
+        let rows = items
+            .iter()
+            .map(|entry| {
+                (
+                    format!("{}:{}", entry.host, entry.port),
+                    format!("  {}  {}", entry.key_type, entry.fingerprint),
+                )
+            })
+            .collect::<Vec<_>>();
+        self.draw_list_overlay(
+            f,
+            area,
+            self.i18n.tr("prompt-known-hosts"),
+            rows,
+            &self.i18n.tr("prompt-known-hosts-empty"),
+            selected,
+            error,
+        );
+    }
+
+    /// A bordered list of `(label, detail)` rows that scrolls to keep `selected` in view,
+    /// with `error` pinned under it.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_list_overlay(
+        &self,
+        f: &mut ratatui::Frame<'_>,
+        area: Rect,
+        title: String,
+        rows: Vec<(String, String)>,
+        empty: &str,
+        selected: usize,
+        error: Option<&str>,
+    ) {
+        let theme = self.theme;
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(Style::default().fg(theme.accent));
+        f.render_widget(Clear, area);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        let error_height = if error.is_some() { 2 } else { 0 };
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(error_height)])
+            .split(inner);
+        if rows.is_empty() {
+            let paragraph = Paragraph::new(Span::styled(
+                empty.to_string(),
+                Style::default().fg(theme.muted),
+            ))
+            .wrap(Wrap { trim: true });
+            f.render_widget(paragraph, layout[0]);
+        } else {
+            let items: Vec<ListItem> = rows
+                .into_iter()
+                .map(|(label, detail)| {
+                    ListItem::new(Line::from(vec![
+                        Span::raw(label),
+                        Span::styled(detail, Style::default().fg(theme.muted)),
+                    ]))
+                })
+                .collect();
+            let list = List::new(items)
+                .style(Style::default().fg(theme.text))
+                .highlight_style(
+                    Style::default()
+                        .fg(theme.selection_fg)
+                        .bg(theme.selection_bg)
+                        .add_modifier(Modifier::BOLD),
+                );
+            let mut state = ListState::default().with_selected(Some(selected));
+            f.render_stateful_widget(list, layout[0], &mut state);
+        }
+        if let Some(error) = error {
+            let paragraph = Paragraph::new(vec![
+                Line::from(""),
+                Line::from(Span::styled(
+                    error.to_string(),
+                    Style::default().fg(theme.error),
+                )),
+            ]);
+            f.render_widget(paragraph, layout[1]);
+        }
+    }
+
+    fn draw_jump_hosts_overlay(
+        &self,
+        f: &mut ratatui::Frame<'_>,
+        area: Rect,
+        items: &[JumpHost],
+        selected: usize,
+        input: Option<&str>,
+        error: Option<&str>,
+    ) {
+        let theme = self.theme;
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(self.i18n.tr("prompt-jump-hosts"))
+            .border_style(Style::default().fg(theme.accent));
+        let mut lines = Vec::new();
+        if items.is_empty() {
+            lines.push(Line::from(Span::styled(
+                self.i18n.tr("prompt-jump-hosts-empty"),
+                Style::default().fg(theme.muted),
+            )));
+        }
+        for (i, hop) in items.iter().enumerate() {
+            let style = if i == selected && input.is_none() {
+                Style::default()
+                    .fg(theme.selection_fg)
+                    .bg(theme.selection_bg)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.text)
+            };
+            let mut auth = self.auth_kind_label(hop.auth_method.as_key());
+            if let AuthMethod::Key {
+                private_key_path, ..
+            } = &hop.auth_method
+            {
+                let _ = write!(auth, " {}", private_key_path.display());
+            }
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("{}. {}@{}:{}", i + 1, hop.username, hop.host, hop.port),
+                    style,
+                ),
+                Span::styled(format!("  {auth}"), Style::default().fg(theme.muted)),
+            ]));
+        }
+        if let Some(input) = input {
+            lines.push(Line::from(""));
+            lines.push(Line::from(self.i18n.tr("prompt-jump-hosts-hint")));
+            lines.push(Line::from(Span::styled(
+                format!("> {}", input),
+                Style::default().fg(theme.accent_alt),
+            )));
+        }
+        if let Some(error) = error {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                error.to_string(),
+                Style::default().fg(theme.error),
+            )));
+        }
+        f.render_widget(Clear, area);
+        let paragraph = Paragraph::new(Text::from(lines))
+            .block(block)
+            .wrap(Wrap { trim: true })
+            .style(Style::default().fg(theme.text));
+        f.render_widget(paragraph, area);
+    }
+
+    fn draw_help_overlay(&self, f: &mut ratatui::Frame<'_>, area: Rect) {
+        let theme = self.theme;
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(self.i18n.tr("help-title"))
+            .border_style(Style::default().fg(theme.accent));
+        let text = Text::from(vec![
+            Line::from(self.i18n.tr("help-connections")),
+            Line::from(""),
+            Line::from(self.i18n.tr("help-session")),
+            Line::from(self.i18n.tr("help-assistant")),
+            Line::from(self.i18n.tr("help-ai-settings")),
+            Line::from(""),
+            Line::from(self.i18n.tr("help-quick-add")),
+            Line::from(""),
+            Line::from(self.i18n.tr("help-edit")),
+            Line::from(self.i18n.tr("help-password")),
+        ]);
+        f.render_widget(Clear, area);
+        let paragraph = Paragraph::new(text)
+            .block(block)
+            .wrap(Wrap { trim: true })
+            .style(Style::default().fg(theme.text));
+        f.render_widget(paragraph, area);
+    }
+
+    fn draw_quick_add_overlay(
+        &self,
+        f: &mut ratatui::Frame<'_>,
+        area: Rect,
+        input: &str,
+        error: Option<&str>,
+    ) {
+        let theme = self.theme;
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(self.i18n.tr("prompt-new-connection"))
             .border_style(Style::default().fg(theme.accent));
         let mut lines = vec![
-            Line::from(self.i18n.tr("prompt-edit-connection-hint")),
+            Line::from(self.i18n.tr("prompt-new-connection-hint")),
             Line::from(""),
             Line::from(Span::styled(
                 format!("> {}", input),
@@ -1129,61 +2713,99 @@ impl AppState {
         f.render_widget(paragraph, area);
     }
 
-    fn draw_password_overlay(
+    fn draw_confirm_overlay(
         &self,
         f: &mut ratatui::Frame<'_>,
         area: Rect,
-        state: &PasswordOverlayState,
+        title: String,
+        message: String,
     ) {
         let theme = self.theme;
         let block = Block::default()
             .borders(Borders::ALL)
-            .title(self.i18n.tr("prompt-password-title"))
-            .border_style(Style::default().fg(theme.accent));
-        let title = match state.mode {
-            PasswordMode::Connect => self.i18n.tr("prompt-password-connect"),
-            PasswordMode::SaveOnly => self.i18n.tr("prompt-password-save"),
-        };
-        let mask = "*".repeat(state.password.chars().count());
-        let master_mask = "*".repeat(state.master.chars().count());
-        let pw_style = if matches!(state.focus, PasswordFocus::Password) {
-            Style::default().fg(theme.accent_alt)
-        } else {
-            Style::default().fg(theme.muted)
-        };
-        let master_style = if matches!(state.focus, PasswordFocus::Master) {
-            Style::default().fg(theme.accent_alt)
-        } else {
-            Style::default().fg(theme.muted)
-        };
-        let save_label = if state.save {
-            self.i18n.tr("prompt-password-save-on")
+            .title(title)
+            .border_style(Style::default().fg(theme.error));
+        f.render_widget(Clear, area);
+        let paragraph = Paragraph::new(Text::from(vec![Line::from(""), Line::from(message)]))
+            .block(block)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .style(Style::default().fg(theme.text));
+        f.render_widget(paragraph, area);
+    }
+
+    fn draw_tool_approval_overlay(&self, f: &mut ratatui::Frame<'_>, area: Rect, call: &ToolCall) {
+        let theme = self.theme;
+        let destructive = is_destructive_tool(&call.name);
+        let color = if destructive {
+            theme.error
         } else {
-            self.i18n.tr("prompt-password-save-off")
+            theme.accent
         };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(self.i18n.tr("tool-approval-title"))
+            .border_style(Style::default().fg(color));
+        let mut lines = vec![Line::from(Span::styled(
+            call.name.clone(),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ))];
+        if destructive {
+            lines.push(Line::from(Span::styled(
+                self.i18n.tr("tool-approval-destructive"),
+                Style::default().fg(theme.error),
+            )));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(self.i18n.tr("tool-approval-args")));
+        let args =
+            serde_json::to_string_pretty(&call.args).unwrap_or_else(|_| call.args.to_string());
+        for line in args.lines() {
+            lines.push(Line::from(Span::styled(
+                line.to_string(),
+                Style::default().fg(theme.accent_alt),
+            )));
+        }
+        if self.pending_tools.len() > 1 {
+            let mut fargs = FluentArgs::new();
+            fargs.set("count", self.pending_tools.len() - 1);
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                self.i18n.tr_args("tool-approval-queued", &fargs),
+                Style::default().fg(theme.muted),
+            )));
+        }
+        f.render_widget(Clear, area);
+        let paragraph = Paragraph::new(Text::from(lines))
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .style(Style::default().fg(theme.text));
+        f.render_widget(paragraph, area);
+    }
+
+    fn draw_input_overlay(
+        &self,
+        f: &mut ratatui::Frame<'_>,
+        area: Rect,
+        title: String,
+        hint: String,
+        input: &str,
+        error: Option<&str>,
+    ) {
+        let theme = self.theme;
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(Style::default().fg(theme.accent));
         let mut lines = vec![
-            Line::from(title),
+            Line::from(hint),
             Line::from(""),
             Line::from(Span::styled(
-                format!("{}: {}", self.i18n.tr("prompt-password-label"), mask),
-                pw_style,
-            )),
-            Line::from(Span::styled(
-                format!(
-                    "{}: {}",
-                    self.i18n.tr("prompt-password-master-label"),
-                    master_mask
-                ),
-                master_style,
-            )),
-            Line::from(format!(
-                "{}: {}",
-                self.i18n.tr("prompt-password-save-label"),
-                save_label
+                format!("> {}", input),
+                Style::default().fg(theme.accent_alt),
             )),
-            Line::from(self.i18n.tr("prompt-password-hint")),
         ];
-        if let Some(error) = state.error.as_deref() {
+        if let Some(error) = error {
             lines.push(Line::from(Span::styled(
                 error.to_string(),
                 Style::default().fg(theme.error),
@@ -1197,28 +2819,46 @@ impl AppState {
         f.render_widget(paragraph, area);
     }
 
-    fn draw_ai_settings_overlay(
+    fn draw_edit_overlay(
         &self,
         f: &mut ratatui::Frame<'_>,
         area: Rect,
-        state: &AiSettingsState,
+        state: &ConnectionFormState,
     ) {
         let theme = self.theme;
         let block = Block::default()
             .borders(Borders::ALL)
-            .title(self.i18n.tr("ai-settings-title"))
+            .title(self.i18n.tr("prompt-edit-connection"))
             .border_style(Style::default().fg(theme.accent));
         let mut lines = Vec::new();
-        for (idx, field) in AI_SETTINGS_FIELDS.iter().enumerate() {
-            let label = self.ai_settings_label(*field);
-            let value = self.ai_settings_value(state, *field);
+        for (idx, field) in CONNECTION_FIELDS.iter().enumerate() {
+            let (label, value) = match field {
+                ConnectionField::Name => (self.i18n.tr("edit-name"), state.draft.name.clone()),
+                ConnectionField::Target => {
+                    (self.i18n.tr("edit-target"), state.draft.target.clone())
+                }
+                ConnectionField::Auth => (
+                    self.i18n.tr("label-auth"),
+                    self.auth_kind_label(state.draft.auth),
+                ),
+                ConnectionField::KeyPath => {
+                    (self.i18n.tr("edit-key-path"), state.draft.key_path.clone())
+                }
+                ConnectionField::CertPath => (
+                    self.i18n.tr("edit-cert-path"),
+                    state.draft.cert_path.clone(),
+                ),
+            };
             let active = idx == state.selected;
+            let used = state.draft.uses_field(*field);
             let label_style = if active {
                 Style::default()
                     .fg(theme.accent_alt)
                     .add_modifier(Modifier::BOLD)
-            } else {
+            } else if used {
                 Style::default().fg(theme.text)
+            } else {
+                Style::default().fg(theme.muted)
             };
             let value_style = if active {
                 Style::default().fg(theme.accent)
@@ -1231,7 +2871,7 @@ impl AppState {
             ]));
         }
         lines.push(Line::from(""));
-        lines.push(Line::from(self.i18n.tr("ai-settings-hint")));
+        lines.push(Line::from(self.i18n.tr("prompt-edit-connection-hint")));
         if state.editing {
             lines.push(Line::from(""));
             lines.push(Line::from(Span::styled(
@@ -1253,19 +2893,162 @@ impl AppState {
         f.render_widget(paragraph, area);
     }
 
-    fn footer_text(&self) -> Text<'_> {
-        match &self.overlay {
-            Overlay::QuickAdd { .. } => Text::from(self.i18n.tr("footer-quick-add")),
-            Overlay::Edit { .. } => Text::from(self.i18n.tr("footer-edit")),
-            Overlay::Password { .. } => Text::from(self.i18n.tr("footer-password")),
-            Overlay::Help => Text::from(self.i18n.tr("footer-help")),
-            Overlay::AiSettings { .. } => Text::from(self.i18n.tr("footer-ai-settings")),
-            Overlay::None => match self.mode {
-                AppMode::Connections => Text::from(self.i18n.tr("footer-connections")),
-                AppMode::Session { .. } => {
-                    if matches!(self.input_focus, InputFocus::Assistant) {
-                        Text::from(self.i18n.tr("footer-assistant"))
-                    } else {
+    fn draw_password_overlay(
+        &self,
+        f: &mut ratatui::Frame<'_>,
+        area: Rect,
+        state: &PasswordOverlayState,
+    ) {
+        let theme = self.theme;
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(self.i18n.tr("prompt-password-title"))
+            .border_style(Style::default().fg(theme.accent));
+        let title = match state.mode {
+            PasswordMode::Connect => self.i18n.tr("prompt-password-connect"),
+            PasswordMode::SaveOnly => self.i18n.tr("prompt-password-save"),
+        };
+        let mask = "*".repeat(state.password.chars().count());
+        let master_mask = "*".repeat(state.master.chars().count());
+        let pw_style = if matches!(state.focus, PasswordFocus::Password) {
+            Style::default().fg(theme.accent_alt)
+        } else {
+            Style::default().fg(theme.muted)
+        };
+        let master_style = if matches!(state.focus, PasswordFocus::Master) {
+            Style::default().fg(theme.accent_alt)
+        } else {
+            Style::default().fg(theme.muted)
+        };
+        let save_label = if state.save {
+            self.i18n.tr("prompt-password-save-on")
+        } else {
+            self.i18n.tr("prompt-password-save-off")
+        };
+        let mut lines = vec![
+            Line::from(title),
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("{}: {}", self.i18n.tr("prompt-password-label"), mask),
+                pw_style,
+            )),
+            Line::from(Span::styled(
+                format!(
+                    "{}: {}",
+                    self.i18n.tr("prompt-password-master-label"),
+                    master_mask
+                ),
+                master_style,
+            )),
+            Line::from(format!(
+                "{}: {}",
+                self.i18n.tr("prompt-password-save-label"),
+                save_label
+            )),
+            Line::from(self.i18n.tr("prompt-password-hint")),
+        ];
+        if let Some(error) = state.error.as_deref() {
+            lines.push(Line::from(Span::styled(
+                error.to_string(),
+                Style::default().fg(theme.error),
+            )));
+        }
+        f.render_widget(Clear, area);
+        let paragraph = Paragraph::new(Text::from(lines))
+            .block(block)
+            .wrap(Wrap { trim: true })
+            .style(Style::default().fg(theme.text));
+        f.render_widget(paragraph, area);
+    }
+
+    fn draw_ai_settings_overlay(
+        &self,
+        f: &mut ratatui::Frame<'_>,
+        area: Rect,
+        state: &AiSettingsState,
+    ) {
+        let theme = self.theme;
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(self.i18n.tr("ai-settings-title"))
+            .border_style(Style::default().fg(theme.accent));
+        let mut lines = Vec::new();
+        for (idx, field) in AI_SETTINGS_FIELDS.iter().enumerate() {
+            let label = self.ai_settings_label(*field);
+            let value = self.ai_settings_value(state, *field);
+            let active = idx == state.selected;
+            let label_style = if active {
+                Style::default()
+                    .fg(theme.accent_alt)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.text)
+            };
+            let value_style = if active {
+                Style::default().fg(theme.accent)
+            } else {
+                Style::default().fg(theme.muted)
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("{label}: "), label_style),
+                Span::styled(value, value_style),
+            ]));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(self.i18n.tr("ai-settings-hint")));
+        if state.editing {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                format!("> {}", state.input),
+                Style::default().fg(theme.accent_alt),
+            )));
+        }
+        if let Some(error) = state.error.as_deref() {
+            lines.push(Line::from(Span::styled(
+                error.to_string(),
+                Style::default().fg(theme.error),
+            )));
+        }
+        f.render_widget(Clear, area);
+        let paragraph = Paragraph::new(Text::from(lines))
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .style(Style::default().fg(theme.text));
+        f.render_widget(paragraph, area);
+    }
+
+    fn footer_text(&self) -> Text<'_> {
+        match &self.overlay {
+            Overlay::QuickAdd { .. } => Text::from(self.i18n.tr("footer-quick-add")),
+            Overlay::Group { .. } => Text::from(self.i18n.tr("footer-group")),
+            Overlay::Locale { .. } | Overlay::TransferDirs { .. } => {
+                Text::from(self.i18n.tr("footer-locale"))
+            }
+            Overlay::Delete { .. } => Text::from(self.i18n.tr("footer-delete")),
+            Overlay::Rename { .. } => Text::from(self.i18n.tr("footer-rename")),
+            Overlay::Mkdir { .. } => Text::from(self.i18n.tr("footer-mkdir")),
+            Overlay::Edit { .. } => Text::from(self.i18n.tr("footer-edit")),
+            Overlay::Password { .. } => Text::from(self.i18n.tr("footer-password")),
+            Overlay::Help => Text::from(self.i18n.tr("footer-help")),
+            Overlay::AiSettings { .. } => Text::from(self.i18n.tr("footer-ai-settings")),
+            Overlay::ToolApproval => Text::from(self.i18n.tr("footer-tool-approval")),
+            Overlay::Bookmarks { .. } => Text::from(self.i18n.tr("footer-bookmarks")),
+            Overlay::KnownHosts { .. } => Text::from(self.i18n.tr("footer-known-hosts")),
+            Overlay::JumpHosts { input: Some(_), .. } => {
+                Text::from(self.i18n.tr("footer-jump-hosts-input"))
+            }
+            Overlay::JumpHosts { .. } => Text::from(self.i18n.tr("footer-jump-hosts")),
+            Overlay::HostKeyChanged { .. } => Text::from(self.i18n.tr("footer-host-key-changed")),
+            Overlay::UnknownHostKey => Text::from(self.i18n.tr("footer-unknown-host-key")),
+            Overlay::None => match self.mode {
+                AppMode::Connections if self.connection_query_editing => {
+                    Text::from(self.i18n.tr("footer-connection-search"))
+                }
+                AppMode::Connections => Text::from(self.i18n.tr("footer-connections")),
+                AppMode::Session { .. } => {
+                    if matches!(self.input_focus, InputFocus::Assistant) {
+                        Text::from(self.i18n.tr("footer-assistant"))
+                    } else {
                         Text::from(self.i18n.tr("footer-session"))
                     }
                 }
@@ -1275,10 +3058,23 @@ impl AppState {
 
     fn session_title(&self) -> String {
         let base = format!("{} · {}", self.i18n.tr("app-name"), self.i18n.tr("session"));
-        if let Some(conn) = &self.active_connection {
+        let title = if let Some(conn) = &self.active_connection {
             format!("{} · {}@{}:{}", base, conn.username, conn.host, conn.port)
         } else {
             base
+        };
+        match &self.reconnect {
+            Some(reconnect) => {
+                let mut args = FluentArgs::new();
+                args.set("attempt", reconnect.attempt);
+                args.set("max", RECONNECT_MAX_ATTEMPTS);
+                format!(
+                    "{} · {}",
+                    title,
+                    self.i18n.tr_args("session-reconnecting", &args)
+                )
+            }
+            None => title,
         }
     }
 
@@ -1290,14 +3086,35 @@ impl AppState {
         )
     }
 
+    fn last_connected_label(&self, value: chrono::DateTime<chrono::Utc>) -> String {
+        let (key, count) = match relative_age(chrono::Utc::now(), value) {
+            Some(RelativeAge::JustNow) => ("time-just-now", 0),
+            Some(RelativeAge::Minutes(n)) => ("time-minutes-ago", n),
+            Some(RelativeAge::Hours(n)) => ("time-hours-ago", n),
+            Some(RelativeAge::Yesterday) => ("time-yesterday", 1),
+            Some(RelativeAge::Days(n)) => ("time-days-ago", n),
+            Some(RelativeAge::Weeks(n)) => ("time-weeks-ago", n),
+            None => return format_timestamp(value, &self.config.ui),
+        };
+        let mut args = FluentArgs::new();
+        args.set("count", count);
+        self.i18n.tr_args(key, &args)
+    }
+
     fn auth_label(&self, auth: &AuthMethod) -> String {
-        match auth {
-            AuthMethod::Agent => self.i18n.tr("auth-agent"),
-            AuthMethod::Password { .. } => self.i18n.tr("auth-password"),
-            AuthMethod::Key { .. } => self.i18n.tr("auth-key"),
-            AuthMethod::KeyboardInteractive => self.i18n.tr("auth-keyboard"),
-            AuthMethod::Certificate { .. } => self.i18n.tr("auth-certificate"),
-        }
+        self.auth_kind_label(auth.as_key())
+    }
+
+    /// Label for an `AuthMethod::as_key` value.
+    fn auth_kind_label(&self, kind: &str) -> String {
+        let key = match kind {
+            "password" => "auth-password",
+            "key" => "auth-key",
+            "keyboard-interactive" => "auth-keyboard",
+            "certificate" => "auth-certificate",
+            _ => "auth-agent",
+        };
+        self.i18n.tr(key)
     }
 
     fn set_status(&mut self, message: String) {
@@ -1313,9 +3130,17 @@ impl AppState {
             parts.push(status.clone());
         }
         if let Some(transfer) = &self.transfer_status {
-            parts.push(self.format_transfer_status(&transfer.progress));
+            parts.push(self.format_transfer_status(&transfer.state, &transfer.progress));
         }
         if matches!(self.mode, AppMode::Session { .. }) {
+            if self.scrollback_offset > 0 {
+                let mut args = FluentArgs::new();
+                args.set("lines", self.scrollback_offset.to_string());
+                parts.push(self.i18n.tr_args("status-scrollback", &args));
+            }
+            if self.recorder.is_some() {
+                parts.push(self.i18n.tr("status-recording"));
+            }
             parts.push(self.sftp_status_label());
             parts.push(self.ai_status_label());
         }
@@ -1401,7 +3226,20 @@ impl AppState {
         if lines.is_empty() {
             lines.push(Line::from(self.i18n.tr("ai-empty")));
         }
-        if let Some(call) = self.pending_tools.front() {
+        if self.pending_tools.len() > 1 {
+            let mut args = FluentArgs::new();
+            args.set("count", self.pending_tools.len());
+            lines.push(Line::from(Span::styled(
+                self.i18n.tr_args("ai-tool-batch", &args),
+                Style::default().fg(theme.accent_alt),
+            )));
+            for (idx, call) in self.pending_tools.iter().enumerate() {
+                lines.push(Line::from(Span::styled(
+                    format!("{}. {}", idx + 1, tool_call_summary(call)),
+                    Style::default().fg(theme.muted),
+                )));
+            }
+        } else if let Some(call) = self.pending_tools.front() {
             let mut args = FluentArgs::new();
             args.set("name", call.name.clone());
             lines.push(Line::from(self.i18n.tr_args("ai-tool-pending", &args)));
@@ -1417,27 +3255,96 @@ impl AppState {
         if self.terminal_size != Some(size) {
             self.terminal_size = Some(size);
             self.terminal_parser.set_size(area.height, area.width);
-            self.pending_shell_resize = Some(size);
+            let send_at = Instant::now() + Duration::from_millis(RESIZE_DEBOUNCE_MS);
+            self.pending_shell_resize = Some((area.width, area.height, send_at));
         }
     }
 
     fn reset_terminal_parser(&mut self) {
         let (width, height) = self.terminal_size.unwrap_or((80, 24));
-        self.terminal_parser = Parser::new(height, width, 0);
+        self.terminal_parser = Parser::new(height, width, TERMINAL_SCROLLBACK_LINES);
         self.terminal_parser.process(b"");
+        self.scrollback_offset = 0;
+        self.hyperlinks = HyperlinkTracker::default();
+        self.bell_count = 0;
+    }
+
+    fn scroll_terminal_to(&mut self, offset: usize) {
+        self.terminal_parser.set_scrollback(offset);
+        self.scrollback_offset = self.terminal_parser.screen().scrollback();
+        self.terminal_parser.set_scrollback(0);
+    }
+
+    fn check_bell(&mut self) {
+        let count = self.terminal_parser.screen().audible_bell_count();
+        if count == self.bell_count {
+            return;
+        }
+        self.bell_count = count;
+        let now = Instant::now();
+        self.bell_flash_until = Some(now + Duration::from_millis(BELL_FLASH_MS));
+        if !self.config.ui.bell_notifications {
+            return;
+        }
+        let away = !self.app_focused
+            || self.input_focus != InputFocus::Terminal
+            || !matches!(self.mode, AppMode::Session { .. });
+        if !away {
+            return;
+        }
+        if let Some(last) = self.last_bell_notification {
+            if now.duration_since(last) < Duration::from_millis(BELL_NOTIFY_INTERVAL_MS) {
+                return;
+            }
+        }
+        self.last_bell_notification = Some(now);
+        let target = self
+            .active_connection
+            .as_ref()
+            .map(connection_target)
+            .unwrap_or_else(|| self.i18n.tr("app-name"));
+        let mut args = FluentArgs::new();
+        args.set("target", target);
+        send_desktop_notification(
+            &self.i18n.tr("notify-bell-title"),
+            &self.i18n.tr_args("notify-bell-body", &args),
+        );
+    }
+
+    fn shell_resize_deadline(&self) -> Option<Instant> {
+        self.pending_shell_resize.map(|(_, _, send_at)| send_at)
     }
 
     async fn apply_pending_resize(&mut self) -> Result<()> {
-        let Some((width, height)) = self.pending_shell_resize.take() else {
+        let Some((width, height, send_at)) = self.pending_shell_resize else {
             return Ok(());
         };
+        if Instant::now() < send_at {
+            return Ok(());
+        }
+        self.pending_shell_resize = None;
         if let Some(shell) = self.shell.as_mut() {
             let _ = shell.resize(width.into(), height.into()).await;
         }
         Ok(())
     }
 
+    fn terminal_encoding(&self) -> TerminalEncoding {
+        self.active_connection
+            .as_ref()
+            .map(|conn| conn.terminal_encoding)
+            .unwrap_or_default()
+    }
+
     fn process_shell_output(&mut self, data: &[u8]) {
+        let decoded = self.terminal_encoding().decode(data);
+        let data: &[u8] = &decoded;
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(err) = recorder.record_output(data) {
+                warn!(error = %err, "recording stopped");
+                self.recorder = None;
+            }
+        }
         let display_data = if let Some(marker) = self
             .shell_capture
             .as_ref()
@@ -1454,7 +3361,16 @@ impl AppState {
             combined
         };
         if !display_data.is_empty() {
-            self.terminal_parser.process(&display_data);
+            let history =
+                (self.scrollback_offset > 0).then(|| scrollback_len(&mut self.terminal_parser));
+            self.hyperlinks
+                .feed(&mut self.terminal_parser, &display_data);
+            if let Some(before) = history {
+                // Keep the scrolled-back view on the same lines while output keeps arriving.
+                let grown = scrollback_len(&mut self.terminal_parser).saturating_sub(before);
+                self.scroll_terminal_to(self.scrollback_offset + grown);
+            }
+            self.check_bell();
         }
         self.shell_log.append(data);
         if let Some(capture) = self.shell_capture.as_mut() {
@@ -1467,6 +3383,7 @@ impl AppState {
     }
 
     async fn start_shell_tool_request(&mut self, req: ShellToolRequest) {
+        let encoding = self.terminal_encoding();
         let Some(shell) = self.shell.as_mut() else {
             warn!("shell tool no active shell");
             let _ = req.tx.send(Err("no active shell".to_string()));
@@ -1484,7 +3401,8 @@ impl AppState {
         };
         let max_bytes = req.max_bytes.clamp(1, SHELL_LOG_MAX_BYTES);
         let start_offset = self.shell_log.end_offset();
-        if let Err(err) = shell.write(input.as_bytes()).await {
+        let bytes = encoding.encode(input.as_bytes());
+        if let Err(err) = shell.write(&bytes).await {
             warn!(error = %err, "shell tool write failed");
             let _ = req.tx.send(Err(err.to_string()));
             return;
@@ -1672,8 +3590,12 @@ impl AppState {
         }
     }
 
-    fn format_transfer_status(&self, progress: &TransferProgress) -> String {
-        let label = self.i18n.tr("status-transfer");
+    fn format_transfer_status(&self, state: &TransferState, progress: &TransferProgress) -> String {
+        let label = match state {
+            TransferState::Paused => self.i18n.tr("status-transfer-paused"),
+            TransferState::Cancelled => self.i18n.tr("status-transfer-cancelled"),
+            _ => self.i18n.tr("status-transfer"),
+        };
         let files = if progress.files_total > 0 {
             format!("{}/{}", progress.files_completed, progress.files_total)
         } else {
@@ -1721,7 +3643,12 @@ impl AppState {
         }
         if self.assistant.busy || self.tool_busy {
             let label = self.i18n.tr("status-ai-busy");
-            return format!("{label} {}", ai_progress_bar(self.ai_spinner_frame));
+            let mut parts = vec![format!(
+                "{label} {}",
+                ai_progress_bar(self.ai_spinner_frame)
+            )];
+            parts.extend(self.agent_budget_parts());
+            return parts.join(" ");
         }
         let mut parts = vec![self.i18n.tr("status-ai-on")];
         if self.config.ai.tools_enabled {
@@ -1733,12 +3660,47 @@ impl AppState {
         if self.config.ai.auto_mode {
             parts.push(self.i18n.tr("status-ai-auto"));
         }
-        if self.config.ai.streaming {
+        if self.streaming_active() {
             parts.push(self.i18n.tr("status-ai-stream"));
+        } else if self.stream_fallback {
+            parts.push(self.i18n.tr("status-ai-stream-fallback"));
         }
+        parts.extend(self.agent_budget_parts());
         parts.join(" ")
     }
 
+    /// Streaming for the current provider, unless it already failed in this session.
+    fn streaming_active(&self) -> bool {
+        self.config.ai.streaming_for_provider() && !self.stream_fallback
+    }
+
+    /// Pending tool count and remaining agent steps, so it is visible why the loop stops.
+    fn agent_budget_parts(&self) -> Vec<String> {
+        let mut parts = Vec::new();
+        if self.config.ai.tools_enabled {
+            let mut args = FluentArgs::new();
+            args.set("count", self.pending_tools.len());
+            parts.push(self.i18n.tr_args("status-ai-pending", &args));
+        }
+        if ai_loop_enabled(&self.config.ai) {
+            let (remaining, max) = if self.config.ai.max_steps == 0 {
+                ("∞".to_string(), "∞".to_string())
+            } else {
+                (
+                    self.agent_steps_remaining
+                        .min(self.config.ai.max_steps)
+                        .to_string(),
+                    self.config.ai.max_steps.to_string(),
+                )
+            };
+            let mut args = FluentArgs::new();
+            args.set("remaining", remaining);
+            args.set("max", max);
+            parts.push(self.i18n.tr_args("status-ai-steps", &args));
+        }
+        parts
+    }
+
     fn ai_settings_label(&self, field: AiSettingsField) -> String {
         match field {
             AiSettingsField::Enabled => self.i18n.tr("ai-settings-enabled"),
@@ -1753,8 +3715,11 @@ impl AppState {
             AiSettingsField::Streaming => self.i18n.tr("ai-settings-streaming"),
             AiSettingsField::Agent => self.i18n.tr("ai-settings-agent"),
             AiSettingsField::AutoMode => self.i18n.tr("ai-settings-auto"),
+            AiSettingsField::ContinueAfterTool => self.i18n.tr("ai-settings-continue"),
             AiSettingsField::MaxSteps => self.i18n.tr("ai-settings-max-steps"),
             AiSettingsField::Tools => self.i18n.tr("ai-settings-tools"),
+            AiSettingsField::NativeTools => self.i18n.tr("ai-settings-native-tools"),
+            AiSettingsField::AllowedTools => self.i18n.tr("ai-settings-allowed-tools"),
             AiSettingsField::SystemPrompt => self.i18n.tr("ai-settings-system"),
         }
     }
@@ -1773,8 +3738,22 @@ impl AppState {
             AiSettingsField::Streaming => self.bool_label(state.draft.streaming),
             AiSettingsField::Agent => self.bool_label(state.draft.agent_enabled),
             AiSettingsField::AutoMode => self.bool_label(state.draft.auto_mode),
+            AiSettingsField::ContinueAfterTool => self.bool_label(state.draft.continue_after_tool),
             AiSettingsField::MaxSteps => state.draft.max_steps.clone(),
             AiSettingsField::Tools => self.bool_label(state.draft.tools_enabled),
+            AiSettingsField::NativeTools => self.bool_label(state.draft.native_tools),
+            AiSettingsField::AllowedTools => {
+                let (tool, _) = TOOLS[state.tool_cursor % TOOLS.len()];
+                let mut args = FluentArgs::new();
+                args.set("tool", tool);
+                args.set(
+                    "state",
+                    self.bool_label(!state.draft.blocked_tools.contains(tool)),
+                );
+                args.set("allowed", TOOLS.len() - state.draft.blocked_tools.len());
+                args.set("total", TOOLS.len());
+                self.i18n.tr_args("ai-settings-allowed-tools-value", &args)
+            }
             AiSettingsField::SystemPrompt => self.truncate_text(&state.draft.system_prompt, 80),
         }
     }
@@ -1793,6 +3772,7 @@ impl AppState {
             "openai" | "openai-compatible" => self.i18n.tr("ai-settings-provider-openai"),
             "openrouter" => self.i18n.tr("ai-settings-provider-openrouter"),
             "anthropic" => self.i18n.tr("ai-settings-provider-anthropic"),
+            "gemini" => self.i18n.tr("ai-settings-provider-gemini"),
             _ => value.to_string(),
         }
     }
@@ -1824,6 +3804,7 @@ impl AppState {
             && provider != "openai-compatible"
             && provider != "openrouter"
             && provider != "anthropic"
+            && provider != "gemini"
         {
             return Some(self.i18n.tr("ai-config-provider"));
         }
@@ -1836,7 +3817,8 @@ impl AppState {
         if (provider == "openai"
             || provider == "openai-compatible"
             || provider == "openrouter"
-            || provider == "anthropic")
+            || provider == "anthropic"
+            || provider == "gemini")
             && self
                 .config
                 .ai
@@ -1881,6 +3863,10 @@ impl AppState {
             .trim()
             .parse()
             .map_err(|_| anyhow::anyhow!(self.i18n.tr("ai-settings-error-number")))?;
+        let allowed_tools = state
+            .draft
+            .allowed_tools()
+            .ok_or_else(|| anyhow::anyhow!(self.i18n.tr("ai-settings-error-no-tools")))?;
         self.config.ai.enabled = state.draft.enabled;
         self.config.ai.provider = state.draft.provider.trim().to_string();
         self.config.ai.endpoint = state.draft.endpoint.trim().to_string();
@@ -1898,12 +3884,17 @@ impl AppState {
         self.config.ai.streaming = state.draft.streaming;
         self.config.ai.agent_enabled = state.draft.agent_enabled;
         self.config.ai.auto_mode = state.draft.auto_mode;
+        self.config.ai.continue_after_tool = state.draft.continue_after_tool;
         self.config.ai.max_steps = max_steps;
         self.config.ai.tools_enabled = state.draft.tools_enabled;
+        self.config.ai.native_tools = state.draft.native_tools;
+        self.config.ai.allowed_tools = allowed_tools;
         if !self.config.ai.tools_enabled {
             self.pending_tools.clear();
+            self.tool_batch = false;
             self.tool_busy = false;
         }
+        self.stream_fallback = false;
         self.rebuild_ai_client()?;
         self.config_manager.save_config(&self.config)?;
         Ok(())
@@ -1919,22 +3910,106 @@ impl AppState {
     }
 
     async fn handle_bus_event(&mut self, event: CoreEvent) -> Result<()> {
-        if let CoreEvent::TransferProgress { job_id, progress } = event {
-            self.transfer_status = Some(TransferStatus {
-                progress: progress.clone(),
-            });
-            if progress.files_total > 0
-                && progress.files_completed >= progress.files_total
-                && self.completed_transfers.insert(job_id)
-                && matches!(self.mode, AppMode::Session { .. })
-            {
-                self.refresh_panels().await?;
+        match event {
+            CoreEvent::TransferProgress {
+                job_id,
+                state,
+                progress,
+            } => {
+                self.transfer_status = Some(TransferStatus {
+                    job_id,
+                    state,
+                    progress: progress.clone(),
+                });
+                if progress.files_total > 0
+                    && progress.files_completed >= progress.files_total
+                    && self.completed_transfers.insert(job_id)
+                    && matches!(self.mode, AppMode::Session { .. })
+                {
+                    self.refresh_panels().await?;
+                }
+            }
+            CoreEvent::TransferFinished {
+                state,
+                progress,
+                results,
+                ..
+            } => {
+                self.notify_transfer_finished(&state, &progress, &results);
             }
+            _ => {}
         }
         Ok(())
     }
 
+    fn notify_transfer_finished(
+        &mut self,
+        state: &TransferState,
+        progress: &TransferProgress,
+        results: &[FileResult],
+    ) {
+        let summary = self.transfer_summary(results);
+        let (title, body) = match state {
+            TransferState::Completed if summary.is_some() => {
+                let body = summary.unwrap_or_default();
+                self.set_status(body.clone());
+                (self.i18n.tr("notify-transfer-done-title"), body)
+            }
+            TransferState::Completed => {
+                let mut args = FluentArgs::new();
+                args.set("files", progress.files_completed);
+                args.set("bytes", format_bytes(progress.bytes_transferred));
+                (
+                    self.i18n.tr("notify-transfer-done-title"),
+                    self.i18n.tr_args("notify-transfer-done-body", &args),
+                )
+            }
+            TransferState::Failed { error } => {
+                let mut args = FluentArgs::new();
+                args.set("error", summary.unwrap_or_else(|| error.clone()));
+                let body = self.i18n.tr_args("notify-transfer-failed-body", &args);
+                self.set_status(body.clone());
+                (self.i18n.tr("notify-transfer-failed-title"), body)
+            }
+            _ => return,
+        };
+        if self.config.transfer.notify_on_finish && !self.app_focused {
+            send_desktop_notification(&title, &body);
+        }
+    }
+
+    /// "8 copied, 2 skipped, 1 failed (path)", or `None` when every file was copied.
+    fn transfer_summary(&self, results: &[FileResult]) -> Option<String> {
+        let count =
+            |pred: fn(&FileOutcome) -> bool| results.iter().filter(|r| pred(&r.outcome)).count();
+        let copied = count(|o| *o == FileOutcome::Copied);
+        let skipped = count(|o| *o == FileOutcome::Skipped);
+        let failed = count(|o| matches!(o, FileOutcome::Failed { .. }));
+        if skipped == 0 && failed == 0 {
+            return None;
+        }
+        let mut args = FluentArgs::new();
+        args.set("copied", copied);
+        args.set("skipped", skipped);
+        args.set("failed", failed);
+        let first_failed = results
+            .iter()
+            .find(|r| matches!(r.outcome, FileOutcome::Failed { .. }));
+        Some(match first_failed {
+            Some(result) => {
+                args.set("path", result.source_path.clone());
+                self.i18n.tr_args("notify-transfer-summary-failed", &args)
+            }
+            None => self.i18n.tr_args("notify-transfer-summary", &args),
+        })
+    }
+
     fn handle_tick(&mut self) {
+        if let Some(until) = self.bell_flash_until {
+            if Instant::now() >= until {
+                self.bell_flash_until = None;
+            }
+        }
         if let Some(connecting) = self.connecting.as_mut() {
             let next = connecting.frame + 1;
             connecting.frame = if next >= CONNECT_SPINNER_FRAMES.len() {
@@ -1989,28 +4064,144 @@ impl AppState {
                 self.enter_session(session_id, conn).await?;
             }
             ConnectEvent::Failure { conn, error } => {
-                if should_prompt_password(&error) {
+                if let CoreError::Connect(SshError::HostKeyChanged {
+                    host,
+                    port,
+                    fingerprint,
+                    key,
+                }) = error
+                {
+                    self.overlay = Overlay::HostKeyChanged {
+                        conn: Box::new(conn),
+                        host,
+                        port,
+                        fingerprint,
+                        key,
+                    };
+                } else if should_prompt_password(&error) {
                     self.open_password_overlay(conn.id, PasswordMode::Connect);
                 } else {
+                    let message = self.connect_failure_message(&error);
+                    self.set_status(message);
+                }
+            }
+            ConnectEvent::Reconnected { session_id } => {
+                self.resume_session(session_id).await?;
+            }
+            ConnectEvent::ReconnectFailed { error } => {
+                let Some(reconnect) = self.reconnect.as_mut() else {
+                    return Ok(());
+                };
+                warn!(attempt = reconnect.attempt, error = %error, "reconnect failed");
+                if reconnect.attempt >= RECONNECT_MAX_ATTEMPTS {
+                    self.leave_session();
                     let mut args = FluentArgs::new();
-                    args.set("error", error);
-                    self.set_status(self.i18n.tr_args("status-connection-failed", &args));
+                    args.set("error", error.to_string());
+                    self.set_status(self.i18n.tr_args("status-reconnect-failed", &args));
+                } else {
+                    reconnect.attempt += 1;
+                    reconnect.retry_at = Some(
+                        Instant::now()
+                            + reconnect_delay(
+                                self.config.ssh.reconnect_backoff_ms,
+                                reconnect.attempt,
+                            ),
+                    );
                 }
             }
         }
         Ok(())
     }
 
-    fn terminal_text(&self, area: Rect) -> Text<'static> {
-        let screen = self.terminal_parser.screen();
-        let (rows, cols) = screen.size();
-        let rows = rows.min(area.height);
-        let cols = cols.min(area.width);
-        let mut lines = Vec::with_capacity(rows as usize);
-        for row in 0..rows {
-            let mut spans = Vec::new();
-            let mut current_style = Style::default();
-            let mut current_text = String::new();
+    fn reconnect_deadline(&self) -> Option<Instant> {
+        self.reconnect.as_ref().and_then(|r| r.retry_at)
+    }
+
+    /// Called once the shell output ends. A shell that exited leaves the session as it is; a
+    /// dropped connection is re-established when `ssh.reconnect` is set.
+    async fn handle_shell_closed(&mut self) -> Result<()> {
+        self.shell = None;
+        self.abort_shell_capture("shell closed");
+        let AppMode::Session { id } = self.mode else {
+            return Ok(());
+        };
+        let lost = match self.sessions.report_shell_closed(id).await {
+            CloseReason::Eof => {
+                self.stop_recording();
+                self.set_status(self.i18n.tr("status-shell-closed"));
+                return Ok(());
+            }
+            CloseReason::KeepaliveTimeout => self.i18n.tr("status-session-timeout"),
+            CloseReason::Transport(error) => {
+                let mut args = FluentArgs::new();
+                args.set("error", error);
+                self.i18n.tr_args("status-session-lost-error", &args)
+            }
+        };
+        self.sessions.disconnect(id).await;
+        let Some(conn) = self.active_connection.clone() else {
+            return Ok(());
+        };
+        if !self.config.ssh.reconnect {
+            self.leave_session();
+            self.set_status(lost);
+            return Ok(());
+        }
+        self.set_status(lost);
+        warn!(connection = %conn.name, "session dropped, reconnecting");
+        self.reconnect = Some(ReconnectState {
+            conn,
+            attempt: 1,
+            retry_at: Some(
+                Instant::now() + reconnect_delay(self.config.ssh.reconnect_backoff_ms, 1),
+            ),
+        });
+        Ok(())
+    }
+
+    fn try_reconnect(&mut self) {
+        let Some(reconnect) = self.reconnect.as_mut() else {
+            return;
+        };
+        reconnect.retry_at = None;
+        let conn = reconnect.conn.clone();
+        let sessions = self.sessions.clone();
+        let tx = self.connect_tx.clone();
+        tokio::spawn(async move {
+            let event = match sessions.connect(conn, None, None).await {
+                Ok(session_id) => ConnectEvent::Reconnected { session_id },
+                Err(error) => ConnectEvent::ReconnectFailed { error },
+            };
+            let _ = tx.send(event).await;
+        });
+    }
+
+    /// Puts a re-established session in place of the dropped one, keeping the panels,
+    /// the terminal contents and the assistant conversation.
+    async fn resume_session(&mut self, session_id: Uuid) -> Result<()> {
+        if self.reconnect.take().is_none() {
+            // The user left the session while the attempt was running.
+            self.sessions.disconnect(session_id).await;
+            return Ok(());
+        }
+        info!(%session_id, "session reconnected");
+        self.mode = AppMode::Session { id: session_id };
+        self.remote_panel_mut().unavailable = None;
+        self.attach_session(session_id).await?;
+        self.set_status(self.i18n.tr("status-reconnected"));
+        Ok(())
+    }
+
+    fn terminal_text(&self, area: Rect) -> Text<'static> {
+        let screen = self.terminal_parser.screen();
+        let (rows, cols) = screen.size();
+        let rows = rows.min(area.height);
+        let cols = cols.min(area.width);
+        let mut lines = Vec::with_capacity(rows as usize);
+        for row in 0..rows {
+            let mut spans = Vec::new();
+            let mut current_style = Style::default();
+            let mut current_text = String::new();
             let mut col = 0;
             while col < cols {
                 if let Some(cell) = screen.cell(row, col) {
@@ -2022,7 +4213,12 @@ impl AppState {
                     if text.is_empty() {
                         text.push(' ');
                     }
-                    let style = style_for_cell(cell);
+                    let mut style = style_for_cell(cell);
+                    if self.scrollback_offset == 0 && self.hyperlinks.link_at(row, col).is_some() {
+                        style = style
+                            .fg(self.theme.accent)
+                            .add_modifier(Modifier::UNDERLINED);
+                    }
                     if current_text.is_empty() {
                         current_style = style;
                         current_text.push_str(&text);
@@ -2061,12 +4257,46 @@ impl AppState {
         Text::from(lines)
     }
 
+    fn cycle_theme(&mut self) {
+        let name = next_theme_name(&self.config.ui.theme);
+        self.theme = Theme::from_name(name).unwrap_or_else(Theme::kawaii);
+        self.config.ui.theme = name.to_string();
+        if let Err(err) = self.config_manager.save_config(&self.config) {
+            error!(error = %err, "ui config save failed");
+        }
+        let mut args = FluentArgs::new();
+        args.set("name", name);
+        self.set_status(self.i18n.tr_args("status-theme", &args));
+    }
+
+    /// Nudges towards keys when `ssh.warn_password_auth` is set and some connections still
+    /// log in with a password.
+    fn warn_password_connections(&mut self) {
+        if !self.config.ssh.warn_password_auth {
+            return;
+        }
+        let names = password_connection_names(&self.connections);
+        if names.is_empty() {
+            return;
+        }
+        warn!(
+            count = names.len(),
+            connections = %names.join(", "),
+            "connections use password auth, consider switching to keys"
+        );
+        let mut args = FluentArgs::new();
+        args.set("count", names.len());
+        self.set_status(self.i18n.tr_args("status-password-auth-warning", &args));
+    }
+
     fn reload_connections(&mut self) {
         self.connections = self.store.list_connections().unwrap_or_default();
-        if self.connections.is_empty() {
+        self.groups = self.store.list_groups().unwrap_or_default();
+        let rows = self.connection_rows().len();
+        if rows == 0 {
             self.selected = 0;
-        } else if self.selected >= self.connections.len() {
-            self.selected = self.connections.len() - 1;
+        } else if self.selected >= rows {
+            self.selected = rows - 1;
         }
     }
 
@@ -2089,16 +4319,32 @@ impl AppState {
             let result = sessions.connect(conn.clone(), None, None).await;
             let event = match result {
                 Ok(session_id) => ConnectEvent::Success { session_id, conn },
-                Err(err) => ConnectEvent::Failure {
-                    conn,
-                    error: err.to_string(),
-                },
+                Err(error) => ConnectEvent::Failure { conn, error },
             };
             let _ = tx.send(event).await;
         });
         Ok(())
     }
 
+    fn connect_failure_message(&self, error: &CoreError) -> String {
+        let mut args = FluentArgs::new();
+        match error {
+            CoreError::Connect(SshError::HostKeyRejected { host, port }) => {
+                args.set("host", format!("{host}:{port}"));
+                self.i18n.tr_args("status-host-key-rejected", &args)
+            }
+            CoreError::Connect(SshError::Timeout) => self.i18n.tr("status-connection-timeout"),
+            CoreError::Connect(SshError::Io(err)) | CoreError::Io(err) => {
+                args.set("error", err.to_string());
+                self.i18n.tr_args("status-connection-network", &args)
+            }
+            other => {
+                args.set("error", other.to_string());
+                self.i18n.tr_args("status-connection-failed", &args)
+            }
+        }
+    }
+
     fn open_password_overlay(&mut self, id: Uuid, mode: PasswordMode) {
         self.overlay = Overlay::Password {
             id,
@@ -2115,14 +4361,9 @@ impl AppState {
 
     fn open_edit_overlay(&mut self, id: Uuid) {
         if let Some(conn) = self.connections.iter().find(|c| c.id == id) {
-            let input = format!(
-                "{}|{}@{}:{}",
-                conn.name, conn.username, conn.host, conn.port
-            );
             self.overlay = Overlay::Edit {
                 id,
-                input,
-                error: None,
+                state: Box::new(ConnectionFormState::from_connection(conn)),
             };
         }
     }
@@ -2134,20 +4375,34 @@ impl AppState {
         };
     }
 
-    fn save_edit_connection(&mut self, id: Uuid, input: &str) -> Result<(), String> {
+    /// Saves the edit form. When the connection switches to password auth the password
+    /// overlay opens next, since there is no stored password for it yet.
+    fn save_edit_connection(&mut self, id: Uuid, draft: &ConnectionDraft) -> Result<(), String> {
         let default_user = whoami::username();
         let default_port = self.config.ssh.port;
-        let (name, username, host, port) =
-            parse_named_target(input, &default_user, default_port)
-                .map_err(|_| self.i18n.tr("prompt-edit-connection-error"))?;
+        let (username, host, port) = parse_target(&draft.target, &default_user, default_port)
+            .map_err(|_| self.i18n.tr("prompt-edit-connection-error"))?;
         let mut conn = self
             .store
             .get_connection(id)
             .map_err(|_| self.i18n.tr("status-connection-error"))?;
-        conn.name = name.unwrap_or_else(|| host.clone());
+        let auth_method = draft.auth_method(id, &conn.auth_method).map_err(|path| {
+            let mut args = FluentArgs::new();
+            args.set("path", path.display().to_string());
+            self.i18n.tr_args("prompt-edit-key-missing", &args)
+        })?;
+        let needs_password = matches!(auth_method, AuthMethod::Password { .. })
+            && !matches!(conn.auth_method, AuthMethod::Password { .. });
+        let name = draft.name.trim();
+        conn.name = if name.is_empty() {
+            host.clone()
+        } else {
+            name.to_string()
+        };
         conn.username = username;
         conn.host = host;
         conn.port = port;
+        conn.auth_method = auth_method;
         conn.updated_at = chrono::Utc::now();
         self.store
             .update_connection(&conn)
@@ -2156,9 +4411,77 @@ impl AppState {
         let mut args = FluentArgs::new();
         args.set("name", conn.name.clone());
         self.set_status(self.i18n.tr_args("status-connection-updated", &args));
+        if needs_password {
+            self.open_password_overlay(id, PasswordMode::SaveOnly);
+        }
         Ok(())
     }
 
+    fn cycle_terminal_encoding(&mut self, id: Uuid) {
+        self.cycle_connection_setting(id, "status-encoding-set", "encoding", |conn| {
+            conn.terminal_encoding = conn.terminal_encoding.next();
+            conn.terminal_encoding.as_key()
+        });
+    }
+
+    fn cycle_transfer_backend(&mut self, id: Uuid) {
+        self.cycle_connection_setting(id, "status-backend-set", "backend", |conn| {
+            conn.transfer_backend = conn.transfer_backend.next();
+            conn.transfer_backend.as_key()
+        });
+    }
+
+    /// Loads connection `id`, lets `advance` step one setting and return its new key,
+    /// saves the connection and reports the value as `$arg` of the `status` message.
+    fn cycle_connection_setting(
+        &mut self,
+        id: Uuid,
+        status: &str,
+        arg: &'static str,
+        advance: impl FnOnce(&mut Connection) -> &'static str,
+    ) {
+        let mut conn = match self.store.get_connection(id) {
+            Ok(conn) => conn,
+            Err(_) => {
+                self.set_status(self.i18n.tr("status-connection-error"));
+                return;
+            }
+        };
+        let value = advance(&mut conn);
+        conn.updated_at = chrono::Utc::now();
+        if self.store.update_connection(&conn).is_err() {
+            self.set_status(self.i18n.tr("status-connection-error"));
+            return;
+        }
+        self.reload_connections();
+        let mut args = FluentArgs::new();
+        args.set("name", conn.name.clone());
+        args.set(arg, value);
+        self.set_status(self.i18n.tr_args(status, &args));
+    }
+
+    /// Flips the favorite flag; the selection follows the connection to its new position.
+    fn toggle_favorite(&mut self, id: Uuid) {
+        let Some(conn) = self.connections.iter().find(|c| c.id == id) else {
+            return;
+        };
+        let favorite = !conn.is_favorite;
+        let mut args = FluentArgs::new();
+        args.set("name", conn.name.clone());
+        if self.store.set_favorite(id, favorite).is_err() {
+            self.set_status(self.i18n.tr("status-connection-error"));
+            return;
+        }
+        self.reload_connections();
+        self.select_connection(id);
+        let key = if favorite {
+            "status-favorite-added"
+        } else {
+            "status-favorite-removed"
+        };
+        self.set_status(self.i18n.tr_args(key, &args));
+    }
+
     fn save_password_for_connection(
         &mut self,
         id: Uuid,
@@ -2204,10 +4527,7 @@ impl AppState {
                 .await;
             let event = match result {
                 Ok(session_id) => ConnectEvent::Success { session_id, conn },
-                Err(err) => ConnectEvent::Failure {
-                    conn,
-                    error: err.to_string(),
-                },
+                Err(error) => ConnectEvent::Failure { conn, error },
             };
             let _ = tx.send(event).await;
         });
@@ -2294,37 +4614,16 @@ impl AppState {
         let (name, username, host, port) =
             parse_named_target(input, &default_user, default_port)
                 .map_err(|_| self.i18n.tr("prompt-new-connection-error"))?;
-        let now = chrono::Utc::now();
-        let id = Uuid::new_v4();
-        let name = name.unwrap_or_else(|| host.clone());
-        let conn = Connection {
-            id,
-            name: name.clone(),
-            host,
-            port,
-            username,
-            auth_method: AuthMethod::Agent,
-            jump_hosts: Vec::new(),
-            proxy: None,
-            startup_commands: Vec::new(),
-            env_vars: Vec::new(),
-            group_id: None,
-            tags: Vec::new(),
-            color: None,
-            icon: None,
-            notes: None,
-            created_at: now,
-            updated_at: now,
-            last_connected_at: None,
-            is_favorite: false,
-        };
+        let mut conn = Connection::new(host, port, username);
+        if let Some(name) = name {
+            conn.name = name;
+        }
+        let (id, name) = (conn.id, conn.name.clone());
         self.store
             .create_connection(&conn)
             .map_err(|_| self.i18n.tr("status-connection-create-error"))?;
         self.reload_connections();
-        if let Some(idx) = self.connections.iter().position(|c| c.id == id) {
-            self.selected = idx;
-        }
+        self.select_connection(id);
         let mut args = FluentArgs::new();
         args.set("name", name);
         self.set_status(self.i18n.tr_args("status-connection-added", &args));
@@ -2339,10 +4638,19 @@ impl AppState {
                 }
                 self.handle_key(key).await
             }
+            Event::Mouse(mouse) => self.handle_mouse(mouse).await,
             Event::Resize(w, h) => {
                 let _ = (w, h);
                 Ok(false)
             }
+            Event::FocusGained => {
+                self.app_focused = true;
+                Ok(false)
+            }
+            Event::FocusLost => {
+                self.app_focused = false;
+                Ok(false)
+            }
             _ => Ok(false),
         }
     }
@@ -2357,12 +4665,319 @@ impl AppState {
         }
     }
 
+    async fn handle_mouse(&mut self, mouse: MouseEvent) -> Result<bool> {
+        if !matches!(self.overlay, Overlay::None) {
+            return Ok(false);
+        }
+        let position = Position::new(mouse.column, mouse.row);
+        let Some(index) = self
+            .panel_areas
+            .iter()
+            .position(|area| area.contains(position))
+        else {
+            return Ok(false);
+        };
+        let left = index == 0;
+        let area = self.panel_areas[index];
+        let panel = if left {
+            &mut self.left_panel
+        } else {
+            &mut self.right_panel
+        };
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.active_panel_left = left;
+                self.input_focus = InputFocus::Files;
+                let Some(entry) = panel_row_at(area, mouse.row)
+                    .map(|row| panel.scroll + row)
+                    .filter(|entry| *entry < panel.entries.len())
+                else {
+                    return Ok(false);
+                };
+                panel.selected = entry;
+                let now = Instant::now();
+                let double = self.last_panel_click.is_some_and(|(l, e, at)| {
+                    l == left
+                        && e == entry
+                        && now.duration_since(at) <= Duration::from_millis(DOUBLE_CLICK_MS)
+                });
+                if double {
+                    self.last_panel_click = None;
+                    self.open_selected().await?;
+                } else {
+                    self.last_panel_click = Some((left, entry, now));
+                }
+            }
+            MouseEventKind::ScrollUp => {
+                panel.scroll_by(-MOUSE_SCROLL_LINES, panel_visible_rows(area));
+            }
+            MouseEventKind::ScrollDown => {
+                panel.scroll_by(MOUSE_SCROLL_LINES, panel_visible_rows(area));
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
     async fn handle_overlay_key(&mut self, key: KeyEvent) -> Result<bool> {
         if key.modifiers.contains(KeyModifiers::CONTROL) && matches!(key.code, KeyCode::Char('q')) {
             return Ok(true);
         }
         let overlay = std::mem::replace(&mut self.overlay, Overlay::None);
         match overlay {
+            Overlay::ToolApproval => {
+                if self.pending_tools.is_empty() {
+                    return Ok(false);
+                }
+                match key.code {
+                    KeyCode::Enter | KeyCode::Char('y') => self.start_next_tool(),
+                    KeyCode::Char('a') => self.approve_tool_batch(),
+                    KeyCode::Char('n') => {
+                        if let Some(skipped) = self.pending_tools.pop_front() {
+                            let mut args = FluentArgs::new();
+                            args.set("name", skipped.name);
+                            self.set_status(self.i18n.tr_args("ai-tool-skipped", &args));
+                        }
+                        self.prompt_tool_approval();
+                    }
+                    KeyCode::Char('d') => {
+                        let mut args = FluentArgs::new();
+                        args.set("count", self.pending_tools.len());
+                        self.pending_tools.clear();
+                        self.push_system_message(self.i18n.tr_args("ai-tool-denied-all", &args));
+                    }
+                    KeyCode::Esc => {}
+                    _ => self.overlay = Overlay::ToolApproval,
+                }
+                Ok(false)
+            }
+            Overlay::HostKeyChanged {
+                conn,
+                host,
+                port,
+                fingerprint,
+                key: host_key,
+            } => {
+                match key.code {
+                    KeyCode::Char('R') => {
+                        let mut args = FluentArgs::new();
+                        args.set("host", format!("{host}:{port}"));
+                        match self.sessions.replace_host_key(&host, port, &host_key) {
+                            Ok(()) => {
+                                warn!(host = %host, port, fingerprint = %fingerprint, "host key replaced");
+                                self.set_status(
+                                    self.i18n.tr_args("status-host-key-replaced", &args),
+                                );
+                                self.start_connection(*conn).await?;
+                            }
+                            Err(err) => {
+                                args.set("error", err.to_string());
+                                self.set_status(
+                                    self.i18n.tr_args("status-host-key-replace-failed", &args),
+                                );
+                            }
+                        }
+                    }
+                    KeyCode::Esc | KeyCode::Char('n') => {
+                        let mut args = FluentArgs::new();
+                        args.set("host", format!("{host}:{port}"));
+                        self.set_status(self.i18n.tr_args("status-host-key-aborted", &args));
+                    }
+                    _ => {
+                        self.overlay = Overlay::HostKeyChanged {
+                            conn,
+                            host,
+                            port,
+                            fingerprint,
+                            key: host_key,
+                        };
+                    }
+                }
+                Ok(false)
+            }
+            Overlay::UnknownHostKey => {
+                let trust = match key.code {
+                    KeyCode::Enter | KeyCode::Char('y') => true,
+                    KeyCode::Esc | KeyCode::Char('n') => false,
+                    _ => {
+                        self.overlay = Overlay::UnknownHostKey;
+                        return Ok(false);
+                    }
+                };
+                if let Some(prompt) = self.pending_host_keys.pop_front() {
+                    if trust {
+                        let mut args = FluentArgs::new();
+                        args.set("host", format!("{}:{}", prompt.host, prompt.port));
+                        self.set_status(self.i18n.tr_args("status-host-key-trusted", &args));
+                    }
+                    let _ = prompt.reply.send(trust);
+                }
+                Ok(false)
+            }
+            Overlay::Bookmarks {
+                kind,
+                mut items,
+                mut selected,
+                mut error,
+            } => {
+                let mut close = false;
+                match key.code {
+                    KeyCode::Esc => close = true,
+                    KeyCode::Up => selected = selected.saturating_sub(1),
+                    KeyCode::Down if selected + 1 < items.len() => selected += 1,
+                    KeyCode::Enter => {
+                        if let Some(bookmark) = items.get(selected) {
+                            match self.jump_to_bookmark(kind, &bookmark.path).await {
+                                Ok(()) => close = true,
+                                Err(err) => error = Some(err),
+                            }
+                        }
+                    }
+                    KeyCode::Char('a') => match self.add_bookmark(kind) {
+                        Ok(bookmark) => {
+                            items = self.panel_bookmarks(kind).unwrap_or_default();
+                            selected = items.iter().position(|b| b.id == bookmark.id).unwrap_or(0);
+                            error = None;
+                        }
+                        Err(err) => error = Some(err),
+                    },
+                    KeyCode::Char('d') | KeyCode::Delete => {
+                        if let Some(bookmark) = items.get(selected) {
+                            if let Err(err) = self.store.delete_bookmark(bookmark.id) {
+                                error = Some(err.to_string());
+                            }
+                            items = self.panel_bookmarks(kind).unwrap_or_default();
+                            selected = selected.min(items.len().saturating_sub(1));
+                        }
+                    }
+                    _ => {}
+                }
+                if !close {
+                    self.overlay = Overlay::Bookmarks {
+                        kind,
+                        items,
+                        selected,
+                        error,
+                    };
+                }
+                Ok(false)
+            }
+            Overlay::KnownHosts {
+                mut items,
+                mut selected,
+                mut error,
+            } => {
+                let mut close = false;
+                match key.code {
+                    KeyCode::Esc => close = true,
+                    KeyCode::Up => selected = selected.saturating_sub(1),
+                    KeyCode::Down if selected + 1 < items.len() => selected += 1,
+                    KeyCode::Char('d') | KeyCode::Delete => {
+                        if let Some(entry) = items.get(selected) {
+                            let mut args = FluentArgs::new();
+                            args.set("host", format!("{}:{}", entry.host, entry.port));
+                            match self.sessions.forget_host_key(&entry.host, entry.port) {
+                                Ok(()) => {
+                                    self.set_status(
+                                        self.i18n.tr_args("status-known-host-removed", &args),
+                                    );
+                                    error = None;
+                                }
+                                Err(err) => error = Some(err.to_string()),
+                            }
+                            items = self.sessions.known_hosts().unwrap_or_default();
+                            selected = selected.min(items.len().saturating_sub(1));
+                        }
+                    }
+                    _ => {}
+                }
+                if !close {
+                    self.overlay = Overlay::KnownHosts {
+                        items,
+                        selected,
+                        error,
+                    };
+                }
+                Ok(false)
+            }
+            Overlay::JumpHosts {
+                id,
+                mut items,
+                mut selected,
+                mut input,
+                mut error,
+            } => {
+                let mut close = false;
+                let mut changed = false;
+                if let Some(mut text) = input.take() {
+                    match key.code {
+                        KeyCode::Esc => error = None,
+                        KeyCode::Enter => match self.parse_jump_host_input(id, &text) {
+                            Ok(hop) => {
+                                items.push(hop);
+                                selected = items.len() - 1;
+                                changed = true;
+                                error = None;
+                            }
+                            Err(err) => {
+                                error = Some(err);
+                                input = Some(text);
+                            }
+                        },
+                        KeyCode::Backspace => {
+                            text.pop();
+                            input = Some(text);
+                        }
+                        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            text.push(c);
+                            input = Some(text);
+                        }
+                        _ => input = Some(text),
+                    }
+                } else {
+                    let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+                    match key.code {
+                        KeyCode::Esc => close = true,
+                        KeyCode::Up if shift && selected > 0 && selected < items.len() => {
+                            items.swap(selected, selected - 1);
+                            selected -= 1;
+                            changed = true;
+                        }
+                        KeyCode::Down if shift && selected + 1 < items.len() => {
+                            items.swap(selected, selected + 1);
+                            selected += 1;
+                            changed = true;
+                        }
+                        KeyCode::Up => selected = selected.saturating_sub(1),
+                        KeyCode::Down if selected + 1 < items.len() => selected += 1,
+                        KeyCode::Char('a') | KeyCode::Insert => {
+                            input = Some(String::new());
+                            error = None;
+                        }
+                        KeyCode::Char('d') | KeyCode::Delete if selected < items.len() => {
+                            items.remove(selected);
+                            selected = selected.min(items.len().saturating_sub(1));
+                            changed = true;
+                        }
+                        _ => {}
+                    }
+                }
+                if changed {
+                    if let Err(err) = self.save_jump_hosts(id, &items) {
+                        error = Some(err);
+                    }
+                }
+                if !close {
+                    self.overlay = Overlay::JumpHosts {
+                        id,
+                        items,
+                        selected,
+                        input,
+                        error,
+                    };
+                }
+                Ok(false)
+            }
             Overlay::Help => {
                 if matches!(key.code, KeyCode::Esc | KeyCode::Char('?')) {
                     self.overlay = Overlay::None;
@@ -2387,10 +5002,8 @@ impl AppState {
                     KeyCode::Backspace => {
                         input.pop();
                     }
-                    KeyCode::Char(c) => {
-                        if !key.modifiers.contains(KeyModifiers::CONTROL) {
-                            input.push(c);
-                        }
+                    KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        input.push(c);
                     }
                     _ => {}
                 }
@@ -2401,7 +5014,7 @@ impl AppState {
                 }
                 Ok(false)
             }
-            Overlay::Edit {
+            Overlay::Group {
                 id,
                 mut input,
                 mut error,
@@ -2411,38 +5024,249 @@ impl AppState {
                     KeyCode::Esc => {
                         close = true;
                     }
-                    KeyCode::Enter => match self.save_edit_connection(id, &input) {
+                    KeyCode::Enter => match self.assign_connection_group(id, &input) {
                         Ok(_) => close = true,
-                        Err(err) => error = Some(err.to_string()),
+                        Err(err) => error = Some(err),
                     },
                     KeyCode::Backspace => {
                         input.pop();
                     }
-                    KeyCode::Char(c) => {
-                        if !key.modifiers.contains(KeyModifiers::CONTROL) {
-                            input.push(c);
-                        }
+                    KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        input.push(c);
                     }
                     _ => {}
                 }
                 if close {
                     self.overlay = Overlay::None;
                 } else {
-                    self.overlay = Overlay::Edit { id, input, error };
+                    self.overlay = Overlay::Group { id, input, error };
                 }
                 Ok(false)
             }
-            Overlay::Password { id, mut state } => {
+            Overlay::Delete {
+                kind,
+                path,
+                name,
+                is_dir,
+            } => {
+                match key.code {
+                    KeyCode::Enter | KeyCode::Char('y') => {
+                        self.delete_entry(kind, &path, &name, is_dir).await;
+                    }
+                    KeyCode::Esc | KeyCode::Char('n') => {}
+                    _ => {
+                        self.overlay = Overlay::Delete {
+                            kind,
+                            path,
+                            name,
+                            is_dir,
+                        };
+                    }
+                }
+                Ok(false)
+            }
+            Overlay::Rename {
+                kind,
+                dir,
+                from,
+                mut input,
+                mut error,
+            } => {
                 let mut close = false;
                 match key.code {
                     KeyCode::Esc => {
                         close = true;
                     }
-                    KeyCode::Tab => {
-                        state.focus = match state.focus {
-                            PasswordFocus::Password => PasswordFocus::Master,
-                            PasswordFocus::Master => PasswordFocus::Password,
-                        };
+                    KeyCode::Enter => match self.rename_entry(kind, &dir, &from, &input).await {
+                        Ok(_) => close = true,
+                        Err(err) => error = Some(err),
+                    },
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        input.push(c);
+                    }
+                    _ => {}
+                }
+                if close {
+                    self.overlay = Overlay::None;
+                } else {
+                    self.overlay = Overlay::Rename {
+                        kind,
+                        dir,
+                        from,
+                        input,
+                        error,
+                    };
+                }
+                Ok(false)
+            }
+            Overlay::Mkdir {
+                kind,
+                dir,
+                mut input,
+                mut error,
+            } => {
+                let mut close = false;
+                match key.code {
+                    KeyCode::Esc => {
+                        close = true;
+                    }
+                    KeyCode::Enter => match self.create_directory(kind, &dir, &input).await {
+                        Ok(_) => close = true,
+                        Err(err) => error = Some(err),
+                    },
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        input.push(c);
+                    }
+                    _ => {}
+                }
+                if close {
+                    self.overlay = Overlay::None;
+                } else {
+                    self.overlay = Overlay::Mkdir {
+                        kind,
+                        dir,
+                        input,
+                        error,
+                    };
+                }
+                Ok(false)
+            }
+            Overlay::Locale {
+                id,
+                mut input,
+                mut error,
+            } => {
+                let mut close = false;
+                match key.code {
+                    KeyCode::Esc => {
+                        close = true;
+                    }
+                    KeyCode::Enter => match self.set_connection_locale(id, &input) {
+                        Ok(_) => close = true,
+                        Err(err) => error = Some(err),
+                    },
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        input.push(c);
+                    }
+                    _ => {}
+                }
+                if close {
+                    self.overlay = Overlay::None;
+                } else {
+                    self.overlay = Overlay::Locale { id, input, error };
+                }
+                Ok(false)
+            }
+            Overlay::TransferDirs {
+                id,
+                mut input,
+                mut error,
+            } => {
+                let mut close = false;
+                match key.code {
+                    KeyCode::Esc => {
+                        close = true;
+                    }
+                    KeyCode::Enter => match self.set_transfer_dirs(id, &input) {
+                        Ok(_) => close = true,
+                        Err(err) => error = Some(err),
+                    },
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        input.push(c);
+                    }
+                    _ => {}
+                }
+                if close {
+                    self.overlay = Overlay::None;
+                } else {
+                    self.overlay = Overlay::TransferDirs { id, input, error };
+                }
+                Ok(false)
+            }
+            Overlay::Edit { id, mut state } => {
+                let mut close = false;
+                if key.modifiers.contains(KeyModifiers::CONTROL)
+                    && matches!(key.code, KeyCode::Char('s'))
+                {
+                    if state.editing {
+                        state.commit_edit();
+                    }
+                    match self.save_edit_connection(id, &state.draft) {
+                        // Saving may have opened the password overlay already.
+                        Ok(()) => return Ok(false),
+                        Err(err) => state.error = Some(err),
+                    }
+                } else if state.editing {
+                    match key.code {
+                        KeyCode::Esc => {
+                            state.editing = false;
+                            state.input.clear();
+                        }
+                        KeyCode::Enter => state.commit_edit(),
+                        KeyCode::Backspace => {
+                            state.input.pop();
+                        }
+                        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            state.input.push(c);
+                        }
+                        _ => {}
+                    }
+                } else {
+                    match key.code {
+                        KeyCode::Esc => close = true,
+                        KeyCode::Up => {
+                            state.selected = (state.selected + CONNECTION_FIELDS.len() - 1)
+                                % CONNECTION_FIELDS.len();
+                        }
+                        KeyCode::Down => {
+                            state.selected = (state.selected + 1) % CONNECTION_FIELDS.len();
+                        }
+                        KeyCode::Left if state.selected_field() == ConnectionField::Auth => {
+                            state.draft.cycle_auth(-1);
+                        }
+                        KeyCode::Right | KeyCode::Char(' ')
+                            if state.selected_field() == ConnectionField::Auth =>
+                        {
+                            state.draft.cycle_auth(1);
+                        }
+                        KeyCode::Enter => {
+                            if state.selected_field() == ConnectionField::Auth {
+                                state.draft.cycle_auth(1);
+                            } else {
+                                state.start_edit();
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                if !close {
+                    self.overlay = Overlay::Edit { id, state };
+                }
+                Ok(false)
+            }
+            Overlay::Password { id, mut state } => {
+                let mut close = false;
+                match key.code {
+                    KeyCode::Esc => {
+                        close = true;
+                    }
+                    KeyCode::Tab => {
+                        state.focus = match state.focus {
+                            PasswordFocus::Password => PasswordFocus::Master,
+                            PasswordFocus::Master => PasswordFocus::Password,
+                        };
                     }
                     KeyCode::F(2) => {
                         state.save = !state.save;
@@ -2492,12 +5316,10 @@ impl AppState {
                             state.master.pop();
                         }
                     },
-                    KeyCode::Char(c) => {
-                        if !key.modifiers.contains(KeyModifiers::CONTROL) {
-                            match state.focus {
-                                PasswordFocus::Password => state.password.push(c),
-                                PasswordFocus::Master => state.master.push(c),
-                            }
+                    KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        match state.focus {
+                            PasswordFocus::Password => state.password.push(c),
+                            PasswordFocus::Master => state.master.push(c),
                         }
                     }
                     _ => {}
@@ -2536,10 +5358,8 @@ impl AppState {
                         KeyCode::Backspace => {
                             state.input.pop();
                         }
-                        KeyCode::Char(c) => {
-                            if !key.modifiers.contains(KeyModifiers::CONTROL) {
-                                state.input.push(c);
-                            }
+                        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            state.input.push(c);
                         }
                         _ => {}
                     }
@@ -2566,6 +5386,9 @@ impl AppState {
                                 state.draft.provider = next;
                                 state.draft.endpoint = endpoint;
                                 state.draft.model = model;
+                            } else if field == AiSettingsField::AllowedTools {
+                                state.tool_cursor =
+                                    (state.tool_cursor + TOOLS.len() - 1) % TOOLS.len();
                             } else {
                                 state.toggle_field(field);
                             }
@@ -2578,6 +5401,8 @@ impl AppState {
                                 state.draft.provider = next;
                                 state.draft.endpoint = endpoint;
                                 state.draft.model = model;
+                            } else if field == AiSettingsField::AllowedTools {
+                                state.tool_cursor = (state.tool_cursor + 1) % TOOLS.len();
                             } else {
                                 state.toggle_field(field);
                             }
@@ -2596,7 +5421,10 @@ impl AppState {
                                     | AiSettingsField::Streaming
                                     | AiSettingsField::Agent
                                     | AiSettingsField::AutoMode
+                                    | AiSettingsField::ContinueAfterTool
                                     | AiSettingsField::Tools
+                                    | AiSettingsField::NativeTools
+                                    | AiSettingsField::AllowedTools
                             ) {
                                 state.toggle_field(field);
                             } else {
@@ -2625,6 +5453,9 @@ impl AppState {
         if key.modifiers.contains(KeyModifiers::CONTROL) && matches!(key.code, KeyCode::Char('q')) {
             return Ok(true);
         }
+        if self.connection_query_editing {
+            return self.handle_connection_search_key(key).await;
+        }
         match key.code {
             KeyCode::Char(c) => match c.to_ascii_lowercase() {
                 'q' => Ok(true),
@@ -2644,14 +5475,50 @@ impl AppState {
                     Ok(false)
                 }
                 'e' => {
-                    if let Some(conn) = self.connections.get(self.selected) {
-                        self.open_edit_overlay(conn.id);
+                    if let Some(id) = self.selected_connection().map(|c| c.id) {
+                        self.open_edit_overlay(id);
+                    }
+                    Ok(false)
+                }
+                'g' => {
+                    if let Some(id) = self.selected_connection().map(|c| c.id) {
+                        self.open_group_overlay(id);
+                    }
+                    Ok(false)
+                }
+                'c' => {
+                    if let Some(id) = self.selected_connection().map(|c| c.id) {
+                        self.cycle_terminal_encoding(id);
+                    }
+                    Ok(false)
+                }
+                't' => {
+                    if let Some(id) = self.selected_connection().map(|c| c.id) {
+                        self.cycle_transfer_backend(id);
+                    }
+                    Ok(false)
+                }
+                'f' => {
+                    if let Some(id) = self.selected_connection().map(|c| c.id) {
+                        self.toggle_favorite(id);
+                    }
+                    Ok(false)
+                }
+                'l' => {
+                    if let Some(id) = self.selected_connection().map(|c| c.id) {
+                        self.open_locale_overlay(id);
+                    }
+                    Ok(false)
+                }
+                'd' => {
+                    if let Some(id) = self.selected_connection().map(|c| c.id) {
+                        self.open_transfer_dirs_overlay(id);
                     }
                     Ok(false)
                 }
                 'p' => {
-                    if let Some(conn) = self.connections.get(self.selected) {
-                        self.open_password_overlay(conn.id, PasswordMode::SaveOnly);
+                    if let Some(id) = self.selected_connection().map(|c| c.id) {
+                        self.open_password_overlay(id, PasswordMode::SaveOnly);
                     }
                     Ok(false)
                 }
@@ -2659,14 +5526,41 @@ impl AppState {
                     self.reload_connections();
                     Ok(false)
                 }
+                's' => {
+                    self.cycle_connection_list_mode();
+                    Ok(false)
+                }
+                'h' => {
+                    self.cycle_theme();
+                    Ok(false)
+                }
+                'k' => {
+                    self.open_known_hosts_overlay();
+                    Ok(false)
+                }
+                'j' => {
+                    if let Some(id) = self.selected_connection().map(|c| c.id) {
+                        self.open_jump_hosts_overlay(id);
+                    }
+                    Ok(false)
+                }
                 '?' => {
                     self.overlay = Overlay::Help;
                     Ok(false)
                 }
+                '/' => {
+                    self.connection_query_editing = true;
+                    self.selected = 0;
+                    Ok(false)
+                }
                 _ => Ok(false),
             },
+            KeyCode::Esc if self.searching_connections() => {
+                self.clear_connection_search();
+                Ok(false)
+            }
             KeyCode::Down => {
-                if self.selected + 1 < self.connections.len() {
+                if self.selected + 1 < self.connection_rows().len() {
                     self.selected += 1;
                 }
                 Ok(false)
@@ -2677,12 +5571,26 @@ impl AppState {
                 }
                 Ok(false)
             }
-            KeyCode::Enter => {
-                if let Some(conn) = self.connections.get(self.selected).cloned() {
+            KeyCode::Enter => match self.connection_rows().get(self.selected).copied() {
+                Some(ConnectionRow::Group(id)) => {
+                    if !self.collapsed_groups.remove(&id) {
+                        self.collapsed_groups.insert(id);
+                    }
+                    Ok(false)
+                }
+                Some(ConnectionRow::Auth(kind)) => {
+                    if !self.collapsed_auth.remove(kind) {
+                        self.collapsed_auth.insert(kind);
+                    }
+                    Ok(false)
+                }
+                Some(ConnectionRow::Connection(idx)) => {
+                    let conn = self.connections[idx].clone();
                     self.start_connection(conn).await?;
+                    Ok(false)
                 }
-                Ok(false)
-            }
+                None => Ok(false),
+            },
             KeyCode::F(9) => {
                 self.open_ai_settings();
                 Ok(false)
@@ -2692,30 +5600,13 @@ impl AppState {
     }
 
     async fn handle_session_key(&mut self, key: KeyEvent) -> Result<bool> {
-        match key.code {
-            KeyCode::F(9) => {
-                self.open_ai_settings();
-                return Ok(false);
-            }
-            KeyCode::F(10) => {
-                self.toggle_ai_panel();
-                return Ok(false);
-            }
-            KeyCode::F(12) => {
-                self.toggle_file_manager();
-                return Ok(false);
-            }
-            _ => {}
+        let files = self.input_focus == InputFocus::Files && !self.file_filter_editing;
+        if let Some(action) = self.keymap.action(&key, files) {
+            self.run_key_action(action).await?;
+            return Ok(false);
         }
-        if key.modifiers.contains(KeyModifiers::CONTROL) {
-            match key.code {
-                KeyCode::Char('q') => return Ok(true),
-                KeyCode::Char('t') => {
-                    self.toggle_focus();
-                    return Ok(false);
-                }
-                _ => {}
-            }
+        if key.modifiers.contains(KeyModifiers::CONTROL) && matches!(key.code, KeyCode::Char('q')) {
+            return Ok(true);
         }
         match self.input_focus {
             InputFocus::Files => self.handle_files_key(key).await,
@@ -2724,17 +5615,18 @@ impl AppState {
         }
     }
 
+    /// Keys of the file panels that the keymap leaves alone.
     async fn handle_files_key(&mut self, key: KeyEvent) -> Result<bool> {
+        if self.file_filter_editing {
+            return self.handle_filter_key(key).await;
+        }
         match key.code {
+            KeyCode::Esc if !self.active_panel().filter.is_empty() => {
+                self.active_panel_mut().set_filter(String::new());
+                Ok(false)
+            }
             KeyCode::Esc => {
-                self.mode = AppMode::Connections;
-                self.shell = None;
-                self.abort_shell_capture("session closed");
-                self.shell_log.clear();
-                self.active_connection = None;
-                self.pending_tools.clear();
-                self.tool_busy = false;
-                self.agent_steps_remaining = 0;
+                self.leave_session();
                 Ok(false)
             }
             KeyCode::Char('?') => {
@@ -2761,81 +5653,302 @@ impl AppState {
                 self.move_selection(1);
                 Ok(false)
             }
-            KeyCode::Enter => {
-                self.open_selected().await?;
-                Ok(false)
-            }
-            KeyCode::Backspace => {
-                self.navigate_up().await?;
-                Ok(false)
-            }
-            KeyCode::F(5) => {
-                self.copy_selected().await?;
-                Ok(false)
-            }
             _ => Ok(false),
         }
     }
 
-    async fn handle_terminal_key(&mut self, key: KeyEvent) -> Result<bool> {
-        if let Some(shell) = self.shell.as_mut() {
-            if let Some(bytes) = key_to_bytes(key) {
-                shell.write(&bytes).await?;
+    async fn run_key_action(&mut self, action: KeyAction) -> Result<()> {
+        match action {
+            KeyAction::AiSettings => self.open_ai_settings(),
+            KeyAction::ToggleAiPanel => self.toggle_ai_panel(),
+            KeyAction::ToggleFileManager => self.toggle_file_manager(),
+            KeyAction::ToggleFocus => self.toggle_focus(),
+            KeyAction::ToggleTools => {
+                self.apply_assistant_command(AssistantCommand::Tools(
+                    !self.config.ai.tools_enabled,
+                ));
+            }
+            KeyAction::ToggleAgent => {
+                self.apply_assistant_command(AssistantCommand::Agent(
+                    !self.config.ai.agent_enabled,
+                ));
+            }
+            KeyAction::ToggleAuto => {
+                self.apply_assistant_command(AssistantCommand::Auto(!self.config.ai.auto_mode));
+            }
+            KeyAction::Open => self.open_selected().await?,
+            KeyAction::NavigateUp => self.navigate_up().await?,
+            KeyAction::Copy => self.copy_selected().await?,
+            KeyAction::QuickSend => self.quick_send_selected().await?,
+            KeyAction::SyncDirectory => self.sync_directory().await?,
+            KeyAction::Compare => self.toggle_compare(),
+            KeyAction::SyncNavigation => {
+                self.sync_navigation = !self.sync_navigation;
+                let key = if self.sync_navigation {
+                    "status-sync-on"
+                } else {
+                    "status-sync-off"
+                };
+                self.set_status(self.i18n.tr(key));
+            }
+            KeyAction::SwapPanels => self.swap_panels(),
+            KeyAction::Delete => self.confirm_delete_selected(),
+            KeyAction::Rename => self.open_rename_overlay(),
+            KeyAction::Sort => self.active_panel_mut().cycle_sort(),
+            KeyAction::Filter => self.file_filter_editing = true,
+            KeyAction::ToggleHidden => {
+                let panel = self.active_panel_mut();
+                panel.show_hidden = !panel.show_hidden;
+                panel.apply_filter();
+                let key = if panel.show_hidden {
+                    "status-hidden-shown"
+                } else {
+                    "status-hidden-hidden"
+                };
+                self.set_status(self.i18n.tr(key));
+            }
+            KeyAction::Mkdir => {
+                let panel = self.active_panel();
+                self.overlay = Overlay::Mkdir {
+                    kind: panel.kind,
+                    dir: panel.path.clone(),
+                    input: String::new(),
+                    error: None,
+                };
             }
+            KeyAction::Bookmarks => self.open_bookmarks_overlay(),
+            KeyAction::PauseTransfer => self.control_transfer(TransferQueue::pause),
+            KeyAction::ResumeTransfer => self.control_transfer(TransferQueue::resume),
+            KeyAction::CancelTransfer => self.control_transfer(TransferQueue::cancel),
         }
-        Ok(false)
+        Ok(())
     }
 
-    async fn handle_assistant_key(&mut self, key: KeyEvent) -> Result<bool> {
-        if key.modifiers.contains(KeyModifiers::CONTROL) {
-            match key.code {
-                KeyCode::Char('y') => {
-                    if !self.pending_tools.is_empty() {
-                        self.start_next_tool();
-                    }
-                    return Ok(false);
-                }
-                KeyCode::Char('n') => {
-                    if let Some(skipped) = self.pending_tools.pop_front() {
-                        let mut args = FluentArgs::new();
-                        args.set("name", skipped.name);
-                        self.set_status(self.i18n.tr_args("ai-tool-skipped", &args));
-                        if self.config.ai.auto_mode || self.config.ai.agent_enabled {
-                            self.start_next_tool();
-                        }
-                    }
-                    return Ok(false);
-                }
-                _ => {}
-            }
-        }
+    /// Keys while typing a panel filter; arrows still move the selection so the list can
+    /// be narrowed and walked without leaving the input.
+    async fn handle_filter_key(&mut self, key: KeyEvent) -> Result<bool> {
         match key.code {
             KeyCode::Esc => {
-                self.exit_assistant_focus();
+                self.file_filter_editing = false;
+                self.active_panel_mut().set_filter(String::new());
             }
             KeyCode::Enter => {
-                self.submit_assistant_request();
-            }
-            KeyCode::Backspace => {
-                self.assistant.input.pop();
-            }
-            KeyCode::PageUp => {
-                self.assistant.scroll = self.assistant.scroll.saturating_sub(5);
+                self.file_filter_editing = false;
+                self.open_selected().await?;
             }
-            KeyCode::PageDown => {
-                self.assistant.scroll = self.assistant.scroll.saturating_add(5);
+            KeyCode::Tab => {
+                self.file_filter_editing = false;
+                self.active_panel_left = !self.active_panel_left;
             }
-            KeyCode::Up => {
-                self.assistant.scroll = self.assistant.scroll.saturating_sub(1);
+            KeyCode::Up => self.move_selection(-1),
+            KeyCode::Down => self.move_selection(1),
+            KeyCode::Backspace => {
+                let panel = self.active_panel_mut();
+                let mut filter = panel.filter.clone();
+                filter.pop();
+                panel.set_filter(filter);
             }
-            KeyCode::Down => {
-                self.assistant.scroll = self.assistant.scroll.saturating_add(1);
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let panel = self.active_panel_mut();
+                let mut filter = panel.filter.clone();
+                filter.push(c);
+                panel.set_filter(filter);
             }
-            KeyCode::Char(c) => {
-                if !key.modifiers.contains(KeyModifiers::CONTROL) {
-                    self.assistant.input.push(c);
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn active_panel(&self) -> &PanelState {
+        if self.active_panel_left {
+            &self.left_panel
+        } else {
+            &self.right_panel
+        }
+    }
+
+    fn active_panel_mut(&mut self) -> &mut PanelState {
+        if self.active_panel_left {
+            &mut self.left_panel
+        } else {
+            &mut self.right_panel
+        }
+    }
+
+    fn local_panel(&self) -> &PanelState {
+        if self.left_panel.kind == PanelKind::Local {
+            &self.left_panel
+        } else {
+            &self.right_panel
+        }
+    }
+
+    fn local_panel_mut(&mut self) -> &mut PanelState {
+        if self.left_panel.kind == PanelKind::Local {
+            &mut self.left_panel
+        } else {
+            &mut self.right_panel
+        }
+    }
+
+    fn remote_panel(&self) -> &PanelState {
+        if self.left_panel.kind == PanelKind::Remote {
+            &self.left_panel
+        } else {
+            &self.right_panel
+        }
+    }
+
+    fn remote_panel_mut(&mut self) -> &mut PanelState {
+        if self.left_panel.kind == PanelKind::Remote {
+            &mut self.left_panel
+        } else {
+            &mut self.right_panel
+        }
+    }
+
+    fn toggle_compare(&mut self) {
+        self.compare_panels = !self.compare_panels;
+        if !self.compare_panels {
+            self.set_status(self.i18n.tr("status-compare-off"));
+            return;
+        }
+        let summary = compare_summary(
+            &self.local_panel().all_entries,
+            &self.remote_panel().all_entries,
+        );
+        let mut args = FluentArgs::new();
+        args.set("local", summary.only_local);
+        args.set("remote", summary.only_remote);
+        args.set("differ", summary.differ);
+        self.set_status(self.i18n.tr_args("status-compare-on", &args));
+    }
+
+    /// Swaps the local and remote panels, keeping the same panel active, and remembers
+    /// the layout.
+    fn swap_panels(&mut self) {
+        std::mem::swap(&mut self.left_panel, &mut self.right_panel);
+        self.active_panel_left = !self.active_panel_left;
+        self.last_panel_click = None;
+        self.config.ui.swap_panels = !self.config.ui.swap_panels;
+        if let Err(err) = self.config_manager.save_config(&self.config) {
+            error!(error = %err, "ui config save failed");
+        }
+        self.set_status(self.i18n.tr("status-panels-swapped"));
+    }
+
+    fn control_transfer(&mut self, action: fn(&TransferQueue, Uuid) -> Result<(), CoreError>) {
+        let job_id = match &self.transfer_status {
+            Some(status)
+                if matches!(
+                    status.state,
+                    TransferState::Queued | TransferState::InProgress | TransferState::Paused
+                ) =>
+            {
+                status.job_id
+            }
+            _ => {
+                self.set_status(self.i18n.tr("transfer-none-active"));
+                return;
+            }
+        };
+        if action(&self.queue, job_id).is_err() {
+            self.set_status(self.i18n.tr("transfer-none-active"));
+        }
+    }
+
+    async fn handle_terminal_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let page = self.terminal_parser.screen().size().0.max(1) as usize;
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        match key.code {
+            KeyCode::PageUp if shift => {
+                self.scroll_terminal_to(self.scrollback_offset + page);
+                return Ok(false);
+            }
+            KeyCode::PageDown if shift => {
+                self.scroll_terminal_to(self.scrollback_offset.saturating_sub(page));
+                return Ok(false);
+            }
+            KeyCode::Esc if self.scrollback_offset > 0 => {
+                self.scrollback_offset = 0;
+                return Ok(false);
+            }
+            _ => {}
+        }
+        // Any other key leaves scrollback and goes to the shell as usual.
+        self.scrollback_offset = 0;
+        let encoding = self.terminal_encoding();
+        if let Some(shell) = self.shell.as_mut() {
+            if let Some(bytes) = key_to_bytes(key) {
+                shell.write(&encoding.encode(&bytes)).await?;
+            }
+        }
+        Ok(false)
+    }
+
+    async fn handle_assistant_key(&mut self, key: KeyEvent) -> Result<bool> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            match key.code {
+                KeyCode::Char('y') => {
+                    if !self.pending_tools.is_empty() {
+                        self.start_next_tool();
+                    }
+                    return Ok(false);
+                }
+                KeyCode::Char('a') => {
+                    self.approve_tool_batch();
+                    return Ok(false);
+                }
+                KeyCode::Char('n') => {
+                    if let Some(skipped) = self.pending_tools.pop_front() {
+                        let mut args = FluentArgs::new();
+                        args.set("name", skipped.name);
+                        self.set_status(self.i18n.tr_args("ai-tool-skipped", &args));
+                        if self.config.ai.auto_mode || self.config.ai.agent_enabled {
+                            self.start_next_tool();
+                        }
+                    }
+                    return Ok(false);
+                }
+                _ => {}
+            }
+        }
+        match key.code {
+            KeyCode::Esc => {
+                self.exit_assistant_focus();
+            }
+            KeyCode::Enter => {
+                self.submit_assistant_request();
+            }
+            KeyCode::Backspace => {
+                self.assistant.history_index = None;
+                self.assistant.input.pop();
+            }
+            KeyCode::PageUp => {
+                self.assistant.scroll = self.assistant.scroll.saturating_sub(5);
+            }
+            KeyCode::PageDown => {
+                self.assistant.scroll = self.assistant.scroll.saturating_add(5);
+            }
+            // Recall needs Alt or text in the input, so plain arrows still scroll an idle pane.
+            KeyCode::Up => {
+                let recall =
+                    key.modifiers.contains(KeyModifiers::ALT) || !self.assistant.input.is_empty();
+                if !(recall && self.assistant.history_prev()) {
+                    self.assistant.scroll = self.assistant.scroll.saturating_sub(1);
+                }
+            }
+            KeyCode::Down => {
+                let recall = key.modifiers.contains(KeyModifiers::ALT)
+                    || self.assistant.history_index.is_some();
+                if !(recall && self.assistant.history_next()) {
+                    self.assistant.scroll = self.assistant.scroll.saturating_add(1);
                 }
             }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.assistant.history_index = None;
+                self.assistant.input.push(c);
+            }
             _ => {}
         }
         Ok(false)
@@ -2924,6 +6037,7 @@ impl AppState {
             return;
         }
         self.assistant.input.clear();
+        self.assistant.record_prompt(&input);
         match parse_assistant_command(&input) {
             Ok(Some(cmd)) => {
                 self.apply_assistant_command(cmd);
@@ -2958,6 +6072,7 @@ impl AppState {
         if self.assistant.busy {
             return;
         }
+        let answering = self.assistant.question.take().is_some();
         self.assistant.push_message(
             AssistantMessage {
                 role: AssistantRole::User,
@@ -2966,32 +6081,46 @@ impl AppState {
             self.config.ai.history_max,
         );
         let messages = self.build_ai_request_messages();
-        if self.config.ai.agent_enabled {
-            self.agent_steps_remaining = if self.config.ai.max_steps == 0 {
+        // An answer to an @ask question resumes the paused agent loop with the budget it had left.
+        let resume =
+            answering && ai_loop_enabled(&self.config.ai) && self.agent_steps_remaining > 0;
+        if !resume {
+            self.agent_steps_remaining = if !ai_loop_enabled(&self.config.ai) {
+                0
+            } else if self.config.ai.max_steps == 0 {
                 u32::MAX
             } else {
                 self.config.ai.max_steps
             };
-        } else {
-            self.agent_steps_remaining = 0;
         }
         self.start_ai_request(messages);
     }
 
     fn start_ai_request(&mut self, messages: Vec<ChatMessage>) {
-        if self.config.ai.agent_enabled && self.agent_steps_remaining > 0 {
-            self.agent_steps_remaining = self.agent_steps_remaining.saturating_sub(1);
-        }
+        // Every request spends a step, so toggling modes mid-loop cannot stretch the budget.
+        self.agent_steps_remaining = self.agent_steps_remaining.saturating_sub(1);
         let cfg = self.config.ai.clone();
         let client = self.ai_client.clone();
         let tx = self.assistant_tx.clone();
+        let streaming = self.streaming_active();
         self.assistant.busy = true;
         tokio::spawn(async move {
             let _ = tx.send(AssistantEvent::Start).await;
-            let result = if cfg.streaming {
-                request_ai_stream(client, &cfg, messages, tx.clone()).await
+            let result = if streaming {
+                match request_ai_stream_retrying(client.clone(), &cfg, messages.clone(), tx.clone())
+                    .await
+                {
+                    Ok(content) => Ok(content),
+                    Err(err) => {
+                        // Some endpoints and proxies reject SSE; retry once without streaming.
+                        let _ = tx
+                            .send(AssistantEvent::StreamFallback(err.to_string()))
+                            .await;
+                        request_ai_retrying(client, &cfg, messages).await
+                    }
+                }
             } else {
-                request_ai(client, &cfg, messages).await
+                request_ai_retrying(client, &cfg, messages).await
             };
             let event = match result {
                 Ok(content) => AssistantEvent::Done(content),
@@ -3057,16 +6186,19 @@ impl AppState {
 
         prompt.push_str("=== Current Context ===\n");
 
-        if let Some(conn) = &self.active_connection {
-            prompt.push_str(&format!(
-                "Connected to: {}@{}:{}\n",
-                conn.username, conn.host, conn.port
-            ));
-            if !conn.name.is_empty() {
-                prompt.push_str(&format!("Connection name: {}\n", conn.name));
+        let ai = &self.config.ai;
+        if ai.context_target {
+            if let Some(conn) = &self.active_connection {
+                prompt.push_str(&format!(
+                    "Connected to: {}@{}:{}\n",
+                    conn.username, conn.host, conn.port
+                ));
+                if !conn.name.is_empty() {
+                    prompt.push_str(&format!("Connection name: {}\n", conn.name));
+                }
+            } else {
+                prompt.push_str("Status: Not connected to any server\n");
             }
-        } else {
-            prompt.push_str("Status: Not connected to any server\n");
         }
 
         let focus_str = match self.input_focus {
@@ -3076,14 +6208,18 @@ impl AppState {
         };
         prompt.push_str(&format!("Current focus: {focus_str}\n"));
 
-        prompt.push_str(&format!("Local directory: {}\n", self.left_panel.path));
-        if let Some(entry) = self.left_panel.entries.get(self.left_panel.selected) {
-            prompt.push_str(&format!("Selected local: {}\n", entry.name));
+        if ai.context_local_path {
+            let local = self.local_panel();
+            prompt.push_str(&format!("Local directory: {}\n", local.path));
+            if let Some(entry) = local.entries.get(local.selected) {
+                prompt.push_str(&format!("Selected local: {}\n", entry.name));
+            }
         }
 
-        if self.shell.is_some() {
-            prompt.push_str(&format!("Remote directory: {}\n", self.right_panel.path));
-            if let Some(entry) = self.right_panel.entries.get(self.right_panel.selected) {
+        if ai.context_remote_path && self.shell.is_some() {
+            let remote = self.remote_panel();
+            prompt.push_str(&format!("Remote directory: {}\n", remote.path));
+            if let Some(entry) = remote.entries.get(remote.selected) {
                 prompt.push_str(&format!("Selected remote: {}\n", entry.name));
             }
         }
@@ -3097,10 +6233,20 @@ impl AppState {
 
         if self.config.ai.tools_enabled {
             prompt.push_str("\n=== Available Tools ===\n");
-            prompt.push_str("Use @tool {\"name\":\"...\",\"args\":{...}} to execute actions.\n");
-            for line in tool_definitions() {
-                prompt.push_str(&line);
-                prompt.push('\n');
+            let native = ai.native_tools_active();
+            if native {
+                prompt.push_str("Call the provided functions to execute actions.\n");
+            } else {
+                prompt
+                    .push_str("Use @tool {\"name\":\"...\",\"args\":{...}} to execute actions.\n");
+            }
+            prompt.push_str("If a required detail is missing, reply with a single line @ask <question> and no tools; the user's answer comes in the next message.\n");
+            // Native function calling sends the definitions with the request instead.
+            if ai.context_tools && !native {
+                for line in tool_definitions(ai) {
+                    prompt.push_str(&line);
+                    prompt.push('\n');
+                }
             }
         }
 
@@ -3145,6 +6291,7 @@ impl AppState {
                 self.config.ai.tools_enabled = value;
                 if !self.config.ai.tools_enabled {
                     self.pending_tools.clear();
+                    self.tool_batch = false;
                     self.tool_busy = false;
                 }
                 self.save_ai_config();
@@ -3168,6 +6315,13 @@ impl AppState {
                 self.start_next_tool();
                 self.push_system_message(self.i18n.tr("ai-tool-running"));
             }
+            AssistantCommand::RunAll => {
+                if self.pending_tools.is_empty() {
+                    self.push_system_message(self.i18n.tr("ai-command-run-empty"));
+                    return;
+                }
+                self.approve_tool_batch();
+            }
             AssistantCommand::Skip => {
                 if let Some(skipped) = self.pending_tools.pop_front() {
                     let mut args = FluentArgs::new();
@@ -3221,10 +6375,14 @@ impl AppState {
             self.i18n.tr("ai-settings-max-steps"),
             self.config.ai.max_steps
         ));
+        let mut streaming = self.bool_label(self.streaming_active());
+        if self.stream_fallback {
+            streaming.push_str(&format!(" ({})", self.i18n.tr("status-ai-stream-fallback")));
+        }
         lines.push(format!(
             "{}: {}",
             self.i18n.tr("ai-settings-streaming"),
-            self.bool_label(self.config.ai.streaming)
+            streaming
         ));
         lines.push(format!(
             "{}: {}",
@@ -3240,12 +6398,72 @@ impl AppState {
     }
 
     fn clear_assistant_state(&mut self) {
+        let history = std::mem::take(&mut self.assistant.history);
         self.assistant = AssistantState::new(&self.i18n);
+        self.assistant.history = history;
         self.pending_tools.clear();
+        self.tool_batch = false;
         self.tool_busy = false;
         self.agent_steps_remaining = 0;
     }
 
+    /// Starts an asciicast of the new shell under `recording.directory` when enabled.
+    fn start_recording(&mut self) {
+        if !self.config.recording.enabled {
+            return;
+        }
+        let Some(conn) = self.active_connection.as_ref() else {
+            return;
+        };
+        let dir = self
+            .config
+            .recording
+            .directory
+            .clone()
+            .unwrap_or_else(|| self.config_manager.paths.recordings_dir.clone());
+        let path = dir.join(recording_file_name(&conn.name, chrono::Local::now()));
+        let (width, height) = self.terminal_size.unwrap_or((80, 24));
+        let title = connection_target(conn);
+        let env = HashMap::from([("TERM".to_string(), "xterm-256color".to_string())]);
+        match AsciinemaRecorder::start(&path, width, height, Some(&title), env) {
+            Ok(recorder) => {
+                info!(path = %path.display(), "recording session");
+                self.recorder = Some(recorder);
+            }
+            Err(err) => {
+                warn!(error = %err, path = %path.display(), "recording not started");
+                let mut args = FluentArgs::new();
+                args.set("error", err.to_string());
+                self.set_status(self.i18n.tr_args("status-recording-failed", &args));
+            }
+        }
+    }
+
+    fn stop_recording(&mut self) {
+        if let Some(mut recorder) = self.recorder.take() {
+            if let Err(err) = recorder.close() {
+                warn!(error = %err, "recording close failed");
+            }
+        }
+    }
+
+    /// Stores the active connection's conversation in memory and on disk.
+    fn save_conversation(&mut self) {
+        let Some(conn) = self.active_connection.as_ref() else {
+            return;
+        };
+        let messages = conversation_to_save(&self.assistant.messages, self.config.ai.history_max);
+        if messages.is_empty() {
+            self.conversations.remove(&conn.id);
+        } else {
+            self.conversations.insert(conn.id, messages);
+        }
+        let path = &self.config_manager.paths.conversations_file;
+        if let Err(err) = store_conversations(path, &self.conversations) {
+            warn!(error = %err, "conversation save failed");
+        }
+    }
+
     fn push_system_message(&mut self, content: String) {
         self.assistant.push_message(
             AssistantMessage {
@@ -3270,7 +6488,9 @@ impl AppState {
         let mut args = FluentArgs::new();
         args.set("key", key);
         args.set("value", value);
-        self.push_system_message(self.i18n.tr_args("ai-command-updated", &args));
+        let message = self.i18n.tr_args("ai-command-updated", &args);
+        self.set_status(message.clone());
+        self.push_system_message(message);
     }
 
     fn save_ai_config(&mut self) {
@@ -3324,9 +6544,28 @@ impl AppState {
                 }
                 self.assistant.scroll = usize::MAX;
             }
-            AssistantEvent::Done(content) => {
+            AssistantEvent::StreamFallback(error) => {
+                if let Some(idx) = self.assistant.stream_index {
+                    if let Some(message) = self.assistant.messages.get_mut(idx) {
+                        message.content.clear();
+                    }
+                }
+                self.stream_fallback = true;
+                warn!(error = %error, "ai streaming failed, falling back");
+                let mut args = FluentArgs::new();
+                args.set("error", error);
+                self.set_status(self.i18n.tr_args("ai-stream-fallback", &args));
+            }
+            AssistantEvent::Done(reply) => {
                 self.assistant.busy = false;
-                let (cleaned, calls) = extract_tool_calls(&content);
+                let (cleaned, mut calls) = extract_tool_calls(&reply.content);
+                calls.extend(reply.tool_calls);
+                let (cleaned, question) = extract_question(&cleaned);
+                if let Some(question) = question {
+                    calls.clear();
+                    self.assistant.question = Some(question);
+                    self.set_status(self.i18n.tr("ai-question-pending"));
+                }
                 if let Some(idx) = self.assistant.stream_index {
                     if let Some(message) = self.assistant.messages.get_mut(idx) {
                         message.content = cleaned.clone();
@@ -3349,6 +6588,8 @@ impl AppState {
                     }
                     if self.config.ai.auto_mode || self.config.ai.agent_enabled {
                         self.start_next_tool();
+                    } else {
+                        self.prompt_tool_approval();
                     }
                 }
                 self.assistant.stream_index = None;
@@ -3375,6 +6616,10 @@ impl AppState {
                 };
                 if result.success {
                     self.set_status(self.i18n.tr(status_key));
+                } else if !self.config.ai.tool_allowed(&result.call.name) {
+                    let mut args = FluentArgs::new();
+                    args.set("name", result.call.name.clone());
+                    self.set_status(self.i18n.tr_args("ai-tool-blocked", &args));
                 } else {
                     let mut args = FluentArgs::new();
                     args.set("error", result.output.clone());
@@ -3388,11 +6633,26 @@ impl AppState {
                     },
                     self.config.ai.history_max,
                 );
+                if self.tool_batch && !result.success && !self.pending_tools.is_empty() {
+                    let mut args = FluentArgs::new();
+                    args.set("count", self.pending_tools.len());
+                    self.pending_tools.clear();
+                    self.push_system_message(self.i18n.tr_args("ai-tool-batch-halted", &args));
+                }
+                if self.pending_tools.is_empty() {
+                    self.tool_batch = false;
+                }
                 if !self.pending_tools.is_empty() {
-                    if self.config.ai.auto_mode || self.config.ai.agent_enabled {
+                    if self.tool_batch || self.config.ai.auto_mode || self.config.ai.agent_enabled {
                         self.start_next_tool();
+                    } else {
+                        self.prompt_tool_approval();
                     }
-                } else if self.config.ai.agent_enabled && self.agent_steps_remaining > 0 {
+                } else if should_follow_up(
+                    &self.config.ai,
+                    self.agent_steps_remaining,
+                    result.success,
+                ) {
                     self.start_agent_followup();
                 }
             }
@@ -3407,6 +6667,39 @@ impl AppState {
         self.start_ai_request(messages);
     }
 
+    /// Shows the approval overlay for the next pending tool call unless something else
+    /// already has the screen or a tool is still running.
+    fn prompt_tool_approval(&mut self) {
+        if self.pending_tools.is_empty()
+            || self.tool_busy
+            || !self.config.ai.tools_enabled
+            || !matches!(self.overlay, Overlay::None)
+        {
+            return;
+        }
+        self.overlay = Overlay::ToolApproval;
+    }
+
+    /// Shows the next unknown host key once nothing else has the screen.
+    fn prompt_new_host_key(&mut self) {
+        if !self.pending_host_keys.is_empty() && matches!(self.overlay, Overlay::None) {
+            self.overlay = Overlay::UnknownHostKey;
+        }
+    }
+
+    /// Approves every pending tool call at once. The batch runs sequentially and
+    /// the first failing call cancels the remaining ones.
+    fn approve_tool_batch(&mut self) {
+        if self.pending_tools.is_empty() || !self.config.ai.tools_enabled {
+            return;
+        }
+        let mut args = FluentArgs::new();
+        args.set("count", self.pending_tools.len());
+        self.push_system_message(self.i18n.tr_args("ai-tool-batch-approved", &args));
+        self.tool_batch = true;
+        self.start_next_tool();
+    }
+
     fn start_next_tool(&mut self) {
         if self.tool_busy || !self.config.ai.tools_enabled {
             return;
@@ -3420,8 +6713,8 @@ impl AppState {
         let sessions = self.sessions.clone();
         let queue = self.queue.clone();
         let config = self.config.clone();
-        let local_base = self.left_panel.path.clone();
-        let remote_base = self.right_panel.path.clone();
+        let local_base = self.local_panel().path.clone();
+        let remote_base = self.remote_panel().path.clone();
         let session_id = match self.mode {
             AppMode::Session { id } => Some(id),
             _ => None,
@@ -3432,6 +6725,9 @@ impl AppState {
         let active_panel_left = self.active_panel_left;
         let shell_tool_tx = self.shell_tool_tx.clone();
         tokio::spawn(async move {
+            let remote = session_id
+                .and_then(|id| sessions.get_session(id))
+                .map(|handle| Arc::new(handle.session) as Arc<dyn ToolRemote>);
             let ctx = ToolContext {
                 sessions,
                 queue,
@@ -3439,6 +6735,7 @@ impl AppState {
                 local_base,
                 remote_base,
                 session_id,
+                remote,
                 connections,
                 left_panel,
                 right_panel,
@@ -3450,36 +6747,164 @@ impl AppState {
         });
     }
 
+    fn leave_session(&mut self) {
+        self.save_conversation();
+        self.stop_recording();
+        self.mode = AppMode::Connections;
+        self.shell = None;
+        self.reconnect = None;
+        self.abort_shell_capture("session closed");
+        self.shell_log.clear();
+        self.active_connection = None;
+        self.pending_tools.clear();
+        self.tool_batch = false;
+        self.tool_busy = false;
+        self.agent_steps_remaining = 0;
+    }
+
     async fn enter_session(&mut self, session_id: Uuid, conn: Connection) -> Result<()> {
+        self.save_conversation();
+        self.stop_recording();
         self.mode = AppMode::Session { id: session_id };
+        let saved = self
+            .conversations
+            .get(&conn.id)
+            .cloned()
+            .unwrap_or_default();
         self.active_connection = Some(conn);
         self.input_focus = InputFocus::Files;
         self.transfer_status = None;
         self.assistant = AssistantState::new(&self.i18n);
+        self.assistant.messages.extend(saved);
         self.pending_tools.clear();
+        self.tool_batch = false;
         self.tool_busy = false;
         self.agent_steps_remaining = 0;
         self.abort_shell_capture("session reset");
         self.shell_log.clear();
-        self.reset_terminal_parser();
         self.ensure_focus_valid();
+        if let Ok((width, height)) = crossterm::terminal::size() {
+            let screen = Rect::new(0, 0, width, height);
+            let inner = terminal_block().inner(self.session_areas(screen).terminal);
+            self.update_terminal_size(inner);
+        }
+        self.reset_terminal_parser();
+        self.remote_panel_mut().unavailable = None;
+        let dirs = self
+            .active_connection
+            .as_ref()
+            .map(|conn| (local_transfer_dir(conn), conn.transfer_remote_dir.clone()));
+        if let Some((local_dir, remote_dir)) = dirs {
+            if let Some(dir) = local_dir {
+                self.local_panel_mut().path = dir;
+            }
+            if let Some(dir) = remote_dir {
+                self.remote_panel_mut().path = dir;
+            }
+        }
+        self.attach_session(session_id).await
+    }
+
+    /// Opens the shell and SFTP halves of `session_id` and refreshes the panels.
+    async fn attach_session(&mut self, session_id: Uuid) -> Result<()> {
         if let Some(handle) = self.sessions.get_session(session_id) {
-            let shell = handle
-                .session
-                .open_shell()
-                .await
-                .map_err(|e| anyhow::anyhow!(e.to_string()))?;
-            self.shell = Some(shell);
+            let shell = match self.terminal_size {
+                Some((width, height)) => {
+                    self.pending_shell_resize = None;
+                    handle
+                        .session
+                        .open_shell_with_size(u32::from(width), u32::from(height))
+                        .await
+                }
+                None => handle.session.open_shell().await,
+            };
+            // Shell-only and SFTP-only servers both exist; keep whichever half works.
+            let sftp_error = handle.session.open_sftp().await.err();
+            self.shell = match (shell, &sftp_error) {
+                (Ok(shell), _) => Some(shell),
+                (Err(err), None) => {
+                    warn!(error = %err, "shell unavailable, continuing with files only");
+                    let mut args = FluentArgs::new();
+                    args.set("error", err.to_string());
+                    let message = self.i18n.tr_args("status-shell-unavailable", &args);
+                    self.terminal_parser.process(message.as_bytes());
+                    self.set_status(message);
+                    None
+                }
+                (Err(err), Some(_)) => return Err(anyhow::anyhow!(err.to_string())),
+            };
+            if self.shell.is_some() && self.recorder.is_none() {
+                self.start_recording();
+            }
+            self.report_shell_env();
+            if self.config.ssh.startup_commands_in_shell() {
+                self.run_startup_commands().await;
+            }
+            if let Some(err) = sftp_error {
+                warn!(error = %err, "sftp unavailable, remote browsing disabled");
+                let mut args = FluentArgs::new();
+                args.set("error", err.to_string());
+                self.remote_panel_mut().unavailable =
+                    Some(self.i18n.tr_args("panel-sftp-unavailable", &args));
+                self.set_status(self.i18n.tr_args("status-sftp-unavailable", &args));
+            }
             self.refresh_panels().await?;
         }
         Ok(())
     }
 
+    /// Says how the environment reached the shell when the server refused part of it or the
+    /// connection sets variables of its own.
+    fn report_shell_env(&mut self) {
+        let Some(env) = self.shell.as_ref().map(|shell| shell.env().clone()) else {
+            return;
+        };
+        let mut args = FluentArgs::new();
+        let key = if !env.exported.is_empty() {
+            args.set("names", env.exported.join(", "));
+            "status-env-exported"
+        } else if !env.refused.is_empty() {
+            args.set("names", env.refused.join(", "));
+            "status-env-refused"
+        } else if self
+            .active_connection
+            .as_ref()
+            .is_some_and(|conn| !conn.env_vars.is_empty())
+        {
+            args.set("names", env.sent.join(", "));
+            "status-env-sent"
+        } else {
+            return;
+        };
+        self.set_status(self.i18n.tr_args(key, &args));
+    }
+
+    /// Types the startup commands of the active connection into the new shell, in order and
+    /// ahead of anything the user types.
+    async fn run_startup_commands(&mut self) {
+        let commands = self
+            .active_connection
+            .as_ref()
+            .map(|conn| conn.startup_commands.clone())
+            .unwrap_or_default();
+        let Some(shell) = self.shell.as_mut() else {
+            return;
+        };
+        for command in commands.iter().filter(|c| !c.trim().is_empty()) {
+            if let Err(err) = shell.write(format!("{command}\n").as_bytes()).await {
+                warn!(error = %err, "startup command not sent");
+                break;
+            }
+        }
+    }
+
     async fn refresh_panels(&mut self) -> Result<()> {
-        self.left_panel.refresh(None).await?;
+        self.local_panel_mut().refresh(None).await?;
         if let AppMode::Session { id } = self.mode {
             if let Some(handle) = self.sessions.get_session(id) {
-                self.right_panel.refresh(Some(&handle.session)).await?;
+                self.remote_panel_mut()
+                    .refresh(Some(&handle.session))
+                    .await?;
             }
         }
         Ok(())
@@ -3496,10 +6921,12 @@ impl AppState {
                 panel.path = join_path(&panel.path, &entry.name, panel.kind == PanelKind::Remote);
                 panel.selected = 0;
                 panel.scroll = 0;
+                panel.filter.clear();
                 if let AppMode::Session { id } = self.mode {
                     let session = self.sessions.get_session(id).map(|h| h.session);
                     panel.refresh(session.as_ref()).await?;
                 }
+                self.mirror_navigation(Some(&entry.name)).await?;
             }
         }
         Ok(())
@@ -3515,80 +6942,553 @@ impl AppState {
             panel.path = parent;
             panel.selected = 0;
             panel.scroll = 0;
+            panel.filter.clear();
             if let AppMode::Session { id } = self.mode {
                 let session = self.sessions.get_session(id).map(|h| h.session);
                 panel.refresh(session.as_ref()).await?;
             }
+            self.mirror_navigation(None).await?;
         }
         Ok(())
     }
 
-    async fn copy_selected(&mut self) -> Result<()> {
-        let (src, dst) = if self.active_panel_left {
-            (&self.left_panel, &self.right_panel)
-        } else {
-            (&self.right_panel, &self.left_panel)
-        };
-        let Some(entry) = src.entries.get(src.selected) else {
+    /// Repeats a navigation step of the active panel in the other one while sync is on:
+    /// into `child`, or up when it is `None`. Nothing moves if the directory is missing there.
+    async fn mirror_navigation(&mut self, child: Option<&str>) -> Result<()> {
+        if !self.sync_navigation {
+            return Ok(());
+        }
+        let AppMode::Session { id } = self.mode else {
             return Ok(());
         };
-
-        let (source, dest, session_id) = match (src.kind, dst.kind, self.mode.clone()) {
-            (PanelKind::Local, PanelKind::Remote, AppMode::Session { id }) => (
-                TransferEndpoint::Local {
-                    path: PathBuf::from(&src.path),
-                },
-                TransferEndpoint::Remote {
-                    session_id: id,
-                    path: dst.path.clone(),
-                },
-                Some(id),
-            ),
-            (PanelKind::Remote, PanelKind::Local, AppMode::Session { id }) => (
-                TransferEndpoint::Remote {
-                    session_id: id,
-                    path: src.path.clone(),
-                },
-                TransferEndpoint::Local {
-                    path: PathBuf::from(&dst.path),
-                },
-                Some(id),
-            ),
-            _ => (
-                TransferEndpoint::Local {
-                    path: PathBuf::new(),
-                },
-                TransferEndpoint::Local {
-                    path: PathBuf::new(),
-                },
-                None,
-            ),
+        let session = self.sessions.get_session(id).map(|h| h.session);
+        let other = if self.active_panel_left {
+            &mut self.right_panel
+        } else {
+            &mut self.left_panel
         };
-
-        if session_id.is_none() {
+        if other.unavailable.is_some() {
             return Ok(());
         }
+        let sftp = match (&session, other.kind) {
+            (Some(session), PanelKind::Remote) => session.open_sftp().await.ok(),
+            _ => None,
+        };
+        let target =
+            match mirror_target(other, child, sftp.as_ref().map(|s| s as &dyn RemoteFs)).await {
+                Ok(Some(target)) => target,
+                Ok(None) => return Ok(()),
+                Err(missing) => {
+                    let mut args = FluentArgs::new();
+                    args.set("path", missing);
+                    self.set_status(self.i18n.tr_args("status-sync-missing", &args));
+                    return Ok(());
+                }
+            };
+        other.path = target;
+        other.selected = 0;
+        other.scroll = 0;
+        other.filter.clear();
+        other.refresh(session.as_ref()).await
+    }
+
+    fn confirm_delete_selected(&mut self) {
+        let panel = if self.active_panel_left {
+            &self.left_panel
+        } else {
+            &self.right_panel
+        };
+        if let Some(entry) = panel.entries.get(panel.selected) {
+            self.overlay = Overlay::Delete {
+                kind: panel.kind,
+                path: join_path(&panel.path, &entry.name, panel.kind == PanelKind::Remote),
+                name: entry.name.clone(),
+                is_dir: entry.is_dir,
+            };
+        }
+    }
+
+    async fn delete_entry(&mut self, kind: PanelKind, path: &str, name: &str, is_dir: bool) {
+        let result = match kind {
+            PanelKind::Local => if is_dir {
+                tokio::fs::remove_dir_all(path).await
+            } else {
+                tokio::fs::remove_file(path).await
+            }
+            .map_err(anyhow::Error::from),
+            PanelKind::Remote => self.delete_remote_entry(path, is_dir).await,
+        };
+        let mut args = FluentArgs::new();
+        args.set("name", name.to_string());
+        match result {
+            Ok(()) => {
+                self.set_status(self.i18n.tr_args("status-deleted", &args));
+                if let Err(err) = self.refresh_panels().await {
+                    warn!(error = %err, "panel refresh after delete failed");
+                }
+            }
+            Err(err) => {
+                args.set("error", err.to_string());
+                self.set_status(self.i18n.tr_args("status-delete-error", &args));
+            }
+        }
+    }
+
+    async fn session_sftp(&self) -> Result<catsolle_ssh::SftpClient> {
+        let AppMode::Session { id } = self.mode else {
+            anyhow::bail!("no active session");
+        };
+        let handle = self
+            .sessions
+            .get_session(id)
+            .ok_or_else(|| anyhow::anyhow!("session not found"))?;
+        Ok(handle.session.open_sftp().await?)
+    }
+
+    async fn delete_remote_entry(&self, path: &str, is_dir: bool) -> Result<()> {
+        let sftp = self.session_sftp().await?;
+        if is_dir {
+            remove_remote_dir_all(&sftp, path).await
+        } else {
+            Ok(sftp.remove_file(path).await?)
+        }
+    }
+
+    fn open_rename_overlay(&mut self) {
+        let panel = if self.active_panel_left {
+            &self.left_panel
+        } else {
+            &self.right_panel
+        };
+        if let Some(entry) = panel.entries.get(panel.selected) {
+            self.overlay = Overlay::Rename {
+                kind: panel.kind,
+                dir: panel.path.clone(),
+                from: entry.name.clone(),
+                input: entry.name.clone(),
+                error: None,
+            };
+        }
+    }
+
+    async fn rename_entry(
+        &mut self,
+        kind: PanelKind,
+        dir: &str,
+        from: &str,
+        input: &str,
+    ) -> Result<(), String> {
+        let to = input.trim();
+        if to.is_empty() || to.contains(['/', '\\']) {
+            return Err(self.i18n.tr("prompt-rename-error"));
+        }
+        if to == from {
+            return Ok(());
+        }
+        let remote = kind == PanelKind::Remote;
+        let from_path = join_path(dir, from, remote);
+        let to_path = join_path(dir, to, remote);
+        let mut args = FluentArgs::new();
+        args.set("name", to.to_string());
+        let result = match kind {
+            PanelKind::Local => {
+                // `rename` would silently replace the target, unlike the SFTP branch.
+                if tokio::fs::try_exists(&to_path).await.unwrap_or(false) {
+                    return Err(self.i18n.tr_args("prompt-rename-exists", &args));
+                }
+                tokio::fs::rename(&from_path, &to_path)
+                    .await
+                    .map_err(anyhow::Error::from)
+            }
+            PanelKind::Remote => match self.session_sftp().await {
+                Ok(sftp) => sftp
+                    .rename(&from_path, &to_path)
+                    .await
+                    .map_err(anyhow::Error::from),
+                Err(err) => Err(err),
+            },
+        };
+        if let Err(err) = result {
+            args.set("error", err.to_string());
+            return Err(self.i18n.tr_args("prompt-rename-failed", &args));
+        }
+        if let Err(err) = self.refresh_panels().await {
+            warn!(error = %err, "panel refresh after rename failed");
+        }
+        self.select_entry(kind, to);
+        let mut args = FluentArgs::new();
+        args.set("from", from.to_string());
+        args.set("to", to.to_string());
+        self.set_status(self.i18n.tr_args("status-renamed", &args));
+        Ok(())
+    }
+
+    async fn create_directory(
+        &mut self,
+        kind: PanelKind,
+        dir: &str,
+        input: &str,
+    ) -> Result<(), String> {
+        let name = input.trim();
+        if name.is_empty() || name.contains(['/', '\\']) {
+            return Err(self.i18n.tr("prompt-mkdir-error"));
+        }
+        let path = join_path(dir, name, kind == PanelKind::Remote);
+        let mut args = FluentArgs::new();
+        args.set("name", name.to_string());
+        let result = match kind {
+            PanelKind::Local => {
+                if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+                    return Err(self.i18n.tr_args("prompt-mkdir-exists", &args));
+                }
+                tokio::fs::create_dir_all(&path)
+                    .await
+                    .map_err(anyhow::Error::from)
+            }
+            PanelKind::Remote => match self.session_sftp().await {
+                Ok(sftp) => {
+                    if sftp.metadata(&path).await.is_ok() {
+                        return Err(self.i18n.tr_args("prompt-mkdir-exists", &args));
+                    }
+                    // `create_dir_all` ignores per-level errors, so confirm the result.
+                    match sftp.create_dir_all(&path).await {
+                        Ok(()) => sftp.metadata(&path).await.map(|_| ()),
+                        Err(err) => Err(err),
+                    }
+                    .map_err(anyhow::Error::from)
+                }
+                Err(err) => Err(err),
+            },
+        };
+        if let Err(err) = result {
+            args.set("error", err.to_string());
+            return Err(self.i18n.tr_args("prompt-mkdir-failed", &args));
+        }
+        if let Err(err) = self.refresh_panels().await {
+            warn!(error = %err, "panel refresh after mkdir failed");
+        }
+        self.select_entry(kind, name);
+        self.set_status(self.i18n.tr_args("status-mkdir", &args));
+        Ok(())
+    }
+
+    /// Bookmarks that apply to the panel of `kind`.
+    fn panel_bookmarks(&self, kind: PanelKind) -> Result<Vec<Bookmark>, CoreError> {
+        let conn_id = self.active_connection.as_ref().map(|c| c.id);
+        let local = kind == PanelKind::Local;
+        Ok(self
+            .store
+            .list_bookmarks(conn_id)?
+            .into_iter()
+            .filter(|b| b.is_local == local)
+            .collect())
+    }
+
+    fn open_jump_hosts_overlay(&mut self, id: Uuid) {
+        if let Some(conn) = self.connections.iter().find(|c| c.id == id) {
+            self.overlay = Overlay::JumpHosts {
+                id,
+                items: conn.jump_hosts.clone(),
+                selected: 0,
+                input: None,
+                error: None,
+            };
+        }
+    }
+
+    fn parse_jump_host_input(&self, id: Uuid, input: &str) -> Result<JumpHost, String> {
+        let conn = self
+            .connections
+            .iter()
+            .find(|c| c.id == id)
+            .ok_or_else(|| self.i18n.tr("status-connection-error"))?;
+        parse_jump_host(
+            input,
+            &whoami::username(),
+            self.config.ssh.port,
+            (&conn.host, conn.port),
+        )
+        .map_err(|err| match err {
+            JumpHostError::Invalid => self.i18n.tr("prompt-jump-hosts-error"),
+            JumpHostError::SameAsTarget => self.i18n.tr("prompt-jump-hosts-same"),
+            JumpHostError::KeyMissing(path) => {
+                let mut args = FluentArgs::new();
+                args.set("path", path.display().to_string());
+                self.i18n.tr_args("prompt-edit-key-missing", &args)
+            }
+        })
+    }
+
+    fn save_jump_hosts(&mut self, id: Uuid, items: &[JumpHost]) -> Result<(), String> {
+        let mut conn = self
+            .store
+            .get_connection(id)
+            .map_err(|_| self.i18n.tr("status-connection-error"))?;
+        conn.jump_hosts = items.to_vec();
+        conn.updated_at = chrono::Utc::now();
+        self.store
+            .update_connection(&conn)
+            .map_err(|_| self.i18n.tr("status-connection-error"))?;
+        self.reload_connections();
+        let mut args = FluentArgs::new();
+        args.set("name", conn.name.clone());
+        args.set("count", items.len() as i64);
+        self.set_status(self.i18n.tr_args("status-jump-hosts-saved", &args));
+        Ok(())
+    }
+
+    fn open_known_hosts_overlay(&mut self) {
+        match self.sessions.known_hosts() {
+            Ok(items) => {
+                self.overlay = Overlay::KnownHosts {
+                    items,
+                    selected: 0,
+                    error: None,
+                };
+            }
+            Err(err) => {
+                let mut args = FluentArgs::new();
+                args.set("error", err.to_string());
+                self.set_status(self.i18n.tr_args("status-known-hosts-error", &args));
+            }
+        }
+    }
+
+    fn open_bookmarks_overlay(&mut self) {
+        let kind = self.active_panel().kind;
+        match self.panel_bookmarks(kind) {
+            Ok(items) => {
+                self.overlay = Overlay::Bookmarks {
+                    kind,
+                    items,
+                    selected: 0,
+                    error: None,
+                };
+            }
+            Err(err) => {
+                let mut args = FluentArgs::new();
+                args.set("error", err.to_string());
+                self.set_status(self.i18n.tr_args("status-bookmark-error", &args));
+            }
+        }
+    }
+
+    /// Bookmarks the current directory of the panel of `kind`, named after its last part.
+    fn add_bookmark(&mut self, kind: PanelKind) -> Result<Bookmark, String> {
+        let path = match kind {
+            PanelKind::Local => self.local_panel().path.clone(),
+            PanelKind::Remote => self.remote_panel().path.clone(),
+        };
+        let name = path
+            .trim_end_matches(['/', '\\'])
+            .rsplit(['/', '\\'])
+            .next()
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&path)
+            .to_string();
+        let conn_id = self.active_connection.as_ref().map(|c| c.id);
+        let bookmark = self
+            .store
+            .add_bookmark(conn_id, &path, &name, kind == PanelKind::Local)
+            .map_err(|err| err.to_string())?;
+        let mut args = FluentArgs::new();
+        args.set("name", bookmark.name.clone());
+        self.set_status(self.i18n.tr_args("status-bookmark-added", &args));
+        Ok(bookmark)
+    }
+
+    /// Opens `path` in the panel of `kind`; when it cannot be listed the panel stays where
+    /// it was.
+    async fn jump_to_bookmark(&mut self, kind: PanelKind, path: &str) -> Result<(), String> {
+        let session = match self.mode {
+            AppMode::Session { id } => self.sessions.get_session(id).map(|h| h.session),
+            AppMode::Connections => None,
+        };
+        let panel = match kind {
+            PanelKind::Local => self.local_panel_mut(),
+            PanelKind::Remote => self.remote_panel_mut(),
+        };
+        let previous = std::mem::replace(&mut panel.path, path.to_string());
+        panel.selected = 0;
+        panel.scroll = 0;
+        panel.filter.clear();
+        if let Err(err) = panel.refresh(session.as_ref()).await {
+            panel.path = previous;
+            if let Err(err) = panel.refresh(session.as_ref()).await {
+                warn!(error = %err, "panel refresh after failed bookmark jump failed");
+            }
+            let mut args = FluentArgs::new();
+            args.set("path", path.to_string());
+            args.set("error", err.to_string());
+            return Err(self.i18n.tr_args("prompt-bookmark-failed", &args));
+        }
+        Ok(())
+    }
+
+    fn select_entry(&mut self, kind: PanelKind, name: &str) {
+        let panel = if self.left_panel.kind == kind {
+            &mut self.left_panel
+        } else {
+            &mut self.right_panel
+        };
+        if let Some(idx) = panel.entries.iter().position(|entry| entry.name == name) {
+            panel.selected = idx;
+        }
+    }
+
+    async fn copy_selected(&mut self) -> Result<()> {
+        self.copy_selected_to(None).await
+    }
+
+    /// Copies the selected entry into the connection's default directory on the other side.
+    async fn quick_send_selected(&mut self) -> Result<()> {
+        let Some(conn) = self.active_connection.as_ref() else {
+            return Ok(());
+        };
+        let dir = match self.active_panel().kind {
+            PanelKind::Local => conn.transfer_remote_dir.clone(),
+            PanelKind::Remote => local_transfer_dir(conn),
+        };
+        let Some(dir) = dir else {
+            self.set_status(self.i18n.tr("status-transfer-dir-missing"));
+            return Ok(());
+        };
+        self.copy_selected_to(Some(&dir)).await?;
+        let mut args = FluentArgs::new();
+        args.set("path", dir);
+        self.set_status(self.i18n.tr_args("status-quick-send", &args));
+        Ok(())
+    }
+
+    /// Queues a copy of the selected entry into `dest_dir`, or the other panel's directory.
+    async fn copy_selected_to(&mut self, dest_dir: Option<&str>) -> Result<()> {
+        let (src, dst) = if self.active_panel_left {
+            (&self.left_panel, &self.right_panel)
+        } else {
+            (&self.right_panel, &self.left_panel)
+        };
+        let Some(entry) = src.entries.get(src.selected) else {
+            return Ok(());
+        };
+        let dst_path = dest_dir.unwrap_or(&dst.path).to_string();
+        let Some((source, dest)) = self.transfer_endpoints(&dst_path) else {
+            return Ok(());
+        };
+        let files = self
+            .transfer_files_for(&source, &dest, entry, &dst_path)
+            .await?;
+        self.enqueue_transfer(source, dest, files, OverwriteMode::Replace)
+            .await
+    }
+
+    /// Copies whatever the other panel is missing or has a different version of, leaving
+    /// files that are newer over there alone. Directories on both sides are walked.
+    async fn sync_directory(&mut self) -> Result<()> {
+        let (src, dst) = if self.active_panel_left {
+            (&self.left_panel, &self.right_panel)
+        } else {
+            (&self.right_panel, &self.left_panel)
+        };
+        let Some((source, dest)) = self.transfer_endpoints(&dst.path) else {
+            return Ok(());
+        };
+        let candidates = sync_candidates(&src.entries, &dst.all_entries);
+        if candidates.is_empty() {
+            self.set_status(self.i18n.tr("status-sync-dir-nothing"));
+            return Ok(());
+        }
+        let count = candidates.len();
+        let mut files = Vec::new();
+        for entry in candidates {
+            files.extend(
+                self.transfer_files_for(&source, &dest, entry, &dst.path)
+                    .await?,
+            );
+        }
+        self.enqueue_transfer(source, dest, files, OverwriteMode::IfNewer)
+            .await?;
+        let mut args = FluentArgs::new();
+        args.set("count", count);
+        self.set_status(self.i18n.tr_args("status-sync-dir-queued", &args));
+        Ok(())
+    }
+
+    /// Endpoints for copying from the active panel's directory into `dst_path` on the
+    /// other side; `None` outside a session.
+    fn transfer_endpoints(&self, dst_path: &str) -> Option<(TransferEndpoint, TransferEndpoint)> {
+        let AppMode::Session { id } = self.mode else {
+            return None;
+        };
+        let src = self.active_panel();
+        let endpoint = |kind: PanelKind, path: &str| match kind {
+            PanelKind::Local => TransferEndpoint::Local {
+                path: PathBuf::from(path),
+            },
+            PanelKind::Remote => TransferEndpoint::Remote {
+                session_id: id,
+                path: path.to_string(),
+            },
+        };
+        let dst_kind = match src.kind {
+            PanelKind::Local => PanelKind::Remote,
+            PanelKind::Remote => PanelKind::Local,
+        };
+        Some((endpoint(src.kind, &src.path), endpoint(dst_kind, dst_path)))
+    }
+
+    /// Files to transfer for `entry` of the active panel, expanding directories.
+    async fn transfer_files_for(
+        &self,
+        source: &TransferEndpoint,
+        dest: &TransferEndpoint,
+        entry: &FileEntry,
+        dst_dir: &str,
+    ) -> Result<Vec<TransferFile>> {
+        let src = self.active_panel();
+        let source_path = join_path(&src.path, &entry.name, src.kind == PanelKind::Remote);
+        let dest_path = join_path(
+            dst_dir,
+            &entry.name,
+            matches!(dest, TransferEndpoint::Remote { .. }),
+        );
+        if !entry.is_dir {
+            return Ok(vec![TransferFile {
+                source_path,
+                dest_path,
+                size: entry.size,
+                is_dir: false,
+            }]);
+        }
+        expand_transfer_files(&self.sessions, source, dest, &source_path, &dest_path)
+            .await
+            .map_err(|e| anyhow::anyhow!(e.to_string()))
+    }
 
+    async fn enqueue_transfer(
+        &self,
+        source: TransferEndpoint,
+        dest: TransferEndpoint,
+        files: Vec<TransferFile>,
+        overwrite: OverwriteMode,
+    ) -> Result<()> {
         let job = TransferJob {
             id: Uuid::new_v4(),
             source,
             dest,
-            files: vec![TransferFile {
-                source_path: join_path(&src.path, &entry.name, src.kind == PanelKind::Remote),
-                dest_path: join_path(&dst.path, &entry.name, dst.kind == PanelKind::Remote),
-                size: entry.size,
-                is_dir: entry.is_dir,
-            }],
+            files,
             options: TransferOptions {
-                overwrite: catsolle_core::transfer::OverwriteMode::Replace,
+                overwrite,
                 preserve_permissions: true,
                 preserve_times: true,
                 verify_checksum: self.config.transfer.verify_checksum,
                 resume: self.config.transfer.resume,
                 buffer_size: self.config.transfer.buffer_size,
+                max_bytes_per_sec: transfer_rate_limit(&self.config.transfer),
+                continue_on_error: self.config.transfer.continue_on_error,
             },
             state: TransferState::Queued,
             progress: Default::default(),
+            results: Vec::new(),
             created_at: chrono::Utc::now(),
         };
 
@@ -3611,6 +7511,54 @@ impl AssistantState {
             scroll: 0,
             busy: false,
             stream_index: None,
+            question: None,
+            history: Vec::new(),
+            history_index: None,
+            history_draft: String::new(),
+        }
+    }
+
+    fn record_prompt(&mut self, prompt: &str) {
+        self.history_index = None;
+        self.history_draft.clear();
+        if self.history.last().is_some_and(|last| last == prompt) {
+            return;
+        }
+        self.history.push(prompt.to_string());
+        if self.history.len() > PROMPT_HISTORY_MAX {
+            self.history.remove(0);
+        }
+    }
+
+    /// Replaces the input with the previous prompt; false when there is none.
+    fn history_prev(&mut self) -> bool {
+        let idx = match self.history_index {
+            _ if self.history.is_empty() => return false,
+            None => {
+                self.history_draft = std::mem::take(&mut self.input);
+                self.history.len() - 1
+            }
+            Some(idx) => idx.saturating_sub(1),
+        };
+        self.history_index = Some(idx);
+        self.input = self.history[idx].clone();
+        true
+    }
+
+    /// Moves towards newer prompts, ending at the stashed draft; false when not recalling.
+    fn history_next(&mut self) -> bool {
+        match self.history_index {
+            None => false,
+            Some(idx) if idx + 1 < self.history.len() => {
+                self.history_index = Some(idx + 1);
+                self.input = self.history[idx + 1].clone();
+                true
+            }
+            Some(_) => {
+                self.history_index = None;
+                self.input = std::mem::take(&mut self.history_draft);
+                true
+            }
         }
     }
 
@@ -3628,6 +7576,7 @@ impl AiSettingsState {
     fn from_config(cfg: &AiConfig) -> Self {
         Self {
             selected: 0,
+            tool_cursor: 0,
             editing: false,
             input: String::new(),
             error: None,
@@ -3644,8 +7593,15 @@ impl AiSettingsState {
                 streaming: cfg.streaming,
                 agent_enabled: cfg.agent_enabled,
                 auto_mode: cfg.auto_mode,
+                continue_after_tool: cfg.continue_after_tool,
                 max_steps: cfg.max_steps.to_string(),
                 tools_enabled: cfg.tools_enabled,
+                native_tools: cfg.native_tools,
+                blocked_tools: TOOLS
+                    .iter()
+                    .filter(|(name, _)| !cfg.tool_allowed(name))
+                    .map(|(name, _)| name.to_string())
+                    .collect(),
                 system_prompt: cfg.system_prompt.clone(),
             },
         }
@@ -3699,44 +7655,202 @@ impl AiSettingsState {
             AiSettingsField::Streaming => self.draft.streaming = !self.draft.streaming,
             AiSettingsField::Agent => self.draft.agent_enabled = !self.draft.agent_enabled,
             AiSettingsField::AutoMode => self.draft.auto_mode = !self.draft.auto_mode,
+            AiSettingsField::ContinueAfterTool => {
+                self.draft.continue_after_tool = !self.draft.continue_after_tool
+            }
             AiSettingsField::Tools => self.draft.tools_enabled = !self.draft.tools_enabled,
+            AiSettingsField::NativeTools => self.draft.native_tools = !self.draft.native_tools,
+            AiSettingsField::AllowedTools => {
+                let tool = TOOLS[self.tool_cursor % TOOLS.len()].0.to_string();
+                if !self.draft.blocked_tools.remove(&tool) {
+                    self.draft.blocked_tools.insert(tool);
+                }
+            }
             _ => {}
         }
     }
 }
 
-impl PanelState {
-    fn local_default() -> Self {
-        let path = std::env::current_dir()
-            .unwrap_or_else(|_| PathBuf::from("."))
-            .to_string_lossy()
-            .to_string();
+impl ConnectionFormState {
+    fn from_connection(conn: &Connection) -> Self {
+        let (key_path, cert_path) = match &conn.auth_method {
+            AuthMethod::Key {
+                private_key_path, ..
+            } => (private_key_path.display().to_string(), String::new()),
+            AuthMethod::Certificate {
+                cert_path,
+                private_key_path,
+                ..
+            } => (
+                private_key_path.display().to_string(),
+                cert_path.display().to_string(),
+            ),
+            _ => (String::new(), String::new()),
+        };
         Self {
-            kind: PanelKind::Local,
-            path,
-            entries: Vec::new(),
             selected: 0,
-            scroll: 0,
+            editing: false,
+            input: String::new(),
+            error: None,
+            draft: ConnectionDraft {
+                name: conn.name.clone(),
+                target: format!("{}@{}:{}", conn.username, conn.host, conn.port),
+                auth: conn.auth_method.as_key(),
+                key_path,
+                cert_path,
+            },
         }
     }
 
-    fn remote_default() -> Self {
-        Self {
-            kind: PanelKind::Remote,
-            path: "/".to_string(),
-            entries: Vec::new(),
-            selected: 0,
-            scroll: 0,
-        }
+    fn selected_field(&self) -> ConnectionField {
+        CONNECTION_FIELDS
+            .get(self.selected)
+            .copied()
+            .unwrap_or(ConnectionField::Name)
     }
 
-    async fn refresh(&mut self, session: Option<&catsolle_ssh::SshSession>) -> Result<()> {
-        self.entries = match self.kind {
-            PanelKind::Local => list_local(&self.path).await?,
-            PanelKind::Remote => {
-                let session = match session {
+    fn start_edit(&mut self) {
+        self.editing = true;
+        self.error = None;
+        self.input = match self.selected_field() {
+            ConnectionField::Name => self.draft.name.clone(),
+            ConnectionField::Target => self.draft.target.clone(),
+            ConnectionField::KeyPath => self.draft.key_path.clone(),
+            ConnectionField::CertPath => self.draft.cert_path.clone(),
+            ConnectionField::Auth => String::new(),
+        };
+    }
+
+    fn commit_edit(&mut self) {
+        let value = std::mem::take(&mut self.input);
+        match self.selected_field() {
+            ConnectionField::Name => self.draft.name = value,
+            ConnectionField::Target => self.draft.target = value,
+            ConnectionField::KeyPath => self.draft.key_path = value,
+            ConnectionField::CertPath => self.draft.cert_path = value,
+            ConnectionField::Auth => {}
+        }
+        self.editing = false;
+    }
+}
+
+impl ConnectionDraft {
+    fn cycle_auth(&mut self, step: isize) {
+        let len = EDIT_AUTH_KINDS.len() as isize;
+        let pos = EDIT_AUTH_KINDS
+            .iter()
+            .position(|kind| *kind == self.auth)
+            .unwrap_or(0) as isize;
+        self.auth = EDIT_AUTH_KINDS[(pos + step).rem_euclid(len) as usize];
+    }
+
+    fn uses_field(&self, field: ConnectionField) -> bool {
+        match field {
+            ConnectionField::KeyPath => matches!(self.auth, "key" | "certificate"),
+            ConnectionField::CertPath => self.auth == "certificate",
+            _ => true,
+        }
+    }
+
+    /// The auth method to save for connection `id`. Stored secrets of `current` carry over
+    /// where they still apply; the error is a key or certificate file that does not exist.
+    fn auth_method(&self, id: Uuid, current: &AuthMethod) -> Result<AuthMethod, PathBuf> {
+        let existing_file = |value: &str| {
+            let path = expand_home(value.trim());
+            if path.is_file() {
+                Ok(path)
+            } else {
+                Err(path)
+            }
+        };
+        let passphrase_ref = match current {
+            AuthMethod::Key { passphrase_ref, .. }
+            | AuthMethod::Certificate { passphrase_ref, .. } => passphrase_ref.clone(),
+            _ => None,
+        };
+        Ok(match self.auth {
+            "key" => AuthMethod::Key {
+                private_key_path: existing_file(&self.key_path)?,
+                passphrase_ref,
+            },
+            "certificate" => AuthMethod::Certificate {
+                cert_path: existing_file(&self.cert_path)?,
+                private_key_path: existing_file(&self.key_path)?,
+                passphrase_ref,
+            },
+            "password" => match current {
+                AuthMethod::Password { secret_ref } => AuthMethod::Password {
+                    secret_ref: secret_ref.clone(),
+                },
+                _ => AuthMethod::Password {
+                    secret_ref: format!("conn:{id}:password"),
+                },
+            },
+            "keyboard-interactive" => AuthMethod::KeyboardInteractive,
+            _ => AuthMethod::Agent,
+        })
+    }
+}
+
+impl AiConfigDraft {
+    /// The `allowed_tools` list to save: empty when nothing is blocked, `None` when
+    /// everything is, since an empty list would mean the opposite.
+    fn allowed_tools(&self) -> Option<Vec<String>> {
+        if self.blocked_tools.is_empty() {
+            return Some(Vec::new());
+        }
+        let allowed: Vec<String> = TOOLS
+            .iter()
+            .filter(|(name, _)| !self.blocked_tools.contains(*name))
+            .map(|(name, _)| name.to_string())
+            .collect();
+        (!allowed.is_empty()).then_some(allowed)
+    }
+}
+
+impl PanelState {
+    fn local_default() -> Self {
+        let path = std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .to_string_lossy()
+            .to_string();
+        Self {
+            kind: PanelKind::Local,
+            path,
+            entries: Vec::new(),
+            selected: 0,
+            scroll: 0,
+            sort: SortMode::default(),
+            all_entries: Vec::new(),
+            filter: String::new(),
+            show_hidden: false,
+            unavailable: None,
+        }
+    }
+
+    fn remote_default() -> Self {
+        Self {
+            kind: PanelKind::Remote,
+            path: "/".to_string(),
+            entries: Vec::new(),
+            selected: 0,
+            scroll: 0,
+            sort: SortMode::default(),
+            all_entries: Vec::new(),
+            filter: String::new(),
+            show_hidden: false,
+            unavailable: None,
+        }
+    }
+
+    async fn refresh(&mut self, session: Option<&catsolle_ssh::SshSession>) -> Result<()> {
+        self.all_entries = match self.kind {
+            PanelKind::Local => list_local(&self.path).await?,
+            PanelKind::Remote => {
+                let session = match session.filter(|_| self.unavailable.is_none()) {
                     Some(session) => session,
                     None => {
+                        self.all_entries = Vec::new();
                         self.entries = Vec::new();
                         return Ok(());
                     }
@@ -3748,13 +7862,61 @@ impl PanelState {
                 list_remote(&sftp, &self.path).await?
             }
         };
+        self.sort.apply(&mut self.all_entries);
+        self.apply_filter();
+        Ok(())
+    }
+
+    /// Rebuilds `entries` from `all_entries`, dropping dotfiles unless `show_hidden` and
+    /// applying a case-insensitive substring match, then clamps the selection.
+    fn apply_filter(&mut self) {
+        let needle = self.filter.to_lowercase();
+        self.entries = self
+            .all_entries
+            .iter()
+            .filter(|e| self.show_hidden || !e.name.starts_with('.'))
+            .filter(|e| needle.is_empty() || e.name.to_lowercase().contains(&needle))
+            .cloned()
+            .collect();
         if self.entries.is_empty() {
             self.selected = 0;
             self.scroll = 0;
         } else if self.selected >= self.entries.len() {
             self.selected = self.entries.len() - 1;
         }
-        Ok(())
+    }
+
+    fn set_filter(&mut self, filter: String) {
+        self.filter = filter;
+        self.scroll = 0;
+        self.apply_filter();
+    }
+
+    /// Switches to the next sort mode, keeping the selected entry selected.
+    fn cycle_sort(&mut self) {
+        let selected = self.entries.get(self.selected).map(|e| e.name.clone());
+        self.sort = self.sort.next();
+        self.sort.apply(&mut self.all_entries);
+        self.sort.apply(&mut self.entries);
+        if let Some(name) = selected {
+            self.selected = self
+                .entries
+                .iter()
+                .position(|e| e.name == name)
+                .unwrap_or(0);
+        }
+    }
+
+    /// Scrolls without moving the viewport past the list, keeping the selection on screen
+    /// so the next `ensure_visible` does not snap it back.
+    fn scroll_by(&mut self, delta: isize, visible: usize) {
+        if visible == 0 || self.entries.is_empty() {
+            return;
+        }
+        let max = self.entries.len().saturating_sub(visible);
+        self.scroll = self.scroll.saturating_add_signed(delta).min(max);
+        let last = (self.scroll + visible - 1).min(self.entries.len() - 1);
+        self.selected = self.selected.clamp(self.scroll, last);
     }
 
     fn ensure_visible(&mut self, visible: usize) {
@@ -3782,6 +7944,55 @@ impl PanelKind {
     }
 }
 
+impl ConnectionListMode {
+    fn next(self) -> Self {
+        match self {
+            ConnectionListMode::Groups => ConnectionListMode::AuthMethod,
+            ConnectionListMode::AuthMethod => ConnectionListMode::Groups,
+        }
+    }
+
+    fn label_key(self) -> &'static str {
+        match self {
+            ConnectionListMode::Groups => "list-by-group",
+            ConnectionListMode::AuthMethod => "list-by-auth",
+        }
+    }
+}
+
+impl SortMode {
+    fn next(self) -> Self {
+        match self {
+            SortMode::DirsFirst => SortMode::NameAsc,
+            SortMode::NameAsc => SortMode::NameDesc,
+            SortMode::NameDesc => SortMode::SizeDesc,
+            SortMode::SizeDesc => SortMode::DirsFirst,
+        }
+    }
+
+    fn label_key(self) -> &'static str {
+        match self {
+            SortMode::DirsFirst => "sort-dirs-first",
+            SortMode::NameAsc => "sort-name-asc",
+            SortMode::NameDesc => "sort-name-desc",
+            SortMode::SizeDesc => "sort-size-desc",
+        }
+    }
+
+    fn apply(self, entries: &mut [FileEntry]) {
+        match self {
+            SortMode::DirsFirst => {
+                entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)))
+            }
+            SortMode::NameAsc => entries.sort_by(|a, b| a.name.cmp(&b.name)),
+            SortMode::NameDesc => entries.sort_by(|a, b| b.name.cmp(&a.name)),
+            SortMode::SizeDesc => {
+                entries.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)))
+            }
+        }
+    }
+}
+
 async fn list_local(path: &str) -> Result<Vec<FileEntry>> {
     let mut out = Vec::new();
     let mut entries = tokio::fs::read_dir(path).await?;
@@ -3791,25 +8002,141 @@ async fn list_local(path: &str) -> Result<Vec<FileEntry>> {
             name: entry.file_name().to_string_lossy().to_string(),
             is_dir: meta.is_dir(),
             size: meta.len(),
+            modified: meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
         });
     }
     out.sort_by(|a, b| a.name.cmp(&b.name));
     Ok(out)
 }
 
-async fn list_remote(sftp: &catsolle_ssh::SftpClient, path: &str) -> Result<Vec<FileEntry>> {
+async fn list_remote(sftp: &dyn RemoteFs, path: &str) -> Result<Vec<FileEntry>> {
     let mut out = Vec::new();
     for entry in sftp.read_dir(path).await? {
         out.push(FileEntry {
             name: entry.name,
             is_dir: entry.is_dir,
             size: entry.size,
+            modified: entry.modified,
         });
     }
     out.sort_by(|a, b| a.name.cmp(&b.name));
     Ok(out)
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EntryDiff {
+    Same,
+    OnlyHere,
+    Differs,
+}
+
+/// How `entry` relates to the same-named entry of the other panel. Directories are not
+/// descended into; files differ on size or on mtimes more than a second apart.
+fn entry_diff(entry: &FileEntry, other: Option<&FileEntry>) -> EntryDiff {
+    let Some(other) = other else {
+        return EntryDiff::OnlyHere;
+    };
+    if entry.is_dir != other.is_dir {
+        return EntryDiff::Differs;
+    }
+    if entry.is_dir {
+        return EntryDiff::Same;
+    }
+    let mtime_differs = match (entry.modified, other.modified) {
+        (Some(a), Some(b)) => a.abs_diff(b) > 1,
+        _ => false,
+    };
+    if entry.size != other.size || mtime_differs {
+        EntryDiff::Differs
+    } else {
+        EntryDiff::Same
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct CompareSummary {
+    only_local: usize,
+    only_remote: usize,
+    differ: usize,
+}
+
+fn compare_summary(local: &[FileEntry], remote: &[FileEntry]) -> CompareSummary {
+    let remote_by_name: HashMap<&str, &FileEntry> =
+        remote.iter().map(|e| (e.name.as_str(), e)).collect();
+    let mut summary = CompareSummary::default();
+    for entry in local {
+        match entry_diff(entry, remote_by_name.get(entry.name.as_str()).copied()) {
+            EntryDiff::OnlyHere => summary.only_local += 1,
+            EntryDiff::Differs => summary.differ += 1,
+            EntryDiff::Same => {}
+        }
+    }
+    let local_names: HashSet<&str> = local.iter().map(|e| e.name.as_str()).collect();
+    summary.only_remote = remote
+        .iter()
+        .filter(|e| !local_names.contains(e.name.as_str()))
+        .count();
+    summary
+}
+
+/// Messages worth keeping across sessions: system notes (the hint included) are rebuilt
+/// on entry, and only the last `max` messages are kept, as in the chat history sent to
+/// the model. A message still streaming is kept as far as it got.
+fn conversation_to_save(messages: &[AssistantMessage], max: usize) -> Vec<AssistantMessage> {
+    let kept: Vec<_> = messages
+        .iter()
+        .filter(|m| m.role != AssistantRole::System)
+        .cloned()
+        .collect();
+    let start = if max > 0 {
+        kept.len().saturating_sub(max)
+    } else {
+        0
+    };
+    kept[start..].to_vec()
+}
+
+fn load_conversations(path: &Path) -> HashMap<Uuid, Vec<AssistantMessage>> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+    serde_json::from_str(&content).unwrap_or_else(|err| {
+        warn!(error = %err, "ignoring unreadable conversations file");
+        HashMap::new()
+    })
+}
+
+fn store_conversations(
+    path: &Path,
+    conversations: &HashMap<Uuid, Vec<AssistantMessage>>,
+) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string(conversations)?)?;
+    Ok(())
+}
+
+/// Entries of `src` worth handing to an `IfNewer` sync into `dst`: anything missing or
+/// different there, plus directories present on both sides so their contents get checked.
+/// Entries whose kind differs between the sides are left for the user to sort out.
+fn sync_candidates<'a>(src: &'a [FileEntry], dst: &[FileEntry]) -> Vec<&'a FileEntry> {
+    let dst_by_name: HashMap<&str, &FileEntry> = dst.iter().map(|e| (e.name.as_str(), e)).collect();
+    src.iter()
+        .filter(|entry| {
+            let other = dst_by_name.get(entry.name.as_str()).copied();
+            if other.is_some_and(|other| other.is_dir != entry.is_dir) {
+                return false;
+            }
+            entry.is_dir || entry_diff(entry, other) != EntryDiff::Same
+        })
+        .collect()
+}
+
 fn join_path(base: &str, name: &str, remote: bool) -> String {
     if remote {
         if base.ends_with('/') {
@@ -3893,6 +8220,8 @@ struct OpenAiRequest {
     temperature: f32,
     max_tokens: u32,
     stream: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<serde_json::Value>,
 }
 
 #[derive(Deserialize)]
@@ -3907,7 +8236,32 @@ struct OpenAiChoice {
 
 #[derive(Deserialize)]
 struct OpenAiMessage {
-    content: String,
+    content: Option<String>,
+    tool_calls: Option<Vec<OpenAiToolCall>>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiToolCall {
+    function: OpenAiFunctionCall,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+struct OpenAiFunctionCall {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    arguments: String,
+}
+
+impl OpenAiFunctionCall {
+    /// Arguments arrive as a JSON string; unparsable ones are passed on as null so the
+    /// tool reports what is missing.
+    fn into_tool_call(self) -> ToolCall {
+        ToolCall {
+            name: tool_name_from_native(&self.name),
+            args: serde_json::from_str(&self.arguments).unwrap_or_default(),
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -3923,6 +8277,21 @@ struct OpenAiStreamChoice {
 #[derive(Deserialize)]
 struct OpenAiStreamDelta {
     content: Option<String>,
+    tool_calls: Option<Vec<OpenAiStreamToolCall>>,
+}
+
+/// Fragment of a streamed tool call; `arguments` arrive in pieces under the same `index`.
+#[derive(Deserialize)]
+struct OpenAiStreamToolCall {
+    #[serde(default)]
+    index: usize,
+    function: Option<OpenAiStreamFunction>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiStreamFunction {
+    name: Option<String>,
+    arguments: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -3999,18 +8368,66 @@ struct AnthropicError {
     message: String,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiRequest {
+    contents: Vec<GeminiContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<GeminiContent>,
+    generation_config: GeminiGenerationConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct GeminiContent {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
+    #[serde(default)]
+    parts: Vec<GeminiPart>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct GeminiPart {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiGenerationConfig {
+    temperature: f32,
+    max_output_tokens: u32,
+}
+
+#[derive(Deserialize)]
+struct GeminiResponse {
+    #[serde(default)]
+    candidates: Vec<GeminiCandidate>,
+}
+
+#[derive(Deserialize)]
+struct GeminiCandidate {
+    content: Option<GeminiContent>,
+}
+
 async fn request_ai_stream(
     client: reqwest::Client,
     cfg: &AiConfig,
     messages: Vec<ChatMessage>,
     tx: mpsc::Sender<AssistantEvent>,
-) -> Result<String> {
+) -> Result<AiReply> {
     let provider = cfg.provider.trim().to_lowercase();
     match provider.as_str() {
-        "ollama" => request_ollama_stream(client, cfg, messages, tx).await,
+        "ollama" => request_ollama_stream(client, cfg, messages, tx)
+            .await
+            .map(AiReply::from),
         "openai" | "openai-compatible" => request_openai_stream(client, cfg, messages, tx).await,
         "openrouter" => request_openai_stream(client, cfg, messages, tx).await,
-        "anthropic" => request_anthropic_stream(client, cfg, messages, tx).await,
+        "anthropic" => request_anthropic_stream(client, cfg, messages, tx)
+            .await
+            .map(AiReply::from),
+        "gemini" => request_gemini_stream(client, cfg, messages, tx)
+            .await
+            .map(AiReply::from),
         _ => Err(anyhow::anyhow!("unknown ai provider: {}", cfg.provider)),
     }
 }
@@ -4019,13 +8436,20 @@ async fn request_ai(
     client: reqwest::Client,
     cfg: &AiConfig,
     messages: Vec<ChatMessage>,
-) -> Result<String> {
+) -> Result<AiReply> {
     let provider = cfg.provider.trim().to_lowercase();
     match provider.as_str() {
-        "ollama" => request_ollama(client, cfg, messages).await,
+        "ollama" => request_ollama(client, cfg, messages)
+            .await
+            .map(AiReply::from),
         "openai" | "openai-compatible" => request_openai(client, cfg, messages).await,
         "openrouter" => request_openai(client, cfg, messages).await,
-        "anthropic" => request_anthropic(client, cfg, messages).await,
+        "anthropic" => request_anthropic(client, cfg, messages)
+            .await
+            .map(AiReply::from),
+        "gemini" => request_gemini(client, cfg, messages)
+            .await
+            .map(AiReply::from),
         _ => Err(anyhow::anyhow!("unknown ai provider: {}", cfg.provider)),
     }
 }
@@ -4049,9 +8473,9 @@ async fn request_ollama(
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
-        return Err(anyhow::anyhow!("ollama error {status}: {text}"));
+        return Err(AiHttpError::new("ollama", status, text).into());
     }
-    let data: OllamaResponse = resp.json().await?;
+    let data: OllamaResponse = read_json(resp).await?;
     let content = data
         .message
         .map(|m| m.content)
@@ -4079,8 +8503,9 @@ async fn request_ollama_stream(
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
-        return Err(anyhow::anyhow!("ollama error {status}: {text}"));
+        return Err(AiHttpError::new("ollama", status, text).into());
     }
+    let resp = ensure_json_response(resp, true).await?;
     let mut out = String::new();
     let stream = resp.bytes_stream().map_err(io::Error::other);
     let reader = tokio_util::io::StreamReader::new(stream);
@@ -4090,7 +8515,7 @@ async fn request_ollama_stream(
         if line.is_empty() {
             continue;
         }
-        let chunk: OllamaStreamChunk = serde_json::from_str(line)?;
+        let chunk: OllamaStreamChunk = parse_json(line)?;
         if let Some(message) = chunk.message {
             if !message.content.is_empty() {
                 out.push_str(&message.content);
@@ -4108,7 +8533,7 @@ async fn request_openai(
     client: reqwest::Client,
     cfg: &AiConfig,
     messages: Vec<ChatMessage>,
-) -> Result<String> {
+) -> Result<AiReply> {
     let url = format!("{}/v1/chat/completions", cfg.endpoint.trim_end_matches('/'));
     let body = OpenAiRequest {
         model: cfg.model.clone(),
@@ -4116,6 +8541,7 @@ async fn request_openai(
         temperature: cfg.temperature,
         max_tokens: cfg.max_tokens,
         stream: false,
+        tools: openai_tools(cfg),
     };
     let mut req = client.post(url).json(&body);
     if let Some(key) = cfg.api_key.as_ref().filter(|v| !v.trim().is_empty()) {
@@ -4125,15 +8551,24 @@ async fn request_openai(
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
-        return Err(anyhow::anyhow!("ai error {status}: {text}"));
+        return Err(AiHttpError::new("ai", status, text).into());
     }
-    let data: OpenAiResponse = resp.json().await?;
-    let content = data
+    let data: OpenAiResponse = read_json(resp).await?;
+    let message = data
         .choices
-        .first()
-        .map(|c| c.message.content.clone())
+        .into_iter()
+        .next()
+        .map(|c| c.message)
         .ok_or_else(|| anyhow::anyhow!("ai empty response"))?;
-    Ok(content)
+    Ok(AiReply {
+        content: message.content.unwrap_or_default(),
+        tool_calls: message
+            .tool_calls
+            .unwrap_or_default()
+            .into_iter()
+            .map(|call| call.function.into_tool_call())
+            .collect(),
+    })
 }
 
 async fn request_openai_stream(
@@ -4141,7 +8576,7 @@ async fn request_openai_stream(
     cfg: &AiConfig,
     messages: Vec<ChatMessage>,
     tx: mpsc::Sender<AssistantEvent>,
-) -> Result<String> {
+) -> Result<AiReply> {
     let url = format!("{}/v1/chat/completions", cfg.endpoint.trim_end_matches('/'));
     let body = OpenAiRequest {
         model: cfg.model.clone(),
@@ -4149,6 +8584,7 @@ async fn request_openai_stream(
         temperature: cfg.temperature,
         max_tokens: cfg.max_tokens,
         stream: true,
+        tools: openai_tools(cfg),
     };
     let mut req = client.post(url).json(&body);
     if let Some(key) = cfg.api_key.as_ref().filter(|v| !v.trim().is_empty()) {
@@ -4158,9 +8594,11 @@ async fn request_openai_stream(
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
-        return Err(anyhow::anyhow!("ai error {status}: {text}"));
+        return Err(AiHttpError::new("ai", status, text).into());
     }
+    let resp = ensure_json_response(resp, true).await?;
     let mut out = String::new();
+    let mut calls = Vec::new();
     let stream = resp.bytes_stream().map_err(io::Error::other);
     let reader = tokio_util::io::StreamReader::new(stream);
     let mut lines = tokio::io::BufReader::new(reader).lines();
@@ -4173,15 +8611,52 @@ async fn request_openai_stream(
         if data == "[DONE]" {
             break;
         }
-        let chunk: OpenAiStreamResponse = serde_json::from_str(data)?;
-        if let Some(delta) = chunk.choices.first().and_then(|c| c.delta.content.clone()) {
+        let chunk: OpenAiStreamResponse = parse_json(data)?;
+        let Some(choice) = chunk.choices.into_iter().next() else {
+            continue;
+        };
+        merge_tool_call_deltas(&mut calls, choice.delta.tool_calls.unwrap_or_default());
+        if let Some(delta) = choice.delta.content {
             if !delta.is_empty() {
                 out.push_str(&delta);
                 let _ = tx.send(AssistantEvent::Delta(delta)).await;
             }
         }
     }
-    Ok(out)
+    Ok(AiReply {
+        content: out,
+        tool_calls: calls
+            .into_iter()
+            .map(OpenAiFunctionCall::into_tool_call)
+            .collect(),
+    })
+}
+
+/// Appends streamed tool call fragments to the calls collected so far.
+fn merge_tool_call_deltas(calls: &mut Vec<OpenAiFunctionCall>, deltas: Vec<OpenAiStreamToolCall>) {
+    for delta in deltas {
+        if calls.len() <= delta.index {
+            calls.resize_with(delta.index + 1, OpenAiFunctionCall::default);
+        }
+        let Some(function) = delta.function else {
+            continue;
+        };
+        let call = &mut calls[delta.index];
+        if let Some(name) = function.name {
+            call.name.push_str(&name);
+        }
+        if let Some(arguments) = function.arguments {
+            call.arguments.push_str(&arguments);
+        }
+    }
+}
+
+fn openai_tools(cfg: &AiConfig) -> Vec<serde_json::Value> {
+    if cfg.native_tools_active() {
+        native_tool_specs(cfg)
+    } else {
+        Vec::new()
+    }
 }
 
 async fn request_anthropic(
@@ -4208,9 +8683,9 @@ async fn request_anthropic(
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
-        return Err(anyhow::anyhow!("ai error {status}: {text}"));
+        return Err(AiHttpError::new("ai", status, text).into());
     }
-    let data: AnthropicResponse = resp.json().await?;
+    let data: AnthropicResponse = read_json(resp).await?;
     let content = data
         .content
         .first()
@@ -4244,8 +8719,9 @@ async fn request_anthropic_stream(
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
-        return Err(anyhow::anyhow!("ai error {status}: {text}"));
+        return Err(AiHttpError::new("ai", status, text).into());
     }
+    let resp = ensure_json_response(resp, true).await?;
     let mut out = String::new();
     let stream = resp.bytes_stream().map_err(io::Error::other);
     let reader = tokio_util::io::StreamReader::new(stream);
@@ -4259,7 +8735,7 @@ async fn request_anthropic_stream(
         if data == "[DONE]" {
             break;
         }
-        let chunk: AnthropicStreamChunk = serde_json::from_str(data)?;
+        let chunk: AnthropicStreamChunk = parse_json(data)?;
         if let Some(err) = chunk.error {
             return Err(anyhow::anyhow!("anthropic error: {}", err.message));
         }
@@ -4278,54 +8754,446 @@ async fn request_anthropic_stream(
     Ok(out)
 }
 
-fn split_system_prompt(messages: Vec<ChatMessage>) -> (Option<String>, Vec<ChatMessage>) {
-    let mut system = String::new();
-    let mut out = Vec::new();
-    for message in messages {
-        if message.role == "system" {
-            if !system.is_empty() {
-                system.push('\n');
-            }
-            system.push_str(&message.content);
-        } else {
-            out.push(message);
+/// Fails with the content type and the start of the body when an endpoint answers with
+/// something other than JSON, which usually means it points at a web page.
+/// Non-success reply from an AI provider, kept typed so retries can look at the status.
+#[derive(Debug, thiserror::Error)]
+#[error("{provider} error {status}: {body}")]
+struct AiHttpError {
+    provider: &'static str,
+    status: reqwest::StatusCode,
+    body: String,
+}
+
+impl AiHttpError {
+    fn new(provider: &'static str, status: reqwest::StatusCode, body: String) -> Self {
+        Self {
+            provider,
+            status,
+            body,
         }
     }
-    let system = if system.trim().is_empty() {
-        None
-    } else {
-        Some(system)
-    };
-    (system, out)
 }
 
-fn extract_tool_calls(content: &str) -> (String, Vec<ToolCall>) {
-    let mut calls = Vec::new();
-    let mut cleaned = Vec::new();
-    for line in content.lines() {
-        let trimmed = line.trim();
-        if let Some(rest) = trimmed.strip_prefix("@tool") {
-            let json = rest.trim();
-            if json.starts_with('{') {
-                if let Ok(call) = serde_json::from_str::<ToolCall>(json) {
-                    calls.push(call);
-                    continue;
-                }
+/// Rate limits, server errors, timeouts and dropped connections are worth another try;
+/// anything else (bad key, unknown model, malformed reply) will fail the same way again.
+fn is_transient_ai_error(err: &anyhow::Error) -> bool {
+    if let Some(http) = err.downcast_ref::<AiHttpError>() {
+        return http.status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            || http.status.is_server_error();
+    }
+    let transient_reqwest =
+        |err: &reqwest::Error| err.is_timeout() || err.is_connect() || err.is_request();
+    err.chain().any(|cause| {
+        if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            return transient_reqwest(err);
+        }
+        let Some(err) = cause.downcast_ref::<io::Error>() else {
+            return false;
+        };
+        // Streamed bodies surface reqwest errors wrapped in io::Error.
+        if let Some(inner) = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<reqwest::Error>())
+        {
+            return transient_reqwest(inner);
+        }
+        matches!(
+            err.kind(),
+            io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::BrokenPipe
+                | io::ErrorKind::TimedOut
+                | io::ErrorKind::UnexpectedEof
+        )
+    })
+}
+
+/// Exponential backoff before retry number `attempt + 1`.
+fn ai_retry_delay(attempt: u32) -> Duration {
+    let ms = AI_RETRY_BASE_MS.saturating_mul(1 << attempt.min(16));
+    Duration::from_millis(ms.min(AI_RETRY_MAX_MS))
+}
+
+/// Runs `request` until it succeeds, fails for good or `max_retries` extra attempts are
+/// spent. The flag returned with each result says whether that attempt may be repeated.
+async fn retry_ai<T, F, Fut>(max_retries: u32, mut request: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = (Result<T>, bool)>,
+{
+    let mut attempt = 0;
+    loop {
+        match request().await {
+            (Err(err), true) if attempt < max_retries && is_transient_ai_error(&err) => {
+                let delay = ai_retry_delay(attempt);
+                warn!(error = %err, attempt = attempt + 1, ?delay, "ai request failed, retrying");
+                tokio::time::sleep(delay).await;
+                attempt += 1;
             }
+            (result, _) => return result,
         }
-        cleaned.push(line);
     }
-    let cleaned = cleaned.join("\n").trim().to_string();
-    (cleaned, calls)
 }
 
-fn parse_assistant_command(input: &str) -> Result<Option<AssistantCommand>, AssistantCommandError> {
-    let trimmed = input.trim();
-    if !trimmed.starts_with('/') {
-        return Ok(None);
-    }
-    let mut parts = trimmed.trim_start_matches('/').split_whitespace();
-    let name = parts.next().unwrap_or("");
+/// `request_ai` with retries on transient errors.
+async fn request_ai_retrying(
+    client: reqwest::Client,
+    cfg: &AiConfig,
+    messages: Vec<ChatMessage>,
+) -> Result<AiReply> {
+    retry_ai(cfg.max_retries, || {
+        let client = client.clone();
+        let messages = messages.clone();
+        async move { (request_ai(client, cfg, messages).await, true) }
+    })
+    .await
+}
+
+/// `request_ai_stream` with retries on transient errors. Deltas go through a per-attempt
+/// channel, and an attempt that already showed output is not repeated, since the retry
+/// would print it twice.
+async fn request_ai_stream_retrying(
+    client: reqwest::Client,
+    cfg: &AiConfig,
+    messages: Vec<ChatMessage>,
+    tx: mpsc::Sender<AssistantEvent>,
+) -> Result<AiReply> {
+    retry_ai(cfg.max_retries, || {
+        let client = client.clone();
+        let messages = messages.clone();
+        let tx = tx.clone();
+        async move {
+            let (attempt_tx, mut attempt_rx) = mpsc::channel(32);
+            let forward = async {
+                let mut emitted = false;
+                while let Some(event) = attempt_rx.recv().await {
+                    emitted = true;
+                    let _ = tx.send(event).await;
+                }
+                emitted
+            };
+            let (result, emitted) = tokio::join!(
+                request_ai_stream(client, cfg, messages, attempt_tx),
+                forward
+            );
+            (result, !emitted)
+        }
+    })
+    .await
+}
+
+async fn ensure_json_response(resp: reqwest::Response, stream: bool) -> Result<reqwest::Response> {
+    let content_type = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    if is_json_content_type(&content_type, stream) {
+        return Ok(resp);
+    }
+    let text = resp.text().await.unwrap_or_default();
+    Err(anyhow::anyhow!(
+        "expected JSON, got {content_type}: {}",
+        body_snippet(&text)
+    ))
+}
+
+async fn read_json<T: serde::de::DeserializeOwned>(resp: reqwest::Response) -> Result<T> {
+    let resp = ensure_json_response(resp, false).await?;
+    let text = resp.text().await?;
+    parse_json(&text)
+}
+
+fn parse_json<T: serde::de::DeserializeOwned>(text: &str) -> Result<T> {
+    serde_json::from_str(text)
+        .map_err(|err| anyhow::anyhow!("invalid JSON response ({err}): {}", body_snippet(text)))
+}
+
+fn is_json_content_type(content_type: &str, stream: bool) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    mime.is_empty() || mime.contains("json") || (stream && mime == "text/event-stream")
+}
+
+fn body_snippet(text: &str) -> String {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.is_empty() {
+        return "<empty body>".to_string();
+    }
+    if collapsed.chars().count() > AI_ERROR_SNIPPET_CHARS {
+        let mut snippet: String = collapsed.chars().take(AI_ERROR_SNIPPET_CHARS).collect();
+        snippet.push_str("...");
+        snippet
+    } else {
+        collapsed
+    }
+}
+
+async fn request_gemini(
+    client: reqwest::Client,
+    cfg: &AiConfig,
+    messages: Vec<ChatMessage>,
+) -> Result<String> {
+    let url = format!(
+        "{}/v1beta/models/{}:generateContent",
+        cfg.endpoint.trim_end_matches('/'),
+        cfg.model.trim()
+    );
+    let resp = gemini_request(&client, url, cfg, messages).send().await?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        return Err(AiHttpError::new("gemini", status, text).into());
+    }
+    let data: GeminiResponse = read_json(resp).await?;
+    gemini_text(&data).ok_or_else(|| anyhow::anyhow!("gemini empty response"))
+}
+
+async fn request_gemini_stream(
+    client: reqwest::Client,
+    cfg: &AiConfig,
+    messages: Vec<ChatMessage>,
+    tx: mpsc::Sender<AssistantEvent>,
+) -> Result<String> {
+    let url = format!(
+        "{}/v1beta/models/{}:streamGenerateContent?alt=sse",
+        cfg.endpoint.trim_end_matches('/'),
+        cfg.model.trim()
+    );
+    let resp = gemini_request(&client, url, cfg, messages).send().await?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        return Err(AiHttpError::new("gemini", status, text).into());
+    }
+    let resp = ensure_json_response(resp, true).await?;
+    let mut out = String::new();
+    let stream = resp.bytes_stream().map_err(io::Error::other);
+    let reader = tokio_util::io::StreamReader::new(stream);
+    let mut lines = tokio::io::BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        let line = line.trim();
+        if line.is_empty() || !line.starts_with("data:") {
+            continue;
+        }
+        let data = line.trim_start_matches("data:").trim();
+        let chunk: GeminiResponse = parse_json(data)?;
+        if let Some(text) = gemini_text(&chunk) {
+            if !text.is_empty() {
+                out.push_str(&text);
+                let _ = tx.send(AssistantEvent::Delta(text)).await;
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn gemini_request(
+    client: &reqwest::Client,
+    url: String,
+    cfg: &AiConfig,
+    messages: Vec<ChatMessage>,
+) -> reqwest::RequestBuilder {
+    let (system, contents) = gemini_contents(messages);
+    let body = GeminiRequest {
+        contents,
+        system_instruction: system.map(|text| GeminiContent {
+            role: None,
+            parts: vec![GeminiPart { text: Some(text) }],
+        }),
+        generation_config: GeminiGenerationConfig {
+            temperature: cfg.temperature,
+            max_output_tokens: cfg.max_tokens,
+        },
+    };
+    let mut req = client.post(url).json(&body);
+    if let Some(key) = cfg.api_key.as_ref().filter(|v| !v.trim().is_empty()) {
+        req = req.header("x-goog-api-key", key);
+    }
+    req
+}
+
+/// Maps chat roles onto Gemini's user/model turns, folding consecutive messages with the
+/// same role into one turn since the API expects them to alternate.
+fn gemini_contents(messages: Vec<ChatMessage>) -> (Option<String>, Vec<GeminiContent>) {
+    let (system, messages) = split_system_prompt(messages);
+    let mut contents: Vec<GeminiContent> = Vec::new();
+    for message in messages {
+        let role = if message.role == "assistant" {
+            "model"
+        } else {
+            "user"
+        };
+        let part = GeminiPart {
+            text: Some(message.content),
+        };
+        match contents.last_mut() {
+            Some(last) if last.role.as_deref() == Some(role) => last.parts.push(part),
+            _ => contents.push(GeminiContent {
+                role: Some(role.to_string()),
+                parts: vec![part],
+            }),
+        }
+    }
+    (system, contents)
+}
+
+fn gemini_text(response: &GeminiResponse) -> Option<String> {
+    let content = response.candidates.first()?.content.as_ref()?;
+    Some(
+        content
+            .parts
+            .iter()
+            .filter_map(|part| part.text.as_deref())
+            .collect(),
+    )
+}
+
+fn split_system_prompt(messages: Vec<ChatMessage>) -> (Option<String>, Vec<ChatMessage>) {
+    let mut system = String::new();
+    let mut out = Vec::new();
+    for message in messages {
+        if message.role == "system" {
+            if !system.is_empty() {
+                system.push('\n');
+            }
+            system.push_str(&message.content);
+        } else {
+            out.push(message);
+        }
+    }
+    let system = if system.trim().is_empty() {
+        None
+    } else {
+        Some(system)
+    };
+    (system, out)
+}
+
+/// Pulls tool calls out of a reply: `@tool {json}` where the JSON may continue over
+/// following lines until its braces balance, or a ```@tool fenced block. Anything that
+/// does not parse stays in the visible text.
+fn extract_tool_calls(content: &str) -> (String, Vec<ToolCall>) {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut calls = Vec::new();
+    let mut cleaned = Vec::new();
+    let mut idx = 0;
+    while idx < lines.len() {
+        let trimmed = lines[idx].trim();
+        if trimmed == "```@tool" {
+            let rest = &lines[idx + 1..];
+            if let Some(close) = rest.iter().position(|line| line.trim() == "```") {
+                let body = rest[..close].join("\n");
+                if let Ok(call) = serde_json::from_str::<ToolCall>(body.trim()) {
+                    calls.push(call);
+                    idx += close + 2;
+                    continue;
+                }
+            }
+        } else if let Some(rest) = trimmed.strip_prefix("@tool") {
+            if let Some((call, extra, trailing)) = parse_tool_block(rest, &lines[idx + 1..]) {
+                calls.push(call);
+                if !trailing.is_empty() {
+                    cleaned.push(trailing);
+                }
+                idx += extra + 1;
+                continue;
+            }
+        }
+        cleaned.push(lines[idx].to_string());
+        idx += 1;
+    }
+    let cleaned = cleaned.join("\n").trim().to_string();
+    (cleaned, calls)
+}
+
+/// Parses the JSON after `@tool`, pulling in following lines until the object closes.
+/// Returns the call, how many following lines it used and any text after the object.
+fn parse_tool_block(first: &str, following: &[&str]) -> Option<(ToolCall, usize, String)> {
+    let mut buf = first.trim().to_string();
+    if !buf.starts_with('{') {
+        return None;
+    }
+    let mut extra = 0;
+    loop {
+        if let Some(end) = json_object_end(&buf) {
+            let call = serde_json::from_str(&buf[..end]).ok()?;
+            return Some((call, extra, buf[end..].trim().to_string()));
+        }
+        buf.push('\n');
+        buf.push_str(following.get(extra)?);
+        extra += 1;
+    }
+}
+
+/// Byte offset just past the JSON object `text` starts with, once its braces balance.
+fn json_object_end(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (idx, ch) in text.char_indices() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(idx + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Pulls an `@ask <question>` line out of a reply. The question stays in the
+/// visible text, without the marker, and is returned so the agent loop can pause.
+fn extract_question(content: &str) -> (String, Option<String>) {
+    let mut question: Option<String> = None;
+    let mut cleaned = Vec::new();
+    for line in content.lines() {
+        if let Some(rest) = line.trim().strip_prefix("@ask") {
+            let text = rest.trim();
+            if !text.is_empty() {
+                match question.as_mut() {
+                    Some(existing) => {
+                        existing.push(' ');
+                        existing.push_str(text);
+                    }
+                    None => question = Some(text.to_string()),
+                }
+                cleaned.push(text.to_string());
+                continue;
+            }
+        }
+        cleaned.push(line.to_string());
+    }
+    (cleaned.join("\n").trim().to_string(), question)
+}
+
+fn parse_assistant_command(input: &str) -> Result<Option<AssistantCommand>, AssistantCommandError> {
+    let trimmed = input.trim();
+    if !trimmed.starts_with('/') {
+        return Ok(None);
+    }
+    let mut parts = trimmed.trim_start_matches('/').split_whitespace();
+    let name = parts.next().unwrap_or("");
     let arg = parts.next();
     match name {
         "ai" => {
@@ -4359,7 +9227,11 @@ fn parse_assistant_command(input: &str) -> Result<Option<AssistantCommand>, Assi
                 .map_err(|_| AssistantCommandError::InvalidValue)?;
             Ok(Some(AssistantCommand::Steps(steps)))
         }
-        "run" => Ok(Some(AssistantCommand::Run)),
+        "run" => match arg {
+            None => Ok(Some(AssistantCommand::Run)),
+            Some("all") => Ok(Some(AssistantCommand::RunAll)),
+            Some(_) => Err(AssistantCommandError::InvalidValue),
+        },
         "skip" => Ok(Some(AssistantCommand::Skip)),
         "clear" => Ok(Some(AssistantCommand::Clear)),
         "status" => Ok(Some(AssistantCommand::Status)),
@@ -4417,12 +9289,10 @@ impl ShellLog {
         let available = self.end_offset().saturating_sub(start) as usize;
         let limit = max_bytes.max(1).min(available);
         let mut bytes = Vec::with_capacity(limit);
-        let mut idx = self.start_offset;
-        for byte in &self.buf {
+        for (idx, byte) in (self.start_offset..).zip(self.buf.iter()) {
             if idx >= start && bytes.len() < limit {
                 bytes.push(*byte);
             }
-            idx += 1;
             if bytes.len() >= limit {
                 break;
             }
@@ -4438,6 +9308,82 @@ impl ShellLog {
     }
 }
 
+impl HyperlinkTracker {
+    fn feed(&mut self, parser: &mut Parser, data: &[u8]) {
+        for chunk in split_osc8_chunks(data, &mut self.pending) {
+            match chunk {
+                TerminalChunk::Text(bytes) => {
+                    let start = parser.screen().cursor_position();
+                    parser.process(&bytes);
+                    if let Some(uri) = self.active.clone() {
+                        let screen = parser.screen();
+                        let end = screen.cursor_position();
+                        self.record(screen, start, end, &uri);
+                    }
+                }
+                TerminalChunk::Link(uri) => {
+                    self.active = uri;
+                }
+            }
+        }
+        self.prune(parser.screen());
+    }
+
+    fn record(&mut self, screen: &vt100::Screen, start: (u16, u16), end: (u16, u16), uri: &str) {
+        if end.0 < start.0 || (end.0 == start.0 && end.1 <= start.1) {
+            return;
+        }
+        let (_, cols) = screen.size();
+        for row in start.0..=end.0 {
+            let start_col = if row == start.0 { start.1 } else { 0 };
+            let end_col = if row == end.0 { end.1 } else { cols };
+            self.push(screen, row, start_col, end_col, uri);
+        }
+    }
+
+    fn push(&mut self, screen: &vt100::Screen, row: u16, start_col: u16, end_col: u16, uri: &str) {
+        if start_col >= end_col {
+            return;
+        }
+        if let Some(last) = self.links.last_mut() {
+            if last.row == row && last.uri == uri && last.end_col == start_col {
+                last.end_col = end_col;
+                last.text = screen.contents_between(row, last.start_col, row, end_col);
+                return;
+            }
+        }
+        let text = screen.contents_between(row, start_col, row, end_col);
+        if text.trim().is_empty() {
+            return;
+        }
+        self.links.push(TerminalLink {
+            row,
+            start_col,
+            end_col,
+            text,
+            uri: uri.to_string(),
+        });
+        if self.links.len() > TERMINAL_LINK_MAX {
+            let excess = self.links.len() - TERMINAL_LINK_MAX;
+            self.links.drain(..excess);
+        }
+    }
+
+    /// Drops links whose cells were scrolled away or overwritten since they were recorded.
+    fn prune(&mut self, screen: &vt100::Screen) {
+        self.links.retain(|link| {
+            screen.contents_between(link.row, link.start_col, link.row, link.end_col) == link.text
+        });
+    }
+
+    fn link_at(&self, row: u16, col: u16) -> Option<&TerminalLink> {
+        self.links
+            .iter()
+            .find(|link| link.row == row && col >= link.start_col && col < link.end_col)
+    }
+}
+
+#[derive(Clone)]
 struct ToolContext {
     sessions: Arc<SessionManager>,
     queue: TransferQueue,
@@ -4445,6 +9391,8 @@ struct ToolContext {
     local_base: String,
     remote_base: String,
     session_id: Option<Uuid>,
+    /// The active session, looked up when the tool starts.
+    remote: Option<Arc<dyn ToolRemote>>,
     connections: Vec<Connection>,
     left_panel: PanelState,
     right_panel: PanelState,
@@ -4452,9 +9400,80 @@ struct ToolContext {
     shell_tool_tx: mpsc::Sender<ShellToolRequest>,
 }
 
+impl ToolContext {
+    fn remote(&self) -> Result<&dyn ToolRemote> {
+        if self.session_id.is_none() {
+            anyhow::bail!("no active session");
+        }
+        self.remote
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("session not found"))
+    }
+}
+
+/// Remote side of the assistant tools: commands run over the session, files go over SFTP.
+/// Tools only see these traits, so tests can run them against a fake server.
+#[async_trait]
+trait ToolRemote: Send + Sync {
+    async fn exec(&self, command: &str) -> Result<(i32, Vec<u8>)>;
+    async fn open_sftp(&self) -> Result<Box<dyn ToolSftp>>;
+}
+
+/// SFTP operations the tools need beyond what transfers use.
+#[async_trait]
+trait ToolSftp: RemoteFs {
+    async fn canonicalize(&self, path: &str) -> Result<String>;
+    async fn remove_file(&self, path: &str) -> Result<()>;
+    async fn remove_dir(&self, path: &str) -> Result<()>;
+    async fn rename(&self, from: &str, to: &str) -> Result<()>;
+    async fn set_permissions(&self, path: &str, mode: u32) -> Result<()>;
+}
+
+#[async_trait]
+impl ToolRemote for catsolle_ssh::SshSession {
+    async fn exec(&self, command: &str) -> Result<(i32, Vec<u8>)> {
+        Ok(catsolle_ssh::SshSession::exec(self, command).await?)
+    }
+
+    async fn open_sftp(&self) -> Result<Box<dyn ToolSftp>> {
+        Ok(Box::new(catsolle_ssh::SshSession::open_sftp(self).await?))
+    }
+}
+
+#[async_trait]
+impl ToolSftp for catsolle_ssh::SftpClient {
+    async fn canonicalize(&self, path: &str) -> Result<String> {
+        Ok(catsolle_ssh::SftpClient::canonicalize(self, path).await?)
+    }
+
+    async fn remove_file(&self, path: &str) -> Result<()> {
+        Ok(catsolle_ssh::SftpClient::remove_file(self, path).await?)
+    }
+
+    async fn remove_dir(&self, path: &str) -> Result<()> {
+        Ok(catsolle_ssh::SftpClient::remove_dir(self, path).await?)
+    }
+
+    async fn rename(&self, from: &str, to: &str) -> Result<()> {
+        Ok(catsolle_ssh::SftpClient::rename(self, from, to).await?)
+    }
+
+    async fn set_permissions(&self, path: &str, mode: u32) -> Result<()> {
+        Ok(catsolle_ssh::SftpClient::set_permissions(self, path, mode).await?)
+    }
+}
+
+const PROMPT_HISTORY_MAX: usize = 100;
 const TOOL_OUTPUT_LIMIT: usize = 8000;
 const TOOL_DEFAULT_TIMEOUT_MS: u64 = 20000;
 const TOOL_DEFAULT_SEARCH_LIMIT: usize = 50;
+const TOOL_DEFAULT_SEARCH_DEPTH: usize = 8;
+const TOOL_REMOTE_SEARCH_CONCURRENCY: usize = 8;
+const TOOL_GREP_LINE_MAX_CHARS: usize = 300;
+const TOOL_SEARCH_REGEX_SIZE_LIMIT: usize = 1 << 20;
+const TOOL_SEARCH_CONTEXT_MAX: usize = 10;
+const TOOL_GREP_FLAGS: &str = "iwxFEl";
+const GREP_MISSING_STATUS: i32 = 127;
 const TOOL_DEFAULT_MAX_BYTES: usize = 1_000_000;
 const TOOL_DISPLAY_MAX_LINES: usize = 20;
 const TOOL_DISPLAY_MAX_CHARS: usize = 2000;
@@ -4469,10 +9488,38 @@ const SHELL_SCREEN_LINES: usize = 20;
 const SHELL_MARKER_PREFIX: &str = "CATSOLLE_DONE";
 const CONNECT_ANIMATION_INTERVAL_MS: u64 = 120;
 const CONNECT_SPINNER_FRAMES: [&str; 4] = ["|", "/", "-", "\\"];
+const RECONNECT_MAX_ATTEMPTS: u32 = 5;
+const RECONNECT_MAX_DELAY_MS: u64 = 30_000;
 const SHELL_TOOL_QUEUE_MAX: usize = 8;
 const AI_PROGRESS_WIDTH: usize = 10;
+const TERMINAL_LINK_MAX: usize = 256;
+const BELL_FLASH_MS: u64 = 400;
+const BELL_NOTIFY_INTERVAL_MS: u64 = 3000;
+const RELATIVE_TIME_MAX_DAYS: i64 = 60;
+const TOOL_SUMMARY_MAX_CHARS: usize = 80;
+const FRAME_INTERVAL_MS: u64 = 16;
+const TERMINAL_SCROLLBACK_LINES: usize = 5000;
+const DOUBLE_CLICK_MS: u64 = 400;
+const RESIZE_DEBOUNCE_MS: u64 = 150;
+const MOUSE_SCROLL_LINES: isize = 3;
+const SHELL_COALESCE_MAX_BYTES: usize = 1024 * 1024;
+const TOOL_DIFF_CONTEXT: usize = 2;
+const TOOL_DIFF_MAX_LINES: usize = 40;
+const AI_ERROR_SNIPPET_CHARS: usize = 200;
+const AI_RETRY_BASE_MS: u64 = 500;
+const AI_RETRY_MAX_MS: u64 = 8000;
+const TOOL_BINARY_SNIFF_BYTES: usize = 8192;
+const TOOL_BINARY_HEAD_BYTES: usize = 32;
+const OSC8_PREFIX: &[u8] = b"\x1b]8;";
 
 async fn execute_tool_call(call: ToolCall, ctx: ToolContext) -> ToolResult {
+    if !ctx.config.ai.tool_allowed(&call.name) {
+        return ToolResult {
+            output: format!("tool {} is not in ai.allowed_tools", call.name),
+            call,
+            success: false,
+        };
+    }
     let result = match call.name.as_str() {
         "local.exec" => tool_local_exec(&call, &ctx).await,
         "remote.exec" => tool_remote_exec(&call, &ctx).await,
@@ -4481,16 +9528,24 @@ async fn execute_tool_call(call: ToolCall, ctx: ToolContext) -> ToolResult {
         "remote.list" => tool_remote_list(&call, &ctx).await,
         "local.read" => tool_local_read(&call, &ctx).await,
         "remote.read" => tool_remote_read(&call, &ctx).await,
+        "local.hash" => tool_local_hash(&call, &ctx).await,
+        "remote.hash" => tool_remote_hash(&call, &ctx).await,
+        "local.stat" => tool_local_stat(&call, &ctx).await,
+        "remote.stat" => tool_remote_stat(&call, &ctx).await,
         "local.write" => tool_local_write(&call, &ctx).await,
         "remote.write" => tool_remote_write(&call, &ctx).await,
         "local.search" => tool_local_search(&call, &ctx).await,
         "remote.search" => tool_remote_search(&call, &ctx).await,
+        "remote.grep" => tool_remote_grep(&call, &ctx).await,
         "local.mkdir" => tool_local_mkdir(&call, &ctx).await,
         "remote.mkdir" => tool_remote_mkdir(&call, &ctx).await,
         "local.remove" => tool_local_remove(&call, &ctx).await,
         "remote.remove" => tool_remote_remove(&call, &ctx).await,
         "local.rename" => tool_local_rename(&call, &ctx).await,
         "remote.rename" => tool_remote_rename(&call, &ctx).await,
+        "local.chmod" => tool_local_chmod(&call, &ctx).await,
+        "remote.chmod" => tool_remote_chmod(&call, &ctx).await,
+        "diff" => tool_diff(&call, &ctx).await,
         "transfer.copy" => tool_transfer_copy(&call, &ctx).await,
         "transfer.copy_selected" => tool_transfer_copy_selected(&call, &ctx).await,
         "connections.list" => tool_connections_list(&call, &ctx).await,
@@ -4563,6 +9618,69 @@ fn resolve_remote_path(value: Option<String>, base: &str) -> String {
     }
 }
 
+/// Parses `local|remote` default transfer directories; an empty side clears it and a
+/// remote directory must be absolute.
+fn parse_transfer_dirs(input: &str) -> Option<(Option<String>, Option<String>)> {
+    let (local, remote) = input.split_once('|').unwrap_or((input, ""));
+    let non_empty = |value: &str| {
+        let value = value.trim();
+        (!value.is_empty()).then(|| value.to_string())
+    };
+    let remote = non_empty(remote);
+    if remote.as_deref().is_some_and(|dir| !dir.starts_with('/')) {
+        return None;
+    }
+    let local = match non_empty(local) {
+        Some(dir) => {
+            let dir = expand_home(&dir);
+            if !dir.is_absolute() {
+                return None;
+            }
+            Some(dir.to_string_lossy().to_string())
+        }
+        None => None,
+    };
+    Some((local, remote))
+}
+
+/// The connection's local transfer directory with `~` resolved, for values saved before
+/// `parse_transfer_dirs` expanded it.
+fn local_transfer_dir(conn: &Connection) -> Option<String> {
+    conn.transfer_local_dir
+        .as_deref()
+        .map(|dir| expand_home(dir).to_string_lossy().to_string())
+}
+
+/// Directory `panel` moves to when mirroring a step into `child`, or up for `None`.
+/// `Ok(None)` when there is no parent to go to, `Err` with the path when it is missing.
+async fn mirror_target(
+    panel: &PanelState,
+    child: Option<&str>,
+    sftp: Option<&dyn RemoteFs>,
+) -> Result<Option<String>, String> {
+    let target = match child {
+        Some(name) => join_path(&panel.path, name, panel.kind == PanelKind::Remote),
+        None => match parent_path(&panel.path) {
+            Some(parent) => parent,
+            None => return Ok(None),
+        },
+    };
+    let exists = match (panel.kind, sftp) {
+        (PanelKind::Local, _) => tokio::fs::metadata(&target)
+            .await
+            .is_ok_and(|meta| meta.is_dir()),
+        (PanelKind::Remote, Some(sftp)) => {
+            sftp.metadata(&target).await.is_ok_and(|meta| meta.is_dir)
+        }
+        (PanelKind::Remote, None) => false,
+    };
+    if exists {
+        Ok(Some(target))
+    } else {
+        Err(target)
+    }
+}
+
 fn remote_parent(path: &str) -> Option<String> {
     let trimmed = path.trim_end_matches('/');
     if trimmed.is_empty() || trimmed == "/" {
@@ -4618,6 +9736,45 @@ fn detect_interactive_prompt_text(screen: &str) -> Option<InteractiveAction> {
     None
 }
 
+/// Whether requests get a step budget: agent mode or `continue_after_tool` feed tool output
+/// back to the model on their own.
+fn ai_loop_enabled(ai: &AiConfig) -> bool {
+    ai.agent_enabled || ai.continue_after_tool
+}
+
+/// Whether a finished tool call (with nothing else queued) triggers a follow-up request.
+/// Agent mode continues after failures too; `continue_after_tool` only after successes.
+fn should_follow_up(ai: &AiConfig, steps_remaining: u32, tool_succeeded: bool) -> bool {
+    steps_remaining > 0 && (ai.agent_enabled || (ai.continue_after_tool && tool_succeeded))
+}
+
+/// Tools that change files or run arbitrary commands; approval flags them.
+fn is_destructive_tool(name: &str) -> bool {
+    TOOLS
+        .iter()
+        .any(|(tool, mutating)| *mutating && *tool == name)
+}
+
+fn tool_call_summary(call: &ToolCall) -> String {
+    let args = if call.args.is_null() {
+        String::new()
+    } else {
+        call.args.to_string()
+    };
+    let mut summary = format!("{} {}", call.name, args);
+    if summary.chars().count() > TOOL_SUMMARY_MAX_CHARS {
+        summary = summary.chars().take(TOOL_SUMMARY_MAX_CHARS).collect();
+        summary.push_str("...");
+    }
+    summary.trim_end().to_string()
+}
+
+/// Wait before reconnect `attempt` (1-based): the configured backoff, doubled per attempt.
+fn reconnect_delay(base_ms: u64, attempt: u32) -> Duration {
+    let factor = 1u64 << attempt.saturating_sub(1).min(16);
+    Duration::from_millis(base_ms.saturating_mul(factor).min(RECONNECT_MAX_DELAY_MS))
+}
+
 fn connection_target(conn: &Connection) -> String {
     let base = format!("{}@{}:{}", conn.username, conn.host, conn.port);
     if conn.name.trim().is_empty() {
@@ -4689,6 +9846,18 @@ fn tool_display_text(content: &str) -> String {
             lines.push(limit_tool_display(content));
             return lines.join("\n");
         }
+        if let Some(binary) = value.get("binary") {
+            if let Some(path) = value.get("path").and_then(|v| v.as_str()) {
+                lines.push(path.to_string());
+            }
+            let kind = binary
+                .get("kind")
+                .and_then(|v| v.as_str())
+                .unwrap_or("binary");
+            let size = binary.get("size").and_then(|v| v.as_u64()).unwrap_or(0);
+            lines.push(format!("{kind}, {}", format_bytes(size)));
+            return lines.join("\n");
+        }
         if let Some(path) = value.get("path").and_then(|v| v.as_str()) {
             let mut line = path.to_string();
             if let Some(bytes) = value.get("bytes").and_then(|v| v.as_u64()) {
@@ -4698,6 +9867,9 @@ fn tool_display_text(content: &str) -> String {
                 line.push_str(&format!(", entries: {}", entries.len()));
             }
             lines.push(line);
+            if let Some(diff) = value.get("diff").and_then(|v| v.as_str()) {
+                lines.push(limit_tool_display(diff));
+            }
             return lines.join("\n");
         }
         if let Some(job_id) = value.get("job_id").and_then(|v| v.as_str()) {
@@ -4713,6 +9885,51 @@ fn tool_display_text(content: &str) -> String {
     lines.join("\n")
 }
 
+/// Short line diff between the previous file content and what a write tool leaves behind.
+/// Returns `None` for binary content.
+fn write_diff(old: &[u8], written: &[u8], append: bool) -> Option<String> {
+    let old = std::str::from_utf8(old).ok()?;
+    let written = std::str::from_utf8(written).ok()?;
+    let new = if append {
+        format!("{old}{written}")
+    } else {
+        written.to_string()
+    };
+    Some(line_diff(old, &new, TOOL_DIFF_CONTEXT, TOOL_DIFF_MAX_LINES))
+}
+
+fn line_diff(old: &str, new: &str, context: usize, max_lines: usize) -> String {
+    let diff = similar::TextDiff::from_lines(old, new);
+    if diff
+        .ops()
+        .iter()
+        .all(|op| op.tag() == similar::DiffTag::Equal)
+    {
+        return "(no changes)".to_string();
+    }
+    let mut out = Vec::new();
+    for (idx, group) in diff.grouped_ops(context).iter().enumerate() {
+        if idx > 0 {
+            out.push("...".to_string());
+        }
+        for change in group.iter().flat_map(|op| diff.iter_changes(op)) {
+            let tag = match change.tag() {
+                similar::ChangeTag::Equal => ' ',
+                similar::ChangeTag::Delete => '-',
+                similar::ChangeTag::Insert => '+',
+            };
+            let line = change.value().trim_end_matches(['\r', '\n']);
+            out.push(format!("{tag} {line}"));
+        }
+    }
+    if out.len() > max_lines {
+        let hidden = out.len() - max_lines;
+        out.truncate(max_lines);
+        out.push(format!("... ({hidden} more lines)"));
+    }
+    out.join("\n")
+}
+
 fn push_exec_summary(lines: &mut Vec<String>, value: &serde_json::Value) {
     if let Some(status) = value.get("status").and_then(|v| v.as_i64()) {
         lines.push(format!("status: {status}"));
@@ -4859,6 +10076,75 @@ fn filter_shell_display_chunk(data: &[u8], token: &str, tail: &mut Vec<u8>) -> V
     out
 }
 
+/// Splits terminal output on OSC 8 hyperlink sequences (`ESC ] 8 ; params ; URI ST`).
+/// An incomplete sequence at the end of the chunk is kept in `tail` for the next call.
+fn split_osc8_chunks(data: &[u8], tail: &mut Vec<u8>) -> Vec<TerminalChunk> {
+    let mut buffer = Vec::with_capacity(tail.len() + data.len());
+    buffer.extend_from_slice(tail);
+    buffer.extend_from_slice(data);
+    tail.clear();
+    let mut chunks = Vec::new();
+    let mut text_start = 0;
+    let mut i = 0;
+    while i < buffer.len() {
+        if buffer[i] != 0x1b {
+            i += 1;
+            continue;
+        }
+        if is_partial_prefix(&buffer[i..], OSC8_PREFIX) {
+            tail.extend_from_slice(&buffer[i..]);
+            break;
+        }
+        if !buffer[i..].starts_with(OSC8_PREFIX) {
+            i += 1;
+            continue;
+        }
+        let body_start = i + OSC8_PREFIX.len();
+        let mut j = body_start;
+        let mut end = None;
+        while j < buffer.len() {
+            if buffer[j] == 0x07 {
+                end = Some((j, j + 1));
+                break;
+            }
+            if buffer[j] == 0x1b {
+                if j + 1 >= buffer.len() {
+                    break;
+                }
+                if buffer[j + 1] == b'\\' {
+                    end = Some((j, j + 2));
+                }
+                break;
+            }
+            j += 1;
+        }
+        let Some((body_end, next)) = end else {
+            if j >= buffer.len() {
+                tail.extend_from_slice(&buffer[i..]);
+                break;
+            }
+            i += 1;
+            continue;
+        };
+        if text_start < i {
+            chunks.push(TerminalChunk::Text(buffer[text_start..i].to_vec()));
+        }
+        let body = String::from_utf8_lossy(&buffer[body_start..body_end]);
+        let uri = body
+            .split_once(';')
+            .map(|(_, uri)| uri.trim().to_string())
+            .filter(|uri| !uri.is_empty());
+        chunks.push(TerminalChunk::Link(uri));
+        i = next;
+        text_start = next;
+    }
+    let text_end = buffer.len() - tail.len();
+    if text_start < text_end {
+        chunks.push(TerminalChunk::Text(buffer[text_start..text_end].to_vec()));
+    }
+    chunks
+}
+
 fn find_shell_marker_in_bytes(bytes: &[u8], token: &str) -> Option<(usize, usize, i32)> {
     let prefix = format!("\x1b]{};{};", SHELL_MARKER_PREFIX, token);
     let prefix_bytes = prefix.as_bytes();
@@ -5046,14 +10332,7 @@ async fn tool_remote_exec(call: &ToolCall, ctx: &ToolContext) -> Result<String>
             }
         }
     }
-    let session_id = ctx
-        .session_id
-        .ok_or_else(|| anyhow::anyhow!("no active session"))?;
-    let handle = ctx
-        .sessions
-        .get_session(session_id)
-        .ok_or_else(|| anyhow::anyhow!("session not found"))?;
-    let exec_fut = handle.session.exec(&command);
+    let exec_fut = ctx.remote()?.exec(&command);
     let (status, output) = if timeout_ms > 0 {
         timeout(Duration::from_millis(timeout_ms), exec_fut).await??
     } else {
@@ -5222,15 +10501,8 @@ async fn tool_remote_list(call: &ToolCall, ctx: &ToolContext) -> Result<String>
     let include_hidden =
         tool_arg_bool(&call.args, "include_hidden").unwrap_or(ctx.config.ui.show_hidden_files);
     let limit = tool_arg_usize(&call.args, "limit").unwrap_or(TOOL_DEFAULT_SEARCH_LIMIT);
-    let session_id = ctx
-        .session_id
-        .ok_or_else(|| anyhow::anyhow!("no active session"))?;
-    let handle = ctx
-        .sessions
-        .get_session(session_id)
-        .ok_or_else(|| anyhow::anyhow!("session not found"))?;
-    let sftp = handle.session.open_sftp().await?;
-    let entries = list_remote(&sftp, &path).await?;
+    let sftp = ctx.remote()?.open_sftp().await?;
+    let entries = list_remote(sftp.as_ref(), &path).await?;
     let mut out = Vec::new();
     for entry in entries {
         if !include_hidden && entry.name.starts_with('.') {
@@ -5252,7 +10524,121 @@ async fn tool_remote_list(call: &ToolCall, ctx: &ToolContext) -> Result<String>
 #[derive(Serialize)]
 struct ToolReadOutput {
     path: String,
-    content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    binary: Option<ToolBinarySummary>,
+    /// The file goes on past `max_bytes`; only its head was read.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    truncated: bool,
+}
+
+#[derive(Serialize)]
+struct ToolBinarySummary {
+    size: usize,
+    kind: &'static str,
+    head_hex: String,
+    note: &'static str,
+}
+
+/// Text files come back as (truncated) content; binary data gets a short summary instead
+/// of a lossy decode that would only burn tokens. `data` is the head of the file as read by
+/// [`read_capped`].
+fn read_tool_output(path: String, data: &[u8], truncated: bool) -> ToolReadOutput {
+    if !looks_binary(data, truncated) {
+        let data = if truncated {
+            drop_split_char(data)
+        } else {
+            data
+        };
+        let text = String::from_utf8_lossy(data);
+        return ToolReadOutput {
+            path,
+            content: Some(trim_output(&text)),
+            binary: None,
+            truncated,
+        };
+    }
+    let head = &data[..data.len().min(TOOL_BINARY_HEAD_BYTES)];
+    ToolReadOutput {
+        path,
+        content: None,
+        binary: Some(ToolBinarySummary {
+            size: data.len(),
+            kind: detect_binary_kind(data),
+            head_hex: hex::encode(head),
+            note: "binary content not shown; use an exec tool (file, xxd, strings) to inspect it",
+        }),
+        truncated,
+    }
+}
+
+/// Reads at most `max_bytes` bytes and reports whether the source had more, so a huge file
+/// never ends up in memory as a whole.
+async fn read_capped<R>(reader: R, max_bytes: usize) -> io::Result<(Vec<u8>, bool)>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    let mut buf = Vec::new();
+    reader
+        .take(max_bytes as u64 + 1)
+        .read_to_end(&mut buf)
+        .await?;
+    let truncated = buf.len() > max_bytes;
+    buf.truncate(max_bytes);
+    Ok((buf, truncated))
+}
+
+/// The read cap can split the last character; drop its leading bytes rather than decode them.
+fn drop_split_char(data: &[u8]) -> &[u8] {
+    match std::str::from_utf8(data) {
+        Err(err) if err.error_len().is_none() => &data[..err.valid_up_to()],
+        _ => data,
+    }
+}
+
+fn looks_binary(data: &[u8], truncated: bool) -> bool {
+    let sample = &data[..data.len().min(TOOL_BINARY_SNIFF_BYTES)];
+    if sample.contains(&0) {
+        return true;
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => false,
+        // A multi-byte character cut off by the sniff window or the read cap is still text.
+        Err(err) => err.error_len().is_some() || (sample.len() == data.len() && !truncated),
+    }
+}
+
+fn detect_binary_kind(data: &[u8]) -> &'static str {
+    const SIGNATURES: [(&[u8], &str); 12] = [
+        (b"\x89PNG\r\n\x1a\n", "png image"),
+        (b"\xff\xd8\xff", "jpeg image"),
+        (b"GIF8", "gif image"),
+        (b"%PDF", "pdf document"),
+        (b"PK\x03\x04", "zip archive"),
+        (b"\x1f\x8b", "gzip archive"),
+        (b"BZh", "bzip2 archive"),
+        (b"\xfd7zXZ\x00", "xz archive"),
+        (b"7z\xbc\xaf\x27\x1c", "7z archive"),
+        (b"\x7fELF", "elf executable"),
+        (b"MZ", "windows executable"),
+        (b"SQLite format 3\x00", "sqlite database"),
+    ];
+    for (magic, kind) in SIGNATURES {
+        if data.starts_with(magic) {
+            return kind;
+        }
+    }
+    if data.len() > 262 && &data[257..262] == b"ustar" {
+        return "tar archive";
+    }
+    if matches!(
+        data.get(..4),
+        Some([0xfe, 0xed, 0xfa, 0xce | 0xcf] | [0xce | 0xcf, 0xfa, 0xed, 0xfe])
+    ) {
+        return "mach-o executable";
+    }
+    "unknown binary"
 }
 
 async fn tool_local_read(call: &ToolCall, ctx: &ToolContext) -> Result<String> {
@@ -5261,15 +10647,9 @@ async fn tool_local_read(call: &ToolCall, ctx: &ToolContext) -> Result<String> {
         &ctx.local_base,
     );
     let max_bytes = tool_arg_usize(&call.args, "max_bytes").unwrap_or(TOOL_DEFAULT_MAX_BYTES);
-    let data = tokio::fs::read(&path).await?;
-    let mut text = String::from_utf8_lossy(&data).to_string();
-    if text.chars().count() > max_bytes {
-        text = text.chars().take(max_bytes).collect();
-    }
-    let result = ToolReadOutput {
-        path: path.to_string_lossy().to_string(),
-        content: trim_output(&text),
-    };
+    let file = tokio::fs::File::open(&path).await?;
+    let (data, truncated) = read_capped(file, max_bytes).await?;
+    let result = read_tool_output(path.to_string_lossy().to_string(), &data, truncated);
     Ok(serde_json::to_string_pretty(&result)?)
 }
 
@@ -5279,24 +10659,120 @@ async fn tool_remote_read(call: &ToolCall, ctx: &ToolContext) -> Result<String>
         &ctx.remote_base,
     );
     let max_bytes = tool_arg_usize(&call.args, "max_bytes").unwrap_or(TOOL_DEFAULT_MAX_BYTES);
-    let session_id = ctx
-        .session_id
-        .ok_or_else(|| anyhow::anyhow!("no active session"))?;
-    let handle = ctx
-        .sessions
-        .get_session(session_id)
-        .ok_or_else(|| anyhow::anyhow!("session not found"))?;
-    let sftp = handle.session.open_sftp().await?;
-    let mut file = sftp.open_read(&path).await?;
-    let mut buf = Vec::new();
-    file.read_to_end(&mut buf).await?;
-    let mut text = String::from_utf8_lossy(&buf).to_string();
-    if text.chars().count() > max_bytes {
-        text = text.chars().take(max_bytes).collect();
+    let sftp = ctx.remote()?.open_sftp().await?;
+    let file = sftp.open_read_at(&path, 0).await?;
+    // The handle is dropped, and closed, as soon as the cap is reached.
+    let (data, truncated) = read_capped(file, max_bytes).await?;
+    let result = read_tool_output(path, &data, truncated);
+    Ok(serde_json::to_string_pretty(&result)?)
+}
+
+#[derive(Serialize)]
+struct ToolHashOutput {
+    path: String,
+    algorithm: &'static str,
+    digest: String,
+}
+
+fn tool_hash_algorithm(args: &serde_json::Value) -> Result<HashAlgorithm> {
+    match tool_arg_string(args, "algorithm") {
+        Some(value) => HashAlgorithm::from_key(&value)
+            .ok_or_else(|| anyhow::anyhow!("unsupported hash algorithm: {value}")),
+        None => Ok(HashAlgorithm::default()),
+    }
+}
+
+async fn tool_local_hash(call: &ToolCall, ctx: &ToolContext) -> Result<String> {
+    let path = resolve_local_path(
+        Some(tool_required_string(&call.args, "path")?),
+        &ctx.local_base,
+    );
+    let algorithm = tool_hash_algorithm(&call.args)?;
+    let digest = catsolle_core::checksum::hash_local_file(&path, algorithm).await?;
+    Ok(serde_json::to_string_pretty(&ToolHashOutput {
+        path: path.to_string_lossy().to_string(),
+        algorithm: algorithm.as_key(),
+        digest: hex::encode(digest),
+    })?)
+}
+
+async fn tool_remote_hash(call: &ToolCall, ctx: &ToolContext) -> Result<String> {
+    let path = resolve_remote_path(
+        Some(tool_required_string(&call.args, "path")?),
+        &ctx.remote_base,
+    );
+    let algorithm = tool_hash_algorithm(&call.args)?;
+    let sftp = ctx.remote()?.open_sftp().await?;
+    let digest = catsolle_core::checksum::hash_remote_file(sftp.as_ref(), &path, algorithm).await?;
+    Ok(serde_json::to_string_pretty(&ToolHashOutput {
+        path,
+        algorithm: algorithm.as_key(),
+        digest: hex::encode(digest),
+    })?)
+}
+
+#[derive(Serialize, Default)]
+struct ToolStatOutput {
+    path: String,
+    size: u64,
+    is_dir: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    permissions: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mtime: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    atime: Option<i64>,
+}
+
+fn format_mode(mode: u32) -> String {
+    format!("{:04o}", mode & 0o7777)
+}
+
+async fn tool_local_stat(call: &ToolCall, ctx: &ToolContext) -> Result<String> {
+    let path = resolve_local_path(
+        Some(tool_required_string(&call.args, "path")?),
+        &ctx.local_base,
+    );
+    let meta = tokio::fs::metadata(&path).await?;
+    #[allow(unused_mut)]
+    let mut result = ToolStatOutput {
+        path: path.to_string_lossy().to_string(),
+        size: meta.len(),
+        is_dir: meta.is_dir(),
+        ..ToolStatOutput::default()
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        result.permissions = Some(format_mode(meta.mode()));
+        result.uid = Some(meta.uid());
+        result.gid = Some(meta.gid());
+        result.mtime = Some(meta.mtime());
+        result.atime = Some(meta.atime());
     }
-    let result = ToolReadOutput {
+    Ok(serde_json::to_string_pretty(&result)?)
+}
+
+async fn tool_remote_stat(call: &ToolCall, ctx: &ToolContext) -> Result<String> {
+    let path = resolve_remote_path(
+        Some(tool_required_string(&call.args, "path")?),
+        &ctx.remote_base,
+    );
+    let sftp = ctx.remote()?.open_sftp().await?;
+    let meta = sftp.metadata(&path).await?;
+    let result = ToolStatOutput {
+        size: meta.size.unwrap_or(0),
+        is_dir: meta.is_dir,
+        permissions: meta.permissions.map(format_mode),
+        uid: meta.uid,
+        gid: meta.gid,
+        mtime: meta.mtime.and_then(|t| i64::try_from(t).ok()),
+        atime: meta.atime.and_then(|t| i64::try_from(t).ok()),
         path,
-        content: trim_output(&text),
     };
     Ok(serde_json::to_string_pretty(&result)?)
 }
@@ -5307,6 +10783,13 @@ struct ToolWriteOutput {
     bytes: usize,
 }
 
+#[derive(Serialize)]
+struct ToolFileWriteOutput {
+    path: String,
+    bytes: usize,
+    diff: Option<String>,
+}
+
 async fn tool_local_write(call: &ToolCall, ctx: &ToolContext) -> Result<String> {
     let path = resolve_local_path(
         Some(tool_required_string(&call.args, "path")?),
@@ -5320,6 +10803,14 @@ async fn tool_local_write(call: &ToolCall, ctx: &ToolContext) -> Result<String>
             tokio::fs::create_dir_all(parent).await?;
         }
     }
+    let previous = match tokio::fs::metadata(&path).await {
+        Ok(meta) if meta.len() as usize <= TOOL_DEFAULT_MAX_BYTES => {
+            tokio::fs::read(&path).await.ok()
+        }
+        Ok(_) => None,
+        Err(_) => Some(Vec::new()),
+    };
+    let diff = previous.and_then(|old| write_diff(&old, content.as_bytes(), append));
     let mut file = tokio::fs::OpenOptions::new()
         .create(true)
         .write(true)
@@ -5328,9 +10819,10 @@ async fn tool_local_write(call: &ToolCall, ctx: &ToolContext) -> Result<String>
         .open(&path)
         .await?;
     file.write_all(content.as_bytes()).await?;
-    let result = ToolWriteOutput {
+    let result = ToolFileWriteOutput {
         path: path.to_string_lossy().to_string(),
         bytes: content.len(),
+        diff,
     };
     Ok(serde_json::to_string_pretty(&result)?)
 }
@@ -5343,24 +10835,41 @@ async fn tool_remote_write(call: &ToolCall, ctx: &ToolContext) -> Result<String>
     let content = tool_required_string(&call.args, "content")?;
     let append = tool_arg_bool(&call.args, "append").unwrap_or(false);
     let create_dirs = tool_arg_bool(&call.args, "create_dirs").unwrap_or(true);
-    let session_id = ctx
-        .session_id
-        .ok_or_else(|| anyhow::anyhow!("no active session"))?;
-    let handle = ctx
-        .sessions
-        .get_session(session_id)
-        .ok_or_else(|| anyhow::anyhow!("session not found"))?;
-    let sftp = handle.session.open_sftp().await?;
-    if create_dirs {
-        if let Some(parent) = remote_parent(&path) {
+    let sftp = ctx.remote()?.open_sftp().await?;
+    if let Some(parent) = remote_parent(&path) {
+        sftp.check_writable(&parent, content.len() as u64).await?;
+        if create_dirs {
             sftp.create_dir_all(&parent).await?;
         }
     }
-    if append {
-        let mut existing = Vec::new();
-        if let Ok(mut file) = sftp.open_read(&path).await {
-            let _ = file.read_to_end(&mut existing).await;
+    // Like the local write, only files small enough to diff are read up front.
+    let previous = match sftp.metadata(&path).await {
+        Ok(meta) if meta.size.unwrap_or(0) as usize <= TOOL_DEFAULT_MAX_BYTES => {
+            match sftp.open_read_at(&path, 0).await {
+                Ok(file) => match read_capped(file, TOOL_DEFAULT_MAX_BYTES).await {
+                    Ok((data, false)) => Some(data),
+                    _ => None,
+                },
+                Err(_) => None,
+            }
         }
+        Ok(_) => None,
+        Err(_) => Some(Vec::new()),
+    };
+    let diff = previous
+        .as_deref()
+        .and_then(|old| write_diff(old, content.as_bytes(), append));
+    if append {
+        // SFTP writes start at offset zero, so appending rewrites the old content first.
+        let existing = match previous {
+            Some(data) => data,
+            None => {
+                let mut data = Vec::new();
+                let mut file = sftp.open_read_at(&path, 0).await?;
+                file.read_to_end(&mut data).await?;
+                data
+            }
+        };
         let mut file = sftp.open_write(&path, true).await?;
         if !existing.is_empty() {
             file.write_all(&existing).await?;
@@ -5370,9 +10879,10 @@ async fn tool_remote_write(call: &ToolCall, ctx: &ToolContext) -> Result<String>
         let mut file = sftp.open_write(&path, true).await?;
         file.write_all(content.as_bytes()).await?;
     }
-    let result = ToolWriteOutput {
+    let result = ToolFileWriteOutput {
         path,
         bytes: content.len(),
+        diff,
     };
     Ok(serde_json::to_string_pretty(&result)?)
 }
@@ -5381,7 +10891,88 @@ async fn tool_remote_write(call: &ToolCall, ctx: &ToolContext) -> Result<String>
 struct ToolSearchOutput {
     path: String,
     query: String,
-    matches: Vec<String>,
+    matches: Vec<ToolSearchMatch>,
+}
+
+/// One matching line, numbered from 1, with up to `context` lines on either side.
+#[derive(Serialize)]
+struct ToolSearchMatch {
+    path: String,
+    line: usize,
+    text: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    before: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    after: Vec<String>,
+}
+
+/// What the search tools look for: a literal substring by default, or a regex tried line by
+/// line when the call sets `regex`.
+enum SearchPattern {
+    Literal(String),
+    Regex(regex::Regex),
+}
+
+impl SearchPattern {
+    fn from_args(args: &serde_json::Value, query: &str) -> Result<Self> {
+        if !tool_arg_bool(args, "regex").unwrap_or(false) {
+            return Ok(Self::Literal(query.to_string()));
+        }
+        let regex = regex::RegexBuilder::new(query)
+            .size_limit(TOOL_SEARCH_REGEX_SIZE_LIMIT)
+            .build()
+            .map_err(|err| anyhow::anyhow!("invalid regex: {err}"))?;
+        Ok(Self::Regex(regex))
+    }
+
+    /// Zero-based indexes of the lines holding a match, in order and without repeats.
+    fn matching_lines(&self, text: &str) -> Vec<usize> {
+        match self {
+            Self::Literal(needle) if needle.is_empty() => Vec::new(),
+            // A literal may span lines, so it is found in the whole text and reported on the
+            // line where it starts.
+            Self::Literal(needle) => {
+                let mut lines: Vec<usize> = Vec::new();
+                let (mut line, mut scanned) = (0, 0);
+                for (offset, _) in text.match_indices(needle.as_str()) {
+                    line += text[scanned..offset].matches('\n').count();
+                    scanned = offset;
+                    if lines.last() != Some(&line) {
+                        lines.push(line);
+                    }
+                }
+                lines
+            }
+            Self::Regex(regex) => text
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| regex.is_match(line))
+                .map(|(index, _)| index)
+                .collect(),
+        }
+    }
+
+    fn find(&self, path: &str, text: &str, context: usize, limit: usize) -> Vec<ToolSearchMatch> {
+        let hits = self.matching_lines(text);
+        if hits.is_empty() {
+            return Vec::new();
+        }
+        let lines: Vec<&str> = text.lines().collect();
+        let clip = |line: &&str| line.chars().take(TOOL_GREP_LINE_MAX_CHARS).collect();
+        hits.into_iter()
+            .take(limit)
+            .map(|index| ToolSearchMatch {
+                path: path.to_string(),
+                line: index + 1,
+                text: clip(&lines[index]),
+                before: lines[index.saturating_sub(context)..index]
+                    .iter()
+                    .map(clip)
+                    .collect(),
+                after: lines[index + 1..].iter().take(context).map(clip).collect(),
+            })
+            .collect()
+    }
 }
 
 async fn tool_local_search(call: &ToolCall, ctx: &ToolContext) -> Result<String> {
@@ -5390,8 +10981,18 @@ async fn tool_local_search(call: &ToolCall, ctx: &ToolContext) -> Result<String>
         tool_arg_string(&call.args, "query").ok_or_else(|| anyhow::anyhow!("query is required"))?;
     let limit = tool_arg_usize(&call.args, "limit").unwrap_or(TOOL_DEFAULT_SEARCH_LIMIT);
     let max_bytes = tool_arg_usize(&call.args, "max_bytes").unwrap_or(TOOL_DEFAULT_MAX_BYTES);
+    let max_depth = tool_arg_usize(&call.args, "max_depth").unwrap_or(TOOL_DEFAULT_SEARCH_DEPTH);
+    let follow_symlinks = tool_arg_bool(&call.args, "follow_symlinks").unwrap_or(false);
+    let context = tool_arg_usize(&call.args, "context")
+        .unwrap_or(0)
+        .min(TOOL_SEARCH_CONTEXT_MAX);
+    let pattern = SearchPattern::from_args(&call.args, &query)?;
     let mut matches = Vec::new();
-    for entry in WalkDir::new(&base).into_iter().filter_map(Result::ok) {
+    // WalkDir reports symlink loops as errors when following links; those entries are skipped.
+    let walker = WalkDir::new(&base)
+        .max_depth(max_depth)
+        .follow_links(follow_symlinks);
+    for entry in walker.into_iter().filter_map(Result::ok) {
         if matches.len() >= limit {
             break;
         }
@@ -5404,9 +11005,9 @@ async fn tool_local_search(call: &ToolCall, ctx: &ToolContext) -> Result<String>
         }
         let data = tokio::fs::read(entry.path()).await?;
         if let Ok(text) = String::from_utf8(data) {
-            if text.contains(&query) {
-                matches.push(entry.path().to_string_lossy().to_string());
-            }
+            let path = entry.path().to_string_lossy();
+            let room = limit - matches.len();
+            matches.extend(pattern.find(&path, &text, context, room));
         }
     }
     let result = ToolSearchOutput {
@@ -5423,42 +11024,89 @@ async fn tool_remote_search(call: &ToolCall, ctx: &ToolContext) -> Result<String
         tool_arg_string(&call.args, "query").ok_or_else(|| anyhow::anyhow!("query is required"))?;
     let limit = tool_arg_usize(&call.args, "limit").unwrap_or(TOOL_DEFAULT_SEARCH_LIMIT);
     let max_bytes = tool_arg_usize(&call.args, "max_bytes").unwrap_or(TOOL_DEFAULT_MAX_BYTES);
-    let session_id = ctx
-        .session_id
-        .ok_or_else(|| anyhow::anyhow!("no active session"))?;
-    let handle = ctx
-        .sessions
-        .get_session(session_id)
-        .ok_or_else(|| anyhow::anyhow!("session not found"))?;
-    let sftp = handle.session.open_sftp().await?;
-    let mut stack = vec![base.clone()];
+    let max_depth = tool_arg_usize(&call.args, "max_depth").unwrap_or(TOOL_DEFAULT_SEARCH_DEPTH);
+    let follow_symlinks = tool_arg_bool(&call.args, "follow_symlinks").unwrap_or(false);
+    let context = tool_arg_usize(&call.args, "context")
+        .unwrap_or(0)
+        .min(TOOL_SEARCH_CONTEXT_MAX);
+    let pattern = &SearchPattern::from_args(&call.args, &query)?;
+    let sftp = ctx.remote()?.open_sftp().await?;
+    let sftp = sftp.as_ref();
+    let mut visited = HashSet::new();
+    if let Ok(canonical) = sftp.canonicalize(&base).await {
+        visited.insert(canonical);
+    }
+    let mut frontier = vec![base.clone()];
+    let mut depth = 0usize;
     let mut matches = Vec::new();
-    while let Some(path) = stack.pop() {
-        if matches.len() >= limit {
-            break;
+    // Breadth-first: each level lists its directories and then reads its candidate files with
+    // up to TOOL_REMOTE_SEARCH_CONCURRENCY requests in flight on the SFTP channel.
+    while !frontier.is_empty() && matches.len() < limit {
+        let is_base = depth == 0;
+        let listings: Vec<_> = futures::stream::iter(std::mem::take(&mut frontier))
+            .map(|path| async move { sftp.read_dir(&path).await })
+            .buffer_unordered(TOOL_REMOTE_SEARCH_CONCURRENCY)
+            .collect()
+            .await;
+        let mut files = Vec::new();
+        for listing in listings {
+            let entries = match listing {
+                Ok(entries) => entries,
+                Err(err) if is_base => return Err(err.into()),
+                Err(_) => continue,
+            };
+            for entry in entries {
+                let (is_dir, size) = if entry.is_symlink {
+                    if !follow_symlinks {
+                        continue;
+                    }
+                    match sftp.metadata(&entry.path).await {
+                        Ok(meta) => (meta.is_dir, meta.size.unwrap_or(0)),
+                        Err(_) => continue,
+                    }
+                } else {
+                    (entry.is_dir, entry.size)
+                };
+                if is_dir {
+                    if depth + 1 >= max_depth {
+                        continue;
+                    }
+                    if entry.is_symlink {
+                        // Only descend into each real directory once so circular links terminate.
+                        let Ok(canonical) = sftp.canonicalize(&entry.path).await else {
+                            continue;
+                        };
+                        if !visited.insert(canonical) {
+                            continue;
+                        }
+                    }
+                    frontier.push(entry.path);
+                } else if depth < max_depth && size <= max_bytes as u64 {
+                    files.push(entry.path);
+                }
+            }
         }
-        let entries = sftp.read_dir(&path).await?;
-        for entry in entries {
+        let mut reads = futures::stream::iter(files)
+            .map(|path| async move {
+                // The listed size can be stale, so the read stays capped as well.
+                let file = sftp.open_read_at(&path, 0).await?;
+                let (buf, _) = read_capped(file, max_bytes).await?;
+                let found = std::str::from_utf8(&buf)
+                    .map(|text| pattern.find(&path, text, context, limit))
+                    .unwrap_or_default();
+                Ok::<_, anyhow::Error>(found)
+            })
+            .buffer_unordered(TOOL_REMOTE_SEARCH_CONCURRENCY);
+        while let Some(found) = reads.next().await {
+            matches.extend(found?);
             if matches.len() >= limit {
+                matches.truncate(limit);
                 break;
             }
-            if entry.is_dir {
-                stack.push(entry.path);
-            } else {
-                if entry.size > max_bytes as u64 {
-                    continue;
-                }
-                let mut file = sftp.open_read(&entry.path).await?;
-                let mut buf = Vec::new();
-                file.read_to_end(&mut buf).await?;
-                if let Ok(text) = String::from_utf8(buf) {
-                    if text.contains(&query) {
-                        matches.push(entry.path.clone());
-                    }
-                }
-            }
         }
+        depth += 1;
     }
+    matches.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
     let result = ToolSearchOutput {
         path: base,
         query,
@@ -5467,6 +11115,101 @@ async fn tool_remote_search(call: &ToolCall, ctx: &ToolContext) -> Result<String
     Ok(serde_json::to_string_pretty(&result)?)
 }
 
+#[derive(Serialize)]
+struct ToolGrepOutput {
+    path: String,
+    pattern: String,
+    status: i32,
+    matches: Vec<String>,
+    truncated: bool,
+}
+
+async fn tool_remote_grep(call: &ToolCall, ctx: &ToolContext) -> Result<String> {
+    let base = resolve_remote_path(tool_arg_string(&call.args, "path"), &ctx.remote_base);
+    let pattern = tool_required_string(&call.args, "pattern")?;
+    let flags = tool_arg_string(&call.args, "flags").unwrap_or_default();
+    let limit = tool_arg_usize(&call.args, "limit").unwrap_or(TOOL_DEFAULT_SEARCH_LIMIT);
+    let timeout_ms = tool_arg_u64(&call.args, "timeout_ms").unwrap_or(TOOL_DEFAULT_TIMEOUT_MS);
+    let command = grep_command(&base, &pattern, &flags)?;
+    let exec_fut = ctx.remote()?.exec(&command);
+    let (status, output) = if timeout_ms > 0 {
+        timeout(Duration::from_millis(timeout_ms), exec_fut).await??
+    } else {
+        exec_fut.await?
+    };
+    if status == GREP_MISSING_STATUS {
+        // No grep on the server: fall back to a regex search over SFTP.
+        let fallback = ToolCall {
+            name: "remote.search".to_string(),
+            args: serde_json::json!({
+                "path": base,
+                "query": grep_fallback_regex(&pattern, &flags),
+                "regex": true,
+                "limit": limit,
+            }),
+        };
+        return tool_remote_search(&fallback, ctx).await;
+    }
+    let text = String::from_utf8_lossy(&output);
+    let mut matches: Vec<String> = text
+        .lines()
+        .take(limit.saturating_add(1))
+        .map(|line| line.chars().take(TOOL_GREP_LINE_MAX_CHARS).collect())
+        .collect();
+    let truncated = matches.len() > limit;
+    matches.truncate(limit);
+    // grep exits with 1 when nothing matched; only 2 and above are real failures.
+    if status > 1 && matches.is_empty() {
+        anyhow::bail!("grep failed with status {status}");
+    }
+    let result = ToolGrepOutput {
+        path: base,
+        pattern,
+        status,
+        matches,
+        truncated,
+    };
+    Ok(serde_json::to_string_pretty(&result)?)
+}
+
+/// Builds the `grep -rn` invocation for `remote.grep`. Only a small set of single-letter
+/// flags is accepted; path and pattern are quoted so they never reach the shell unescaped.
+fn grep_command(path: &str, pattern: &str, flags: &str) -> Result<String> {
+    let mut options = "-rn".to_string();
+    for flag in flags.chars().filter(|c| *c != '-' && !c.is_whitespace()) {
+        if !TOOL_GREP_FLAGS.contains(flag) {
+            anyhow::bail!("unsupported grep flag: -{flag}");
+        }
+        if !options.contains(flag) {
+            options.push(flag);
+        }
+    }
+    let grep = catsolle_ssh::shell::join(&[options.as_str(), "-e", pattern, "--", path]);
+    Ok(format!(
+        "command -v grep >/dev/null 2>&1 || exit {GREP_MISSING_STATUS}; grep {grep} 2>/dev/null"
+    ))
+}
+
+/// The regex `remote.search` runs in place of grep, so a pattern finds the same lines on
+/// hosts without grep. `-l` has no counterpart and is ignored.
+fn grep_fallback_regex(pattern: &str, flags: &str) -> String {
+    let mut regex = if flags.contains('F') {
+        regex::escape(pattern)
+    } else {
+        pattern.to_string()
+    };
+    if flags.contains('w') {
+        regex = format!(r"\b(?:{regex})\b");
+    }
+    if flags.contains('x') {
+        regex = format!("^(?:{regex})$");
+    }
+    if flags.contains('i') {
+        regex = format!("(?i){regex}");
+    }
+    regex
+}
+
 async fn tool_local_mkdir(call: &ToolCall, ctx: &ToolContext) -> Result<String> {
     let path = resolve_local_path(
         Some(tool_required_string(&call.args, "path")?),
@@ -5484,14 +11227,7 @@ async fn tool_remote_mkdir(call: &ToolCall, ctx: &ToolContext) -> Result<String>
         Some(tool_required_string(&call.args, "path")?),
         &ctx.remote_base,
     );
-    let session_id = ctx
-        .session_id
-        .ok_or_else(|| anyhow::anyhow!("no active session"))?;
-    let handle = ctx
-        .sessions
-        .get_session(session_id)
-        .ok_or_else(|| anyhow::anyhow!("session not found"))?;
-    let sftp = handle.session.open_sftp().await?;
+    let sftp = ctx.remote()?.open_sftp().await?;
     sftp.create_dir_all(&path).await?;
     Ok(serde_json::to_string_pretty(&ToolWriteOutput {
         path,
@@ -5526,47 +11262,50 @@ async fn tool_local_remove(call: &ToolCall, ctx: &ToolContext) -> Result<String>
     })?)
 }
 
+/// Depth-first removal of a remote directory. Failures below `path` are skipped so as much
+/// as possible is removed; the error surfaces when `path` itself cannot be removed.
+async fn remove_remote_dir_all(sftp: &dyn ToolSftp, path: &str) -> Result<()> {
+    let mut stack = vec![(path.to_string(), false)];
+    while let Some((current, visited)) = stack.pop() {
+        if visited {
+            let removed = sftp.remove_dir(&current).await;
+            if current == path {
+                removed?;
+            }
+            continue;
+        }
+        stack.push((current.clone(), true));
+        for entry in sftp.read_dir(&current).await? {
+            if entry.is_dir {
+                stack.push((entry.path, false));
+            } else {
+                let _ = sftp.remove_file(&entry.path).await;
+            }
+        }
+    }
+    Ok(())
+}
+
 async fn tool_remote_remove(call: &ToolCall, ctx: &ToolContext) -> Result<String> {
     let path = resolve_remote_path(
         Some(tool_required_string(&call.args, "path")?),
         &ctx.remote_base,
     );
     let recursive = tool_arg_bool(&call.args, "recursive").unwrap_or(false);
-    let session_id = ctx
-        .session_id
-        .ok_or_else(|| anyhow::anyhow!("no active session"))?;
-    let handle = ctx
-        .sessions
-        .get_session(session_id)
-        .ok_or_else(|| anyhow::anyhow!("session not found"))?;
-    let sftp = handle.session.open_sftp().await?;
+    let sftp = ctx.remote()?.open_sftp().await?;
     if recursive {
         let meta = sftp.metadata(&path).await?;
-        if !meta.file_type().is_dir() {
+        if !meta.is_dir {
             sftp.remove_file(&path).await?;
             return Ok(serde_json::to_string_pretty(&ToolWriteOutput {
                 path,
                 bytes: 0,
             })?);
         }
-        let mut stack = vec![(path.clone(), false)];
-        while let Some((current, visited)) = stack.pop() {
-            if visited {
-                let _ = sftp.remove_dir(&current).await;
-                continue;
-            }
-            stack.push((current.clone(), true));
-            for entry in sftp.read_dir(&current).await? {
-                if entry.is_dir {
-                    stack.push((entry.path, false));
-                } else {
-                    let _ = sftp.remove_file(&entry.path).await;
-                }
-            }
-        }
+        remove_remote_dir_all(sftp.as_ref(), &path).await?;
     } else {
         let meta = sftp.metadata(&path).await?;
-        if meta.file_type().is_dir() {
+        if meta.is_dir {
             sftp.remove_dir(&path).await?;
         } else {
             sftp.remove_file(&path).await?;
@@ -5603,14 +11342,7 @@ async fn tool_remote_rename(call: &ToolCall, ctx: &ToolContext) -> Result<String
         Some(tool_required_string(&call.args, "to")?),
         &ctx.remote_base,
     );
-    let session_id = ctx
-        .session_id
-        .ok_or_else(|| anyhow::anyhow!("no active session"))?;
-    let handle = ctx
-        .sessions
-        .get_session(session_id)
-        .ok_or_else(|| anyhow::anyhow!("session not found"))?;
-    let sftp = handle.session.open_sftp().await?;
+    let sftp = ctx.remote()?.open_sftp().await?;
     sftp.rename(&from, &to).await?;
     Ok(serde_json::to_string_pretty(&ToolWriteOutput {
         path: to,
@@ -5618,6 +11350,143 @@ async fn tool_remote_rename(call: &ToolCall, ctx: &ToolContext) -> Result<String
     })?)
 }
 
+#[derive(Serialize)]
+struct ToolChmodOutput {
+    path: String,
+    mode: String,
+}
+
+/// Parses an octal mode such as "600", "0755" or "0o644".
+fn parse_mode(value: &str) -> Result<u32> {
+    let digits = value.trim();
+    let digits = digits.strip_prefix("0o").unwrap_or(digits);
+    match u32::from_str_radix(digits, 8) {
+        Ok(mode) if mode <= 0o7777 && !digits.is_empty() => Ok(mode),
+        _ => Err(anyhow::anyhow!("invalid octal mode: {value}")),
+    }
+}
+
+async fn tool_local_chmod(call: &ToolCall, ctx: &ToolContext) -> Result<String> {
+    let path = resolve_local_path(
+        Some(tool_required_string(&call.args, "path")?),
+        &ctx.local_base,
+    );
+    let mode = parse_mode(&tool_required_string(&call.args, "mode")?)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).await?;
+        Ok(serde_json::to_string_pretty(&ToolChmodOutput {
+            path: path.to_string_lossy().to_string(),
+            mode: format_mode(mode),
+        })?)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (path, mode);
+        Err(anyhow::anyhow!(
+            "local.chmod is unsupported on this platform"
+        ))
+    }
+}
+
+async fn tool_remote_chmod(call: &ToolCall, ctx: &ToolContext) -> Result<String> {
+    let path = resolve_remote_path(
+        Some(tool_required_string(&call.args, "path")?),
+        &ctx.remote_base,
+    );
+    let mode = parse_mode(&tool_required_string(&call.args, "mode")?)?;
+    let sftp = ctx.remote()?.open_sftp().await?;
+    sftp.set_permissions(&path, mode).await?;
+    Ok(serde_json::to_string_pretty(&ToolChmodOutput {
+        path,
+        mode: format_mode(mode),
+    })?)
+}
+
+#[derive(Serialize)]
+struct ToolDiffOutput {
+    left: String,
+    right: String,
+    diff: String,
+    /// Sides longer than `max_bytes`; only their heads were compared.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    left_truncated: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    right_truncated: bool,
+}
+
+async fn tool_diff(call: &ToolCall, ctx: &ToolContext) -> Result<String> {
+    let max_bytes = tool_arg_usize(&call.args, "max_bytes").unwrap_or(TOOL_DEFAULT_MAX_BYTES);
+    let left_raw = tool_required_string(&call.args, "left")?;
+    let right_raw = tool_required_string(&call.args, "right")?;
+    let (left_ep, left) =
+        parse_transfer_endpoint(&left_raw, &ctx.local_base, &ctx.remote_base, ctx.session_id)?;
+    let (right_ep, right) = parse_transfer_endpoint(
+        &right_raw,
+        &ctx.local_base,
+        &ctx.remote_base,
+        ctx.session_id,
+    )?;
+    let (left_data, left_truncated) = read_endpoint_file(&left_ep, &left, max_bytes, ctx).await?;
+    let (right_data, right_truncated) =
+        read_endpoint_file(&right_ep, &right, max_bytes, ctx).await?;
+    let diff = file_diff(
+        &left,
+        &right,
+        drop_split_char(&left_data),
+        drop_split_char(&right_data),
+    );
+    Ok(serde_json::to_string_pretty(&ToolDiffOutput {
+        left,
+        right,
+        diff: trim_output(&diff),
+        left_truncated,
+        right_truncated,
+    })?)
+}
+
+/// Head of a local or remote file, read with [`read_capped`].
+async fn read_endpoint_file(
+    endpoint: &TransferEndpoint,
+    path: &str,
+    max_bytes: usize,
+    ctx: &ToolContext,
+) -> Result<(Vec<u8>, bool)> {
+    match endpoint {
+        TransferEndpoint::Local { .. } => {
+            let file = tokio::fs::File::open(path).await?;
+            Ok(read_capped(file, max_bytes).await?)
+        }
+        TransferEndpoint::Remote { .. } => {
+            let sftp = ctx.remote()?.open_sftp().await?;
+            let file = sftp.open_read_at(path, 0).await?;
+            Ok(read_capped(file, max_bytes).await?)
+        }
+    }
+}
+
+/// Unified diff of two text files, or a count of differing bytes when either is not UTF-8.
+fn file_diff(left_name: &str, right_name: &str, left: &[u8], right: &[u8]) -> String {
+    match (std::str::from_utf8(left), std::str::from_utf8(right)) {
+        (Ok(left), Ok(right)) => {
+            if left == right {
+                return "(no changes)".to_string();
+            }
+            similar::TextDiff::from_lines(left, right)
+                .unified_diff()
+                .context_radius(TOOL_DIFF_CONTEXT)
+                .header(left_name, right_name)
+                .to_string()
+        }
+        _ => {
+            let differing = left.iter().zip(right).filter(|(a, b)| a != b).count()
+                + left.len().abs_diff(right.len());
+            format!("binary, {differing} bytes differ")
+        }
+    }
+}
+
 #[derive(Serialize)]
 struct ToolTransferOutput {
     job_id: String,
@@ -5631,28 +11500,33 @@ async fn tool_transfer_copy(call: &ToolCall, ctx: &ToolContext) -> Result<String
         parse_transfer_endpoint(&source_raw, &ctx.local_base, &ctx.remote_base, session_id)?;
     let (dest_ep, dest_path) =
         parse_transfer_endpoint(&dest_raw, &ctx.local_base, &ctx.remote_base, session_id)?;
-    let (is_dir, size) = resolve_transfer_meta(&source_ep, &source_path, ctx).await?;
-    let file = TransferFile {
-        source_path: source_path.clone(),
-        dest_path: dest_path.clone(),
-        size,
-        is_dir,
-    };
+    let files = expand_transfer_files(
+        &ctx.sessions,
+        &source_ep,
+        &dest_ep,
+        &source_path,
+        &dest_path,
+    )
+    .await
+    .map_err(|e| anyhow::anyhow!(e.to_string()))?;
     let job = TransferJob {
         id: Uuid::new_v4(),
         source: source_ep,
         dest: dest_ep,
-        files: vec![file],
+        files,
         options: TransferOptions {
-            overwrite: catsolle_core::transfer::OverwriteMode::Replace,
+            overwrite: OverwriteMode::Replace,
             preserve_permissions: true,
             preserve_times: true,
             verify_checksum: ctx.config.transfer.verify_checksum,
             resume: ctx.config.transfer.resume,
             buffer_size: ctx.config.transfer.buffer_size,
+            max_bytes_per_sec: transfer_rate_limit(&ctx.config.transfer),
+            continue_on_error: ctx.config.transfer.continue_on_error,
         },
         state: TransferState::Queued,
         progress: Default::default(),
+        results: Vec::new(),
         created_at: chrono::Utc::now(),
     };
     let job_id = job.id;
@@ -5703,27 +11577,38 @@ async fn tool_transfer_copy_selected(_call: &ToolCall, ctx: &ToolContext) -> Res
             ))
         }
     };
-    let file = TransferFile {
-        source_path: join_path(&src.path, &entry.name, src.kind == PanelKind::Remote),
-        dest_path: join_path(&dst.path, &entry.name, dst.kind == PanelKind::Remote),
-        size: entry.size,
-        is_dir: entry.is_dir,
+    let source_path = join_path(&src.path, &entry.name, src.kind == PanelKind::Remote);
+    let dest_path = join_path(&dst.path, &entry.name, dst.kind == PanelKind::Remote);
+    let files = if entry.is_dir {
+        expand_transfer_files(&ctx.sessions, &source, &dest, &source_path, &dest_path)
+            .await
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?
+    } else {
+        vec![TransferFile {
+            source_path,
+            dest_path,
+            size: entry.size,
+            is_dir: false,
+        }]
     };
     let job = TransferJob {
         id: Uuid::new_v4(),
         source,
         dest,
-        files: vec![file],
+        files,
         options: TransferOptions {
-            overwrite: catsolle_core::transfer::OverwriteMode::Replace,
+            overwrite: OverwriteMode::Replace,
             preserve_permissions: true,
             preserve_times: true,
             verify_checksum: ctx.config.transfer.verify_checksum,
             resume: ctx.config.transfer.resume,
             buffer_size: ctx.config.transfer.buffer_size,
+            max_bytes_per_sec: transfer_rate_limit(&ctx.config.transfer),
+            continue_on_error: ctx.config.transfer.continue_on_error,
         },
         state: TransferState::Queued,
         progress: Default::default(),
+        results: Vec::new(),
         created_at: chrono::Utc::now(),
     };
     let job_id = job.id;
@@ -5736,30 +11621,6 @@ async fn tool_transfer_copy_selected(_call: &ToolCall, ctx: &ToolContext) -> Res
     })?)
 }
 
-async fn resolve_transfer_meta(
-    source: &TransferEndpoint,
-    path: &str,
-    ctx: &ToolContext,
-) -> Result<(bool, u64)> {
-    match source {
-        TransferEndpoint::Local { .. } => {
-            let meta = tokio::fs::metadata(path).await?;
-            Ok((meta.is_dir(), meta.len()))
-        }
-        TransferEndpoint::Remote { session_id, .. } => {
-            let handle = ctx
-                .sessions
-                .get_session(*session_id)
-                .ok_or_else(|| anyhow::anyhow!("session not found"))?;
-            let sftp = handle.session.open_sftp().await?;
-            let meta = sftp.metadata(path).await?;
-            let is_dir = meta.file_type().is_dir();
-            let size = meta.size.unwrap_or(0);
-            Ok((is_dir, size))
-        }
-    }
-}
-
 fn parse_transfer_endpoint(
     raw: &str,
     local_base: &str,
@@ -5821,41 +11682,229 @@ async fn tool_connections_list(_call: &ToolCall, ctx: &ToolContext) -> Result<St
     })?)
 }
 
+/// Lines currently held in the parser's scrollback buffer.
+fn scrollback_len(parser: &mut Parser) -> usize {
+    let offset = parser.screen().scrollback();
+    parser.set_scrollback(usize::MAX);
+    let len = parser.screen().scrollback();
+    parser.set_scrollback(offset);
+    len
+}
+
 fn panel_visible_rows(area: Rect) -> usize {
     area.height.saturating_sub(2) as usize
 }
 
-fn format_bytes(bytes: u64) -> String {
-    const KIB: f64 = 1024.0;
-    const MIB: f64 = KIB * 1024.0;
-    const GIB: f64 = MIB * 1024.0;
-    let b = bytes as f64;
-    if b >= GIB {
-        format!("{:.1}GiB", b / GIB)
-    } else if b >= MIB {
-        format!("{:.1}MiB", b / MIB)
-    } else if b >= KIB {
-        format!("{:.1}KiB", b / KIB)
+fn terminal_block() -> Block<'static> {
+    Block::default().borders(Borders::ALL).title("terminal")
+}
+
+/// Row offset inside a bordered panel for a screen row, `None` on the borders.
+fn panel_row_at(area: Rect, row: u16) -> Option<usize> {
+    let offset = row.checked_sub(area.y.saturating_add(1))? as usize;
+    (offset < panel_visible_rows(area)).then_some(offset)
+}
+
+/// `<local time>-<connection name>.cast`, with anything unsafe in a file name replaced.
+fn recording_file_name(name: &str, at: chrono::DateTime<chrono::Local>) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!(
+        "{}-{}.cast",
+        at.format("%Y%m%d-%H%M%S"),
+        name.trim_matches('.')
+    )
+}
+
+fn transfer_rate_limit(cfg: &TransferConfig) -> Option<u64> {
+    (cfg.max_bytes_per_sec > 0).then_some(cfg.max_bytes_per_sec)
+}
+
+/// Renders a timestamp with `ui.datetime_format` in `ui.timezone` ("local" or "utc").
+/// Falls back to the default format when the configured one is not a valid strftime pattern.
+fn format_timestamp(value: chrono::DateTime<chrono::Utc>, ui: &UiConfig) -> String {
+    let mut out = String::new();
+    let result = if ui.timezone.eq_ignore_ascii_case("utc") {
+        write!(out, "{}", value.format(&ui.datetime_format))
     } else {
-        format!("{}B", bytes)
+        write!(
+            out,
+            "{}",
+            value
+                .with_timezone(&chrono::Local)
+                .format(&ui.datetime_format)
+        )
+    };
+    if result.is_err() || out.is_empty() {
+        let fallback = UiConfig::default().datetime_format;
+        out.clear();
+        let _ = write!(out, "{}", value.format(&fallback));
     }
+    out
 }
 
-fn format_duration(secs: u64) -> String {
-    let hours = secs / 3600;
-    let minutes = (secs % 3600) / 60;
-    let seconds = secs % 60;
-    if hours > 0 {
-        format!("{hours}h{minutes:02}m")
-    } else if minutes > 0 {
-        format!("{minutes}m{seconds:02}s")
-    } else {
-        format!("{seconds}s")
+/// Buckets the age of `value` for "time ago" labels. Returns `None` once the
+/// timestamp is older than `RELATIVE_TIME_MAX_DAYS`, so callers show an absolute date.
+fn relative_age(
+    now: chrono::DateTime<chrono::Utc>,
+    value: chrono::DateTime<chrono::Utc>,
+) -> Option<RelativeAge> {
+    let age = now.signed_duration_since(value);
+    let minutes = age.num_minutes().max(0);
+    if minutes < 1 {
+        return Some(RelativeAge::JustNow);
+    }
+    if minutes < 60 {
+        return Some(RelativeAge::Minutes(minutes));
+    }
+    let hours = age.num_hours();
+    if hours < 24 {
+        return Some(RelativeAge::Hours(hours));
+    }
+    let days = age.num_days();
+    if days > RELATIVE_TIME_MAX_DAYS {
+        return None;
+    }
+    if days < 2 {
+        return Some(RelativeAge::Yesterday);
+    }
+    if days < 14 {
+        return Some(RelativeAge::Days(days));
+    }
+    Some(RelativeAge::Weeks(days / 7))
+}
+
+/// Flattens connections under their group headers. Without any groups the list stays flat;
+/// otherwise connections with no (or an unknown) group go under a trailing ungrouped header.
+fn connection_rows(
+    groups: &[ConnectionGroup],
+    connections: &[Connection],
+    collapsed: &HashSet<Option<Uuid>>,
+) -> Vec<ConnectionRow> {
+    if groups.is_empty() {
+        return (0..connections.len())
+            .map(ConnectionRow::Connection)
+            .collect();
+    }
+    let known = |id: &Uuid| groups.iter().any(|g| g.id == *id);
+    let mut rows = Vec::new();
+    let push_group = |id: Option<Uuid>, rows: &mut Vec<ConnectionRow>| {
+        let members: Vec<usize> = connections
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.group_id.filter(|g| known(g)) == id)
+            .map(|(idx, _)| idx)
+            .collect();
+        if id.is_none() && members.is_empty() {
+            return;
+        }
+        rows.push(ConnectionRow::Group(id));
+        if !collapsed.contains(&id) {
+            rows.extend(members.into_iter().map(ConnectionRow::Connection));
+        }
+    };
+    for group in groups {
+        push_group(Some(group.id), &mut rows);
+    }
+    push_group(None, &mut rows);
+    rows
+}
+
+/// Connections whose name, host or username fuzzily match `query`, best match first; ties
+/// keep the alphabetical order of `connections`.
+fn search_connection_rows(connections: &[Connection], query: &str) -> Vec<ConnectionRow> {
+    let mut scored: Vec<(i64, usize)> = connections
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, c)| {
+            [&c.name, &c.host, &c.username]
+                .into_iter()
+                .filter_map(|field| fuzzy_score(query, field))
+                .max()
+                .map(|score| (score, idx))
+        })
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored
+        .into_iter()
+        .map(|(_, idx)| ConnectionRow::Connection(idx))
+        .collect()
+}
+
+/// Scores `text` when it contains the characters of `query` in order, ignoring case.
+/// Runs of adjacent matches and matches at word starts count more; skipped characters
+/// count against.
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last: Option<usize> = None;
+    for q in query.chars().flat_map(char::to_lowercase) {
+        let found = pos + text[pos..].iter().position(|c| *c == q)?;
+        score += 1;
+        if last.is_some_and(|last| last + 1 == found) {
+            score += 4;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        score -= (found - last.map_or(0, |last| last + 1)).min(3) as i64;
+        last = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+/// Connections that authenticate with a stored password, by name or host.
+fn password_connection_names(connections: &[Connection]) -> Vec<&str> {
+    connections
+        .iter()
+        .filter(|c| matches!(c.auth_method, AuthMethod::Password { .. }))
+        .map(|c| {
+            if c.name.is_empty() {
+                c.host.as_str()
+            } else {
+                c.name.as_str()
+            }
+        })
+        .collect()
+}
+
+/// Lists connections under auth method headers in `AUTH_KIND_ORDER`, skipping methods
+/// no connection uses.
+fn connection_rows_by_auth(
+    connections: &[Connection],
+    collapsed: &HashSet<&'static str>,
+) -> Vec<ConnectionRow> {
+    let mut rows = Vec::new();
+    for kind in AUTH_KIND_ORDER {
+        let members: Vec<usize> = connections
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.auth_method.as_key() == kind)
+            .map(|(idx, _)| idx)
+            .collect();
+        if members.is_empty() {
+            continue;
+        }
+        rows.push(ConnectionRow::Auth(kind));
+        if !collapsed.contains(kind) {
+            rows.extend(members.into_iter().map(ConnectionRow::Connection));
+        }
     }
+    rows
 }
 
-fn provider_list() -> [&'static str; 4] {
-    ["ollama", "openai", "openrouter", "anthropic"]
+fn provider_list() -> [&'static str; 5] {
+    ["ollama", "openai", "openrouter", "anthropic", "gemini"]
 }
 
 fn cycle_provider(current: &str, delta: i32) -> String {
@@ -5889,6 +11938,10 @@ fn provider_defaults(provider: &str) -> (String, String) {
             "https://api.anthropic.com".to_string(),
             "opus-4.5".to_string(),
         ),
+        "gemini" => (
+            "https://generativelanguage.googleapis.com".to_string(),
+            "gemini-2.0-flash".to_string(),
+        ),
         _ => (
             "http://localhost:11434".to_string(),
             "qwen2.5:3b".to_string(),
@@ -5896,7 +11949,41 @@ fn provider_defaults(provider: &str) -> (String, String) {
     }
 }
 
-fn tool_definitions() -> Vec<String> {
+/// Every tool `execute_tool_call` knows, in the order `tool_definitions` lists them, with
+/// whether it can change files or run arbitrary commands.
+const TOOLS: [(&str, bool); 28] = [
+    ("local.exec", true),
+    ("remote.shell.exec", true),
+    ("remote.exec", true),
+    ("local.list", false),
+    ("remote.list", false),
+    ("local.read", false),
+    ("remote.read", false),
+    ("local.hash", false),
+    ("remote.hash", false),
+    ("local.stat", false),
+    ("remote.stat", false),
+    ("local.write", true),
+    ("remote.write", true),
+    ("local.search", false),
+    ("remote.search", false),
+    ("remote.grep", false),
+    ("local.mkdir", true),
+    ("remote.mkdir", true),
+    ("local.remove", true),
+    ("remote.remove", true),
+    ("local.rename", true),
+    ("remote.rename", true),
+    ("local.chmod", true),
+    ("remote.chmod", true),
+    ("diff", false),
+    ("transfer.copy", true),
+    ("transfer.copy_selected", true),
+    ("connections.list", false),
+];
+
+/// Tool descriptions for the system prompt, leaving out tools the allowlist blocks.
+fn tool_definitions(ai: &AiConfig) -> Vec<String> {
     vec![
         "Tools:",
         "- local.exec {command, timeout_ms?}",
@@ -5906,25 +11993,111 @@ fn tool_definitions() -> Vec<String> {
         "- remote.list {path?, limit?, include_hidden?}",
         "- local.read {path, max_bytes?}",
         "- remote.read {path, max_bytes?}",
+        "- local.hash {path, algorithm? (sha256 default, sha1, md5)} returns the hex digest",
+        "- remote.hash {path, algorithm? (sha256 default, sha1, md5)} returns the hex digest without saving a local copy",
+        "- local.stat {path} returns size, is_dir, permissions (octal), uid, gid, mtime and atime",
+        "- remote.stat {path} returns size, is_dir, permissions (octal), uid, gid, mtime and atime where the server reports them",
         "- local.write {path, content, append?, create_dirs?}",
         "- remote.write {path, content, append?, create_dirs?}",
-        "- local.search {path?, query, limit?, max_bytes?}",
-        "- remote.search {path?, query, limit?, max_bytes?}",
+        "- local.search {path?, query, regex? (default false; matched per line), context? (lines around each hit, max 10), limit? (matching lines), max_bytes?, max_depth? (default 8), follow_symlinks? (default false)} returns {path, line, text} per matching line",
+        "- remote.search {path?, query, regex? (default false; matched per line), context? (lines around each hit, max 10), limit? (matching lines), max_bytes?, max_depth? (default 8), follow_symlinks? (default false)} returns {path, line, text} per matching line",
+        "- remote.grep {path?, pattern, flags? (subset of -iwxFEl), limit?, timeout_ms?} runs grep -rn on the server; prefer it over remote.search, which it falls back to when grep is missing",
         "- local.mkdir {path}",
         "- remote.mkdir {path}",
         "- local.remove {path, recursive?}",
         "- remote.remove {path, recursive?}",
         "- local.rename {from, to}",
         "- remote.rename {from, to}",
+        "- local.chmod {path, mode} sets permissions from an octal string like \"600\" (unix only)",
+        "- remote.chmod {path, mode} sets permissions from an octal string like \"755\"",
+        "- diff {left, right, max_bytes?} unified diff of two files; prefix paths with local: or remote: as in transfer.copy; left_truncated/right_truncated mark sides cut at max_bytes",
         "- transfer.copy {source, dest}",
         "- transfer.copy_selected {}",
         "- connections.list {}",
     ]
     .into_iter()
+    .filter(|line| {
+        line.strip_prefix("- ")
+            .and_then(|rest| rest.split_whitespace().next())
+            .is_none_or(|name| ai.tool_allowed(name))
+    })
     .map(|s| s.to_string())
     .collect()
 }
 
+/// Function names may not contain dots, so `remote.shell.exec` goes out as
+/// `remote__shell__exec`.
+fn tool_name_to_native(name: &str) -> String {
+    name.replace('.', "__")
+}
+
+fn tool_name_from_native(name: &str) -> String {
+    name.replace("__", ".")
+}
+
+/// Function-calling definitions built from `tool_definitions`, so both conventions
+/// describe the same tools. Argument types are left open; `?` marks optional arguments
+/// and a parenthesized note becomes the argument description.
+fn native_tool_specs(ai: &AiConfig) -> Vec<serde_json::Value> {
+    tool_definitions(ai)
+        .iter()
+        .filter_map(|line| native_tool_spec(line))
+        .collect()
+}
+
+fn native_tool_spec(line: &str) -> Option<serde_json::Value> {
+    let (name, rest) = line.strip_prefix("- ")?.split_once(' ')?;
+    let (args, description) = rest.strip_prefix('{')?.split_once('}')?;
+    let mut properties = serde_json::Map::new();
+    let mut required = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut parts = Vec::new();
+    for (idx, c) in args.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(&args[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&args[start..]);
+    for part in parts.into_iter().map(str::trim).filter(|p| !p.is_empty()) {
+        let (arg, note) = match part.split_once(" (") {
+            Some((arg, note)) => (arg, Some(note.trim_end_matches(')'))),
+            None => (part, None),
+        };
+        let (arg, optional) = match arg.strip_suffix('?') {
+            Some(arg) => (arg, true),
+            None => (arg, false),
+        };
+        let mut property = serde_json::Map::new();
+        if let Some(note) = note {
+            property.insert("description".to_string(), note.into());
+        }
+        properties.insert(arg.to_string(), property.into());
+        if !optional {
+            required.push(arg);
+        }
+    }
+    let mut function = serde_json::json!({
+        "name": tool_name_to_native(name),
+        "parameters": {
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        },
+    });
+    let description = description.trim();
+    if !description.is_empty() {
+        function["description"] = description.into();
+    }
+    Some(serde_json::json!({ "type": "function", "function": function }))
+}
+
 fn wrapped_line_count(lines: &[Line<'_>], width: u16) -> usize {
     let width = width.max(1) as usize;
     lines
@@ -6055,6 +12228,45 @@ fn parse_target(
     Ok((user_part.to_string(), host, port))
 }
 
+/// Parses a jump host typed as `user@host:port`, optionally followed by a private key path;
+/// without one the hop authenticates through the agent.
+fn parse_jump_host(
+    input: &str,
+    default_user: &str,
+    default_port: u16,
+    target: (&str, u16),
+) -> Result<JumpHost, JumpHostError> {
+    let input = input.trim();
+    let (hop, key) = match input.split_once(char::is_whitespace) {
+        Some((hop, key)) => (hop, Some(key.trim())),
+        None => (input, None),
+    };
+    let (username, host, port) =
+        parse_target(hop, default_user, default_port).map_err(|_| JumpHostError::Invalid)?;
+    if host.eq_ignore_ascii_case(target.0) && port == target.1 {
+        return Err(JumpHostError::SameAsTarget);
+    }
+    let auth_method = match key {
+        Some(key) => {
+            let path = expand_home(key);
+            if !path.is_file() {
+                return Err(JumpHostError::KeyMissing(path));
+            }
+            AuthMethod::Key {
+                private_key_path: path,
+                passphrase_ref: None,
+            }
+        }
+        None => AuthMethod::Agent,
+    };
+    Ok(JumpHost {
+        host,
+        port,
+        username,
+        auth_method,
+    })
+}
+
 fn split_host_port(input: &str, default_port: u16) -> Result<(String, u16), ()> {
     let input = input.trim();
     if input.is_empty() {
@@ -6092,12 +12304,52 @@ fn agent_available() -> bool {
     std::env::var("SSH_AUTH_SOCK").is_ok()
 }
 
-fn should_prompt_password(err: &str) -> bool {
-    let lower = err.to_lowercase();
-    lower.contains("missing password")
-        || lower.contains("master password required")
-        || lower.contains("ssh_auth_sock")
-        || lower.contains("agent authentication failed")
+/// Fires a best-effort OS notification through the platform notifier
+/// (`notify-send` on Linux/BSD, `osascript` on macOS).
+fn send_desktop_notification(summary: &str, body: &str) {
+    let mut cmd = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {} with title {}",
+            applescript_string(body),
+            applescript_string(summary)
+        );
+        let mut cmd = Command::new("osascript");
+        cmd.arg("-e").arg(script);
+        cmd
+    } else if cfg!(windows) {
+        return;
+    } else {
+        let mut cmd = Command::new("notify-send");
+        cmd.arg("--app-name=catsolle").arg(summary).arg(body);
+        cmd
+    };
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    match cmd.spawn() {
+        Ok(mut child) => {
+            tokio::spawn(async move {
+                let _ = child.wait().await;
+            });
+        }
+        Err(err) => warn!("desktop notification failed: {}", err),
+    }
+}
+
+/// AppleScript string literal; only backslash and double quote need escaping.
+fn applescript_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn should_prompt_password(err: &CoreError) -> bool {
+    match err {
+        CoreError::CredentialsRequired(_) => true,
+        CoreError::Connect(err) => matches!(
+            err,
+            SshError::AgentUnavailable(_) | SshError::AgentAuthFailed
+        ),
+        _ => false,
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -6304,30 +12556,12 @@ fn parse_inline_markdown(text: &str, ctx: &MdRenderContext) -> Vec<Span<'static>
 #[cfg(test)]
 mod tests {
     use super::*;
+    use catsolle_core::memory_fs::MemoryFs;
 
     fn sample_connection(name: &str) -> Connection {
-        let now = chrono::Utc::now();
-        Connection {
-            id: Uuid::new_v4(),
-            name: name.to_string(),
-            host: "example.com".to_string(),
-            port: 22,
-            username: "root".to_string(),
-            auth_method: AuthMethod::Agent,
-            jump_hosts: Vec::new(),
-            proxy: None,
-            startup_commands: Vec::new(),
-            env_vars: Vec::new(),
-            group_id: None,
-            tags: Vec::new(),
-            color: None,
-            icon: None,
-            notes: None,
-            created_at: now,
-            updated_at: now,
-            last_connected_at: None,
-            is_favorite: false,
-        }
+        let mut conn = Connection::new("example.com".to_string(), 22, "root".to_string());
+        conn.name = name.to_string();
+        conn
     }
 
     #[test]
@@ -6347,6 +12581,50 @@ mod tests {
         assert!(calls.is_empty());
     }
 
+    #[test]
+    fn extracts_pretty_printed_tool_calls() {
+        let input = "Checking.\n@tool {\n  \"name\": \"remote.read\",\n  \"args\": {\"path\": \"/etc/{x}\"}\n}\nThen I will edit it.";
+        let (cleaned, calls) = extract_tool_calls(input);
+        assert_eq!(cleaned, "Checking.\nThen I will edit it.");
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].args["path"], "/etc/{x}");
+
+        let fenced =
+            "```@tool\n{\n  \"name\": \"local.list\",\n  \"args\": {}\n}\n```\nListing now.";
+        let (cleaned, calls) = extract_tool_calls(fenced);
+        assert_eq!(cleaned, "Listing now.");
+        assert_eq!(calls[0].name, "local.list");
+    }
+
+    #[test]
+    fn keeps_malformed_tool_blocks_as_text() {
+        let unclosed = "@tool {\n  \"name\": \"local.list\",\nno closing brace";
+        let (cleaned, calls) = extract_tool_calls(unclosed);
+        assert_eq!(cleaned, unclosed);
+        assert!(calls.is_empty());
+
+        let fenced = "```@tool\n{\"name\": }\n```\nDone";
+        let (cleaned, calls) = extract_tool_calls(fenced);
+        assert_eq!(cleaned, fenced);
+        assert!(calls.is_empty());
+    }
+
+    #[test]
+    fn extracts_clarifying_question() {
+        let content = "Need a detail.\n@ask Which port should nginx listen on?";
+        let (cleaned, question) = extract_question(content);
+        assert_eq!(
+            cleaned,
+            "Need a detail.\nWhich port should nginx listen on?"
+        );
+        assert_eq!(
+            question.as_deref(),
+            Some("Which port should nginx listen on?")
+        );
+        let (_, question) = extract_question("@ask\nplain text");
+        assert!(question.is_none());
+    }
+
     #[test]
     fn parses_assistant_commands() {
         assert_eq!(
@@ -6369,6 +12647,10 @@ mod tests {
             parse_assistant_command("/run").unwrap(),
             Some(AssistantCommand::Run)
         );
+        assert_eq!(
+            parse_assistant_command("/run all").unwrap(),
+            Some(AssistantCommand::RunAll)
+        );
         assert_eq!(parse_assistant_command("hello").unwrap(), None);
     }
 
@@ -6498,6 +12780,76 @@ mod tests {
         assert!(tail.is_empty());
     }
 
+    #[test]
+    fn splits_osc8_hyperlinks() {
+        let input = b"see \x1b]8;;https://example.com\x1b\\docs\x1b]8;;\x07 now";
+        let mut tail = Vec::new();
+        let chunks = split_osc8_chunks(input, &mut tail);
+        assert_eq!(
+            chunks,
+            vec![
+                TerminalChunk::Text(b"see ".to_vec()),
+                TerminalChunk::Link(Some("https://example.com".to_string())),
+                TerminalChunk::Text(b"docs".to_vec()),
+                TerminalChunk::Link(None),
+                TerminalChunk::Text(b" now".to_vec()),
+            ]
+        );
+        assert!(tail.is_empty());
+    }
+
+    #[test]
+    fn keeps_partial_osc8_in_tail() {
+        let mut tail = Vec::new();
+        let chunks = split_osc8_chunks(b"ab\x1b]8;id=1;https://exa", &mut tail);
+        assert_eq!(chunks, vec![TerminalChunk::Text(b"ab".to_vec())]);
+        let chunks = split_osc8_chunks(b"mple.com\x07x", &mut tail);
+        assert_eq!(
+            chunks,
+            vec![
+                TerminalChunk::Link(Some("https://example.com".to_string())),
+                TerminalChunk::Text(b"x".to_vec()),
+            ]
+        );
+        assert!(tail.is_empty());
+    }
+
+    #[test]
+    fn tracks_hyperlink_cells() {
+        let mut parser = Parser::new(4, 40, 0);
+        let mut links = HyperlinkTracker::default();
+        links.feed(&mut parser, b"go \x1b]8;;https://a.b\x07here\x1b]8;;\x07!");
+        assert!(links.link_at(0, 2).is_none());
+        assert_eq!(
+            links.link_at(0, 3).map(|l| l.uri.as_str()),
+            Some("https://a.b")
+        );
+        assert!(links.link_at(0, 7).is_none());
+        parser.process(b"\r\x1b[2K");
+        links.prune(parser.screen());
+        assert!(links.link_at(0, 3).is_none());
+    }
+
+    #[test]
+    fn line_diff_shows_changes_with_context() {
+        let old = "a\nb\nc\nd\ne\nf\ng\n";
+        let new = "a\nb\nc\nD\ne\nf\ng\nh\n";
+        assert_eq!(
+            line_diff(old, new, 1, 40),
+            "  c\n- d\n+ D\n  e\n...\n  g\n+ h"
+        );
+        assert_eq!(line_diff("same\n", "same\n", 2, 40), "(no changes)");
+    }
+
+    #[test]
+    fn write_diff_handles_append_and_binary() {
+        assert_eq!(
+            write_diff(b"one\n", b"two\n", true).as_deref(),
+            Some("  one\n+ two")
+        );
+        assert!(write_diff(&[0xff, 0xfe], b"x", false).is_none());
+    }
+
     #[test]
     fn tool_display_exec_summary() {
         let content =
@@ -6515,6 +12867,50 @@ mod tests {
         assert_eq!(ai_progress_bar(3), "[--->------]");
     }
 
+    #[test]
+    fn formats_timestamp_with_config() {
+        let ts = chrono::DateTime::parse_from_rfc3339("2024-03-05T14:07:09Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let mut ui = UiConfig {
+            timezone: "utc".to_string(),
+            ..UiConfig::default()
+        };
+        assert_eq!(format_timestamp(ts, &ui), "2024-03-05 14:07");
+        ui.datetime_format = "%d.%m.%Y".to_string();
+        assert_eq!(format_timestamp(ts, &ui), "05.03.2024");
+        ui.datetime_format = "%Q".to_string();
+        assert_eq!(format_timestamp(ts, &ui), "2024-03-05 14:07");
+    }
+
+    #[test]
+    fn buckets_relative_age() {
+        let now = chrono::Utc::now();
+        let ago = |secs: i64| now - chrono::Duration::seconds(secs);
+        assert_eq!(relative_age(now, ago(20)), Some(RelativeAge::JustNow));
+        assert_eq!(
+            relative_age(now, ago(5 * 60)),
+            Some(RelativeAge::Minutes(5))
+        );
+        assert_eq!(
+            relative_age(now, ago(3 * 3600)),
+            Some(RelativeAge::Hours(3))
+        );
+        assert_eq!(
+            relative_age(now, ago(30 * 3600)),
+            Some(RelativeAge::Yesterday)
+        );
+        assert_eq!(
+            relative_age(now, ago(4 * 86400)),
+            Some(RelativeAge::Days(4))
+        );
+        assert_eq!(
+            relative_age(now, ago(15 * 86400)),
+            Some(RelativeAge::Weeks(2))
+        );
+        assert_eq!(relative_age(now, ago(90 * 86400)), None);
+    }
+
     #[test]
     fn connection_target_without_name() {
         let conn = sample_connection("");
@@ -6551,4 +12947,1513 @@ mod tests {
         let screen = "Processing triggers for man-db...";
         assert_eq!(detect_interactive_prompt_text(screen), None);
     }
+
+    #[test]
+    fn rejects_html_ai_responses_with_snippet() {
+        assert!(is_json_content_type(
+            "application/json; charset=utf-8",
+            false
+        ));
+        assert!(is_json_content_type("application/x-ndjson", true));
+        assert!(is_json_content_type("text/event-stream", true));
+        assert!(!is_json_content_type("text/event-stream", false));
+        assert!(!is_json_content_type("text/html; charset=utf-8", true));
+        assert_eq!(
+            body_snippet("<html>\n  <body>Login</body>\n</html>"),
+            "<html> <body>Login</body> </html>"
+        );
+        let err = parse_json::<serde_json::Value>("<!doctype html>").unwrap_err();
+        assert!(err.to_string().ends_with(": <!doctype html>"));
+    }
+
+    #[test]
+    fn maps_chat_messages_to_gemini_turns() {
+        let message = |role: &str, content: &str| ChatMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+        };
+        let (system, contents) = gemini_contents(vec![
+            message("system", "be brief"),
+            message("user", "hi"),
+            message("assistant", "hello"),
+            message("user", "tool output"),
+            message("user", "next"),
+        ]);
+        assert_eq!(system.as_deref(), Some("be brief"));
+        let roles: Vec<_> = contents
+            .iter()
+            .map(|c| (c.role.as_deref().unwrap(), c.parts.len()))
+            .collect();
+        assert_eq!(roles, vec![("user", 1), ("model", 1), ("user", 2)]);
+
+        let data: GeminiResponse = serde_json::from_str(
+            r#"{"candidates":[{"content":{"role":"model","parts":[{"text":"a"},{"text":"b"}]}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(gemini_text(&data).as_deref(), Some("ab"));
+    }
+
+    #[test]
+    fn groups_connections_under_collapsible_headers() {
+        let group = ConnectionGroup {
+            id: Uuid::new_v4(),
+            name: "prod".to_string(),
+            parent_id: None,
+            sort_order: 0,
+        };
+        let mut grouped = sample_connection("web");
+        grouped.group_id = Some(group.id);
+        let mut orphan = sample_connection("old");
+        orphan.group_id = Some(Uuid::new_v4());
+        let connections = vec![sample_connection("db"), grouped, orphan];
+        let mut collapsed = HashSet::new();
+
+        assert_eq!(
+            connection_rows(&[], &connections, &collapsed).len(),
+            connections.len()
+        );
+        let groups = vec![group.clone()];
+        assert_eq!(
+            connection_rows(&groups, &connections, &collapsed),
+            vec![
+                ConnectionRow::Group(Some(group.id)),
+                ConnectionRow::Connection(1),
+                ConnectionRow::Group(None),
+                ConnectionRow::Connection(0),
+                ConnectionRow::Connection(2),
+            ]
+        );
+        collapsed.insert(Some(group.id));
+        assert_eq!(
+            connection_rows(&groups, &connections, &collapsed)[..2],
+            [
+                ConnectionRow::Group(Some(group.id)),
+                ConnectionRow::Group(None)
+            ]
+        );
+    }
+
+    #[test]
+    fn fuzzy_search_ranks_connections() {
+        let mut db = sample_connection("db-primary");
+        db.host = "10.0.0.5".to_string();
+        let mut web = sample_connection("web");
+        web.host = "prod-web.example.com".to_string();
+        let mut backup = sample_connection("backup");
+        backup.username = "deploy".to_string();
+        let connections = vec![backup, db, web];
+
+        assert_eq!(fuzzy_score("dbp", "db-primary"), Some(12));
+        assert!(fuzzy_score("web", "web") > fuzzy_score("web", "w-e-b"));
+        assert_eq!(fuzzy_score("xyz", "web"), None);
+        assert_eq!(
+            search_connection_rows(&connections, "web"),
+            vec![ConnectionRow::Connection(2)]
+        );
+        assert_eq!(
+            search_connection_rows(&connections, "DEPLOY"),
+            vec![ConnectionRow::Connection(0)]
+        );
+        assert_eq!(
+            search_connection_rows(&connections, "p"),
+            vec![
+                ConnectionRow::Connection(2),
+                ConnectionRow::Connection(1),
+                ConnectionRow::Connection(0),
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_connections_by_auth_method() {
+        let mut password = sample_connection("legacy");
+        password.auth_method = AuthMethod::Password {
+            secret_ref: String::new(),
+        };
+        let connections = vec![sample_connection("db"), password, sample_connection("web")];
+        let mut collapsed = HashSet::new();
+        assert_eq!(
+            connection_rows_by_auth(&connections, &collapsed),
+            vec![
+                ConnectionRow::Auth("password"),
+                ConnectionRow::Connection(1),
+                ConnectionRow::Auth("agent"),
+                ConnectionRow::Connection(0),
+                ConnectionRow::Connection(2),
+            ]
+        );
+        collapsed.insert("password");
+        assert_eq!(
+            connection_rows_by_auth(&connections, &collapsed)[..2],
+            [
+                ConnectionRow::Auth("password"),
+                ConnectionRow::Auth("agent")
+            ]
+        );
+        assert_eq!(
+            ConnectionListMode::default().next().next(),
+            ConnectionListMode::Groups
+        );
+        assert_eq!(password_connection_names(&connections), ["legacy"]);
+    }
+
+    #[test]
+    fn resolves_and_cycles_themes() {
+        for name in THEME_NAMES {
+            assert!(Theme::from_name(name).is_some());
+        }
+        assert!(Theme::from_name(" Kawaii ").is_some());
+        assert!(Theme::from_name("solarized").is_none());
+        assert_eq!(next_theme_name("neko-dark"), "neko-light");
+        assert_eq!(next_theme_name("mono"), "neko-dark");
+        assert_eq!(next_theme_name("solarized"), "neko-dark");
+    }
+
+    #[test]
+    fn names_recordings_after_the_connection() {
+        use chrono::TimeZone;
+        let at = chrono::Local.with_ymd_and_hms(2026, 3, 4, 5, 6, 7).unwrap();
+        assert_eq!(
+            recording_file_name("prod / db", at),
+            "20260304-050607-prod___db.cast"
+        );
+        assert_eq!(recording_file_name("../x", at), "20260304-050607-_x.cast");
+    }
+
+    #[test]
+    fn parses_key_descriptors() {
+        for action in KeyAction::ALL {
+            assert_eq!(KeyAction::from_name(action.name()), Some(action));
+            for spec in action.default_keys() {
+                assert!(KeyBinding::parse(spec).is_ok(), "{spec}");
+            }
+        }
+        let cases = [
+            ("F5", KeyCode::F(5), KeyModifiers::NONE),
+            ("ctrl+T", KeyCode::Char('t'), KeyModifiers::CONTROL),
+            ("shift+F6", KeyCode::F(6), KeyModifiers::SHIFT),
+            ("shift+s", KeyCode::Char('S'), KeyModifiers::NONE),
+            ("alt+Enter", KeyCode::Enter, KeyModifiers::ALT),
+            ("ctrl++", KeyCode::Char('+'), KeyModifiers::CONTROL),
+            ("space", KeyCode::Char(' '), KeyModifiers::NONE),
+        ];
+        for (spec, code, modifiers) in cases {
+            assert_eq!(
+                KeyBinding::parse(spec).unwrap(),
+                KeyBinding { code, modifiers },
+                "{spec}"
+            );
+        }
+        assert!(KeyBinding::parse("hyper+x").is_err());
+        assert!(KeyBinding::parse("F25").is_err());
+        assert!(KeyBinding::parse("ab").is_err());
+    }
+
+    #[test]
+    fn keymap_overrides_defaults() {
+        let key = |code, modifiers| KeyEvent::new(code, modifiers);
+        let defaults = Keymap::default();
+        assert_eq!(
+            defaults.action(&key(KeyCode::Char('S'), KeyModifiers::SHIFT), true),
+            Some(KeyAction::SyncDirectory)
+        );
+        assert_eq!(
+            defaults.action(&key(KeyCode::F(6), KeyModifiers::NONE), false),
+            Some(KeyAction::ToggleTools)
+        );
+        assert_eq!(
+            defaults.action(&key(KeyCode::F(6), KeyModifiers::NONE), true),
+            Some(KeyAction::PauseTransfer)
+        );
+        assert_eq!(
+            defaults.action(&key(KeyCode::F(8), KeyModifiers::NONE), true),
+            Some(KeyAction::ToggleAuto)
+        );
+        assert_eq!(
+            defaults.action(&key(KeyCode::Char('S'), KeyModifiers::SHIFT), false),
+            None
+        );
+
+        let keymap =
+            Keymap::parse("copy = [\"F5\", \"ctrl+c\"]\nrename = \"s\"\nmkdir = []\n").unwrap();
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('c'), KeyModifiers::CONTROL), true),
+            Some(KeyAction::Copy)
+        );
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('s'), KeyModifiers::NONE), true),
+            Some(KeyAction::Rename)
+        );
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('r'), KeyModifiers::NONE), true),
+            None
+        );
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('m'), KeyModifiers::NONE), true),
+            None
+        );
+        let cancel = Keymap::parse("cancel_transfer = \"F8\"").unwrap();
+        assert_eq!(
+            cancel.action(&key(KeyCode::F(8), KeyModifiers::NONE), true),
+            Some(KeyAction::CancelTransfer)
+        );
+        assert_eq!(
+            cancel.action(&key(KeyCode::F(8), KeyModifiers::NONE), false),
+            None
+        );
+        assert!(Keymap::parse("launch = \"F1\"").is_err());
+        assert!(Keymap::parse("copy = \"F99\"").is_err());
+    }
+
+    #[test]
+    fn summarizes_binary_reads() {
+        let text = read_tool_output("a.txt".to_string(), "héllo".as_bytes(), false);
+        assert_eq!(text.content.as_deref(), Some("héllo"));
+        assert!(text.binary.is_none());
+        let cut = read_tool_output("a.txt".to_string(), &"héllo".as_bytes()[..2], true);
+        assert_eq!(cut.content.as_deref(), Some("h"));
+        assert!(cut.truncated);
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend_from_slice(&[0u8; 100]);
+        let image = read_tool_output("a.png".to_string(), &png, false);
+        assert!(image.content.is_none());
+        let binary = image.binary.unwrap();
+        assert_eq!(binary.kind, "png image");
+        assert_eq!(binary.size, 108);
+        assert_eq!(binary.head_hex.len(), TOOL_BINARY_HEAD_BYTES * 2);
+
+        assert!(looks_binary(b"caf\xe9 latin-1", false));
+        let mut long = "a".repeat(TOOL_BINARY_SNIFF_BYTES - 1).into_bytes();
+        long.extend_from_slice("é".as_bytes());
+        assert!(!looks_binary(&long, false));
+    }
+
+    #[test]
+    fn formats_permission_bits_as_octal() {
+        assert_eq!(format_mode(0o100644), "0644");
+        assert_eq!(format_mode(0o40755), "0755");
+        assert_eq!(format_mode(0o104755), "4755");
+    }
+
+    #[test]
+    fn diffs_text_and_binary_files() {
+        let diff = file_diff("a", "b", b"one\ntwo\n", b"one\nthree\n");
+        assert!(diff.starts_with("--- a\n+++ b\n"));
+        assert!(diff.contains("-two\n+three\n"));
+        assert_eq!(file_diff("a", "b", b"same", b"same"), "(no changes)");
+        assert_eq!(
+            file_diff("a", "b", b"\xff\x00\x01", b"\xff\x02"),
+            "binary, 2 bytes differ"
+        );
+    }
+
+    #[test]
+    fn allowlist_follows_blocked_tools() {
+        let mut cfg = AiConfig::default();
+        let mut state = AiSettingsState::from_config(&cfg);
+        assert_eq!(state.draft.allowed_tools(), Some(Vec::new()));
+
+        state.toggle_field(AiSettingsField::AllowedTools);
+        let allowed = state.draft.allowed_tools().unwrap();
+        assert_eq!(allowed.len(), TOOLS.len() - 1);
+        assert!(!allowed.iter().any(|name| name == "local.exec"));
+
+        cfg.allowed_tools = vec!["local.list".to_string()];
+        let mut state = AiSettingsState::from_config(&cfg);
+        state.tool_cursor = TOOLS.iter().position(|(n, _)| *n == "local.list").unwrap();
+        state.toggle_field(AiSettingsField::AllowedTools);
+        assert_eq!(state.draft.allowed_tools(), None);
+
+        let defs = tool_definitions(&cfg);
+        assert!(defs.iter().any(|line| line.starts_with("- local.list ")));
+        assert!(!defs.iter().any(|line| line.starts_with("- local.exec ")));
+        assert_eq!(defs[0], "Tools:");
+    }
+
+    /// Answers one HTTP request with `body` and hands back the request head (lowercased)
+    /// and JSON body, standing in for a provider API.
+    async fn serve_once(
+        content_type: &'static str,
+        body: String,
+    ) -> (String, tokio::task::JoinHandle<(String, serde_json::Value)>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 4096];
+            let (head, body_start, length) = loop {
+                let n = stream.read(&mut chunk).await.unwrap();
+                assert!(n > 0, "client closed before sending a request");
+                buf.extend_from_slice(&chunk[..n]);
+                if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                    let head = String::from_utf8_lossy(&buf[..pos]).to_ascii_lowercase();
+                    let length = head
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length:"))
+                        .map(|v| v.trim().parse::<usize>().unwrap())
+                        .unwrap_or(0);
+                    break (head, pos + 4, length);
+                }
+            };
+            while buf.len() < body_start + length {
+                let n = stream.read(&mut chunk).await.unwrap();
+                buf.extend_from_slice(&chunk[..n]);
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            let request = serde_json::from_slice(&buf[body_start..body_start + length]).unwrap();
+            (head, request)
+        });
+        (base, server)
+    }
+
+    fn provider_config(provider: &str, endpoint: String) -> AiConfig {
+        AiConfig {
+            provider: provider.to_string(),
+            endpoint,
+            api_key: Some("secret".to_string()),
+            model: "test-model".to_string(),
+            ..AiConfig::default()
+        }
+    }
+
+    fn chat(role: &str, content: &str) -> ChatMessage {
+        ChatMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    fn test_client() -> reqwest::Client {
+        reqwest::Client::builder().no_proxy().build().unwrap()
+    }
+
+    fn deltas(rx: &mut mpsc::Receiver<AssistantEvent>) -> Vec<String> {
+        let mut out = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let AssistantEvent::Delta(text) = event {
+                out.push(text);
+            }
+        }
+        out
+    }
+
+    #[tokio::test]
+    async fn openai_request_and_reply() {
+        let (base, server) = serve_once(
+            "application/json",
+            r#"{"choices":[{"message":{"content":"checking","tool_calls":[{"id":"c1","type":"function","function":{"name":"local__list","arguments":"{\"path\":\"/tmp\"}"}}]}}]}"#.to_string(),
+        )
+        .await;
+        let mut cfg = provider_config("openai", format!("{base}/"));
+        cfg.native_tools = true;
+        let reply = request_ai(
+            test_client(),
+            &cfg,
+            vec![chat("system", "be brief"), chat("user", "list /tmp")],
+        )
+        .await
+        .unwrap();
+        assert_eq!(reply.content, "checking");
+        assert_eq!(reply.tool_calls[0].name, "local.list");
+        assert_eq!(reply.tool_calls[0].args["path"], "/tmp");
+
+        let (head, body) = server.await.unwrap();
+        assert!(head.starts_with("post /v1/chat/completions "));
+        assert!(head.contains("authorization: bearer secret"));
+        assert_eq!(body["model"], "test-model");
+        assert_eq!(body["stream"], false);
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["messages"][1]["content"], "list /tmp");
+        assert!(!body["tools"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn openai_stream_collects_deltas() {
+        let events = [
+            r#"{"choices":[{"delta":{"content":"hel"}}]}"#,
+            r#"{"choices":[{"delta":{"content":"lo"}}]}"#,
+            r#"{"choices":[]}"#,
+        ];
+        let mut sse: String = events.iter().map(|e| format!("data: {e}\n\n")).collect();
+        sse.push_str(": keep-alive\n\ndata: [DONE]\n\n");
+        let (base, server) = serve_once("text/event-stream", sse).await;
+        let cfg = provider_config("openai-compatible", base);
+        let (tx, mut rx) = mpsc::channel(16);
+        let reply = request_ai_stream(test_client(), &cfg, vec![chat("user", "hi")], tx)
+            .await
+            .unwrap();
+        assert_eq!(reply.content, "hello");
+        assert!(reply.tool_calls.is_empty());
+        assert_eq!(deltas(&mut rx), ["hel", "lo"]);
+        let (_, body) = server.await.unwrap();
+        assert_eq!(body["stream"], true);
+        assert!(body.get("tools").is_none());
+    }
+
+    #[tokio::test]
+    async fn ollama_request_and_reply() {
+        let (base, server) = serve_once(
+            "application/json",
+            r#"{"message":{"role":"assistant","content":"pong"},"done":true}"#.to_string(),
+        )
+        .await;
+        let cfg = provider_config("ollama", base);
+        let reply = request_ai(test_client(), &cfg, vec![chat("user", "ping")])
+            .await
+            .unwrap();
+        assert_eq!(reply.content, "pong");
+        let (head, body) = server.await.unwrap();
+        assert!(head.starts_with("post /api/chat "));
+        assert_eq!(body["stream"], false);
+        assert_eq!(body["options"]["num_predict"], cfg.max_tokens);
+        assert_eq!(body["messages"][0]["role"], "user");
+
+        let lines = [
+            r#"{"message":{"content":"po"},"done":false}"#,
+            "",
+            r#"{"message":{"content":"ng"},"done":false}"#,
+            r#"{"done":true}"#,
+        ]
+        .join("\n");
+        let (base, server) = serve_once("application/x-ndjson", lines).await;
+        let cfg = provider_config("ollama", base);
+        let (tx, mut rx) = mpsc::channel(16);
+        let reply = request_ai_stream(test_client(), &cfg, vec![chat("user", "ping")], tx)
+            .await
+            .unwrap();
+        assert_eq!(reply.content, "pong");
+        assert_eq!(deltas(&mut rx), ["po", "ng"]);
+        assert_eq!(server.await.unwrap().1["stream"], true);
+    }
+
+    #[tokio::test]
+    async fn anthropic_request_and_reply() {
+        let (base, server) = serve_once(
+            "application/json",
+            r#"{"content":[{"type":"text","text":"hi there"}]}"#.to_string(),
+        )
+        .await;
+        let cfg = provider_config("anthropic", base);
+        let messages = vec![chat("system", "rules"), chat("user", "hi")];
+        let reply = request_ai(test_client(), &cfg, messages.clone())
+            .await
+            .unwrap();
+        assert_eq!(reply.content, "hi there");
+        let (head, body) = server.await.unwrap();
+        assert!(head.starts_with("post /v1/messages "));
+        assert!(head.contains("x-api-key: secret"));
+        assert!(head.contains("anthropic-version: 2023-06-01"));
+        assert_eq!(body["system"], "rules");
+        assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+
+        let events = [
+            r#"{"type":"message_start"}"#,
+            r#"{"type":"content_block_start","content_block":{"type":"text","text":""}}"#,
+            r#"{"type":"content_block_delta","delta":{"type":"text_delta","text":"hi "}}"#,
+            r#"{"type":"content_block_delta","delta":{"type":"text_delta","text":"there"}}"#,
+            r#"{"type":"message_stop"}"#,
+        ];
+        let sse: String = events
+            .iter()
+            .map(|e| format!("event: x\ndata: {e}\n\n"))
+            .collect();
+        let (base, server) = serve_once("text/event-stream", sse).await;
+        let cfg = provider_config("anthropic", base);
+        let (tx, mut rx) = mpsc::channel(16);
+        let reply = request_ai_stream(test_client(), &cfg, messages, tx)
+            .await
+            .unwrap();
+        assert_eq!(reply.content, "hi there");
+        assert_eq!(deltas(&mut rx), ["hi ", "there"]);
+        assert_eq!(server.await.unwrap().1["stream"], true);
+
+        let (base, _server) = serve_once(
+            "text/event-stream",
+            "data: {\"type\":\"error\",\"error\":{\"message\":\"overloaded\"}}\n\n".to_string(),
+        )
+        .await;
+        let cfg = provider_config("anthropic", base);
+        let (tx, _rx) = mpsc::channel(16);
+        let err = request_ai_stream(test_client(), &cfg, vec![chat("user", "hi")], tx)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "anthropic error: overloaded");
+    }
+
+    #[tokio::test]
+    async fn retries_only_transient_ai_errors() {
+        let http = |code: u16| -> anyhow::Error {
+            AiHttpError::new(
+                "ai",
+                reqwest::StatusCode::from_u16(code).unwrap(),
+                String::new(),
+            )
+            .into()
+        };
+        assert!(is_transient_ai_error(&http(429)));
+        assert!(is_transient_ai_error(&http(503)));
+        assert!(!is_transient_ai_error(&http(401)));
+        assert!(is_transient_ai_error(&anyhow::Error::new(io::Error::from(
+            io::ErrorKind::ConnectionReset
+        ))));
+        assert!(!is_transient_ai_error(&anyhow::anyhow!(
+            "invalid JSON response"
+        )));
+        assert_eq!(
+            http(500).to_string(),
+            "ai error 500 Internal Server Error: "
+        );
+        assert_eq!(ai_retry_delay(0), Duration::from_millis(AI_RETRY_BASE_MS));
+        assert_eq!(ai_retry_delay(30), Duration::from_millis(AI_RETRY_MAX_MS));
+
+        let calls = std::cell::Cell::new(0);
+        let result = retry_ai(2, || {
+            calls.set(calls.get() + 1);
+            let attempt = calls.get();
+            async move {
+                if attempt == 1 {
+                    (Err(http(503)), true)
+                } else {
+                    (Ok(attempt), true)
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 2);
+
+        for (error, repeatable) in [(http(401), true), (http(503), false)] {
+            calls.set(0);
+            let mut error = Some(error);
+            let result: Result<()> = retry_ai(2, || {
+                calls.set(calls.get() + 1);
+                let error = error.take().unwrap_or_else(|| http(503));
+                async move { (Err(error), repeatable) }
+            })
+            .await;
+            assert!(result.is_err());
+            assert_eq!(calls.get(), 1);
+        }
+    }
+
+    #[test]
+    fn converts_native_tool_calls() {
+        let spec = native_tool_spec(
+            "- local.hash {path, algorithm? (sha256 default, sha1, md5)} returns the hex digest",
+        )
+        .unwrap();
+        assert_eq!(spec["function"]["name"], "local__hash");
+        assert_eq!(spec["function"]["description"], "returns the hex digest");
+        let params = &spec["function"]["parameters"];
+        assert_eq!(params["required"], serde_json::json!(["path"]));
+        assert_eq!(
+            params["properties"]["algorithm"]["description"],
+            "sha256 default, sha1, md5"
+        );
+        assert!(native_tool_spec("Tools:").is_none());
+        let ai = AiConfig::default();
+        assert_eq!(native_tool_specs(&ai).len(), TOOLS.len());
+
+        let data: OpenAiResponse = serde_json::from_str(
+            r#"{"choices":[{"message":{"content":null,"tool_calls":[{"id":"c1","type":"function","function":{"name":"remote__shell__exec","arguments":"{\"input\":\"ls\"}"}}]}}]}"#,
+        )
+        .unwrap();
+        let message = data.choices.into_iter().next().unwrap().message;
+        assert!(message.content.is_none());
+        let call = message
+            .tool_calls
+            .unwrap()
+            .remove(0)
+            .function
+            .into_tool_call();
+        assert_eq!(call.name, "remote.shell.exec");
+        assert_eq!(call.args["input"], "ls");
+
+        let mut calls = Vec::new();
+        for chunk in [
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"name":"transfer__copy_selected","arguments":""}}]}}]}"#,
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{}"}}]}}]}"#,
+        ] {
+            let chunk: OpenAiStreamResponse = serde_json::from_str(chunk).unwrap();
+            let delta = chunk.choices.into_iter().next().unwrap().delta;
+            merge_tool_call_deltas(&mut calls, delta.tool_calls.unwrap());
+        }
+        let call = calls.remove(0).into_tool_call();
+        assert_eq!(call.name, "transfer.copy_selected");
+        assert!(call.args.is_object());
+    }
+
+    #[test]
+    fn compares_panel_entries() {
+        let file = |name: &str, size: u64, modified: Option<u64>| FileEntry {
+            name: name.to_string(),
+            is_dir: false,
+            size,
+            modified,
+        };
+        let dir = |name: &str| FileEntry {
+            name: name.to_string(),
+            is_dir: true,
+            size: 4096,
+            modified: Some(1),
+        };
+        let local = vec![
+            file("same.txt", 10, Some(100)),
+            file("touched.txt", 10, Some(100)),
+            file("grown.txt", 10, None),
+            file("new.txt", 1, None),
+            dir("src"),
+            dir("build"),
+        ];
+        let remote = vec![
+            file("same.txt", 10, Some(101)),
+            file("touched.txt", 10, Some(200)),
+            file("grown.txt", 12, None),
+            dir("src"),
+            file("build", 0, None),
+            file("old.log", 5, None),
+        ];
+        assert_eq!(entry_diff(&local[0], Some(&remote[0])), EntryDiff::Same);
+        assert_eq!(entry_diff(&local[1], Some(&remote[1])), EntryDiff::Differs);
+        assert_eq!(entry_diff(&local[3], None), EntryDiff::OnlyHere);
+        assert_eq!(
+            compare_summary(&local, &remote),
+            CompareSummary {
+                only_local: 1,
+                only_remote: 1,
+                differ: 3,
+            }
+        );
+        let names: Vec<&str> = sync_candidates(&local, &remote)
+            .into_iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(names, ["touched.txt", "grown.txt", "new.txt", "src"]);
+    }
+
+    #[test]
+    fn saves_conversation_without_system_notes() {
+        let i18n = I18n::new("en", &[]).unwrap();
+        let mut state = AssistantState::new(&i18n);
+        for (role, content) in [
+            (AssistantRole::User, "one"),
+            (AssistantRole::Assistant, "two"),
+            (AssistantRole::System, "note"),
+            (AssistantRole::Tool, "three"),
+        ] {
+            state.messages.push(AssistantMessage {
+                role,
+                content: content.to_string(),
+            });
+        }
+        let saved = conversation_to_save(&state.messages, 2);
+        let contents: Vec<&str> = saved.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["two", "three"]);
+        assert_eq!(conversation_to_save(&state.messages, 0).len(), 3);
+
+        let json = serde_json::to_string(&saved).unwrap();
+        assert!(json.contains(r#""role":"tool""#));
+        let restored: Vec<AssistantMessage> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored[1].role, AssistantRole::Tool);
+    }
+
+    #[test]
+    fn recalls_prompt_history() {
+        let i18n = I18n::new("en", &[]).unwrap();
+        let mut state = AssistantState::new(&i18n);
+        assert!(!state.history_prev());
+        state.record_prompt("first");
+        state.record_prompt("second");
+        state.record_prompt("second");
+        assert_eq!(state.history, ["first", "second"]);
+
+        state.input = "draft".to_string();
+        assert!(state.history_prev());
+        assert_eq!(state.input, "second");
+        assert!(state.history_prev());
+        assert!(state.history_prev());
+        assert_eq!(state.input, "first");
+        assert!(state.history_next());
+        assert_eq!(state.input, "second");
+        assert!(state.history_next());
+        assert_eq!(state.input, "draft");
+        assert!(!state.history_next());
+
+        for idx in 0..PROMPT_HISTORY_MAX + 5 {
+            state.record_prompt(&idx.to_string());
+        }
+        assert_eq!(state.history.len(), PROMPT_HISTORY_MAX);
+        assert_eq!(state.history[0], "5");
+    }
+
+    #[test]
+    fn follows_up_only_within_budget() {
+        let mut ai = AiConfig {
+            agent_enabled: false,
+            ..AiConfig::default()
+        };
+        assert!(!ai_loop_enabled(&ai));
+        assert!(!should_follow_up(&ai, 3, true));
+
+        ai.continue_after_tool = true;
+        assert!(ai_loop_enabled(&ai));
+        assert!(should_follow_up(&ai, 1, true));
+        assert!(!should_follow_up(&ai, 1, false));
+        assert!(!should_follow_up(&ai, 0, true));
+
+        ai.agent_enabled = true;
+        assert!(should_follow_up(&ai, 1, false));
+        assert!(!should_follow_up(&ai, 0, false));
+    }
+
+    #[test]
+    fn flags_destructive_tools() {
+        for name in [
+            "local.exec",
+            "remote.shell.exec",
+            "remote.exec",
+            "local.write",
+            "remote.write",
+            "local.remove",
+            "remote.remove",
+            "local.rename",
+            "remote.rename",
+            "local.chmod",
+            "remote.chmod",
+            "transfer.copy",
+            "transfer.copy_selected",
+        ] {
+            assert!(is_destructive_tool(name), "{name}");
+        }
+        for name in [
+            "local.list",
+            "remote.read",
+            "remote.stat",
+            "diff",
+            "unknown",
+        ] {
+            assert!(!is_destructive_tool(name), "{name}");
+        }
+        for (name, mutating) in TOOLS {
+            assert_eq!(is_destructive_tool(name), mutating, "{name}");
+        }
+    }
+
+    #[tokio::test]
+    async fn mirror_target_stops_at_directories_missing_on_the_other_side() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("both")).unwrap();
+        std::fs::write(dir.path().join("file"), b"").unwrap();
+        let mut local = PanelState::local_default();
+        local.path = dir.path().to_string_lossy().to_string();
+        let both = dir.path().join("both").to_string_lossy().to_string();
+        assert_eq!(
+            mirror_target(&local, Some("both"), None).await,
+            Ok(Some(both))
+        );
+        let missing = dir.path().join("only-here").to_string_lossy().to_string();
+        assert_eq!(
+            mirror_target(&local, Some("only-here"), None).await,
+            Err(missing)
+        );
+        let file = dir.path().join("file").to_string_lossy().to_string();
+        assert_eq!(mirror_target(&local, Some("file"), None).await, Err(file));
+
+        let fs = MemoryFs::default()
+            .with_dir("/")
+            .with_dir("/srv")
+            .with_dir("/srv/both");
+        let mut remote = PanelState::remote_default();
+        remote.path = "/srv".to_string();
+        assert_eq!(
+            mirror_target(&remote, Some("both"), Some(&fs)).await,
+            Ok(Some("/srv/both".to_string()))
+        );
+        assert_eq!(
+            mirror_target(&remote, Some("only-here"), Some(&fs)).await,
+            Err("/srv/only-here".to_string())
+        );
+        assert_eq!(
+            mirror_target(&remote, None, Some(&fs)).await,
+            Ok(Some("/".to_string()))
+        );
+        assert_eq!(
+            mirror_target(&remote, Some("both"), None).await,
+            Err("/srv/both".to_string())
+        );
+        remote.path = "/".to_string();
+        assert_eq!(mirror_target(&remote, None, Some(&fs)).await, Ok(None));
+    }
+
+    #[test]
+    fn parses_transfer_dir_pairs() {
+        let downloads = expand_home("~/Downloads");
+        if downloads.is_absolute() {
+            assert_eq!(
+                parse_transfer_dirs(" ~/Downloads | /srv/uploads "),
+                Some((
+                    Some(downloads.to_string_lossy().to_string()),
+                    Some("/srv/uploads".to_string())
+                ))
+            );
+        }
+        assert_eq!(
+            parse_transfer_dirs("|/var/www"),
+            Some((None, Some("/var/www".to_string())))
+        );
+        assert_eq!(parse_transfer_dirs(""), Some((None, None)));
+        assert_eq!(parse_transfer_dirs("/tmp|relative"), None);
+        assert_eq!(parse_transfer_dirs("Downloads|/srv"), None);
+        assert_eq!(parse_transfer_dirs("./out"), None);
+    }
+
+    #[test]
+    fn parses_octal_modes() {
+        assert_eq!(parse_mode("600").unwrap(), 0o600);
+        assert_eq!(parse_mode("0755").unwrap(), 0o755);
+        assert_eq!(parse_mode("0o4755").unwrap(), 0o4755);
+        assert!(parse_mode("").is_err());
+        assert!(parse_mode("789").is_err());
+        assert!(parse_mode("17777").is_err());
+    }
+
+    #[test]
+    fn builds_quoted_grep_commands() {
+        let command = grep_command("/srv/my app", "it's $(rm -rf /)", "-i -w -i").unwrap();
+        assert!(
+            command.ends_with("grep -rniw -e 'it'\\''s $(rm -rf /)' -- '/srv/my app' 2>/dev/null")
+        );
+        assert!(grep_command("/srv", "x", "-r").is_err());
+        assert!(grep_command("/srv", "x", "i")
+            .unwrap()
+            .contains("grep -rni -e x -- /srv"));
+        assert!(grep_command("/srv", "x", "; rm").is_err());
+        assert_eq!(
+            applescript_string(r#"say "hi" \ bye"#),
+            r#""say \"hi\" \\ bye""#
+        );
+    }
+
+    #[test]
+    fn maps_mouse_rows_and_scrolls_panels() {
+        let area = Rect::new(0, 5, 40, 10);
+        assert_eq!(panel_row_at(area, 5), None);
+        assert_eq!(panel_row_at(area, 6), Some(0));
+        assert_eq!(panel_row_at(area, 13), Some(7));
+        assert_eq!(panel_row_at(area, 14), None);
+
+        let mut panel = PanelState::local_default();
+        panel.entries = (0..20)
+            .map(|i| FileEntry {
+                name: format!("f{i}"),
+                is_dir: false,
+                size: 0,
+                modified: None,
+            })
+            .collect();
+        let visible = panel_visible_rows(area);
+        panel.scroll_by(MOUSE_SCROLL_LINES, visible);
+        assert_eq!((panel.scroll, panel.selected), (3, 3));
+        panel.scroll_by(100, visible);
+        assert_eq!((panel.scroll, panel.selected), (12, 12));
+        panel.selected = 19;
+        panel.scroll_by(-MOUSE_SCROLL_LINES, visible);
+        assert_eq!((panel.scroll, panel.selected), (9, 16));
+        panel.ensure_visible(visible);
+        assert_eq!(panel.scroll, 9);
+    }
+
+    #[test]
+    fn measures_and_clamps_scrollback() {
+        let mut parser = Parser::new(4, 20, 10);
+        assert_eq!(scrollback_len(&mut parser), 0);
+        for i in 0..6 {
+            parser.process(format!("line {i}\r\n").as_bytes());
+        }
+        assert_eq!(scrollback_len(&mut parser), 3);
+        assert_eq!(parser.screen().scrollback(), 0);
+        for i in 0..20 {
+            parser.process(format!("more {i}\r\n").as_bytes());
+        }
+        assert_eq!(scrollback_len(&mut parser), 10);
+        parser.set_scrollback(2);
+        assert_eq!(scrollback_len(&mut parser), 10);
+        assert_eq!(parser.screen().scrollback(), 2);
+    }
+
+    #[test]
+    fn sorts_panels_and_keeps_selection() {
+        let entry = |name: &str, is_dir: bool, size: u64| FileEntry {
+            name: name.to_string(),
+            is_dir,
+            size,
+            modified: None,
+        };
+        let mut panel = PanelState::local_default();
+        panel.entries = vec![
+            entry("b.txt", false, 10),
+            entry("src", true, 0),
+            entry("a.txt", false, 300),
+            entry("docs", true, 0),
+        ];
+        let names = |panel: &PanelState| {
+            panel
+                .entries
+                .iter()
+                .map(|e| e.name.as_str())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        panel.sort.apply(&mut panel.entries);
+        assert_eq!(names(&panel), "docs,src,a.txt,b.txt");
+        panel.selected = 3;
+        panel.cycle_sort();
+        assert_eq!(panel.sort, SortMode::NameAsc);
+        assert_eq!(names(&panel), "a.txt,b.txt,docs,src");
+        assert_eq!(panel.selected, 1);
+        panel.cycle_sort();
+        assert_eq!(names(&panel), "src,docs,b.txt,a.txt");
+        panel.cycle_sort();
+        assert_eq!(names(&panel), "a.txt,b.txt,docs,src");
+        panel.cycle_sort();
+        assert_eq!(panel.sort, SortMode::DirsFirst);
+    }
+
+    #[test]
+    fn doubles_reconnect_delay_up_to_a_cap() {
+        assert_eq!(reconnect_delay(1000, 1), Duration::from_millis(1000));
+        assert_eq!(reconnect_delay(1000, 3), Duration::from_millis(4000));
+        assert_eq!(
+            reconnect_delay(1000, 10),
+            Duration::from_millis(RECONNECT_MAX_DELAY_MS)
+        );
+        assert_eq!(reconnect_delay(0, 2), Duration::ZERO);
+    }
+
+    #[test]
+    fn parses_jump_hosts() {
+        let hop = parse_jump_host("admin@bastion:2200", "me", 22, ("box", 22)).unwrap();
+        assert_eq!(
+            (hop.username.as_str(), hop.host.as_str(), hop.port),
+            ("admin", "bastion", 2200)
+        );
+        assert!(matches!(hop.auth_method, AuthMethod::Agent));
+
+        let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+        let hop = parse_jump_host(&format!("bastion {manifest}"), "me", 22, ("box", 22)).unwrap();
+        assert_eq!(hop.username, "me");
+        assert!(matches!(
+            hop.auth_method,
+            AuthMethod::Key { ref private_key_path, .. } if private_key_path == Path::new(manifest)
+        ));
+
+        assert_eq!(
+            parse_jump_host("root@BOX", "me", 22, ("box", 22)).unwrap_err(),
+            JumpHostError::SameAsTarget
+        );
+        assert!(parse_jump_host("root@box:2222", "me", 22, ("box", 22)).is_ok());
+        assert_eq!(
+            parse_jump_host("@bastion", "me", 22, ("box", 22)).unwrap_err(),
+            JumpHostError::Invalid
+        );
+        assert_eq!(
+            parse_jump_host("bastion /nonexistent/key", "me", 22, ("box", 22)).unwrap_err(),
+            JumpHostError::KeyMissing(PathBuf::from("/nonexistent/key"))
+        );
+    }
+
+    #[test]
+    fn builds_auth_method_from_edit_form() {
+        let id = Uuid::new_v4();
+        let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+        let mut draft = ConnectionDraft {
+            name: "box".to_string(),
+            target: "me@box:22".to_string(),
+            auth: "agent",
+            key_path: manifest.to_string(),
+            cert_path: String::new(),
+        };
+        assert!(matches!(
+            draft.auth_method(id, &AuthMethod::Agent),
+            Ok(AuthMethod::Agent)
+        ));
+
+        draft.cycle_auth(1);
+        let current = AuthMethod::Certificate {
+            cert_path: PathBuf::from("/old.pub"),
+            private_key_path: PathBuf::from("/old"),
+            passphrase_ref: Some("pass".to_string()),
+        };
+        match draft.auth_method(id, &current) {
+            Ok(AuthMethod::Key {
+                private_key_path,
+                passphrase_ref,
+            }) => {
+                assert_eq!(private_key_path, PathBuf::from(manifest));
+                assert_eq!(passphrase_ref.as_deref(), Some("pass"));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+        draft.key_path = "/nonexistent/id_ed25519".to_string();
+        assert_eq!(
+            draft.auth_method(id, &current).unwrap_err(),
+            PathBuf::from("/nonexistent/id_ed25519")
+        );
+
+        draft.cycle_auth(1);
+        match draft.auth_method(id, &AuthMethod::Agent) {
+            Ok(AuthMethod::Password { secret_ref }) => {
+                assert_eq!(secret_ref, format!("conn:{id}:password"));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+        draft.cycle_auth(-3);
+        assert_eq!(draft.auth, "keyboard-interactive");
+    }
+
+    #[test]
+    fn prompts_for_password_on_typed_auth_errors() {
+        assert!(should_prompt_password(&CoreError::CredentialsRequired(
+            "missing password".to_string()
+        )));
+        assert!(should_prompt_password(&CoreError::Connect(
+            SshError::AgentAuthFailed
+        )));
+        assert!(!should_prompt_password(&CoreError::Connect(
+            SshError::Timeout
+        )));
+        assert!(!should_prompt_password(&CoreError::Connect(
+            SshError::HostKeyRejected {
+                host: "example.com".to_string(),
+                port: 22,
+            }
+        )));
+        assert!(!should_prompt_password(&CoreError::Connect(
+            SshError::AuthFailed
+        )));
+        assert!(!should_prompt_password(&CoreError::Connect(
+            SshError::HostKeyChanged {
+                host: "example.com".to_string(),
+                port: 22,
+                fingerprint: "SHA256:abc".to_string(),
+                key: Box::new(
+                    "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAILM+rvN+ot98qgEN796jTiQfZfG1KaT0PtFDJ/XFSqti"
+                        .parse()
+                        .unwrap()
+                ),
+            }
+        )));
+        assert!(!should_prompt_password(&CoreError::Invalid(
+            "missing password".to_string()
+        )));
+    }
+
+    #[test]
+    fn filters_panel_entries_case_insensitively() {
+        let mut panel = PanelState::local_default();
+        panel.all_entries = ["Cargo.toml", "README.md", "src", "cargo-lock"]
+            .into_iter()
+            .map(|name| FileEntry {
+                name: name.to_string(),
+                is_dir: false,
+                size: 0,
+                modified: None,
+            })
+            .collect();
+        panel.apply_filter();
+        panel.selected = 3;
+        panel.set_filter("CARGO".to_string());
+        let names: Vec<_> = panel.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["Cargo.toml", "cargo-lock"]);
+        assert_eq!(panel.selected, 1);
+        panel.set_filter("zzz".to_string());
+        assert!(panel.entries.is_empty());
+        assert_eq!(panel.selected, 0);
+        panel.set_filter(String::new());
+        assert_eq!(panel.entries.len(), 4);
+    }
+
+    #[test]
+    fn hides_dotfiles_unless_enabled() {
+        let mut panel = PanelState::remote_default();
+        panel.all_entries = [".bashrc", ".ssh", "notes.txt"]
+            .into_iter()
+            .map(|name| FileEntry {
+                name: name.to_string(),
+                is_dir: false,
+                size: 0,
+                modified: None,
+            })
+            .collect();
+        panel.apply_filter();
+        assert_eq!(panel.entries.len(), 1);
+        panel.show_hidden = true;
+        panel.set_filter("SS".to_string());
+        assert_eq!(panel.entries[0].name, ".ssh");
+    }
+
+    /// Server stand-in for the tool tests: the shared in-memory file tree and one canned
+    /// reply for every command.
+    #[derive(Clone, Default)]
+    struct FakeServer {
+        fs: MemoryFs,
+        exec_reply: (i32, Vec<u8>),
+        commands: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl FakeServer {
+        /// Adds a file, or a directory when `data` is `None`.
+        fn with(mut self, path: &str, data: Option<&[u8]>) -> Self {
+            self.fs = match data {
+                Some(data) => self.fs.with_file(path, data, None),
+                None => self.fs.with_dir(path),
+            };
+            self
+        }
+
+        /// `Some(None)` for a directory, `Some(Some(data))` for a file.
+        fn get(&self, path: &str) -> Option<Option<Vec<u8>>> {
+            match self.fs.file(path) {
+                Some(data) => Some(Some(data)),
+                None => self.fs.is_dir(path).then_some(None),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ToolRemote for FakeServer {
+        async fn exec(&self, command: &str) -> Result<(i32, Vec<u8>)> {
+            self.commands.lock().unwrap().push(command.to_string());
+            Ok(self.exec_reply.clone())
+        }
+
+        async fn open_sftp(&self) -> Result<Box<dyn ToolSftp>> {
+            Ok(Box::new(self.fs.clone()))
+        }
+    }
+
+    #[async_trait]
+    impl ToolSftp for MemoryFs {
+        async fn canonicalize(&self, path: &str) -> Result<String> {
+            Ok(path.trim_end_matches('/').to_string())
+        }
+
+        async fn remove_file(&self, path: &str) -> Result<()> {
+            Ok(MemoryFs::remove_file(self, path)?)
+        }
+
+        async fn remove_dir(&self, path: &str) -> Result<()> {
+            Ok(MemoryFs::remove_dir(self, path)?)
+        }
+
+        async fn rename(&self, from: &str, to: &str) -> Result<()> {
+            Ok(MemoryFs::rename(self, from, to)?)
+        }
+
+        async fn set_permissions(&self, _path: &str, _mode: u32) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn tool_context(session_id: Option<Uuid>, remote: Option<FakeServer>) -> ToolContext {
+        let bus = EventBus::new(16);
+        let sessions = Arc::new(SessionManager::new(
+            ConnectionStore::new(PathBuf::from("unused.sqlite")),
+            catsolle_keychain::KeychainManager::new(
+                "catsolle-test",
+                PathBuf::from("unused-secrets"),
+                false,
+            ),
+            AppConfig::default(),
+            bus.clone(),
+        ));
+        let queue = TransferQueue::new(sessions.clone(), bus, TransferConfig::default());
+        let (shell_tool_tx, _) = mpsc::channel(1);
+        ToolContext {
+            sessions,
+            queue,
+            config: AppConfig::default(),
+            local_base: ".".to_string(),
+            remote_base: "/home/u".to_string(),
+            session_id,
+            remote: remote.map(|remote| Arc::new(remote) as Arc<dyn ToolRemote>),
+            connections: Vec::new(),
+            left_panel: PanelState::local_default(),
+            right_panel: PanelState::remote_default(),
+            active_panel_left: true,
+            shell_tool_tx,
+        }
+    }
+
+    async fn run_tool(ctx: &ToolContext, name: &str, args: serde_json::Value) -> ToolResult {
+        let call = ToolCall {
+            name: name.to_string(),
+            args,
+        };
+        execute_tool_call(call, ctx.clone()).await
+    }
+
+    #[tokio::test]
+    async fn remote_file_tools_resolve_against_the_remote_panel() {
+        let server = FakeServer::default()
+            .with("/home", None)
+            .with("/home/u", None)
+            .with("/home/u/notes.txt", Some(b"hello"));
+        let ctx = tool_context(Some(Uuid::new_v4()), Some(server.clone()));
+
+        let read = run_tool(
+            &ctx,
+            "remote.read",
+            serde_json::json!({ "path": "notes.txt" }),
+        )
+        .await;
+        assert!(read.success, "{}", read.output);
+        assert!(read.output.contains("\"content\": \"hello\""));
+
+        let write = run_tool(
+            &ctx,
+            "remote.write",
+            serde_json::json!({ "path": "sub/new.txt", "content": "one" }),
+        )
+        .await;
+        assert!(write.success, "{}", write.output);
+        assert_eq!(server.get("/home/u/sub"), Some(None));
+        assert_eq!(
+            server.get("/home/u/sub/new.txt"),
+            Some(Some(b"one".to_vec()))
+        );
+        let append = run_tool(
+            &ctx,
+            "remote.write",
+            serde_json::json!({ "path": "sub/new.txt", "content": "two", "append": true }),
+        )
+        .await;
+        assert!(append.success, "{}", append.output);
+        assert_eq!(
+            server.get("/home/u/sub/new.txt"),
+            Some(Some(b"onetwo".to_vec()))
+        );
+
+        let list = run_tool(&ctx, "remote.list", serde_json::json!({})).await;
+        assert!(list.output.contains("notes.txt") && list.output.contains("\"sub\""));
+
+        let renamed = run_tool(
+            &ctx,
+            "remote.rename",
+            serde_json::json!({ "from": "notes.txt", "to": "/tmp/notes.txt" }),
+        )
+        .await;
+        assert!(renamed.success, "{}", renamed.output);
+        assert_eq!(server.get("/tmp/notes.txt"), Some(Some(b"hello".to_vec())));
+
+        let kept = run_tool(&ctx, "remote.remove", serde_json::json!({ "path": "sub" })).await;
+        assert!(!kept.success);
+        let removed = run_tool(
+            &ctx,
+            "remote.remove",
+            serde_json::json!({ "path": "sub", "recursive": true }),
+        )
+        .await;
+        assert!(removed.success, "{}", removed.output);
+        assert_eq!(server.get("/home/u/sub"), None);
+        assert_eq!(server.get("/home/u/sub/new.txt"), None);
+    }
+
+    #[tokio::test]
+    async fn remote_reads_stop_at_max_bytes() {
+        let log = "é".repeat(5000);
+        let server = FakeServer::default()
+            .with("/home/u", None)
+            .with("/home/u/app.log", Some(log.as_bytes()));
+        let ctx = tool_context(Some(Uuid::new_v4()), Some(server));
+
+        let head = run_tool(
+            &ctx,
+            "remote.read",
+            serde_json::json!({ "path": "app.log", "max_bytes": 7 }),
+        )
+        .await;
+        assert!(head.success, "{}", head.output);
+        let output: serde_json::Value = serde_json::from_str(&head.output).unwrap();
+        assert_eq!(output["content"], "ééé");
+        assert_eq!(output["truncated"], true);
+
+        let whole = run_tool(
+            &ctx,
+            "remote.read",
+            serde_json::json!({ "path": "app.log" }),
+        )
+        .await;
+        let output: serde_json::Value = serde_json::from_str(&whole.output).unwrap();
+        assert!(output.get("truncated").is_none());
+    }
+
+    #[tokio::test]
+    async fn remote_writes_skip_the_diff_for_large_files() {
+        let big = vec![b'x'; TOOL_DEFAULT_MAX_BYTES + 1];
+        let server = FakeServer::default()
+            .with("/home/u", None)
+            .with("/home/u/big.log", Some(&big))
+            .with("/home/u/small.txt", Some(b"one\n"));
+        let ctx = tool_context(Some(Uuid::new_v4()), Some(server.clone()));
+
+        let big_write = run_tool(
+            &ctx,
+            "remote.write",
+            serde_json::json!({ "path": "big.log", "content": "short" }),
+        )
+        .await;
+        assert!(big_write.success, "{}", big_write.output);
+        assert!(big_write.output.contains("\"diff\": null"));
+        assert_eq!(server.get("/home/u/big.log"), Some(Some(b"short".to_vec())));
+
+        let small_write = run_tool(
+            &ctx,
+            "remote.write",
+            serde_json::json!({ "path": "small.txt", "content": "two\n" }),
+        )
+        .await;
+        let output: serde_json::Value = serde_json::from_str(&small_write.output).unwrap();
+        assert_eq!(output["diff"], "- one\n+ two");
+    }
+
+    #[tokio::test]
+    async fn diff_reads_each_side_up_to_max_bytes() {
+        let server = FakeServer::default()
+            .with("/home/u", None)
+            .with("/home/u/a.txt", Some("same\nold tail\n".as_bytes()))
+            .with("/home/u/b.txt", Some("same\néé".as_bytes()));
+        let ctx = tool_context(Some(Uuid::new_v4()), Some(server));
+
+        let result = run_tool(
+            &ctx,
+            "diff",
+            serde_json::json!({ "left": "remote:a.txt", "right": "remote:b.txt", "max_bytes": 8 }),
+        )
+        .await;
+        assert!(result.success, "{}", result.output);
+        let output: serde_json::Value = serde_json::from_str(&result.output).unwrap();
+        assert_eq!(output["left_truncated"], true);
+        assert_eq!(output["right_truncated"], true);
+        let diff = output["diff"].as_str().unwrap();
+        assert!(diff.contains("-old") && diff.contains("+é"), "{diff}");
+
+        let whole = run_tool(
+            &ctx,
+            "diff",
+            serde_json::json!({ "left": "remote:a.txt", "right": "remote:a.txt" }),
+        )
+        .await;
+        assert!(!whole.output.contains("truncated"), "{}", whole.output);
+    }
+
+    #[tokio::test]
+    async fn remote_grep_falls_back_to_search_without_grep() {
+        let server = FakeServer {
+            exec_reply: (GREP_MISSING_STATUS, Vec::new()),
+            ..FakeServer::default()
+        }
+        .with("/home/u", None)
+        .with("/home/u/a.conf", Some(b"listen 80"))
+        .with("/home/u/b.conf", Some(b"listen 443"));
+        let ctx = tool_context(Some(Uuid::new_v4()), Some(server.clone()));
+
+        let result = run_tool(
+            &ctx,
+            "remote.grep",
+            serde_json::json!({ "pattern": "4[0-9]+$" }),
+        )
+        .await;
+        assert!(result.success, "{}", result.output);
+        assert!(result.output.contains("/home/u/b.conf"));
+        assert!(!result.output.contains("/home/u/a.conf"));
+        let literal = run_tool(
+            &ctx,
+            "remote.grep",
+            serde_json::json!({ "pattern": "LISTEN 4", "flags": "iF" }),
+        )
+        .await;
+        assert!(
+            literal.output.contains("/home/u/b.conf"),
+            "{}",
+            literal.output
+        );
+        let commands = server.commands.lock().unwrap();
+        assert_eq!(commands.len(), 2);
+        assert!(commands[0].contains("grep"));
+    }
+
+    #[tokio::test]
+    async fn remote_search_reports_matching_lines() {
+        let server = FakeServer::default()
+            .with("/home/u", None)
+            .with("/home/u/a.conf", Some(b"user www\nlisten 8080\n"))
+            .with("/home/u/b.conf", Some(b"listen [::]:80\n"));
+        let ctx = tool_context(Some(Uuid::new_v4()), Some(server));
+
+        let result = run_tool(
+            &ctx,
+            "remote.search",
+            serde_json::json!({ "query": "^listen \\d+$", "regex": true }),
+        )
+        .await;
+        assert!(result.success, "{}", result.output);
+        let output: serde_json::Value = serde_json::from_str(&result.output).unwrap();
+        assert_eq!(
+            output["matches"],
+            serde_json::json!([{ "path": "/home/u/a.conf", "line": 2, "text": "listen 8080" }])
+        );
+
+        let around = run_tool(
+            &ctx,
+            "remote.search",
+            serde_json::json!({ "query": "listen", "context": 1 }),
+        )
+        .await;
+        let output: serde_json::Value = serde_json::from_str(&around.output).unwrap();
+        assert_eq!(
+            output["matches"],
+            serde_json::json!([
+                { "path": "/home/u/a.conf", "line": 2, "text": "listen 8080", "before": ["user www"] },
+                { "path": "/home/u/b.conf", "line": 1, "text": "listen [::]:80" },
+            ])
+        );
+
+        let literal = run_tool(&ctx, "remote.search", serde_json::json!({ "query": "\\d" })).await;
+        assert!(
+            literal.output.contains("\"matches\": []"),
+            "{}",
+            literal.output
+        );
+
+        let invalid = run_tool(
+            &ctx,
+            "remote.search",
+            serde_json::json!({ "query": "listen (", "regex": true }),
+        )
+        .await;
+        assert!(!invalid.success);
+        assert!(
+            invalid.output.starts_with("invalid regex"),
+            "{}",
+            invalid.output
+        );
+    }
+
+    #[tokio::test]
+    async fn remote_tools_need_a_live_session() {
+        let args = serde_json::json!({ "path": "x" });
+        let none = run_tool(&tool_context(None, None), "remote.stat", args.clone()).await;
+        assert_eq!(none.output, "no active session");
+        let gone = tool_context(Some(Uuid::new_v4()), None);
+        let gone = run_tool(&gone, "remote.stat", args).await;
+        assert_eq!(gone.output, "session not found");
+    }
 }
@@ -1,14 +1,25 @@
 use anyhow::Result;
-use catsolle_cli::{Cli, Command, KeyCommand};
+use catsolle_cli::{Cli, Command, CopyLocation, KeyCommand, LocalForwardSpec, RemoteForwardSpec};
 use catsolle_config::{AppPaths, ConfigManager, I18n};
-use catsolle_core::{ConnectionStore, EventBus, SessionManager, TransferQueue};
+use catsolle_core::connection::LOCALE_ENV_KEYS;
+use catsolle_core::transfer::OverwriteMode;
+use catsolle_core::{
+    expand_transfer_files, format_bytes, format_duration, Connection, ConnectionStore, Event,
+    EventBus, FileOutcome, RemoteFs, SessionHandle, SessionManager, TransferEndpoint, TransferJob,
+    TransferOptions, TransferQueue, TransferState,
+};
 use catsolle_keychain::{AgentManager, KeyAlgorithm, KeyManager, KeychainManager};
-use catsolle_ssh::{AuthMethod, HostKeyPolicy, SshClient, SshConnectConfig};
+use catsolle_ssh::{AuthMethod, HostKeyPolicy, SftpClient, SshClient, SshConnectConfig};
+use chrono::Utc;
 use clap::Parser;
-use std::path::PathBuf;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::broadcast;
 use tracing_subscriber::prelude::*;
+use uuid::Uuid;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -16,8 +27,15 @@ async fn main() -> Result<()> {
     let paths = AppPaths::new()?;
     let config_manager = ConfigManager::new(paths.clone());
     let config = config_manager.load(std::env::current_dir().ok().as_deref(), None)?;
-    let interactive = matches!(cli.command, None | Some(Command::Connect { .. }));
-    let _log_guard = init_logging(&config, &paths, config.logging.stdout && !interactive)?;
+    // These own stdout, so logs would end up in the terminal or the captured output.
+    let owns_stdout = matches!(
+        cli.command,
+        None | Some(Command::Connect { .. })
+            | Some(Command::Exec { .. })
+            | Some(Command::Cp { .. })
+            | Some(Command::Play { .. })
+    );
+    let _log_guard = init_logging(&config, &paths, config.logging.stdout && !owns_stdout)?;
     let i18n = I18n::new(
         &config.locale.default_language,
         &config.locale.preferred_languages,
@@ -46,17 +64,110 @@ async fn main() -> Result<()> {
     );
 
     match cli.command {
-        Some(Command::Config { init }) => {
+        Some(Command::Config {
+            init,
+            export,
+            import,
+            set,
+        }) => {
             if init {
                 config_manager.save_default()?;
                 println!("config initialized at {}", paths.config_file.display());
             }
+            if !set.is_empty() {
+                config_manager.set_values(&set)?;
+                println!(
+                    "{} settings saved to {}",
+                    set.len(),
+                    paths.config_file.display()
+                );
+            }
+            if let Some(path) = export {
+                std::fs::write(&path, store.export_json()?)?;
+                println!("connections exported to {}", path.display());
+            }
+            if let Some(path) = import {
+                let imported = store.import_json(&std::fs::read_to_string(&path)?)?;
+                println!("imported {} connections", imported.len());
+                let need_secrets = imported.iter().filter(|c| c.uses_stored_secrets()).count();
+                if need_secrets > 0 {
+                    println!(
+                        "{need_secrets} of them use saved passwords or passphrases; re-enter those before connecting"
+                    );
+                }
+            }
         }
         Some(Command::Keys { command }) => {
             handle_keys(command).await?;
         }
-        Some(Command::Connect { target, quick: _ }) => {
-            connect_quick(&target).await?;
+        Some(Command::Connect {
+            target,
+            quick: _,
+            dynamic_forward,
+            local_forward,
+            remote_forward,
+        }) => {
+            connect_quick(
+                &target,
+                &config.ssh.locale,
+                dynamic_forward,
+                &local_forward,
+                &remote_forward,
+            )
+            .await?;
+        }
+        Some(Command::Exec {
+            target,
+            command,
+            timeout_ms,
+        }) => {
+            let command = command.join(" ");
+            let run = exec_command(&store, &session_manager, &target, &command);
+            let (status, output, errors) = match timeout_ms {
+                Some(ms) => tokio::time::timeout(Duration::from_millis(ms), run)
+                    .await
+                    .map_err(|_| anyhow::anyhow!("timed out after {ms} ms"))??,
+                None => run.await?,
+            };
+            let mut stdout = tokio::io::stdout();
+            stdout.write_all(&output).await?;
+            stdout.flush().await?;
+            let mut stderr = tokio::io::stderr();
+            stderr.write_all(&errors).await?;
+            stderr.flush().await?;
+            drop(_log_guard);
+            std::process::exit(status);
+        }
+        Some(Command::Cp {
+            source,
+            dest,
+            recursive,
+            verify,
+            resume,
+        }) => {
+            let request = CopyRequest {
+                source,
+                dest,
+                recursive,
+                verify: verify.unwrap_or(config.transfer.verify_checksum),
+                resume: resume.unwrap_or(config.transfer.resume),
+            };
+            copy_files(
+                &store,
+                &session_manager,
+                &transfer_queue,
+                &bus,
+                &config.transfer,
+                request,
+            )
+            .await?;
+        }
+        Some(Command::Play {
+            file,
+            speed,
+            max_idle,
+        }) => {
+            play_recording(&file, speed, max_idle).await?;
         }
         None => {
             catsolle_tui::run(
@@ -125,6 +236,21 @@ fn init_logging(
     Ok(guard)
 }
 
+/// Writes recorded output straight to the terminal, sleeping between events.
+async fn play_recording(path: &Path, speed: f64, max_idle: Option<f64>) -> Result<()> {
+    let (_, events) = catsolle_core::recording::read_cast(path)?;
+    let delays = catsolle_core::recording::playback_delays(&events, speed, max_idle);
+    let mut stdout = tokio::io::stdout();
+    for (event, delay) in events.iter().zip(delays) {
+        tokio::time::sleep(delay).await;
+        if event.kind == "o" {
+            stdout.write_all(event.data.as_bytes()).await?;
+            stdout.flush().await?;
+        }
+    }
+    Ok(())
+}
+
 async fn handle_keys(command: KeyCommand) -> Result<()> {
     match command {
         KeyCommand::Generate {
@@ -166,6 +292,33 @@ async fn handle_keys(command: KeyCommand) -> Result<()> {
                 }
             }
         }
+        KeyCommand::Fingerprint { path } => {
+            let key = KeyManager::fingerprint(Path::new(&path))?;
+            println!("{} {} {}", key.algorithm, key.fingerprint, key.comment);
+        }
+        KeyCommand::Remove { name, yes } => {
+            let manager = KeyManager::new(default_ssh_dir());
+            let files = manager.key_files(&name)?;
+            if files.is_empty() {
+                anyhow::bail!("no key named {name}");
+            }
+            if !yes {
+                for path in &files {
+                    println!("{}", path.display());
+                }
+                print!("delete these files? [y/N] ");
+                std::io::Write::flush(&mut std::io::stdout())?;
+                let mut answer = String::new();
+                std::io::stdin().read_line(&mut answer)?;
+                if !matches!(answer.trim(), "y" | "Y" | "yes") {
+                    println!("nothing deleted");
+                    return Ok(());
+                }
+            }
+            for path in manager.remove(&name)? {
+                println!("deleted {}", path.display());
+            }
+        }
         KeyCommand::AddAgent { path } => {
             let mut agent = AgentManager::connect().await?;
             agent
@@ -173,13 +326,246 @@ async fn handle_keys(command: KeyCommand) -> Result<()> {
                 .await?;
             println!("added to agent");
         }
+        KeyCommand::AgentList => {
+            let mut agent = AgentManager::connect().await?;
+            let keys = agent.list_identities().await?;
+            if keys.is_empty() {
+                println!("the agent has no identities");
+            }
+            for key in keys {
+                println!(
+                    "{} {} {}",
+                    key.public_key.algorithm().as_str(),
+                    key.fingerprint,
+                    key.public_key.comment()
+                );
+            }
+        }
+        KeyCommand::AgentRemove { fingerprint } => {
+            let mut agent = AgentManager::connect().await?;
+            let key = agent.remove_by_fingerprint(&fingerprint).await?;
+            println!("removed {} from agent", key.fingerprint);
+        }
     }
     Ok(())
 }
 
-async fn connect_quick(target: &str) -> Result<()> {
+/// Connects to the saved connection named `target` (or with that host), falling back to
+/// an ad-hoc `user@host[:port]` login through the agent that is not saved.
+async fn connect_target(
+    store: &ConnectionStore,
+    session_manager: &SessionManager,
+    target: &str,
+) -> Result<SessionHandle> {
+    let saved = store
+        .list_connections()?
+        .into_iter()
+        .find(|conn| conn.name == target || conn.host == target);
+    let conn = match saved {
+        Some(conn) => conn,
+        None => {
+            let (user, host, port) = parse_target(target)?;
+            Connection::new(host, port, user)
+        }
+    };
+    let id = session_manager.connect(conn, None, None).await?;
+    session_manager
+        .get_session(id)
+        .ok_or_else(|| anyhow::anyhow!("session to {target} closed right after connecting"))
+}
+
+async fn exec_command(
+    store: &ConnectionStore,
+    session_manager: &SessionManager,
+    target: &str,
+    command: &str,
+) -> Result<(i32, Vec<u8>, Vec<u8>)> {
+    let handle = connect_target(store, session_manager, target).await?;
+    Ok(handle.session.exec_split(command).await?)
+}
+
+struct CopyRequest {
+    source: CopyLocation,
+    dest: CopyLocation,
+    recursive: bool,
+    verify: bool,
+    resume: bool,
+}
+
+/// Copies between this machine and one server through the transfer queue, printing
+/// progress to stderr. Fails when the job does not complete.
+async fn copy_files(
+    store: &ConnectionStore,
+    session_manager: &SessionManager,
+    queue: &TransferQueue,
+    bus: &EventBus,
+    transfer: &catsolle_config::TransferConfig,
+    request: CopyRequest,
+) -> Result<()> {
+    let (local, remote_target, remote_path, upload) = match (request.source, request.dest) {
+        (CopyLocation::Local(local), CopyLocation::Remote { target, path }) => {
+            (local, target, path, true)
+        }
+        (CopyLocation::Remote { target, path }, CopyLocation::Local(local)) => {
+            (local, target, path, false)
+        }
+        (CopyLocation::Local(_), CopyLocation::Local(_)) => {
+            anyhow::bail!("one side of the copy must be remote (TARGET:PATH)")
+        }
+        (CopyLocation::Remote { .. }, CopyLocation::Remote { .. }) => {
+            anyhow::bail!("copying between two servers is not supported")
+        }
+    };
+    let handle = connect_target(store, session_manager, &remote_target).await?;
+    let session_id = handle.id;
+    // Without SFTP nothing can be stat'ed up front; SCP reports errors while copying.
+    let sftp = handle.session.open_sftp().await.ok();
+
+    let local_endpoint = TransferEndpoint::Local {
+        path: local.clone(),
+    };
+    let remote_endpoint = TransferEndpoint::Remote {
+        session_id,
+        path: remote_path.clone(),
+    };
+    let local_str = local.to_string_lossy().to_string();
+    let (source, dest, source_path, dest_path, source_is_dir, dest_is_dir) = if upload {
+        let source_is_dir = local.is_dir();
+        let dest_is_dir = remote_is_dir(sftp.as_ref(), &remote_path).await;
+        (
+            local_endpoint,
+            remote_endpoint,
+            local_str,
+            remote_path,
+            source_is_dir,
+            dest_is_dir,
+        )
+    } else {
+        let source_is_dir = remote_is_dir(sftp.as_ref(), &remote_path).await;
+        (
+            remote_endpoint,
+            local_endpoint,
+            remote_path,
+            local_str,
+            source_is_dir,
+            local.is_dir(),
+        )
+    };
+    if source_is_dir && !request.recursive {
+        anyhow::bail!("{source_path} is a directory (use -r)");
+    }
+    // Like scp, copying onto an existing directory puts the source inside it.
+    let dest_path = if dest_is_dir {
+        let name = source_path
+            .trim_end_matches(['/', '\\'])
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or_default();
+        if upload {
+            format!("{}/{name}", dest_path.trim_end_matches('/'))
+        } else {
+            Path::new(&dest_path)
+                .join(name)
+                .to_string_lossy()
+                .to_string()
+        }
+    } else {
+        dest_path
+    };
+
+    let files =
+        expand_transfer_files(session_manager, &source, &dest, &source_path, &dest_path).await?;
+    let job = TransferJob {
+        id: Uuid::new_v4(),
+        source,
+        dest,
+        files,
+        options: TransferOptions {
+            overwrite: OverwriteMode::Replace,
+            preserve_permissions: transfer.preserve_permissions,
+            preserve_times: transfer.preserve_times,
+            verify_checksum: request.verify,
+            resume: request.resume,
+            buffer_size: transfer.buffer_size,
+            max_bytes_per_sec: (transfer.max_bytes_per_sec > 0)
+                .then_some(transfer.max_bytes_per_sec),
+            continue_on_error: transfer.continue_on_error,
+        },
+        state: TransferState::Queued,
+        progress: Default::default(),
+        results: Vec::new(),
+        created_at: Utc::now(),
+    };
+    let job_id = job.id;
+    let mut events = bus.subscribe();
+    queue.enqueue(job).await?;
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => {
+                anyhow::bail!("transfer queue stopped")
+            }
+        };
+        match event {
+            Event::TransferProgress {
+                job_id: id,
+                progress,
+                ..
+            } if id == job_id => {
+                let mut line = format!(
+                    "{}/{} files, {}/{}",
+                    progress.files_completed,
+                    progress.files_total,
+                    format_bytes(progress.bytes_transferred),
+                    format_bytes(progress.bytes_total)
+                );
+                if progress.speed_bps > 0 {
+                    line.push_str(&format!(", {}/s", format_bytes(progress.speed_bps)));
+                }
+                if let Some(eta) = progress.eta_seconds {
+                    line.push_str(&format!(", ETA {}", format_duration(eta)));
+                }
+                eprint!("\r\x1b[K{line}");
+            }
+            Event::TransferFinished {
+                job_id: id,
+                state,
+                results,
+                ..
+            } if id == job_id => {
+                eprintln!();
+                for result in &results {
+                    if let FileOutcome::Failed { error } = &result.outcome {
+                        eprintln!("{}: {error}", result.source_path);
+                    }
+                }
+                return match state {
+                    TransferState::Completed => Ok(()),
+                    TransferState::Failed { error } => Err(anyhow::anyhow!(error)),
+                    other => Err(anyhow::anyhow!("transfer ended as {other:?}")),
+                };
+            }
+            _ => {}
+        }
+    }
+}
+
+async fn remote_is_dir(sftp: Option<&SftpClient>, path: &str) -> bool {
+    match sftp {
+        Some(sftp) => RemoteFs::metadata(sftp, path)
+            .await
+            .is_ok_and(|meta| meta.is_dir),
+        None => false,
+    }
+}
+
+/// Agent login to `user@host[:port]`, trusting keys seen for the first time.
+fn quick_config(target: &str, locale: &str) -> Result<SshConnectConfig> {
     let (user, host, port) = parse_target(target)?;
-    let cfg = SshConnectConfig {
+    let (term_width, term_height) = crossterm::terminal::size().unwrap_or((120, 40));
+    Ok(SshConnectConfig {
         host,
         port,
         username: user,
@@ -192,15 +578,63 @@ async fn connect_quick(target: &str) -> Result<()> {
         connect_timeout_ms: 15000,
         request_pty: true,
         term: "xterm-256color".to_string(),
-        term_width: 120,
-        term_height: 40,
-        env: Vec::new(),
+        term_width: u32::from(term_width),
+        term_height: u32::from(term_height),
+        env: LOCALE_ENV_KEYS
+            .into_iter()
+            .filter(|_| !locale.is_empty())
+            .map(|key| (key.to_string(), locale.to_string()))
+            .collect(),
+        env_export_fallback: false,
         startup_commands: Vec::new(),
         agent_forwarding: false,
         x11_forwarding: false,
-    };
+    })
+}
 
+async fn connect_quick(
+    target: &str,
+    locale: &str,
+    dynamic_forward: Option<SocketAddr>,
+    local_forward: &[LocalForwardSpec],
+    remote_forward: &[RemoteForwardSpec],
+) -> Result<()> {
+    let cfg = quick_config(target, locale)?;
     let session = SshClient::connect(cfg, None).await?;
+    let _forward = match dynamic_forward {
+        Some(addr) => {
+            let forward = session.open_dynamic_forward(addr).await?;
+            eprintln!("SOCKS5 proxy listening on {}", forward.local_addr());
+            Some(forward)
+        }
+        None => None,
+    };
+    let mut tunnels = Vec::with_capacity(local_forward.len());
+    for spec in local_forward {
+        let forward = session
+            .open_local_forward(spec.bind, spec.host.clone(), spec.port)
+            .await?;
+        eprintln!(
+            "Forwarding {} -> {}:{}",
+            forward.local_addr(),
+            spec.host,
+            spec.port
+        );
+        tunnels.push(forward);
+    }
+    let mut remote_tunnels = Vec::with_capacity(remote_forward.len());
+    for spec in remote_forward {
+        let forward = session
+            .open_remote_forward(spec.remote_port, spec.host.clone(), spec.port)
+            .await?;
+        eprintln!(
+            "Forwarding remote port {} -> {}:{}",
+            forward.remote_port(),
+            spec.host,
+            spec.port
+        );
+        remote_tunnels.push(forward);
+    }
     let mut shell = session.open_shell().await?;
 
     let mut stdin = tokio::io::stdin();